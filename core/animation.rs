@@ -0,0 +1,232 @@
+use glam::{Mat4, Quat, Vec3};
+
+// How a keyframe blends into the next one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    // Hold the value until the next keyframe
+    Step,
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    // Maps 0..1 to 0..1
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Step => 0.0,
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+pub trait Interpolate: Copy {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self {
+        from + (to - from) * t
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self {
+        from.lerp(to, t)
+    }
+}
+
+impl Interpolate for Quat {
+    fn interpolate(from: Self, to: Self, t: f32) -> Self {
+        from.slerp(to, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    // Used between this keyframe and the next
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    pub fn new(time: f32, value: T, easing: Easing) -> Self {
+        Self { time, value, easing }
+    }
+}
+
+// Keyframes for a single value, e.g. a node's translation or a camera's FOV
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Interpolate> Track<T> {
+    pub fn new(mut keyframes: Vec<Keyframe<T>>) -> Self {
+        assert!(!keyframes.is_empty(), "a track needs at least one keyframe");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { keyframes }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().unwrap().time
+    }
+
+    // Holds the first and last value outside the keyframes
+    pub fn sample(&self, time: f32) -> T {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return self.keyframes[0].value;
+        }
+        let from = &self.keyframes[next - 1];
+        let Some(to) = self.keyframes.get(next) else {
+            return from.value;
+        };
+
+        let t = (time - from.time) / (to.time - from.time);
+        T::interpolate(from.value, to.value, from.easing.apply(t))
+    }
+}
+
+// What glTF calls the target paths of a node's animation channels. Any of
+// them can be missing, the node then keeps its default for that part.
+#[derive(Default)]
+pub struct TransformTracks {
+    pub translation: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+}
+
+impl TransformTracks {
+    pub fn duration(&self) -> f32 {
+        let translation = self.translation.as_ref().map_or(0.0, Track::duration);
+        let rotation = self.rotation.as_ref().map_or(0.0, Track::duration);
+        let scale = self.scale.as_ref().map_or(0.0, Track::duration);
+        translation.max(rotation).max(scale)
+    }
+
+    pub fn sample(&self, time: f32) -> Mat4 {
+        Mat4::from_scale_rotation_translation(
+            self.scale.as_ref().map_or(Vec3::ONE, |track| track.sample(time)),
+            self.rotation.as_ref().map_or(Quat::IDENTITY, |track| track.sample(time)),
+            self.translation.as_ref().map_or(Vec3::ZERO, |track| track.sample(time)),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playback {
+    // Stop on the last frame
+    Once,
+    Loop,
+    // Play forwards, then backwards, then forwards again...
+    PingPong,
+}
+
+// The playhead. Tracks are stateless, so one timeline can drive any number
+// of them and scrubbing is just a seek.
+pub struct Timeline {
+    pub duration: f32,
+    pub playback: Playback,
+    pub speed: f32,
+    pub playing: bool,
+    elapsed: f32,
+}
+
+impl Timeline {
+    pub fn new(duration: f32, playback: Playback) -> Self {
+        Self {
+            duration,
+            playback,
+            speed: 1.0,
+            playing: true,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if self.playing {
+            self.elapsed += delta_time * self.speed;
+        }
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.elapsed = time;
+    }
+
+    // Where to sample the tracks, always within 0..=duration
+    pub fn time(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 0.0;
+        }
+        match self.playback {
+            Playback::Once => self.elapsed.clamp(0.0, self.duration),
+            Playback::Loop => self.elapsed.rem_euclid(self.duration),
+            Playback::PingPong => {
+                let t = self.elapsed.rem_euclid(2.0 * self.duration);
+                if t > self.duration {
+                    2.0 * self.duration - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.playback == Playback::Once && self.elapsed >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `easing` from 0 to 1, linear from 1 to 2
+    fn track(easing: Easing) -> Track<f32> {
+        // Out of order on purpose, new() sorts them
+        Track::new(vec![
+            Keyframe::new(2.0, 30.0, Easing::Linear),
+            Keyframe::new(0.0, 10.0, easing),
+            Keyframe::new(1.0, 20.0, Easing::Linear),
+        ])
+    }
+
+    #[test]
+    fn samples_hit_the_keyframes() {
+        let track = track(Easing::Linear);
+        assert_eq!(track.duration(), 2.0);
+        assert_eq!(track.sample(0.0), 10.0);
+        assert_eq!(track.sample(1.0), 20.0);
+        assert_eq!(track.sample(2.0), 30.0);
+    }
+
+    #[test]
+    fn holds_the_ends_outside_the_keyframes() {
+        let track = track(Easing::Linear);
+        assert_eq!(track.sample(-1.0), 10.0);
+        assert_eq!(track.sample(5.0), 30.0);
+    }
+
+    #[test]
+    fn eases_between_keyframes() {
+        assert_eq!(track(Easing::Linear).sample(0.5), 15.0);
+        assert_eq!(track(Easing::Step).sample(0.99), 10.0);
+        assert_eq!(track(Easing::EaseIn).sample(0.5), 12.5);
+        assert_eq!(track(Easing::EaseOut).sample(0.5), 17.5);
+        assert_eq!(track(Easing::EaseInOut).sample(0.5), 15.0);
+        // The easing belongs to the keyframe a span starts at
+        assert_eq!(track(Easing::Step).sample(1.5), 25.0);
+    }
+
+    #[test]
+    fn single_keyframe_is_constant() {
+        let track = Track::new(vec![Keyframe::new(1.0, Vec3::X, Easing::Linear)]);
+        assert_eq!(track.sample(0.0), Vec3::X);
+        assert_eq!(track.sample(3.0), Vec3::X);
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Something too big to keep in the repository, downloaded the first time a
+// sample asks for it and kept in a cache shared by every sample after that
+pub struct Asset {
+    // The file name in the cache, and what asset:name on the command line
+    // refers to
+    pub name: &'static str,
+    pub url: &'static str,
+    // Lowercase hex. None only while adding an asset: debug builds cache it
+    // unchecked and print the hash to pin, release builds refuse it.
+    pub sha256: Option<&'static str>,
+}
+
+// CC0 panoramas from Poly Haven, tonemapped to JPEG so the image crate can
+// read them; good input for little-planet, kmeans, seam-carving and
+// jpeg-decode alike
+pub const CATALOG: &[Asset] = &[
+    Asset {
+        name: "kloppenheim_06.jpg",
+        url: "https://dl.polyhaven.org/file/ph-assets/HDRIs/extra/Tonemapped%20JPG/kloppenheim_06.jpg",
+        sha256: None,
+    },
+    Asset {
+        name: "venice_sunset.jpg",
+        url: "https://dl.polyhaven.org/file/ph-assets/HDRIs/extra/Tonemapped%20JPG/venice_sunset.jpg",
+        sha256: None,
+    },
+];
+
+#[derive(Debug)]
+pub enum AssetError {
+    Unknown(String),
+    Download(String),
+    Io(std::io::Error),
+    Checksum { expected: String, actual: String },
+    Unpinned { actual: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Unknown(name) => {
+                let names: Vec<&str> = CATALOG.iter().map(|asset| asset.name).collect();
+                write!(f, "no asset called {}, there's {}", name, names.join(", "))
+            }
+            AssetError::Download(error) => write!(f, "download failed: {}", error),
+            AssetError::Io(error) => write!(f, "{}", error),
+            AssetError::Checksum { expected, actual } => write!(f, "sha256 is {}, expected {}", actual, expected),
+            AssetError::Unpinned { actual } => write!(f, "sha256 is {}, but the catalog doesn't pin one to check it against", actual),
+        }
+    }
+}
+
+impl From<std::io::Error> for AssetError {
+    fn from(error: std::io::Error) -> Self {
+        AssetError::Io(error)
+    }
+}
+
+// WGPU_SAMPLES_CACHE if set, otherwise the platform's usual cache
+// directory, otherwise next to wherever the samples are run from
+pub fn cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("WGPU_SAMPLES_CACHE") {
+        return PathBuf::from(dir);
+    }
+    let platform = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")));
+    match platform {
+        Some(dir) => dir.join("wgpu-samples"),
+        None => PathBuf::from("asset-cache"),
+    }
+}
+
+pub fn find(name: &str) -> Result<&'static Asset, AssetError> {
+    CATALOG.iter().find(|asset| asset.name == name).ok_or_else(|| AssetError::Unknown(name.to_string()))
+}
+
+// The asset's file in the cache, downloading it first if it isn't there
+pub fn fetch(asset: &Asset) -> Result<PathBuf, AssetError> {
+    let dir = cache_dir();
+    let path = dir.join(asset.name);
+    if path.exists() {
+        if cached_matches(asset, &path)? {
+            return Ok(path);
+        }
+        // E.g. cached unchecked by a debug build before the hash was pinned
+        eprintln!("{}: the cached file doesn't match the catalog, downloading it again", asset.name);
+        std::fs::remove_file(&path)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    // Downloaded next to where it goes and only renamed into place once
+    // it's complete and checked, so an interrupted run never leaves a
+    // broken file for the next one to trust
+    let partial = dir.join(format!("{}.part", asset.name));
+    let actual = download(asset, &partial)?;
+    match asset.sha256 {
+        Some(expected) if expected != actual => {
+            std::fs::remove_file(&partial)?;
+            return Err(AssetError::Checksum {
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+        Some(_) => {}
+        None if cfg!(debug_assertions) => eprintln!("{}: sha256 {}, not pinned in the catalog yet", asset.name, actual),
+        None => {
+            std::fs::remove_file(&partial)?;
+            return Err(AssetError::Unpinned { actual });
+        }
+    }
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+// Pinned assets are hashed again every time they're used; the files are
+// big, but so is the trouble a silently corrupted one causes
+fn cached_matches(asset: &Asset, path: &Path) -> Result<bool, AssetError> {
+    match asset.sha256 {
+        Some(expected) => Ok(file_sha256(path)? == expected),
+        None => Ok(true),
+    }
+}
+
+fn file_sha256(path: &Path) -> Result<String, AssetError> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// A sample's file argument: a path as it is, or asset:name for something
+// from the catalog, fetched first if need be
+pub fn resolve(arg: &str) -> Result<PathBuf, AssetError> {
+    match arg.strip_prefix("asset:") {
+        Some(name) => find(name).and_then(fetch),
+        None => Ok(PathBuf::from(arg)),
+    }
+}
+
+// Streams to `partial`, hashing along the way, with progress on stderr.
+// Returns the hash.
+#[cfg(not(target_arch = "wasm32"))]
+fn download(asset: &Asset, partial: &Path) -> Result<String, AssetError> {
+    use std::io::{Read, Write};
+
+    use sha2::{Digest, Sha256};
+
+    let response = ureq::get(asset.url).call().map_err(|error| AssetError::Download(error.to_string()))?;
+    let total: Option<u64> = response.header("Content-Length").and_then(|length| length.parse().ok());
+    let mut reader = response.into_reader();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(partial)?);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut received = 0u64;
+    // Percent with a length to go by, otherwise MiB, only printed when
+    // it changes
+    let mut reported = None;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        hasher.update(&buffer[..read]);
+        received += read as u64;
+
+        let progress = match total {
+            Some(total) => received * 100 / total.max(1),
+            None => received >> 20,
+        };
+        if reported != Some(progress) {
+            reported = Some(progress);
+            match total {
+                Some(total) => eprint!("\rdownloading {}: {}% of {:.1}MiB", asset.name, progress, total as f64 / (1 << 20) as f64),
+                None => eprint!("\rdownloading {}: {}MiB", asset.name, progress),
+            }
+        }
+    }
+    eprintln!();
+    file.flush()?;
+
+    Ok(hex(&hasher.finalize()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(_asset: &Asset, _partial: &Path) -> Result<String, AssetError> {
+    Err(AssetError::Download("the web build has no downloader, the browser fetches its own assets".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256("abc"), from FIPS 180-2
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    // sha256("")
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn asset(sha256: Option<&'static str>) -> Asset {
+        Asset {
+            name: "test.bin",
+            url: "https://example.com/test.bin",
+            sha256,
+        }
+    }
+
+    fn cached(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("wgpu-samples-{}-{}.bin", test, std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+        path
+    }
+
+    #[test]
+    fn hashes_files() {
+        let path = cached("hashes-files");
+        let hash = file_sha256(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(hash, ABC);
+    }
+
+    #[test]
+    fn checks_cached_files_against_the_pinned_hash() {
+        let path = cached("checks-cached");
+        let matching = cached_matches(&asset(Some(ABC)), &path).unwrap();
+        let other = cached_matches(&asset(Some(EMPTY)), &path).unwrap();
+        let unpinned = cached_matches(&asset(None), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matching);
+        assert!(!other);
+        assert!(unpinned);
+    }
+
+    #[test]
+    fn finds_catalog_entries_by_name() {
+        assert_eq!(find("venice_sunset.jpg").unwrap().name, "venice_sunset.jpg");
+        assert!(matches!(find("nope.jpg"), Err(AssetError::Unknown(name)) if name == "nope.jpg"));
+    }
+}
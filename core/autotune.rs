@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use wgpu::{BindGroup, ComputePipeline, Device, PipelineLayout};
+
+use crate::settings::Settings;
+use crate::timing::time_submission;
+use crate::Gpu;
+
+// Dispatches per timed submission, and timed submissions per candidate;
+// the fastest submission counts, as the others caught something else
+// running
+const DISPATCHES: u32 = 16;
+const ROUNDS: u32 = 3;
+
+// A compute kernel to tune. Its shader has WORKGROUP_X and WORKGROUP_Y
+// where the workgroup size goes, e.g. @workgroup_size(WORKGROUP_X,
+// WORKGROUP_Y), and is compiled once per candidate size.
+pub struct Kernel<'a> {
+    // Identifies the kernel in the settings file; rename it when the
+    // shader changes enough that an old result shouldn't be trusted
+    pub name: &'a str,
+    pub source: &'a str,
+    pub entry_point: &'a str,
+    pub layout: &'a PipelineLayout,
+    // Dispatched for real while timing, so the kernel has to be fine with
+    // running many times over the same buffers
+    pub bind_group: &'a BindGroup,
+    // How many invocations a dispatch covers, y 1 for a 1D kernel
+    pub invocations: [u32; 2],
+}
+
+pub fn specialize(source: &str, size: [u32; 2]) -> String {
+    source.replace("WORKGROUP_X", &size[0].to_string()).replace("WORKGROUP_Y", &size[1].to_string())
+}
+
+pub fn create_pipeline(device: &Device, kernel: &Kernel, size: [u32; 2]) -> ComputePipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(kernel.name),
+        source: wgpu::ShaderSource::Wgsl(specialize(kernel.source, size).into()),
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(kernel.name),
+        layout: Some(kernel.layout),
+        module: &module,
+        entry_point: kernel.entry_point,
+    })
+}
+
+pub fn workgroups(invocations: [u32; 2], size: [u32; 2]) -> [u32; 2] {
+    [(invocations[0] + size[0] - 1) / size[0], (invocations[1] + size[1] - 1) / size[1]]
+}
+
+// Every power of two size the device allows from 32 invocations up, only
+// x for 1D kernels
+fn candidates(limits: &wgpu::Limits, two_dimensional: bool) -> Vec<[u32; 2]> {
+    let mut sizes = Vec::new();
+    for x in (0..11).map(|shift| 1 << shift) {
+        for y in (0..11).map(|shift| 1 << shift) {
+            if !two_dimensional && y > 1 {
+                break;
+            }
+            let fits = x <= limits.max_compute_workgroup_size_x
+                && y <= limits.max_compute_workgroup_size_y
+                && x * y <= limits.max_compute_invocations_per_workgroup;
+            if fits && x * y >= 32 {
+                sizes.push([x, y]);
+            }
+        }
+    }
+    sizes
+}
+
+fn time(gpu: &Gpu, kernel: &Kernel, pipeline: &ComputePipeline, size: [u32; 2], dispatches: u32) -> Duration {
+    let [x, y] = workgroups(kernel.invocations, size);
+    time_submission(gpu, |encoder| {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Autotune Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, kernel.bind_group, &[]);
+        for _ in 0..dispatches {
+            compute_pass.dispatch_workgroups(x, y, 1);
+        }
+    })
+}
+
+// Times the kernel at every candidate size, see time_submission
+fn benchmark(gpu: &Gpu, kernel: &Kernel) -> [u32; 2] {
+    let mut best = None;
+    for size in candidates(&gpu.device.limits(), kernel.invocations[1] > 1) {
+        let pipeline = create_pipeline(&gpu.device, kernel, size);
+        // The first submission pays for pipeline creation in the driver
+        time(gpu, kernel, &pipeline, size, 1);
+        let fastest = (0..ROUNDS).map(|_| time(gpu, kernel, &pipeline, size, DISPATCHES)).min().unwrap();
+        let faster = match best {
+            Some((_, time)) => fastest < time,
+            None => true,
+        };
+        if faster {
+            best = Some((size, fastest));
+        }
+    }
+    let (size, fastest) = best.unwrap();
+    println!(
+        "{}: fastest workgroup size {}x{}, {:.3}ms per dispatch",
+        kernel.name,
+        size[0],
+        size[1],
+        fastest.as_secs_f64() * 1000.0 / DISPATCHES as f64,
+    );
+    size
+}
+
+// The best workgroup size for the kernel on this adapter, benchmarked the
+// first time and remembered in the settings file after that. --retune
+// benchmarks again regardless.
+pub fn workgroup_size(gpu: &Gpu, kernel: &Kernel) -> [u32; 2] {
+    let info = gpu.adapter.get_info();
+    let adapter = format!("{} ({:?}, {})", info.name, info.backend, info.driver_info);
+    let mut settings = Settings::load();
+    let retune = std::env::args().any(|arg| arg == "--retune");
+    let cached = settings.workgroup_sizes.get(&adapter).and_then(|kernels| kernels.get(kernel.name));
+    // Unless the limits the sample asked for no longer allow it
+    if let Some(&size) = cached {
+        if !retune && candidates(&gpu.device.limits(), kernel.invocations[1] > 1).contains(&size) {
+            return size;
+        }
+    }
+
+    let size = benchmark(gpu, kernel);
+    settings.workgroup_sizes.entry(adapter).or_default().insert(kernel.name.to_string(), size);
+    settings.save();
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workgroups_round_up() {
+        assert_eq!(workgroups([100, 1], [64, 1]), [2, 1]);
+        assert_eq!(workgroups([128, 64], [64, 8]), [2, 8]);
+        assert_eq!(workgroups([1, 1], [256, 1]), [1, 1]);
+        assert_eq!(workgroups([1920, 1080], [16, 16]), [120, 68]);
+    }
+
+    #[test]
+    fn candidates_respect_limits() {
+        let limits = wgpu::Limits {
+            max_compute_workgroup_size_x: 128,
+            max_compute_workgroup_size_y: 8,
+            max_compute_invocations_per_workgroup: 256,
+            ..wgpu::Limits::downlevel_defaults()
+        };
+        let sizes = candidates(&limits, true);
+        assert!(!sizes.is_empty());
+        for [x, y] in &sizes {
+            assert!(*x <= 128 && *y <= 8 && x * y <= 256, "{}x{}", x, y);
+            assert!(x * y >= 32, "{}x{}", x, y);
+        }
+        assert!(sizes.contains(&[32, 8]));
+        assert!(sizes.contains(&[128, 2]));
+        assert!(!sizes.contains(&[64, 8]));
+    }
+
+    #[test]
+    fn one_dimensional_candidates_only_vary_x() {
+        let sizes = candidates(&wgpu::Limits::downlevel_defaults(), false);
+        assert_eq!(sizes, vec![[32, 1], [64, 1], [128, 1], [256, 1]]);
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Frame times of a run, written out as CSV when the sample exits
+pub struct Benchmark {
+    output: PathBuf,
+    frame_times: Vec<f32>,
+}
+
+impl Benchmark {
+    pub fn new(output: PathBuf) -> Self {
+        Self {
+            output,
+            frame_times: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, frame_time: f32) {
+        self.frame_times.push(frame_time);
+    }
+
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    // One row per frame plus a summary on stdout, which is usually all
+    // that's needed to compare two machines
+    pub fn write(&self) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&self.output)?);
+        writeln!(file, "frame,frame_time_ms")?;
+        for (frame, frame_time) in self.frame_times.iter().enumerate() {
+            writeln!(file, "{},{:.3}", frame, frame_time * 1000.0)?;
+        }
+        file.flush()?;
+
+        if self.frame_times.is_empty() {
+            return Ok(());
+        }
+        let mut sorted = self.frame_times.clone();
+        sorted.sort_by(f32::total_cmp);
+        let average = sorted.iter().sum::<f32>() / sorted.len() as f32;
+        let p99 = sorted[(sorted.len() - 1) * 99 / 100];
+        println!(
+            "{} frames, average {:.2}ms, 99th percentile {:.2}ms, wrote {}",
+            sorted.len(),
+            average * 1000.0,
+            p99 * 1000.0,
+            self.output.display(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use glam::{Mat4, Vec3};
+
+// Where a free camera is and which way it looks, as set by --flythrough or
+// a script through Sample::set_camera
+#[derive(Clone, Copy, Debug)]
+pub struct CameraPose {
+    pub position: Vec3,
+    // Radians, yaw 0 looks down -z and pitch 0 at the horizon
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl CameraPose {
+    pub fn direction(&self) -> Vec3 {
+        Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    // For samples whose camera otherwise orbits or sits still, to swap in
+    // for their own view matrix
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_to_rh(self.position, self.direction(), Vec3::Y)
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::gpu::Gpu;
+use crate::readback::read_texture;
+
+// F11 starts and stops writing every presented frame to
+// captures/<sample>-<time>/00000.png, for turning into a video later
+pub struct FrameRecorder {
+    name: String,
+    directory: Option<PathBuf>,
+    frame: u32,
+}
+
+impl FrameRecorder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            directory: None,
+            frame: 0,
+        }
+    }
+
+    // Returns true for events that shouldn't reach the sample as well
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::F11),
+                    ..
+                },
+                ..
+            } => {
+                match self.directory.take() {
+                    Some(directory) => println!("wrote {} frames to {}", self.frame, directory.display()),
+                    None => self.start(),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn start(&mut self) {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let directory = PathBuf::from("captures").join(format!("{}-{}", self.name, seconds));
+        if let Err(error) = std::fs::create_dir_all(&directory) {
+            eprintln!("capture: {}: {}", directory.display(), error);
+            return;
+        }
+        println!("recording to {}, F11 stops", directory.display());
+        self.directory = Some(directory);
+        self.frame = 0;
+    }
+
+    // Whether frames should be rendered somewhere they can be read back
+    // from, see ReadableFrame
+    pub fn recording(&self) -> bool {
+        self.directory.is_some()
+    }
+
+    // Call after the sample rendered into `frame`, which needs COPY_SRC
+    pub fn record(&mut self, gpu: &Gpu, frame: &wgpu::Texture) {
+        let directory = match &self.directory {
+            Some(directory) => directory,
+            None => return,
+        };
+        let size = [frame.width(), frame.height()];
+        let pixels = match read_texture(gpu, frame, [0, 0], size) {
+            Some(pixels) => pixels,
+            None => {
+                eprintln!("capture: can't convert {:?} to RGBA8", frame.format());
+                self.directory = None;
+                return;
+            }
+        };
+
+        let path = directory.join(format!("{:05}.png", self.frame));
+        if let Err(error) = image::save_buffer(&path, &pixels, size[0], size[1], image::ColorType::Rgba8) {
+            eprintln!("capture: {}: {}", path.display(), error);
+            self.directory = None;
+            return;
+        }
+        self.frame += 1;
+    }
+}
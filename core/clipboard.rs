@@ -0,0 +1,116 @@
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::gpu::Gpu;
+use crate::readback::read_texture;
+
+// Shift + drag selects a rectangle of the window, F12 copies it to the
+// clipboard, or the whole frame when nothing is selected
+#[derive(Default)]
+pub struct ClipboardCopy {
+    cursor: [f64; 2],
+    shift: bool,
+    drag_start: Option<[f64; 2]>,
+    // Corners in physical pixels, not yet clamped to the frame
+    selection: Option<([f64; 2], [f64; 2])>,
+    requested: bool,
+}
+
+impl ClipboardCopy {
+    // Returns true for events that shouldn't reach the sample as well
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.shift = modifiers.shift();
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = [position.x, position.y];
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.shift => {
+                self.drag_start = Some(self.cursor);
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } if self.drag_start.is_some() => {
+                let start = self.drag_start.take().unwrap();
+                self.selection = Some((start, self.cursor));
+                println!(
+                    "selected {:.0}x{:.0}, F12 copies it",
+                    (self.cursor[0] - start[0]).abs(),
+                    (self.cursor[1] - start[1]).abs(),
+                );
+                true
+            }
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::F12),
+                    ..
+                },
+                ..
+            } => {
+                self.requested = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Whether the next frame should be rendered somewhere it can be read
+    // back from, see ReadableFrame
+    pub fn requested(&self) -> bool {
+        self.requested
+    }
+
+    // Call after the sample rendered into `frame`, which needs COPY_SRC
+    pub fn copy_if_requested(&mut self, gpu: &Gpu, frame: &wgpu::Texture) {
+        if !std::mem::take(&mut self.requested) {
+            return;
+        }
+
+        let (origin, size) = match self.selection.take() {
+            Some((a, b)) => {
+                let clamp = |value: f64, max: u32| (value.max(0.0) as u32).min(max);
+                let min = [clamp(a[0].min(b[0]), frame.width()), clamp(a[1].min(b[1]), frame.height())];
+                let max = [clamp(a[0].max(b[0]), frame.width()), clamp(a[1].max(b[1]), frame.height())];
+                (min, [max[0] - min[0], max[1] - min[1]])
+            }
+            None => ([0, 0], [frame.width(), frame.height()]),
+        };
+        if size[0] == 0 || size[1] == 0 {
+            eprintln!("clipboard: empty selection");
+            return;
+        }
+
+        match read_texture(gpu, frame, origin, size) {
+            Some(pixels) => set_clipboard_image(size, pixels),
+            None => eprintln!("clipboard: can't convert {:?} to RGBA8", frame.format()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_clipboard_image(size: [u32; 2], pixels: Vec<u8>) {
+    let image = arboard::ImageData {
+        width: size[0] as usize,
+        height: size[1] as usize,
+        bytes: pixels.into(),
+    };
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_image(image)) {
+        Ok(()) => println!("copied {}x{} to the clipboard", size[0], size[1]),
+        Err(error) => eprintln!("clipboard: {}", error),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn set_clipboard_image(_size: [u32; 2], _pixels: Vec<u8>) {
+    eprintln!("clipboard: not available on the web");
+}
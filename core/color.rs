@@ -0,0 +1,193 @@
+use glam::{Mat3, Vec3};
+use wgpu::TextureFormat;
+
+// Keeping track of which space colors are in, so they get decoded and
+// encoded exactly once. The double gamma bugs this avoids:
+// - sRGB pixel data uploaded as Rgba8Unorm and used as if linear, or
+//   uploaded as Rgba8UnormSrgb and decoded again in the shader
+// - a shader encoding its output for a surface that already encodes,
+//   or not encoding it for one that doesn't
+//
+// Shaders work in linear sRGB. wgpu 0.16 can't ask for a wide gamut
+// surface, so Display P3 content is converted to sRGB for display and
+// anything outside sRGB clips.
+
+// Defines srgb_to_linear, linear_to_srgb, the Display P3 conversions and
+// encode_for_target, paste it in front of the shader
+pub const WGSL: &str = include_str!("shaders/color.wgsl");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    // sRGB primaries and transfer curve, what 8 bit images usually are
+    Srgb,
+    LinearSrgb,
+    // Display P3 primaries with the sRGB curve, what phone photos often are
+    DisplayP3,
+    LinearDisplayP3,
+}
+
+impl ColorSpace {
+    pub fn is_linear(self) -> bool {
+        matches!(self, ColorSpace::LinearSrgb | ColorSpace::LinearDisplayP3)
+    }
+
+    pub fn to_linear_srgb(self, color: Vec3) -> Vec3 {
+        match self {
+            ColorSpace::Srgb => color.to_array().map(srgb_to_linear).into(),
+            ColorSpace::LinearSrgb => color,
+            ColorSpace::DisplayP3 => p3_to_srgb() * Vec3::from(color.to_array().map(srgb_to_linear)),
+            ColorSpace::LinearDisplayP3 => p3_to_srgb() * color,
+        }
+    }
+
+    pub fn from_linear_srgb(self, color: Vec3) -> Vec3 {
+        match self {
+            ColorSpace::Srgb => color.to_array().map(linear_to_srgb).into(),
+            ColorSpace::LinearSrgb => color,
+            ColorSpace::DisplayP3 => (srgb_to_p3() * color).to_array().map(linear_to_srgb).into(),
+            ColorSpace::LinearDisplayP3 => srgb_to_p3() * color,
+        }
+    }
+
+    // The format to upload 8 bit pixels in this space as, so that sampling
+    // them gives linear values; P3 still needs linear_p3_to_linear_srgb
+    pub fn texture_format(self) -> TextureFormat {
+        if self.is_linear() {
+            TextureFormat::Rgba8Unorm
+        } else {
+            TextureFormat::Rgba8UnormSrgb
+        }
+    }
+}
+
+// What a format does with the linear values a shader writes to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    // The *Srgb formats encode on write and decode on read
+    Hardware,
+    // Other unorm formats store values as they are, so the shader encodes
+    Shader,
+    // Float formats, linear all the way
+    Linear,
+}
+
+impl Encoding {
+    pub fn of(format: TextureFormat) -> Self {
+        if format.is_srgb() {
+            return Encoding::Hardware;
+        }
+        match format {
+            TextureFormat::R16Float
+            | TextureFormat::Rg16Float
+            | TextureFormat::Rgba16Float
+            | TextureFormat::R32Float
+            | TextureFormat::Rg32Float
+            | TextureFormat::Rgba32Float
+            | TextureFormat::Rg11b10Float
+            | TextureFormat::Rgb9e5Ufloat => Encoding::Linear,
+            _ => Encoding::Shader,
+        }
+    }
+
+    // For encode_for_target in WGSL
+    pub fn shader_value(self) -> u32 {
+        (self == Encoding::Shader) as u32
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Same matrices as color.wgsl, column major. The digits are kept as they
+// are there rather than rounded to what f32 holds, so the two stay easy to
+// compare.
+#[allow(clippy::excessive_precision)]
+fn p3_to_srgb() -> Mat3 {
+    Mat3::from_cols_array(&[
+        1.224940, -0.042057, -0.019638,
+        -0.224940, 1.042057, -0.078636,
+        0.0, 0.0, 1.098273,
+    ])
+}
+
+#[allow(clippy::excessive_precision)]
+fn srgb_to_p3() -> Mat3 {
+    Mat3::from_cols_array(&[
+        0.822462, 0.033194, 0.017083,
+        0.177538, 0.966806, 0.072397,
+        0.0, 0.0, 0.910520,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3, expected: Vec3, tolerance: f32) {
+        assert!(
+            (actual - expected).abs().max_element() <= tolerance,
+            "{} isn't within {} of {}",
+            actual,
+            tolerance,
+            expected,
+        );
+    }
+
+    #[test]
+    fn transfer_curve_matches_reference_values() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.214041).abs() < 1e-5);
+        assert!((linear_to_srgb(0.5) - 0.735357).abs() < 1e-5);
+        // The two pieces meet at the threshold
+        assert!((srgb_to_linear(0.04045) - 0.0031308).abs() < 1e-6);
+    }
+
+    #[test]
+    fn transfer_curve_round_trips() {
+        for i in 0..=255 {
+            let value = i as f32 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(value)) - value).abs() < 1e-5, "{}", value);
+        }
+    }
+
+    #[test]
+    fn display_p3_matches_reference_values() {
+        // P3's primaries in linear sRGB, outside sRGB's gamut
+        assert_close(ColorSpace::LinearDisplayP3.to_linear_srgb(Vec3::X), Vec3::new(1.22494, -0.042057, -0.019638), 1e-6);
+        assert_close(ColorSpace::LinearDisplayP3.to_linear_srgb(Vec3::Y), Vec3::new(-0.22494, 1.042057, -0.078636), 1e-6);
+        // sRGB red as CSS writes it in display-p3
+        assert_close(ColorSpace::DisplayP3.from_linear_srgb(Vec3::X), Vec3::new(0.9175, 0.2003, 0.1386), 1e-3);
+        // Both share a white point
+        assert_close(ColorSpace::DisplayP3.to_linear_srgb(Vec3::ONE), Vec3::ONE, 1e-5);
+    }
+
+    #[test]
+    fn every_space_round_trips() {
+        let spaces = [ColorSpace::Srgb, ColorSpace::LinearSrgb, ColorSpace::DisplayP3, ColorSpace::LinearDisplayP3];
+        let steps = [0.0, 0.02, 0.25, 0.5, 0.75, 1.0];
+        for space in spaces {
+            for r in steps {
+                for g in steps {
+                    for b in steps {
+                        let color = Vec3::new(r, g, b);
+                        assert_close(space.from_linear_srgb(space.to_linear_srgb(color)), color, 1e-4);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use wgpu::{Device, TextureView};
+
+// Soft particles: particles read the opaque scene's depth and fade out as
+// they get close to it, instead of cutting a hard line where a quad goes
+// through the floor. The usual ways of getting this wrong, which the
+// helpers here are shaped to avoid:
+// - a depth texture created without TEXTURE_BINDING
+// - the opaque pass not storing depth, so the particles read garbage
+// - the particle pass writing depth to the texture it's also reading
+// - binding it as a filterable float texture, depth isn't filterable
+// - binding it as a depth texture, which GL can't textureLoad from; as an
+//   unfilterable float texture every backend can
+// - multisampled depth, which needs texture_depth_multisampled_2d and a
+//   per-sample read; not covered here
+
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// Defines `depth_fade` and `linearize_depth`, paste it in front of the
+// particle shader
+pub const WGSL: &str = include_str!("shaders/depth_fade.wgsl");
+
+// Only the view is kept, it holds on to its texture
+pub struct SceneDepth {
+    view: TextureView,
+}
+
+impl SceneDepth {
+    pub fn new(device: &Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Scene Depth"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { view }
+    }
+
+    // For the opaque pass: cleared, written and stored for the particles
+    pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: true,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    // For the particle pass: depth tested but never written, which is what
+    // lets the same texture be bound for reading in that pass. The
+    // pipeline needs depth_write_enabled: false to match.
+    pub fn read_only_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: None,
+            stencil_ops: None,
+        }
+    }
+
+    pub fn layout_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+        wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }
+    }
+
+    // Read with textureLoad, so no sampler goes with it
+    pub fn binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::TextureView(&self.view)
+    }
+}
+
+// Depth state for particle pipelines drawn with `read_only_attachment`
+pub fn particle_depth_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Less,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
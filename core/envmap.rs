@@ -0,0 +1,349 @@
+use std::borrow::Cow;
+
+use glam::{Mat4, Quat};
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroupLayout, CommandEncoder, ComputePipeline, Device, Sampler, Texture, TextureView};
+
+// Every cubemap and equirect these utilities create. HDR, filterable and
+// usable as a storage texture everywhere compute runs.
+pub const ENVIRONMENT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+// A cubemap with room for a full mip chain down to 1x1
+pub fn create_cubemap(device: &Device, face_size: u32, label: &str) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: face_size,
+            height: face_size,
+            depth_or_array_layers: 6,
+        },
+        mip_level_count: face_size.ilog2() + 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: ENVIRONMENT_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+// All of a cubemap's mips, for sampling it in a shader
+pub fn cube_view(cubemap: &Texture) -> TextureView {
+    cubemap.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        ..Default::default()
+    })
+}
+
+fn cube_mip_view(cubemap: &Texture, mip: u32) -> TextureView {
+    cubemap.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        ..Default::default()
+    })
+}
+
+// The six faces of one mip as an array, which is how compute writes them
+fn face_array_view(cubemap: &Texture, mip: u32) -> TextureView {
+    cubemap.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        ..Default::default()
+    })
+}
+
+// A compute pipeline and the layout its one bind group uses: params,
+// source, sampler, destination
+struct Pass {
+    pipeline: ComputePipeline,
+    layout: BindGroupLayout,
+}
+
+impl Pass {
+    fn new(
+        device: &Device,
+        label: &str,
+        source: &str,
+        entry_point: &str,
+        source_dimension: wgpu::TextureViewDimension,
+        destination_dimension: wgpu::TextureViewDimension,
+    ) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: source_dimension,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: ENVIRONMENT_FORMAT,
+                        view_dimension: destination_dimension,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point,
+        });
+
+        Self { pipeline, layout }
+    }
+}
+
+// Converts between equirectangular images and cubemaps, rotates cubemaps
+// and fills in their mip chains, all on the GPU. Everything is recorded
+// into the caller's encoder, nothing has run until it's submitted.
+pub struct EnvironmentTools {
+    sampler: Sampler,
+    equirect_to_cube: Pass,
+    cube_to_equirect: Pass,
+    rotate: Pass,
+    downsample: Pass,
+}
+
+impl EnvironmentTools {
+    pub fn new(device: &Device) -> Self {
+        // Repeat horizontally for the equirect's longitude wrap; cubes
+        // ignore address modes and filter across faces by themselves
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Environment Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        // WGSL has no includes, so the shared helpers are pasted in front
+        let equirect_to_cube = Pass::new(
+            device,
+            "Equirect To Cube",
+            concat!(include_str!("shaders/cube_common.wgsl"), include_str!("shaders/equirect_to_cube.wgsl")),
+            "cs_main",
+            wgpu::TextureViewDimension::D2,
+            wgpu::TextureViewDimension::D2Array,
+        );
+        let cube_to_equirect = Pass::new(
+            device,
+            "Cube To Equirect",
+            concat!(include_str!("shaders/cube_common.wgsl"), include_str!("shaders/cube_to_equirect.wgsl")),
+            "cs_main",
+            wgpu::TextureViewDimension::Cube,
+            wgpu::TextureViewDimension::D2,
+        );
+        let filter_source = concat!(include_str!("shaders/cube_common.wgsl"), include_str!("shaders/cube_filter.wgsl"));
+        let rotate = Pass::new(
+            device,
+            "Rotate Cube",
+            filter_source,
+            "cs_rotate",
+            wgpu::TextureViewDimension::Cube,
+            wgpu::TextureViewDimension::D2Array,
+        );
+        let downsample = Pass::new(
+            device,
+            "Downsample Cube",
+            filter_source,
+            "cs_downsample",
+            wgpu::TextureViewDimension::Cube,
+            wgpu::TextureViewDimension::D2Array,
+        );
+
+        Self {
+            sampler,
+            equirect_to_cube,
+            cube_to_equirect,
+            rotate,
+            downsample,
+        }
+    }
+
+    // Only fills mip 0, call generate_mips for the rest. `rotation` turns
+    // the environment, e.g. to move the sun somewhere else.
+    pub fn equirect_to_cubemap(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        equirect: &TextureView,
+        face_size: u32,
+        rotation: Quat,
+    ) -> Texture {
+        let cubemap = create_cubemap(device, face_size, "Environment Cubemap");
+        self.dispatch(
+            device,
+            encoder,
+            &self.equirect_to_cube,
+            rotation,
+            equirect,
+            &face_array_view(&cubemap, 0),
+            [face_size, face_size, 6],
+        );
+        cubemap
+    }
+
+    // Twice as wide as high, from the cubemap's top mip
+    pub fn cubemap_to_equirect(&self, device: &Device, encoder: &mut CommandEncoder, cubemap: &Texture, width: u32) -> Texture {
+        let equirect = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Environment Equirect"),
+            size: wgpu::Extent3d {
+                width,
+                height: width / 2,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ENVIRONMENT_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        self.dispatch(
+            device,
+            encoder,
+            &self.cube_to_equirect,
+            Quat::IDENTITY,
+            &cube_mip_view(cubemap, 0),
+            &equirect.create_view(&wgpu::TextureViewDescriptor::default()),
+            [width, width / 2, 1],
+        );
+        equirect
+    }
+
+    // A rotated copy of the cubemap's top mip, call generate_mips for the rest
+    pub fn rotate_cubemap(&self, device: &Device, encoder: &mut CommandEncoder, cubemap: &Texture, rotation: Quat) -> Texture {
+        let face_size = cubemap.width();
+        let rotated = create_cubemap(device, face_size, "Rotated Cubemap");
+        self.dispatch(
+            device,
+            encoder,
+            &self.rotate,
+            rotation,
+            &cube_mip_view(cubemap, 0),
+            &face_array_view(&rotated, 0),
+            [face_size, face_size, 6],
+        );
+        rotated
+    }
+
+    // Each mip is filtered from the one above it. Reading mip n - 1 while
+    // writing mip n of the same texture is fine, the two views don't overlap.
+    pub fn generate_mips(&self, device: &Device, encoder: &mut CommandEncoder, cubemap: &Texture) {
+        for mip in 1..cubemap.mip_level_count() {
+            let size = (cubemap.width() >> mip).max(1);
+            self.dispatch(
+                device,
+                encoder,
+                &self.downsample,
+                Quat::IDENTITY,
+                &cube_mip_view(cubemap, mip - 1),
+                &face_array_view(cubemap, mip),
+                [size, size, 6],
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn dispatch(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        pass: &Pass,
+        rotation: Quat,
+        source: &TextureView,
+        destination: &TextureView,
+        size: [u32; 3],
+    ) {
+        // The shaders look up the source along rotation * direction, so
+        // turning the result by `rotation` means looking up by its inverse
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Environment Params"),
+            contents: bytemuck::cast_slice(&Mat4::from_quat(rotation.inverse()).to_cols_array()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Environment Bind Group"),
+            layout: &pass.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(destination),
+                },
+            ],
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Environment Pass"),
+        });
+        compute_pass.set_pipeline(&pass.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            (size[0] + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            (size[1] + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            size[2],
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use glam::Vec3;
+use serde::Deserialize;
+
+use crate::animation::{Easing, Interpolate, Keyframe, Playback, Timeline, Track};
+use crate::camera::CameraPose;
+
+// One entry of the script:
+// { "time": 2.5, "position": [0.0, 12.0, 0.0], "yaw": 0.0, "pitch": -0.3 }
+#[derive(Deserialize)]
+struct PoseKeyframe {
+    time: f32,
+    position: [f32; 3],
+    yaw: f32,
+    pitch: f32,
+}
+
+// A camera path replayed once from start to end, so every run covers the
+// exact same views
+pub struct Flythrough {
+    timeline: Timeline,
+    position: Track<Vec3>,
+    yaw: Track<f32>,
+    pitch: Track<f32>,
+}
+
+impl Flythrough {
+    // The error says what's wrong with the file, not which file it is
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let keyframes: Vec<PoseKeyframe> = serde_json::from_str(&json).map_err(|error| error.to_string())?;
+        if keyframes.is_empty() {
+            return Err("no keyframes".to_string());
+        }
+
+        let position = track(&keyframes, |keyframe| Vec3::from(keyframe.position));
+
+        Ok(Self {
+            timeline: Timeline::new(position.duration(), Playback::Once),
+            position,
+            yaw: track(&keyframes, |keyframe| keyframe.yaw),
+            pitch: track(&keyframes, |keyframe| keyframe.pitch),
+        })
+    }
+
+    pub fn advance(&mut self, delta_time: f32) -> CameraPose {
+        self.timeline.advance(delta_time);
+        let time = self.timeline.time();
+        CameraPose {
+            position: self.position.sample(time),
+            yaw: self.yaw.sample(time),
+            pitch: self.pitch.sample(time),
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.timeline.finished()
+    }
+}
+
+// Poses are sampled linearly, the script is expected to be dense enough
+// for that to look smooth
+fn track<T: Interpolate>(keyframes: &[PoseKeyframe], value: impl Fn(&PoseKeyframe) -> T) -> Track<T> {
+    Track::new(
+        keyframes
+            .iter()
+            .map(|keyframe| Keyframe::new(keyframe.time, value(keyframe), Easing::Linear))
+            .collect(),
+    )
+}
@@ -0,0 +1,85 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Which passes of a frame read and wrote which resources, in the order they
+// were recorded. Recorded by whatever schedules the passes, e.g. PostChain,
+// and shown by --dump-graph and the overlay.
+#[derive(Clone, Debug, Default)]
+pub struct FrameGraph {
+    pub passes: Vec<PassNode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PassNode {
+    pub name: String,
+    pub reads: Vec<String>,
+    pub writes: Vec<String>,
+}
+
+impl FrameGraph {
+    pub fn clear(&mut self) {
+        self.passes.clear();
+    }
+
+    pub fn add_pass(&mut self, name: &str, reads: &[&str], writes: &[&str]) {
+        self.passes.push(PassNode {
+            name: name.to_string(),
+            reads: reads.iter().map(|read| read.to_string()).collect(),
+            writes: writes.iter().map(|write| write.to_string()).collect(),
+        });
+    }
+
+    // For every pass, the earlier passes whose writes it reads, with the
+    // resource in between; what the overlay draws as arrows
+    pub fn dependencies(&self) -> Vec<(usize, usize, &str)> {
+        let mut dependencies = Vec::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for read in &pass.reads {
+                let writer = self.passes[..index].iter().rposition(|other| other.writes.contains(read));
+                if let Some(writer) = writer {
+                    dependencies.push((writer, index, read.as_str()));
+                }
+            }
+        }
+        dependencies
+    }
+
+    // Graphviz: passes as boxes, resources as ellipses. A resource gets a
+    // node per write, numbered, so ping-ponged targets don't turn the graph
+    // into loops.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph frame {\n    rankdir=LR;\n    node [fontname=\"sans-serif\"];\n");
+        let mut versions: Vec<(&str, usize)> = Vec::new();
+        let resource_node = |versions: &Vec<(&str, usize)>, name: &str| {
+            let version = versions.iter().find(|(other, _)| *other == name).map_or(0, |(_, version)| *version);
+            format!("\"{}#{}\"", name, version)
+        };
+
+        for (index, pass) in self.passes.iter().enumerate() {
+            let _ = writeln!(dot, "    pass{} [shape=box, style=filled, fillcolor=\"#dde8f8\", label=\"{}\"];", index, pass.name);
+            for read in &pass.reads {
+                let node = resource_node(&versions, read);
+                let _ = writeln!(dot, "    {} [shape=ellipse, label=\"{}\"];", node, read);
+                let _ = writeln!(dot, "    {} -> pass{};", node, index);
+            }
+            for write in &pass.writes {
+                match versions.iter_mut().find(|(other, _)| *other == write.as_str()) {
+                    Some((_, version)) => *version += 1,
+                    None => versions.push((write, 1)),
+                }
+                let node = resource_node(&versions, write);
+                let _ = writeln!(dot, "    {} [shape=ellipse, label=\"{}\"];", node, write);
+                let _ = writeln!(dot, "    pass{} -> {};", index, node);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn write_dot(&self, path: &Path) {
+        match std::fs::write(path, self.to_dot()) {
+            Ok(()) => println!("wrote the frame graph to {}, e.g. dot -Tsvg -O {}", path.display(), path.display()),
+            Err(error) => eprintln!("{}: {}", path.display(), error),
+        }
+    }
+}
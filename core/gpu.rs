@@ -0,0 +1,207 @@
+use wgpu::{InstanceDescriptor, Instance};
+use winit::window::Window;
+
+use crate::quirks::Quirks;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceCounts {
+    pub buffers: usize,
+    pub textures: usize,
+    pub texture_views: usize,
+    pub samplers: usize,
+    pub bind_groups: usize,
+    pub bind_group_layouts: usize,
+    pub pipeline_layouts: usize,
+    pub shader_modules: usize,
+    pub render_pipelines: usize,
+    pub compute_pipelines: usize,
+    pub render_bundles: usize,
+    pub query_sets: usize,
+}
+
+// Everything a sample needs to talk to the GPU and present to the window
+pub struct Gpu {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    // None when rendering headless
+    pub surface: Option<wgpu::Surface>,
+    pub config: wgpu::SurfaceConfiguration,
+    // Workarounds applied for this backend and driver
+    pub quirks: Quirks,
+}
+
+impl Gpu {
+    // `optional_features` are enabled when the adapter has them; check
+    // device.features() before relying on one
+    pub async fn init(window: &Window, features: wgpu::Features, optional_features: wgpu::Features, limits: wgpu::Limits) -> Self {
+        let instance = create_instance();
+
+        let surface = unsafe { instance.create_surface(window) }.unwrap();
+
+        let (adapter, device, queue) = request_device(&instance, Some(&surface), features, optional_features, limits).await;
+
+        let quirks = Quirks::new(adapter.get_info().backend);
+        let capabilities = surface.get_capabilities(&adapter);
+        let size = window.inner_size();
+        let mut config = surface.get_default_config(&adapter, size.width, size.height).unwrap();
+        config.format = quirks.surface_format(&capabilities.formats);
+        surface.configure(&device, &config);
+
+        Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            surface: Some(surface),
+            config,
+            quirks,
+        }
+    }
+
+    // No window and no surface; the config only describes the offscreen
+    // target so samples can size and format their pipelines the same way
+    pub async fn headless(
+        width: u32,
+        height: u32,
+        features: wgpu::Features,
+        optional_features: wgpu::Features,
+        limits: wgpu::Limits,
+    ) -> Self {
+        let instance = create_instance();
+
+        let (adapter, device, queue) = request_device(&instance, None, features, optional_features, limits).await;
+
+        let quirks = Quirks::new(adapter.get_info().backend);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            surface: None,
+            config,
+            quirks,
+        }
+    }
+
+    pub fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        winit::dpi::PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.config.width as f32 / self.config.height as f32
+    }
+
+    // The MSAA sample count to use for a color format, see Quirks::sample_count
+    pub fn sample_count(&self, format: wgpu::TextureFormat, requested: u32) -> u32 {
+        self.quirks.sample_count(&self.adapter, format, requested)
+    }
+
+    // Returns false for sizes the surface can't take, e.g. while minimized
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) -> bool {
+        if new_size.width == 0 || new_size.height == 0 {
+            return false;
+        }
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        self.reconfigure();
+        true
+    }
+
+    // Live objects as counted by wgpu-core, for catching leaks. None on
+    // the web, where the browser owns them and doesn't tell.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resource_counts(&self) -> Option<ResourceCounts> {
+        let report = self.instance.generate_report();
+        // Reported per backend, and only for the backends wgpu was built
+        // with on this platform
+        let hub = match self.adapter.get_info().backend {
+            #[cfg(any(windows, all(unix, not(any(target_os = "macos", target_os = "ios")))))]
+            wgpu::Backend::Vulkan => report.vulkan,
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            wgpu::Backend::Metal => report.metal,
+            #[cfg(windows)]
+            wgpu::Backend::Dx12 => report.dx12,
+            #[cfg(windows)]
+            wgpu::Backend::Dx11 => report.dx11,
+            wgpu::Backend::Gl => report.gl,
+            _ => None,
+        }?;
+        Some(ResourceCounts {
+            buffers: hub.buffers.num_occupied,
+            textures: hub.textures.num_occupied,
+            texture_views: hub.texture_views.num_occupied,
+            samplers: hub.samplers.num_occupied,
+            bind_groups: hub.bind_groups.num_occupied,
+            bind_group_layouts: hub.bind_group_layouts.num_occupied,
+            pipeline_layouts: hub.pipeline_layouts.num_occupied,
+            shader_modules: hub.shader_modules.num_occupied,
+            render_pipelines: hub.render_pipelines.num_occupied,
+            compute_pipelines: hub.compute_pipelines.num_occupied,
+            render_bundles: hub.render_bundles.num_occupied,
+            query_sets: hub.query_sets.num_occupied,
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn resource_counts(&self) -> Option<ResourceCounts> {
+        None
+    }
+
+    // For lost or outdated surfaces: same size, fresh swapchain
+    pub fn reconfigure(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+}
+
+// WGPU_BACKEND=vulkan, dx12, metal or gl limits it to one backend, e.g. for
+// comparing them on the same machine
+fn create_instance() -> Instance {
+    Instance::new(InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+        ..Default::default()
+    })
+}
+
+async fn request_device(
+    instance: &Instance,
+    compatible_surface: Option<&wgpu::Surface>,
+    features: wgpu::Features,
+    optional_features: wgpu::Features,
+    limits: wgpu::Limits,
+) -> (wgpu::Adapter, wgpu::Device, wgpu::Queue) {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference:
+                wgpu::PowerPreference::default(),
+            compatible_surface,
+            force_fallback_adapter: false,
+        })
+        .await
+        .unwrap();
+
+    let features = features | (adapter.features() & optional_features);
+    let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features,
+                limits,
+                label: None,
+            },
+            None, // Trace path
+        ).await.unwrap();
+
+    (adapter, device, queue)
+}
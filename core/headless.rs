@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use crate::gpu::Gpu;
+use crate::readback::read_texture;
+use crate::sample::Sample;
+
+// --headless [--output frame.png] [--size 800x600] [--frames 1]
+pub struct HeadlessOptions {
+    pub output: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    // Samples that converge over time (e.g. progressive-bake) need more
+    // than one frame before the capture is worth looking at
+    pub frames: u32,
+}
+
+impl HeadlessOptions {
+    // None without --headless, an error for values that don't parse
+    pub fn from_args() -> Result<Option<Self>, String> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        Self::parse(&args)
+    }
+
+    fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        if !args.iter().any(|arg| arg == "--headless") {
+            return Ok(None);
+        }
+
+        let mut options = Self {
+            output: PathBuf::from("frame.png"),
+            width: 800,
+            height: 600,
+            frames: 1,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !matches!(arg.as_str(), "--output" | "--size" | "--frames") {
+                continue;
+            }
+            let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+            match arg.as_str() {
+                "--output" => options.output = PathBuf::from(value),
+                "--size" => (options.width, options.height) = parse_size(value)?,
+                _ => options.frames = value.parse().map_err(|_| format!("--frames {}: not a number of frames", value))?,
+            }
+        }
+        Ok(Some(options))
+    }
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let error = || format!("--size {}: expected WIDTHxHEIGHT, e.g. 800x600", value);
+    let (width, height) = value.split_once('x').ok_or_else(error)?;
+    let width: u32 = width.parse().map_err(|_| error())?;
+    let height: u32 = height.parse().map_err(|_| error())?;
+    if width == 0 || height == 0 {
+        return Err(format!("--size {}: both sides have to be at least 1", value));
+    }
+    Ok((width, height))
+}
+
+// Renders into an offscreen texture instead of a surface and writes the
+// last frame out as a PNG
+pub fn run_headless<S: Sample>(title: &str, options: &HeadlessOptions) {
+    let gpu = async_std::task::block_on(Gpu::headless(
+        options.width,
+        options.height,
+        S::required_features(),
+        S::optional_features(),
+        S::required_limits(),
+    ));
+    // Only known once there's a device
+    let max_size = gpu.device.limits().max_texture_dimension_2d;
+    if options.width > max_size || options.height > max_size {
+        eprintln!("{}: --size {}x{} is larger than this adapter's {} texels", title, options.width, options.height, max_size);
+        std::process::exit(1);
+    }
+    let mut sample = S::init(&gpu);
+
+    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Headless Target"),
+        size: wgpu::Extent3d {
+            width: options.width,
+            height: options.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: gpu.config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    for _ in 0..options.frames.max(1) {
+        sample.render(&gpu, &view);
+    }
+
+    let pixels = read_texture(&gpu, &texture, [0, 0], [options.width, options.height]).unwrap_or_else(|| {
+        eprintln!("{}: can't read back {:?} frames", title, gpu.config.format);
+        std::process::exit(1);
+    });
+    if let Err(error) = image::save_buffer(
+        &options.output,
+        &pixels,
+        options.width,
+        options.height,
+        image::ColorType::Rgba8,
+    ) {
+        eprintln!("{}: {}", options.output.display(), error);
+        std::process::exit(1);
+    }
+
+    match sample.status() {
+        Some(status) => println!("{} - {}: wrote {}", title, status, options.output.display()),
+        None => println!("{}: wrote {}", title, options.output.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<HeadlessOptions>, String> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        HeadlessOptions::parse(&args)
+    }
+
+    #[test]
+    fn options_parse() {
+        assert!(parse("--size 640x480").unwrap().is_none());
+        let options = parse("--headless --size 640x480 --frames 3 --output out.png").unwrap().unwrap();
+        assert_eq!((options.width, options.height, options.frames), (640, 480, 3));
+        assert_eq!(options.output, PathBuf::from("out.png"));
+    }
+
+    #[test]
+    fn bad_options_are_errors() {
+        for args in ["--headless --size 0x0", "--headless --size 640", "--headless --size axb", "--headless --frames many", "--headless --size"] {
+            assert!(parse(args).is_err(), "{}", args);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+pub mod animation;
+#[cfg(feature = "assets")]
+pub mod assets;
+#[cfg(feature = "json")]
+pub mod autotune;
+mod benchmark;
+mod camera;
+#[cfg(feature = "capture")]
+mod capture;
+#[cfg(feature = "capture")]
+mod clipboard;
+pub mod color;
+pub mod depth_fade;
+pub mod envmap;
+#[cfg(feature = "json")]
+mod flythrough;
+pub mod framegraph;
+pub mod font;
+mod gpu;
+#[cfg(feature = "png")]
+mod headless;
+#[cfg(feature = "tracing")]
+mod logging;
+#[cfg(feature = "egui-overlay")]
+mod overlay;
+mod pacing;
+pub mod post;
+pub mod profiling;
+pub mod quirks;
+#[cfg(feature = "png")]
+mod readback;
+mod sample;
+mod scene;
+#[cfg(feature = "json")]
+mod settings;
+pub mod snapshot;
+pub mod timing;
+pub mod upload;
+
+pub use camera::CameraPose;
+pub use gpu::{Gpu, ResourceCounts};
+#[cfg(feature = "png")]
+pub use headless::{run_headless, HeadlessOptions};
+pub use sample::{run_sample, Sample};
+pub use scene::{scene, Scene, SceneFactory, SceneStack};
+
+// So samples implementing Sample::ui use the same egui as the overlay
+#[cfg(feature = "egui-overlay")]
+pub use egui;
@@ -0,0 +1,12 @@
+use tracing_subscriber::EnvFilter;
+
+// Prints wgpu's log records and the framework's spans to stderr. RUST_LOG
+// picks what, e.g. RUST_LOG=wgpu_core=info or RUST_LOG=framework=trace for
+// every profile scope.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
@@ -0,0 +1,181 @@
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::Window;
+
+use crate::framegraph::FrameGraph;
+use crate::gpu::Gpu;
+use crate::profiling;
+use crate::sample::Sample;
+
+// An egui window drawn over the sample with the frame time, the profile
+// scopes and whatever the sample adds in Sample::ui. F1 hides it.
+pub struct Overlay {
+    context: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    visible: bool,
+    // Smoothed, the raw value is too jumpy to read
+    frame_time: f32,
+}
+
+impl Overlay {
+    pub fn new<T>(event_loop: &EventLoopWindowTarget<T>, window: &Window, gpu: &Gpu) -> Self {
+        let mut state = egui_winit::State::new(event_loop);
+        state.set_pixels_per_point(window.scale_factor() as f32);
+        Self {
+            context: egui::Context::default(),
+            state,
+            renderer: egui_wgpu::Renderer::new(&gpu.device, gpu.config.format, None, 1),
+            visible: true,
+            frame_time: 0.0,
+        }
+    }
+
+    // Returns true for events that shouldn't reach the sample as well
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::F1),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.visible = !self.visible;
+            return true;
+        }
+        if !self.visible {
+            return false;
+        }
+        self.state.on_event(&self.context, event).consumed
+    }
+
+    // Call after the sample rendered into `view`, draws on top of it
+    pub fn draw<S: Sample>(&mut self, gpu: &Gpu, window: &Window, view: &wgpu::TextureView, sample: &mut S, frame_time: f32) {
+        self.frame_time += (frame_time - self.frame_time) * 0.1;
+        if !self.visible {
+            return;
+        }
+
+        let input = self.state.take_egui_input(window);
+        let frame_time = self.frame_time;
+        let output = self.context.run(input, |context| {
+            egui::Window::new("wgpu-samples").default_width(220.0).show(context, |ui| {
+                ui.label(format!("{:.2}ms ({:.0} fps)", frame_time * 1000.0, 1.0 / frame_time.max(1e-6)));
+                let timings = profiling::summary();
+                if !timings.is_empty() {
+                    ui.separator();
+                    egui::Grid::new("profile").show(ui, |ui| {
+                        for timing in timings {
+                            ui.label(timing.name);
+                            ui.label(format!("{:.2}ms", timing.milliseconds_per_frame));
+                            ui.end_row();
+                        }
+                    });
+                }
+                if let Some(graph) = sample.frame_graph() {
+                    ui.separator();
+                    ui.collapsing("frame graph", |ui| {
+                        egui::ScrollArea::horizontal().show(ui, |ui| draw_graph(ui, graph));
+                    });
+                }
+                ui.separator();
+                sample.ui(ui);
+            });
+        });
+        self.state.handle_platform_output(window, &self.context, output.platform_output);
+
+        let paint_jobs = self.context.tessellate(output.shapes);
+        let screen = egui_wgpu::renderer::ScreenDescriptor {
+            size_in_pixels: [gpu.config.width, gpu.config.height],
+            pixels_per_point: window.scale_factor() as f32,
+        };
+        for (id, delta) in &output.textures_delta.set {
+            self.renderer.update_texture(&gpu.device, &gpu.queue, *id, delta);
+        }
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Overlay Encoder"),
+                },
+            );
+        let uploads = self.renderer.update_buffers(&gpu.device, &gpu.queue, &mut encoder, &paint_jobs, &screen);
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Overlay Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            self.renderer.render(&mut render_pass, &paint_jobs, &screen);
+        }
+        gpu.queue.submit(uploads.into_iter().chain(std::iter::once(encoder.finish())));
+
+        for id in &output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}
+
+// Passes left to right in the order they were recorded, an arrow for every
+// resource one hands to a later one. Arrows that skip passes go around
+// underneath, a lane each.
+fn draw_graph(ui: &mut egui::Ui, graph: &FrameGraph) {
+    const NODE: [f32; 2] = [110.0, 28.0];
+    const GAP: f32 = 70.0;
+    const LANE: f32 = 14.0;
+
+    let dependencies = graph.dependencies();
+    let lanes = dependencies.iter().filter(|(from, to, _)| to - from > 1).count();
+    let size = egui::vec2(
+        graph.passes.len() as f32 * (NODE[0] + GAP),
+        NODE[1] + (lanes + 1) as f32 * LANE,
+    );
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let origin = response.rect.min;
+    let node_rect = |index: usize| {
+        egui::Rect::from_min_size(origin + egui::vec2(index as f32 * (NODE[0] + GAP), 0.0), egui::vec2(NODE[0], NODE[1]))
+    };
+    let visuals = ui.visuals();
+    let stroke = egui::Stroke::new(1.0, visuals.text_color());
+    let label_font = egui::FontId::proportional(9.0);
+
+    for (index, pass) in graph.passes.iter().enumerate() {
+        let rect = node_rect(index);
+        painter.rect(rect, 4.0, visuals.extreme_bg_color, stroke);
+        painter.text(rect.center(), egui::Align2::CENTER_CENTER, &pass.name, egui::FontId::proportional(12.0), visuals.text_color());
+    }
+
+    let mut lane = 0;
+    for (from, to, resource) in dependencies {
+        let start = node_rect(from);
+        let end = node_rect(to);
+        if to == from + 1 {
+            painter.arrow(start.right_center(), end.left_center() - start.right_center(), stroke);
+            painter.text(
+                start.right_center() + egui::vec2(GAP / 2.0, -3.0),
+                egui::Align2::CENTER_BOTTOM,
+                resource,
+                label_font.clone(),
+                visuals.weak_text_color(),
+            );
+        } else {
+            lane += 1;
+            let y = origin.y + NODE[1] + lane as f32 * LANE;
+            let down = egui::pos2(start.center_bottom().x, y);
+            let across = egui::pos2(end.center_bottom().x, y);
+            painter.line_segment([start.center_bottom(), down], stroke);
+            painter.line_segment([down, across], stroke);
+            painter.arrow(across, end.center_bottom() - across, stroke);
+            painter.text(down + egui::vec2(4.0, -1.0), egui::Align2::LEFT_BOTTOM, resource, label_font.clone(), visuals.weak_text_color());
+        }
+    }
+}
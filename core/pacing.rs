@@ -0,0 +1,523 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytemuck::{Pod, Zeroable};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::window::Window;
+
+use crate::gpu::Gpu;
+
+// Frame pacing diagnostics, on with --pacing and shown with F3. Every frame
+// records the time from one present to the next, how much of that the CPU
+// spent outside of acquiring the next surface texture, and the GPU time of
+// the sample's submissions when the adapter has timestamp queries. A present more than
+// 1.5 refresh periods after the last one missed a vsync, and gets blamed on:
+// - the CPU, when its own time didn't fit in a period
+// - the GPU, when the CPU fit but the GPU didn't
+// - presentation, when both fit and the frame was late anyway, e.g. the
+//   compositor or a swapchain that's too short
+//
+// The graph is drawn over the frame after the sample, a summary goes to
+// stderr every second while it's shown.
+
+const HISTORY: usize = 240;
+// GPU times being read back at once; frames beyond that go untimed
+const SLOTS: usize = 4;
+const GRAPH_SIZE: [u32; 2] = [480, 160];
+const MISSED: f32 = 1.5;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Graph {
+    scale: f32,
+    period: f32,
+    newest: u32,
+    count: u32,
+}
+
+// Milliseconds, as the graph shader reads them
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+struct FrameTimes {
+    interval: f32,
+    cpu: f32,
+    // Negative until the timestamps are read back, or when there are none
+    gpu: f32,
+    class: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    OnTime,
+    Cpu,
+    Gpu,
+    Presentation,
+}
+
+impl FrameTimes {
+    fn classify(&self, period: f32) -> Bound {
+        if self.interval <= period * MISSED {
+            Bound::OnTime
+        } else if self.cpu > period {
+            Bound::Cpu
+        } else if self.gpu > period {
+            Bound::Gpu
+        } else {
+            Bound::Presentation
+        }
+    }
+}
+
+struct TimerSlot {
+    buffer: wgpu::Buffer,
+    // Frame number the timestamps belong to, while they're in flight
+    frame: Option<u64>,
+    mapped: Arc<AtomicBool>,
+    // Set when the readback couldn't be mapped, the frame goes untimed
+    failed: Arc<AtomicBool>,
+}
+
+// Timestamps written in their own submissions before and after the
+// sample's, so they also count any gap between the sample's submissions
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    slots: Vec<TimerSlot>,
+    // Milliseconds per tick
+    tick: f32,
+    // Slot timing the current frame, if one was free
+    current: Option<usize>,
+}
+
+impl GpuTimer {
+    fn new(gpu: &Gpu) -> Option<Self> {
+        if !gpu.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = gpu.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Pacing Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2 * SLOTS as u32,
+        });
+        let resolve_buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pacing Resolve"),
+            size: 16 * SLOTS as u64,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let slots = (0..SLOTS)
+            .map(|_| TimerSlot {
+                buffer: gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Pacing Readback"),
+                    size: 16,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                frame: None,
+                mapped: Arc::new(AtomicBool::new(false)),
+                failed: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            slots,
+            tick: gpu.queue.get_timestamp_period() / 1_000_000.0,
+            current: None,
+        })
+    }
+
+    fn begin(&mut self, gpu: &Gpu, frame: u64) {
+        self.current = self.slots.iter().position(|slot| slot.frame.is_none());
+        let slot = match self.current {
+            Some(slot) => slot,
+            None => return,
+        };
+        self.slots[slot].frame = Some(frame);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Pacing Begin Encoder"),
+                },
+            );
+        encoder.write_timestamp(&self.query_set, 2 * slot as u32);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    fn end(&mut self, gpu: &Gpu) {
+        let slot = match self.current.take() {
+            Some(slot) => slot,
+            None => return,
+        };
+        let first = 2 * slot as u32;
+        let offset = 16 * slot as u64;
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Pacing End Encoder"),
+                },
+            );
+        encoder.write_timestamp(&self.query_set, first + 1);
+        encoder.resolve_query_set(&self.query_set, first..first + 2, &self.resolve_buffer, offset);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, offset, &self.slots[slot].buffer, 0, 16);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let mapped = self.slots[slot].mapped.clone();
+        let failed = self.slots[slot].failed.clone();
+        self.slots[slot].buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            match result {
+                Ok(()) => mapped.store(true, Ordering::Release),
+                Err(_) => failed.store(true, Ordering::Release),
+            }
+        });
+    }
+
+    // GPU milliseconds of the frames whose timestamps arrived since the last call
+    fn poll(&mut self, gpu: &Gpu) -> Vec<(u64, f32)> {
+        gpu.device.poll(wgpu::Maintain::Poll);
+        let mut times = Vec::new();
+        for slot in &mut self.slots {
+            // Free the slot again, or it stays in flight for good
+            if slot.failed.swap(false, Ordering::Acquire) {
+                slot.frame = None;
+                continue;
+            }
+            if !slot.mapped.swap(false, Ordering::Acquire) {
+                continue;
+            }
+            {
+                let data = slot.buffer.slice(..).get_mapped_range();
+                let ticks: &[u64] = bytemuck::cast_slice(&data);
+                // Some drivers hand out timestamps that go backwards
+                let elapsed = ticks[1].saturating_sub(ticks[0]);
+                times.push((slot.frame.unwrap(), elapsed as f32 * self.tick));
+            }
+            slot.buffer.unmap();
+            slot.frame = None;
+        }
+        times
+    }
+}
+
+pub struct FramePacing {
+    visible: bool,
+    frames: [FrameTimes; HISTORY],
+    // Frames presented so far; frame n lives at n % HISTORY
+    frame: u64,
+    period: f32,
+    last_present: Option<Instant>,
+    acquire_start: Instant,
+    acquire: f32,
+    // Acquire milliseconds and frames since the last summary
+    acquire_total: f32,
+    summary_frames: usize,
+    last_summary: Instant,
+    timer: Option<GpuTimer>,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    graph_buffer: wgpu::Buffer,
+    frames_buffer: wgpu::Buffer,
+}
+
+impl FramePacing {
+    pub fn new(gpu: &Gpu, window: &Window) -> Self {
+        // The monitor's refresh rate when winit knows it, 60Hz otherwise
+        let period = window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate_millihertz())
+            .map(|millihertz| 1_000_000.0 / millihertz as f32)
+            .unwrap_or(1000.0 / 60.0);
+
+        let device = &gpu.device;
+        let graph_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pacing Graph"),
+            size: std::mem::size_of::<Graph>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let frames_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pacing Frames"),
+            size: (std::mem::size_of::<FrameTimes>() * HISTORY) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Pacing Graph Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/pacing_graph.wgsl").into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pacing Graph Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Pacing Graph Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: graph_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: frames_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            visible: false,
+            frames: [FrameTimes { gpu: -1.0, ..FrameTimes::default() }; HISTORY],
+            frame: 0,
+            period,
+            last_present: None,
+            acquire_start: Instant::now(),
+            acquire: 0.0,
+            acquire_total: 0.0,
+            summary_frames: 0,
+            last_summary: Instant::now(),
+            timer: GpuTimer::new(gpu),
+            pipeline,
+            bind_group,
+            graph_buffer,
+            frames_buffer,
+        }
+    }
+
+    // Returns true for events that shouldn't reach the sample as well
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::F3),
+                    ..
+                },
+                ..
+            } => {
+                self.visible = !self.visible;
+                if self.visible {
+                    println!(
+                        "frame pacing: bars are present intervals, green on time, blue CPU bound, orange GPU bound, \
+                         purple presentation bound; cyan ticks CPU time, yellow GPU time{}",
+                        if self.timer.is_some() { "" } else { " (no timestamp queries here)" },
+                    );
+                    self.last_summary = Instant::now();
+                    self.summary_frames = 0;
+                    self.acquire_total = 0.0;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Around get_current_texture, which is where a full swapchain blocks
+    pub fn begin_acquire(&mut self) {
+        self.acquire_start = Instant::now();
+    }
+
+    pub fn end_acquire(&mut self) {
+        self.acquire = self.acquire_start.elapsed().as_secs_f32() * 1000.0;
+    }
+
+    // Around the sample's rendering, for the GPU time
+    pub fn begin_render(&mut self, gpu: &Gpu) {
+        if !self.visible {
+            return;
+        }
+        if let Some(timer) = &mut self.timer {
+            timer.begin(gpu, self.frame);
+        }
+    }
+
+    pub fn end_render(&mut self, gpu: &Gpu) {
+        if let Some(timer) = &mut self.timer {
+            timer.end(gpu);
+        }
+    }
+
+    // Call right before presenting; draws the graph into `view` when shown
+    pub fn draw(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        if let Some(timer) = &mut self.timer {
+            for (frame, gpu_time) in timer.poll(gpu) {
+                if self.frame - frame < HISTORY as u64 {
+                    let times = &mut self.frames[frame as usize % HISTORY];
+                    times.gpu = gpu_time;
+                    times.class = times.classify(self.period) as u32 as f32;
+                }
+            }
+        }
+        if !self.visible {
+            return;
+        }
+
+        let newest = self.frame.checked_sub(1).map_or(0, |frame| frame as usize % HISTORY);
+        let count = (self.frame as usize).min(HISTORY);
+        // Room for the slowest frame on screen, in whole refresh periods
+        let slowest = self.frames[..count].iter().map(|times| times.interval.max(times.gpu)).fold(0.0, f32::max);
+        let scale = ((slowest / self.period).ceil().max(2.0) + 0.5) * self.period;
+        gpu.queue.write_buffer(
+            &self.graph_buffer,
+            0,
+            bytemuck::bytes_of(&Graph {
+                scale,
+                period: self.period,
+                newest: newest as u32,
+                count: count as u32,
+            }),
+        );
+        gpu.queue.write_buffer(&self.frames_buffer, 0, bytemuck::cast_slice(&self.frames));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Pacing Graph Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pacing Graph Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            // Bottom left corner, shrunk on small windows
+            let width = GRAPH_SIZE[0].min(gpu.config.width);
+            let height = GRAPH_SIZE[1].min(gpu.config.height);
+            render_pass.set_viewport(0.0, (gpu.config.height - height) as f32, width as f32, height as f32, 0.0, 1.0);
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    // Call right after presenting
+    pub fn presented(&mut self) {
+        let now = Instant::now();
+        // The first present, and the first after a reset, have nothing to
+        // measure an interval from. They still count as frames, so that
+        // GPU times tagged with a frame number land on their own frame.
+        let interval = self.last_present.map(|last_present| (now - last_present).as_secs_f32() * 1000.0);
+        let slot = self.frame as usize % HISTORY;
+        let mut times = FrameTimes {
+            interval: interval.unwrap_or(0.0),
+            cpu: interval.map_or(0.0, |interval| (interval - self.acquire).max(0.0)),
+            // draw() may already have stored this frame's GPU time
+            gpu: self.frames[slot].gpu,
+            class: 0.0,
+        };
+        times.class = times.classify(self.period) as u32 as f32;
+        self.frames[slot] = times;
+        self.frame += 1;
+        // The next frame reuses an old slot, whose GPU time isn't its own
+        self.frames[self.frame as usize % HISTORY].gpu = -1.0;
+        self.summary_frames += 1;
+        if interval.is_some() {
+            self.acquire_total += self.acquire;
+        }
+        self.last_present = Some(now);
+
+        if self.visible && self.last_summary.elapsed() >= Duration::from_secs(1) {
+            self.print_summary();
+            self.last_summary = Instant::now();
+            self.summary_frames = 0;
+            self.acquire_total = 0.0;
+        }
+    }
+
+    // A frame that didn't present for a while, e.g. the window was hidden,
+    // isn't a missed vsync
+    pub fn reset(&mut self) {
+        self.last_present = None;
+    }
+
+    fn print_summary(&self) {
+        let mut count = 0;
+        let mut missed = [0; 4];
+        let mut total = 0.0;
+        for age in 0..self.summary_frames.min(HISTORY) {
+            let times = &self.frames[(self.frame as usize - 1 - age) % HISTORY];
+            // Frames without an interval, see presented()
+            if times.interval <= 0.0 {
+                continue;
+            }
+            count += 1;
+            total += times.interval;
+            missed[times.classify(self.period) as usize] += 1;
+        }
+        if count == 0 {
+            return;
+        }
+        eprintln!(
+            "pacing: {:.1}ms average, {:.2}ms acquiring, {} missed vsyncs ({} CPU, {} GPU, {} presentation bound)",
+            total / count as f32,
+            self.acquire_total / count as f32,
+            count - missed[Bound::OnTime as usize],
+            missed[Bound::Cpu as usize],
+            missed[Bound::Gpu as usize],
+            missed[Bound::Presentation as usize],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: f32 = 1000.0 / 60.0;
+
+    fn frame(interval: f32, cpu: f32, gpu: f32) -> FrameTimes {
+        FrameTimes { interval, cpu, gpu, class: 0.0 }
+    }
+
+    #[test]
+    fn late_frames_are_blamed_in_order() {
+        assert_eq!(frame(PERIOD, 30.0, 30.0).classify(PERIOD), Bound::OnTime);
+        assert_eq!(frame(PERIOD * MISSED, 2.0, 2.0).classify(PERIOD), Bound::OnTime);
+        // The CPU is blamed first, even when the GPU was slow too
+        assert_eq!(frame(2.0 * PERIOD, 20.0, 30.0).classify(PERIOD), Bound::Cpu);
+        assert_eq!(frame(2.0 * PERIOD, 5.0, 20.0).classify(PERIOD), Bound::Gpu);
+        assert_eq!(frame(2.0 * PERIOD, 5.0, 5.0).classify(PERIOD), Bound::Presentation);
+    }
+
+    #[test]
+    fn unknown_gpu_time_is_not_gpu_bound() {
+        assert_eq!(frame(2.0 * PERIOD, 5.0, -1.0).classify(PERIOD), Bound::Presentation);
+    }
+
+    #[test]
+    fn frames_without_an_interval_are_on_time() {
+        assert_eq!(frame(0.0, 0.0, 40.0).classify(PERIOD), Bound::OnTime);
+    }
+}
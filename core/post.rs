@@ -0,0 +1,476 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroupLayout, CommandEncoder, Device, Queue, RenderPipeline, Sampler, Texture, TextureView};
+
+use crate::framegraph::FrameGraph;
+
+// Every target between stages. HDR, so stages before the tonemap can work
+// with values over 1.
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// The shared vertex shader and sampler, stages paste their own shaders
+// after it. Public so stages outside the framework can do the same.
+pub const COMMON_WGSL: &str = include_str!("shaders/post_common.wgsl");
+
+// A target stages can render into and read back from
+pub fn create_target(device: &Device, width: u32, height: u32, label: &str) -> TextureView {
+    create_target_texture(device, width, height, label).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// The same, for stages that hand out views of it through PostFrame::shared
+pub fn create_target_texture(device: &Device, width: u32, height: u32, label: &str) -> Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+// A fragment shader run over a whole target: params at binding 0, the
+// chain's linear clamping sampler at 1 and `textures` inputs from 2 on
+pub struct FullscreenPass {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    blended: bool,
+}
+
+impl FullscreenPass {
+    // `source` gets COMMON_WGSL pasted in front of it. With a blend state
+    // the pass draws over what's in the output already, otherwise it
+    // replaces it.
+    pub fn new(
+        device: &Device,
+        label: &str,
+        source: &str,
+        entry_point: &str,
+        textures: u32,
+        format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+    ) -> Self {
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ];
+        for index in 0..textures {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: 2 + index,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+        }
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        });
+
+        // WGSL has no includes, so the shared part is pasted in front
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{}{}", COMMON_WGSL, source))),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_fullscreen",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipeline,
+            layout,
+            sampler,
+            blended: blend.is_some(),
+        }
+    }
+
+    // Every draw gets its own params buffer, so one pass can be drawn
+    // several times a frame with different params. Empty params are fine.
+    pub fn draw(&self, device: &Device, encoder: &mut CommandEncoder, params: &[u8], inputs: &[&TextureView], output: &TextureView) {
+        // Uniform bindings can't be empty
+        let mut contents = params.to_vec();
+        contents.resize(contents.len().max(16), 0);
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post Params"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            },
+        ];
+        for (index, input) in inputs.iter().enumerate() {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2 + index as u32,
+                resource: wgpu::BindingResource::TextureView(input),
+            });
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Bind Group"),
+            layout: &self.layout,
+            entries: &entries,
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: if self.blended { wgpu::LoadOp::Load } else { wgpu::LoadOp::Clear(wgpu::Color::BLACK) },
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+// What a stage gets to work with for one frame
+pub struct PostFrame<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub encoder: &'a mut CommandEncoder,
+    // The previous stage's result, or the scene for the first one
+    pub input: &'a TextureView,
+    // Where this stage's result goes; every pixel has to be written
+    pub output: &'a TextureView,
+    pub width: u32,
+    pub height: u32,
+    // Intermediate results stages leave for the ones after them, by name,
+    // like the bloom for lens dirt. Cleared every frame.
+    pub shared: &'a mut SharedTargets,
+}
+
+// A name to view map that remembers who used what, for the frame graph
+#[derive(Default)]
+pub struct SharedTargets {
+    views: HashMap<&'static str, TextureView>,
+    reads: RefCell<Vec<&'static str>>,
+    writes: Vec<&'static str>,
+}
+
+impl SharedTargets {
+    pub fn insert(&mut self, name: &'static str, view: TextureView) {
+        self.writes.push(name);
+        self.views.insert(name, view);
+    }
+
+    pub fn get(&self, name: &'static str) -> Option<&TextureView> {
+        let view = self.views.get(name);
+        if view.is_some() {
+            self.reads.borrow_mut().push(name);
+        }
+        view
+    }
+
+    // What the last stage read and wrote
+    fn take_accesses(&mut self) -> (Vec<&'static str>, Vec<&'static str>) {
+        (self.reads.take(), std::mem::take(&mut self.writes))
+    }
+
+    fn clear(&mut self) {
+        self.views.clear();
+        self.take_accesses();
+    }
+}
+
+// One step of a PostChain. Stages own whatever pipelines and targets they
+// need; the chain only hands them an input and an output.
+pub trait PostStage {
+    fn name(&self) -> &str;
+
+    // Once when the stage is added and again on every resize, for stages
+    // with targets that follow the window's size
+    fn resize(&mut self, _device: &Device, _width: u32, _height: u32) {}
+
+    fn record(&mut self, frame: &mut PostFrame);
+}
+
+// Stages run in the order they were added, ping-ponging between two HDR
+// targets, then the result is copied to the surface. Any stage can be
+// switched off without the others noticing.
+pub struct PostChain {
+    stages: Vec<(Box<dyn PostStage>, bool)>,
+    // The scene renders into the first
+    targets: [TextureView; 2],
+    present: FullscreenPass,
+    shared: SharedTargets,
+    // What the last run did
+    graph: FrameGraph,
+    width: u32,
+    height: u32,
+}
+
+impl PostChain {
+    pub fn new(device: &Device, config: &wgpu::SurfaceConfiguration) -> Self {
+        Self {
+            stages: Vec::new(),
+            targets: [0, 1].map(|index| create_target(device, config.width, config.height, &format!("Post Target {}", index))),
+            present: FullscreenPass::new(device, "Post Present", include_str!("shaders/post_copy.wgsl"), "fs_main", 1, config.format, None),
+            shared: SharedTargets::default(),
+            graph: FrameGraph::default(),
+            width: config.width,
+            height: config.height,
+        }
+    }
+
+    pub fn push(&mut self, device: &Device, mut stage: impl PostStage + 'static) {
+        stage.resize(device, self.width, self.height);
+        self.stages.push((Box::new(stage), true));
+    }
+
+    // Where the scene goes, in HDR_FORMAT
+    pub fn input(&self) -> &TextureView {
+        &self.targets[0]
+    }
+
+    pub fn resize(&mut self, device: &Device, config: &wgpu::SurfaceConfiguration) {
+        self.width = config.width;
+        self.height = config.height;
+        self.targets = [0, 1].map(|index| create_target(device, config.width, config.height, &format!("Post Target {}", index)));
+        for (stage, _) in &mut self.stages {
+            stage.resize(device, config.width, config.height);
+        }
+    }
+
+    // Names and whether they're on, in order
+    pub fn stages(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.stages.iter().map(|(stage, enabled)| (stage.name(), *enabled))
+    }
+
+    // The scene, the enabled stages and the copy to the surface, as the last
+    // run recorded them
+    pub fn graph(&self) -> &FrameGraph {
+        &self.graph
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some((_, enabled)) = self.stages.get_mut(index) {
+            *enabled = !*enabled;
+        }
+    }
+
+    pub fn run(&mut self, device: &Device, queue: &Queue, encoder: &mut CommandEncoder, surface: &TextureView) {
+        const TARGETS: [&str; 2] = ["post target 0", "post target 1"];
+        self.shared.clear();
+        self.graph.clear();
+        // Rendered by the sample before the chain runs
+        self.graph.add_pass("scene", &[], &[TARGETS[0]]);
+        let mut current = 0;
+        for (stage, enabled) in &mut self.stages {
+            if !*enabled {
+                continue;
+            }
+            let (first, second) = self.targets.split_at(1);
+            let (input, output) = if current == 0 { (&first[0], &second[0]) } else { (&second[0], &first[0]) };
+            stage.record(&mut PostFrame {
+                device,
+                queue,
+                encoder,
+                input,
+                output,
+                width: self.width,
+                height: self.height,
+                shared: &mut self.shared,
+            });
+            let (shared_reads, shared_writes) = self.shared.take_accesses();
+            let reads: Vec<&str> = std::iter::once(TARGETS[current]).chain(shared_reads).collect();
+            let writes: Vec<&str> = std::iter::once(TARGETS[1 - current]).chain(shared_writes).collect();
+            self.graph.add_pass(stage.name(), &reads, &writes);
+            current = 1 - current;
+        }
+        self.present.draw(device, encoder, &[], &[&self.targets[current]], surface);
+        self.graph.add_pass("present", &[TARGETS[current]], &["surface"]);
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct BloomParams {
+    texel_size: [f32; 2],
+    threshold: f32,
+    intensity: f32,
+}
+
+const BLOOM_LEVELS: usize = 6;
+
+// Bright parts blurred wide by downsampling them through a chain of ever
+// smaller targets and adding them back up. Leaves the blur as "bloom" in
+// the shared results.
+pub struct Bloom {
+    pub threshold: f32,
+    pub intensity: f32,
+    prefilter: FullscreenPass,
+    downsample: FullscreenPass,
+    upsample: FullscreenPass,
+    composite: FullscreenPass,
+    // Half size, quarter size and so on, with their sizes
+    levels: Vec<(Texture, TextureView, [u32; 2])>,
+}
+
+impl Bloom {
+    pub fn new(device: &Device) -> Self {
+        let source = include_str!("shaders/bloom.wgsl");
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+        Self {
+            threshold: 1.0,
+            intensity: 0.15,
+            prefilter: FullscreenPass::new(device, "Bloom Prefilter", source, "fs_prefilter", 1, HDR_FORMAT, None),
+            downsample: FullscreenPass::new(device, "Bloom Downsample", source, "fs_downsample", 1, HDR_FORMAT, None),
+            upsample: FullscreenPass::new(device, "Bloom Upsample", source, "fs_upsample", 1, HDR_FORMAT, Some(additive)),
+            composite: FullscreenPass::new(device, "Bloom Composite", source, "fs_composite", 2, HDR_FORMAT, None),
+            levels: Vec::new(),
+        }
+    }
+
+    fn params(&self, size: [u32; 2]) -> BloomParams {
+        BloomParams {
+            texel_size: [1.0 / size[0] as f32, 1.0 / size[1] as f32],
+            threshold: self.threshold,
+            intensity: self.intensity,
+        }
+    }
+}
+
+impl PostStage for Bloom {
+    fn name(&self) -> &str {
+        "bloom"
+    }
+
+    fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.levels = (1..=BLOOM_LEVELS)
+            .map(|level| {
+                let size = [(width >> level).max(1), (height >> level).max(1)];
+                let texture = create_target_texture(device, size[0], size[1], "Bloom Level");
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (texture, view, size)
+            })
+            .collect();
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let device = frame.device;
+        let input_size = [frame.width, frame.height];
+        let params = self.params(input_size);
+        self.prefilter.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[frame.input], &self.levels[0].1);
+
+        for level in 1..self.levels.len() {
+            let params = self.params(self.levels[level - 1].2);
+            self.downsample.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[&self.levels[level - 1].1], &self.levels[level].1);
+        }
+        // Back up, each level adding the blurrier one below it
+        for level in (0..self.levels.len() - 1).rev() {
+            let params = self.params(self.levels[level + 1].2);
+            self.upsample.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[&self.levels[level + 1].1], &self.levels[level].1);
+        }
+
+        self.composite.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[frame.input, &self.levels[0].1], frame.output);
+        let bloom = self.levels[0].0.create_view(&wgpu::TextureViewDescriptor::default());
+        frame.shared.insert("bloom", bloom);
+    }
+}
+
+// HDR to displayable with the ACES curve. Stages after it see values
+// between 0 and 1.
+pub struct Tonemap {
+    pub exposure: f32,
+    pass: FullscreenPass,
+}
+
+impl Tonemap {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            exposure: 1.0,
+            pass: FullscreenPass::new(device, "Tonemap", include_str!("shaders/tonemap.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for Tonemap {
+    fn name(&self) -> &str {
+        "tonemap"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let params = [self.exposure, 0.0, 0.0, 0.0];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input], frame.output);
+    }
+}
@@ -0,0 +1,128 @@
+// Scoped CPU timings. Only collected with the profiling feature, without it
+// a scope is an empty struct; with the tracing feature every scope is also
+// a span.
+
+#[cfg(feature = "profiling")]
+use std::sync::Mutex;
+#[cfg(feature = "profiling")]
+use std::time::{Duration, Instant};
+
+// Times the rest of the enclosing block, e.g. framework::profile_scope!("upload")
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::profiling::Scope::new($name);
+    };
+}
+
+// Average over the last second
+#[derive(Clone, Debug)]
+pub struct Timing {
+    pub name: &'static str,
+    pub milliseconds_per_frame: f32,
+    pub calls_per_frame: f32,
+}
+
+pub struct Scope {
+    #[cfg(feature = "profiling")]
+    name: &'static str,
+    #[cfg(feature = "profiling")]
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl Scope {
+    #[cfg_attr(not(any(feature = "profiling", feature = "tracing")), allow(unused_variables))]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            #[cfg(feature = "profiling")]
+            name,
+            #[cfg(feature = "profiling")]
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            _span: tracing::trace_span!("scope", name).entered(),
+        }
+    }
+}
+
+#[cfg(feature = "profiling")]
+struct Stats {
+    // Totals since `since`, few enough scopes that a Vec beats a HashMap
+    totals: Vec<(&'static str, Duration, u32)>,
+    frames: u32,
+    since: Option<Instant>,
+    summary: Vec<Timing>,
+}
+
+#[cfg(feature = "profiling")]
+static STATS: Mutex<Stats> = Mutex::new(Stats {
+    totals: Vec::new(),
+    frames: 0,
+    since: None,
+    summary: Vec::new(),
+});
+
+#[cfg(feature = "profiling")]
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        let mut stats = STATS.lock().unwrap();
+        match stats.totals.iter_mut().find(|(name, ..)| *name == self.name) {
+            Some((_, time, calls)) => {
+                *time += elapsed;
+                *calls += 1;
+            }
+            None => stats.totals.push((self.name, elapsed, 1)),
+        }
+    }
+}
+
+// Called by the framework once per presented frame
+#[cfg(feature = "profiling")]
+pub fn end_frame() {
+    let mut stats = STATS.lock().unwrap();
+    stats.frames += 1;
+    let since = *stats.since.get_or_insert_with(Instant::now);
+    if since.elapsed() < Duration::from_secs(1) {
+        return;
+    }
+
+    let frames = stats.frames as f32;
+    let mut summary: Vec<Timing> = stats
+        .totals
+        .drain(..)
+        .map(|(name, time, calls)| Timing {
+            name,
+            milliseconds_per_frame: time.as_secs_f32() * 1000.0 / frames,
+            calls_per_frame: calls as f32 / frames,
+        })
+        .collect();
+    summary.sort_by(|a, b| b.milliseconds_per_frame.total_cmp(&a.milliseconds_per_frame));
+    stats.frames = 0;
+    stats.since = Some(Instant::now());
+
+    // The overlay shows them instead when there is one
+    if cfg!(not(feature = "egui-overlay")) {
+        let line: Vec<String> = summary
+            .iter()
+            .map(|timing| format!("{} {:.2}ms", timing.name, timing.milliseconds_per_frame))
+            .collect();
+        eprintln!("profile: {}", line.join(", "));
+    }
+    stats.summary = summary;
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn end_frame() {}
+
+// Slowest first, empty until the first second has passed
+#[cfg(feature = "profiling")]
+pub fn summary() -> Vec<Timing> {
+    STATS.lock().unwrap().summary.clone()
+}
+
+#[cfg(not(feature = "profiling"))]
+pub fn summary() -> Vec<Timing> {
+    Vec::new()
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use wgpu::TextureFormat;
+
+// Known differences between backends and drivers, worked around once in the
+// framework's surface and pipeline setup instead of in every sample. Each
+// is printed the first time it's applied, so a sample that looks off on one
+// machine says why.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Quirk {
+    // The surface prefers a linear format although it offers an sRGB one,
+    // seen with GL and some Vulkan drivers. The samples write linear color
+    // and count on the surface to encode it, so the sRGB one is used.
+    LinearSurfacePreferred { preferred: TextureFormat, used: TextureFormat },
+    // No sRGB surface format at all, e.g. WebGL2 in some browsers. Not
+    // worked around: samples that don't check come out too dark.
+    NoSrgbSurface(TextureFormat),
+    // The format can't be rendered and resolved at the requested sample
+    // count, e.g. some formats on DX12 feature level 11 and GL ES, so the
+    // highest count that can is used
+    SampleCountLowered { format: TextureFormat, requested: u32, used: u32 },
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Quirk::LinearSurfacePreferred { preferred, used } => {
+                write!(f, "surface prefers {:?}, using {:?} instead", preferred, used)
+            }
+            Quirk::NoSrgbSurface(format) => write!(f, "no sRGB surface format, {:?} won't encode colors", format),
+            Quirk::SampleCountLowered { format, requested, used } => {
+                write!(f, "{:?} can't resolve {}x MSAA, using {}x", format, requested, used)
+            }
+        }
+    }
+}
+
+pub struct Quirks {
+    backend: wgpu::Backend,
+    applied: Mutex<Vec<Quirk>>,
+}
+
+impl Quirks {
+    pub fn new(backend: wgpu::Backend) -> Self {
+        Self {
+            backend,
+            applied: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Records the quirk and prints it, unless it was already applied
+    pub fn apply(&self, quirk: Quirk) {
+        let mut applied = self.applied.lock().unwrap();
+        if !applied.contains(&quirk) {
+            eprintln!("quirk ({:?}): {}", self.backend, quirk);
+            applied.push(quirk);
+        }
+    }
+
+    // Everything applied so far, in order
+    pub fn applied(&self) -> Vec<Quirk> {
+        self.applied.lock().unwrap().clone()
+    }
+
+    // The preferred surface format, or the first sRGB one if that isn't sRGB
+    pub fn surface_format(&self, formats: &[TextureFormat]) -> TextureFormat {
+        let preferred = formats[0];
+        if preferred.is_srgb() {
+            return preferred;
+        }
+        match formats.iter().find(|format| format.is_srgb()) {
+            Some(&used) => {
+                self.apply(Quirk::LinearSurfacePreferred { preferred, used });
+                used
+            }
+            None => {
+                self.apply(Quirk::NoSrgbSurface(preferred));
+                preferred
+            }
+        }
+    }
+
+    // The highest count up to `requested` the color format can be rendered
+    // and resolved at on this adapter, which isn't always the 4 WebGPU
+    // guarantees
+    pub fn sample_count(&self, adapter: &wgpu::Adapter, format: TextureFormat, requested: u32) -> u32 {
+        let flags = adapter.get_texture_format_features(format).flags;
+        let resolvable = flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE);
+        let mut count = requested;
+        while count > 1 && !(resolvable && flags.sample_count_supported(count)) {
+            count /= 2;
+        }
+        if count != requested {
+            self.apply(Quirk::SampleCountLowered { format, requested, used: count });
+        }
+        count
+    }
+}
@@ -0,0 +1,212 @@
+use crate::gpu::Gpu;
+
+// Tightly packed RGBA8 rows, top to bottom, of the `size` texels starting at
+// `origin`. None for formats that aren't 8 bit RGBA or BGRA. The texture
+// needs COPY_SRC.
+pub fn read_texture(gpu: &Gpu, texture: &wgpu::Texture, origin: [u32; 2], size: [u32; 2]) -> Option<Vec<u8>> {
+    let swap_red_blue = match texture.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        // The usual surface format on Windows and Vulkan
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => return None,
+    };
+    let [width, height] = size;
+
+    // Texture to buffer copies need rows aligned to 256 bytes
+    let bytes_per_row = width * 4;
+    let padded_bytes_per_row = wgpu::util::align_to(bytes_per_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback"),
+        size: (padded_bytes_per_row * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        gpu.device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            },
+        );
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: origin[0],
+                y: origin[1],
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    // Blocks until the copy is done and the callback above has run
+    gpu.device.poll(wgpu::Maintain::Wait);
+
+    let mut pixels: Vec<u8> = slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..bytes_per_row as usize])
+        .copied()
+        .collect();
+    buffer.unmap();
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    Some(pixels)
+}
+
+// wgpu 0.16 can't tell whether a surface may be copied from, so frames
+// that are read back are rendered into this texture instead and then
+// drawn onto the surface
+#[cfg(feature = "capture")]
+pub struct ReadableFrame {
+    texture: wgpu::Texture,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+#[cfg(feature = "capture")]
+impl ReadableFrame {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shaders/frame_copy.wgsl"));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Frame Copy Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Frame Copy Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Frame Copy Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(gpu.config.format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            texture: create_readable_texture(gpu),
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    // What to render this frame into instead of the surface
+    pub fn view(&mut self, gpu: &Gpu) -> wgpu::TextureView {
+        if self.texture.width() != gpu.config.width || self.texture.height() != gpu.config.height {
+            self.texture = create_readable_texture(gpu);
+        }
+        self.texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    // What was rendered into view(), to read back from
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    // Draws what was rendered into view() onto the surface's `view`
+    pub fn draw(&self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Frame Copy Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&self.texture.create_view(&wgpu::TextureViewDescriptor::default())),
+            }],
+        });
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Frame Copy Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Frame Copy Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+// The surface's size and format, plus COPY_SRC
+#[cfg(feature = "capture")]
+fn create_readable_texture(gpu: &Gpu) -> wgpu::Texture {
+    gpu.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Readable Frame"),
+        size: wgpu::Extent3d {
+            width: gpu.config.width,
+            height: gpu.config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: gpu.config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
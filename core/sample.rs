@@ -0,0 +1,441 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use winit::{
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::{Window, WindowBuilder},
+};
+
+#[cfg(target_arch = "wasm32")]
+use winit::event_loop::EventLoopProxy;
+
+use crate::benchmark::Benchmark;
+#[cfg(feature = "capture")]
+use crate::capture::FrameRecorder;
+#[cfg(feature = "capture")]
+use crate::clipboard::ClipboardCopy;
+use crate::camera::CameraPose;
+#[cfg(feature = "json")]
+use crate::flythrough::Flythrough;
+use crate::framegraph::FrameGraph;
+use crate::gpu::Gpu;
+#[cfg(feature = "png")]
+use crate::headless::{run_headless, HeadlessOptions};
+#[cfg(feature = "egui-overlay")]
+use crate::overlay::Overlay;
+use crate::pacing::FramePacing;
+use crate::profiling;
+#[cfg(feature = "capture")]
+use crate::readback::ReadableFrame;
+use crate::snapshot::{Snapshot, SnapshotError};
+
+// The parts of a sample that actually differ from one to the next
+pub trait Sample: 'static + Sized {
+    fn required_features() -> wgpu::Features {
+        wgpu::Features::empty()
+    }
+
+    // Enabled when the adapter has them; init() checks device.features()
+    // and falls back without them
+    fn optional_features() -> wgpu::Features {
+        wgpu::Features::empty()
+    }
+
+    fn required_limits() -> wgpu::Limits {
+        // WebGL doesn't support all of wgpu's features, so if
+        // we're building for the web we'll have to disable some.
+        if cfg!(target_arch = "wasm32") {
+            wgpu::Limits::downlevel_webgl2_defaults()
+        } else {
+            wgpu::Limits::default()
+        }
+    }
+
+    fn init(gpu: &Gpu) -> Self;
+
+    // Rebuild whatever depends on the surface's size or format. Called after
+    // a resize, after a lost surface was reconfigured and when a scene
+    // comes back to the top of a SceneStack.
+    fn reinit_surface_resources(&mut self, _gpu: &Gpu) {}
+
+    // Window events the framework doesn't handle itself, e.g. input
+    fn update(&mut self, _event: &WindowEvent) {}
+
+    // Record and submit the frame; the framework presents it afterwards
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView);
+
+    // Samples with a free camera implement this so --flythrough can drive it
+    fn set_camera(&mut self, _pose: &CameraPose) {}
+
+    // Named values a script can tweak while the sample runs, e.g. the
+    // sequencer's. Returns false for names the sample doesn't have.
+    fn set_parameter(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+
+    // Shown in the title bar after the sample's name
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    // The passes of the last frame, for samples that schedule them through
+    // something that records one, like PostChain
+    fn frame_graph(&self) -> Option<&FrameGraph> {
+        None
+    }
+
+    // Bump when save_state's sections change, so old snapshots are refused
+    // rather than loaded into the wrong layout
+    const STATE_VERSION: u32 = 1;
+
+    // Simulations whose state builds up over time add it here, for F5 and
+    // --save-state. Leaving the snapshot empty means there's nothing to save.
+    fn save_state(&self, _gpu: &Gpu, _snapshot: &mut Snapshot) {}
+
+    // Called right after init with what save_state wrote, for --load-state
+    fn load_state(&mut self, _gpu: &Gpu, _snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+
+    // Controls for the overlay window, below the frame time
+    #[cfg(feature = "egui-overlay")]
+    fn ui(&mut self, _ui: &mut egui::Ui) {}
+}
+
+pub fn run_sample<S: Sample>(title: &str) {
+    #[cfg(feature = "tracing")]
+    crate::logging::init();
+
+    #[cfg(feature = "png")]
+    match HeadlessOptions::from_args() {
+        Ok(Some(options)) => {
+            run_headless::<S>(title, &options);
+            return;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            eprintln!("{}: {}", title, error);
+            std::process::exit(1);
+        }
+    }
+    #[cfg(not(feature = "png"))]
+    if std::env::args().any(|arg| arg == "--headless") {
+        eprintln!("{} was built without the framework's png feature, which --headless needs", title);
+        return;
+    }
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title(title)
+        .build(&event_loop)
+        .unwrap();
+
+    // --pacing turns on the frame pacing diagnostics, with GPU times where
+    // the adapter has timestamp queries
+    let pacing_enabled = std::env::args().any(|arg| arg == "--pacing");
+    let mut optional_features = S::optional_features();
+    if pacing_enabled {
+        optional_features |= wgpu::Features::TIMESTAMP_QUERY;
+    }
+    let mut gpu = async_std::task::block_on(Gpu::init(&window, S::required_features(), optional_features, S::required_limits()));
+    #[cfg(feature = "tracing")]
+    tracing::info!(adapter = ?gpu.adapter.get_info(), "initialized");
+    // The framework's own GPU objects are created before the sample, which
+    // may push scenes, so they're below every scene's leak baseline
+    #[cfg(feature = "capture")]
+    let mut readable_frame = ReadableFrame::new(&gpu);
+    #[cfg(feature = "egui-overlay")]
+    let mut overlay = Overlay::new(&event_loop, &window, &gpu);
+    let mut pacing = pacing_enabled.then(|| FramePacing::new(&gpu, &window));
+    let mut sample = S::init(&gpu);
+    let title = title.to_string();
+    // --load-state path resumes from a snapshot, --save-state path is where
+    // F5 and exiting write one
+    if let Some(path) = arg_value("--load-state") {
+        let loaded = Snapshot::load(Path::new(&path), &title, S::STATE_VERSION)
+            .and_then(|snapshot| sample.load_state(&gpu, &snapshot));
+        if let Err(error) = loaded {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        }
+    }
+    let save_state = arg_value("--save-state").map(PathBuf::from);
+    // --flythrough path.json replays a camera path and exits at its end,
+    // --benchmark frames.csv records every frame time along the way
+    #[cfg(feature = "json")]
+    let mut flythrough = arg_value("--flythrough").map(|path| {
+        Flythrough::load(Path::new(&path)).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        })
+    });
+    #[cfg(not(feature = "json"))]
+    if arg_value("--flythrough").is_some() {
+        eprintln!("{} was built without the framework's json feature, which --flythrough needs", title);
+    }
+    let mut benchmark = arg_value("--benchmark").map(|path| Benchmark::new(PathBuf::from(path)));
+    // --dump-graph frame.dot writes the first frame's graph as Graphviz
+    let mut dump_graph = arg_value("--dump-graph").map(PathBuf::from);
+    #[cfg(feature = "capture")]
+    let mut clipboard = ClipboardCopy::default();
+    #[cfg(feature = "capture")]
+    let mut recorder = FrameRecorder::new(&title);
+    let mut last_frame = Instant::now();
+    // Set while the window is minimized or fully covered, so we stop
+    // submitting frames nobody can see
+    let mut occluded = false;
+    // winit 0.28 doesn't send Occluded on the web, the page's visibility
+    // stands in for it
+    #[cfg(target_arch = "wasm32")]
+    watch_visibility(event_loop.create_proxy());
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent {
+            ref event,
+            window_id,
+        } if window_id == window.id() => {
+                match event {
+                    WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                    WindowEvent::Resized(physical_size) => {
+                        if gpu.resize(*physical_size) {
+                            sample.reinit_surface_resources(&gpu);
+                        }
+                    }
+                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                        // new_inner_size is &&mut so we have to dereference it twice
+                        if gpu.resize(**new_inner_size) {
+                            sample.reinit_surface_resources(&gpu);
+                        }
+                    }
+                    WindowEvent::Occluded(is_occluded) => {
+                        occluded = *is_occluded;
+                        set_occluded(occluded, &window, &mut gpu, &mut sample, &mut pacing, control_flow);
+                    }
+                    _ => {
+                        #[cfg(feature = "egui-overlay")]
+                        if overlay.handle_event(event) {
+                            return;
+                        }
+                        #[cfg(feature = "capture")]
+                        if clipboard.handle_event(event) || recorder.handle_event(event) {
+                            return;
+                        }
+                        if is_key_press(event, VirtualKeyCode::F5) {
+                            let path = save_state.clone().unwrap_or_else(|| PathBuf::from(format!("{}.state", title)));
+                            save_snapshot(&gpu, &sample, &title, &path);
+                            return;
+                        }
+                        if let Some(pacing) = &mut pacing {
+                            if pacing.handle_event(event) {
+                                return;
+                            }
+                        }
+                        sample.update(event);
+                    }
+                }
+        }
+        // Sent by the visibilitychange listener
+        #[cfg(target_arch = "wasm32")]
+        Event::UserEvent(()) => {
+            occluded = page_hidden();
+            set_occluded(occluded, &window, &mut gpu, &mut sample, &mut pacing, control_flow);
+        }
+        Event::RedrawRequested(window_id) if window_id == window.id() && occluded => {}
+        Event::RedrawRequested(window_id) if window_id == window.id() => {
+            let acquired = {
+                let _scope = profiling::Scope::new("acquire");
+                if let Some(pacing) = &mut pacing {
+                    pacing.begin_acquire();
+                }
+                let acquired = gpu.surface.as_ref().unwrap().get_current_texture();
+                if let Some(pacing) = &mut pacing {
+                    pacing.end_acquire();
+                }
+                acquired
+            };
+            match acquired {
+                Ok(frame) => {
+                    let now = Instant::now();
+                    let frame_time = (now - last_frame).as_secs_f32();
+                    last_frame = now;
+
+                    if let Some(benchmark) = &mut benchmark {
+                        benchmark.record(frame_time);
+                    }
+                    #[cfg(feature = "json")]
+                    if let Some(flythrough) = &mut flythrough {
+                        sample.set_camera(&flythrough.advance(frame_time));
+                        if flythrough.finished() {
+                            *control_flow = ControlFlow::Exit;
+                        }
+                    }
+
+                    let view = frame.texture.create_view(
+                        &wgpu::TextureViewDescriptor::default(),
+                    );
+                    // A frame that's read back is rendered somewhere it can
+                    // be copied from, and drawn onto the surface after
+                    #[cfg(feature = "capture")]
+                    let readable_view = (clipboard.requested() || recorder.recording())
+                        .then(|| readable_frame.view(&gpu));
+                    #[cfg(not(feature = "capture"))]
+                    let readable_view: Option<wgpu::TextureView> = None;
+                    {
+                        let _scope = profiling::Scope::new("render");
+                        if let Some(pacing) = &mut pacing {
+                            pacing.begin_render(&gpu);
+                        }
+                        sample.render(&gpu, readable_view.as_ref().unwrap_or(&view));
+                        if let Some(pacing) = &mut pacing {
+                            pacing.end_render(&gpu);
+                        }
+                    }
+                    // Before the overlay, so it isn't in the copies
+                    #[cfg(feature = "capture")]
+                    if readable_view.is_some() {
+                        clipboard.copy_if_requested(&gpu, readable_frame.texture());
+                        recorder.record(&gpu, readable_frame.texture());
+                        readable_frame.draw(&gpu, &view);
+                    }
+                    if let Some(path) = dump_graph.take() {
+                        match sample.frame_graph() {
+                            Some(graph) => graph.write_dot(&path),
+                            None => eprintln!("{} doesn't record a frame graph", title),
+                        }
+                    }
+                    if let Some(pacing) = &mut pacing {
+                        pacing.draw(&gpu, &view);
+                    }
+                    #[cfg(feature = "egui-overlay")]
+                    {
+                        let _scope = profiling::Scope::new("overlay");
+                        overlay.draw(&gpu, &window, &view, &mut sample, frame_time);
+                    }
+                    {
+                        let _scope = profiling::Scope::new("present");
+                        frame.present();
+                    }
+                    if let Some(pacing) = &mut pacing {
+                        pacing.presented();
+                    }
+                    profiling::end_frame();
+
+                    if let Some(status) = sample.status() {
+                        window.set_title(&format!("{} - {}", title, status));
+                    }
+                }
+                // Reconfigure the surface if it's lost or no longer matches the window
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    gpu.reconfigure();
+                    sample.reinit_surface_resources(&gpu);
+                }
+                // The system is out of memory, we should probably quit
+                Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
+                // Timeouts should be resolved by the next frame
+                Err(e) => eprintln!("{:?}", e),
+            }
+        }
+        Event::LoopDestroyed => {
+            if let Some(path) = &save_state {
+                save_snapshot(&gpu, &sample, &title, path);
+            }
+            if let Some(benchmark) = &benchmark {
+                if let Err(error) = benchmark.write() {
+                    eprintln!("{}: {}", benchmark.output().display(), error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        // RedrawRequested will only trigger once, unless we manually
+        // request it.
+        Event::MainEventsCleared if !occluded => window.request_redraw(),
+        _ => {}
+    });
+}
+
+fn set_occluded<S: Sample>(
+    occluded: bool,
+    window: &Window,
+    gpu: &mut Gpu,
+    sample: &mut S,
+    pacing: &mut Option<FramePacing>,
+    control_flow: &mut ControlFlow,
+) {
+    if occluded {
+        // Sleep until the next event instead of spinning
+        *control_flow = ControlFlow::Wait;
+    } else {
+        // The size may have changed while we were hidden, so reconfigure
+        // before the first frame back
+        if gpu.resize(window.inner_size()) {
+            sample.reinit_surface_resources(gpu);
+        }
+        *control_flow = ControlFlow::Poll;
+        if let Some(pacing) = pacing {
+            pacing.reset();
+        }
+        window.request_redraw();
+    }
+}
+
+// Wakes the event loop with a UserEvent whenever the tab is hidden or shown
+#[cfg(target_arch = "wasm32")]
+fn watch_visibility(proxy: EventLoopProxy<()>) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+
+    let document = match web_sys::window().and_then(|window| window.document()) {
+        Some(document) => document,
+        None => return,
+    };
+    let listener = Closure::<dyn FnMut()>::new(move || {
+        let _ = proxy.send_event(());
+    });
+    if let Err(error) = document.add_event_listener_with_callback("visibilitychange", listener.as_ref().unchecked_ref()) {
+        eprintln!("can't watch the page's visibility: {:?}", error);
+        return;
+    }
+    // The listener lives as long as the page
+    listener.forget();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn page_hidden() -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .map_or(false, |document| document.hidden())
+}
+
+fn save_snapshot<S: Sample>(gpu: &Gpu, sample: &S, title: &str, path: &Path) {
+    let mut snapshot = Snapshot::new(title, S::STATE_VERSION);
+    sample.save_state(gpu, &mut snapshot);
+    if snapshot.is_empty() {
+        eprintln!("{} has no state to save", title);
+        return;
+    }
+    match snapshot.save(path) {
+        Ok(()) => println!("saved the state to {}", path.display()),
+        Err(error) => eprintln!("{}: {}", path.display(), error),
+    }
+}
+
+fn is_key_press(event: &WindowEvent, key: VirtualKeyCode) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(pressed),
+                ..
+            },
+            ..
+        } if *pressed == key
+    )
+}
+
+// The value following `flag` on the command line, if any
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != flag);
+    args.next()?;
+    args.next()
+}
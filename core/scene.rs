@@ -0,0 +1,137 @@
+use winit::event::WindowEvent;
+
+use crate::camera::CameraPose;
+use crate::gpu::{Gpu, ResourceCounts};
+use crate::sample::Sample;
+
+// The object safe half of Sample, so different samples can live on one stack
+pub trait Scene {
+    fn reinit_surface_resources(&mut self, gpu: &Gpu);
+    fn update(&mut self, event: &WindowEvent);
+    fn set_camera(&mut self, pose: &CameraPose);
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool;
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView);
+    fn status(&self) -> Option<String>;
+}
+
+impl<S: Sample> Scene for S {
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        Sample::reinit_surface_resources(self, gpu);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        Sample::update(self, event);
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        Sample::set_camera(self, pose);
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        Sample::set_parameter(self, name, value)
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        Sample::render(self, gpu, view);
+    }
+
+    fn status(&self) -> Option<String> {
+        Sample::status(self)
+    }
+}
+
+pub type SceneFactory = fn(&Gpu) -> Box<dyn Scene>;
+
+pub fn scene<S: Sample>(gpu: &Gpu) -> Box<dyn Scene> {
+    Box::new(S::init(gpu))
+}
+
+struct Entry {
+    name: &'static str,
+    scene: Box<dyn Scene>,
+    // What was alive before the scene was created, everything above that
+    // belongs to it and has to be gone again once it's dropped
+    baseline: Option<ResourceCounts>,
+}
+
+// Only the top scene is updated and rendered, the ones below keep their
+// resources until they're back on top. Drive it from render(), where the
+// frame's surface texture is alive, so the counts always line up.
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: Vec<Entry>,
+}
+
+impl SceneStack {
+    pub fn push(&mut self, gpu: &Gpu, name: &'static str, factory: SceneFactory) {
+        let baseline = gpu.resource_counts();
+        self.scenes.push(Entry {
+            name,
+            scene: factory(gpu),
+            baseline,
+        });
+    }
+
+    // Refuses to pop the last scene, there'd be nothing left to render
+    pub fn pop(&mut self, gpu: &Gpu) -> bool {
+        if self.scenes.len() < 2 {
+            return false;
+        }
+        let entry = self.scenes.pop().unwrap();
+        Self::release(gpu, entry);
+
+        // The window may have been resized while it was covered
+        self.scenes.last_mut().unwrap().scene.reinit_surface_resources(gpu);
+        true
+    }
+
+    // Replaces the top scene. The old one is gone before the new one is
+    // created, so the two are never resident at the same time.
+    pub fn switch(&mut self, gpu: &Gpu, name: &'static str, factory: SceneFactory) {
+        if let Some(entry) = self.scenes.pop() {
+            Self::release(gpu, entry);
+        }
+        self.push(gpu, name, factory);
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn top_name(&self) -> Option<&'static str> {
+        self.scenes.last().map(|entry| entry.name)
+    }
+
+    pub fn top_mut(&mut self) -> Option<&mut (dyn Scene + 'static)> {
+        self.scenes.last_mut().map(|entry| entry.scene.as_mut())
+    }
+
+    pub fn top(&self) -> Option<&dyn Scene> {
+        self.scenes.last().map(|entry| entry.scene.as_ref())
+    }
+
+    // Only the top scene, the others catch up when they're popped back to
+    pub fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        if let Some(entry) = self.scenes.last_mut() {
+            entry.scene.reinit_surface_resources(gpu);
+        }
+    }
+
+    fn release(gpu: &Gpu, entry: Entry) {
+        let Entry { name, scene, baseline } = entry;
+        drop(scene);
+
+        // Dropping only marks the scene's objects as destroyed; wgpu frees
+        // them once the GPU is done with them and the device is maintained,
+        // so do that right away instead of on the next submit
+        gpu.device.poll(wgpu::Maintain::Wait);
+
+        if let (Some(baseline), Some(counts)) = (baseline, gpu.resource_counts()) {
+            debug_assert_eq!(baseline, counts, "scene {} leaked GPU resources", name);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+// Next to wherever the samples are run from, shared by all of them
+const PATH: &str = "settings.json";
+
+// What the samples remember between runs. Every field has a default, so
+// files from older builds and hand edited ones still load.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    // Per adapter, per kernel name, the fastest workgroup size found
+    pub workgroup_sizes: HashMap<String, HashMap<String, [u32; 2]>>,
+}
+
+impl Settings {
+    // A missing or unreadable file is just no settings yet
+    pub fn load() -> Self {
+        std::fs::read_to_string(Path::new(PATH))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let json = serde_json::to_string_pretty(self).unwrap();
+        if let Err(error) = std::fs::write(Path::new(PATH), json) {
+            eprintln!("couldn't write {}: {}", PATH, error);
+        }
+    }
+}
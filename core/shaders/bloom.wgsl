@@ -0,0 +1,63 @@
+struct Params {
+    // Of the texture being read
+    texel_size: vec2<f32>,
+    threshold: f32,
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+// Only bound for the composite
+@group(0) @binding(3)
+var bloom: texture_2d<f32>;
+
+// Four bilinear taps between texels, averaging a 4x4 block
+fn box4(uv: vec2<f32>) -> vec3<f32> {
+    let offset = params.texel_size;
+    return (textureSampleLevel(source, post_sampler, uv + vec2<f32>(-offset.x, -offset.y), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, uv + vec2<f32>(offset.x, -offset.y), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, uv + vec2<f32>(-offset.x, offset.y), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, uv + vec2<f32>(offset.x, offset.y), 0.0).rgb) * 0.25;
+}
+
+// Keeps what's brighter than the threshold, with a soft knee so there's
+// no hard edge where it starts
+@fragment
+fn fs_prefilter(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = box4(in.uv);
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = params.threshold * 0.5;
+    let soft = clamp(brightness - params.threshold + knee, 0.0, 2.0 * knee);
+    let contribution = max(soft * soft / (4.0 * knee + 0.0001), brightness - params.threshold);
+    return vec4<f32>(color * contribution / max(brightness, 0.0001), 1.0);
+}
+
+@fragment
+fn fs_downsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(box4(in.uv), 1.0);
+}
+
+// A 3x3 tent over the smaller level, added onto the bigger one
+@fragment
+fn fs_upsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let o = params.texel_size;
+    var sum = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb * 4.0;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-o.x, 0.0), 0.0).rgb * 2.0;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(o.x, 0.0), 0.0).rgb * 2.0;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(0.0, -o.y), 0.0).rgb * 2.0;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(0.0, o.y), 0.0).rgb * 2.0;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-o.x, -o.y), 0.0).rgb;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(o.x, -o.y), 0.0).rgb;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-o.x, o.y), 0.0).rgb;
+    sum += textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(o.x, o.y), 0.0).rgb;
+    return vec4<f32>(sum / 16.0, 1.0);
+}
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let scene = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb;
+    let glow = textureSampleLevel(bloom, post_sampler, in.uv, 0.0).rgb;
+    return vec4<f32>(scene + glow * params.intensity, 1.0);
+}
@@ -0,0 +1,50 @@
+// Pasted in front of shaders that convert colors, see color.rs. Everything
+// in between stays linear sRGB: decode where colors come in, encode only
+// where the target format doesn't.
+
+// The exact piecewise curves; pow(c, 2.2) is close, but off by up to a few
+// 8 bit steps in the darks
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+// Display P3 has sRGB's white point and transfer curve, only wider
+// primaries. P3 colors outside sRGB come out below zero or above one.
+fn linear_p3_to_linear_srgb(c: vec3<f32>) -> vec3<f32> {
+    let m = mat3x3<f32>(
+        vec3<f32>(1.224940, -0.042057, -0.019638),
+        vec3<f32>(-0.224940, 1.042057, -0.078636),
+        vec3<f32>(0.0, 0.0, 1.098273)
+    );
+    return m * c;
+}
+
+fn linear_srgb_to_linear_p3(c: vec3<f32>) -> vec3<f32> {
+    let m = mat3x3<f32>(
+        vec3<f32>(0.822462, 0.033194, 0.017083),
+        vec3<f32>(0.177538, 0.966806, 0.072397),
+        vec3<f32>(0.0, 0.0, 0.910520)
+    );
+    return m * c;
+}
+
+// The values of color::Encoding::shader_value
+const ENCODING_NONE: u32 = 0u;
+const ENCODING_SRGB: u32 = 1u;
+
+// For the last write of a linear color: sRGB formats encode it themselves
+// and float formats keep it linear, anything else stores what it gets
+fn encode_for_target(c: vec3<f32>, encoding: u32) -> vec3<f32> {
+    if encoding == ENCODING_SRGB {
+        return linear_to_srgb(clamp(c, vec3<f32>(0.0), vec3<f32>(1.0)));
+    }
+    return c;
+}
@@ -0,0 +1,40 @@
+const PI: f32 = 3.14159265;
+
+struct Params {
+    rotation: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source_sampler: sampler;
+
+// Direction through `uv` (-1..1, v pointing down) on cube face `face`, in
+// the +X, -X, +Y, -Y, +Z, -Z layer order wgpu samples cubes with. uv may
+// go past -1..1, which points into the neighbouring face.
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    var dir: vec3<f32>;
+    switch face {
+        case 0u: { dir = vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1u: { dir = vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2u: { dir = vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3u: { dir = vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4u: { dir = vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { dir = vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+    return normalize(dir);
+}
+
+// Longitude 0 (the middle of the image) looks down -Z, the top row looks
+// straight up
+fn direction_to_equirect(dir: vec3<f32>) -> vec2<f32> {
+    let lon = atan2(dir.x, -dir.z);
+    let lat = asin(clamp(dir.y, -1.0, 1.0));
+    return vec2<f32>(lon / (2.0 * PI) + 0.5, 0.5 - lat / PI);
+}
+
+fn equirect_to_direction(uv: vec2<f32>) -> vec3<f32> {
+    let lon = (uv.x - 0.5) * 2.0 * PI;
+    let lat = (0.5 - uv.y) * PI;
+    return vec3<f32>(cos(lat) * sin(lon), sin(lat), -cos(lat) * cos(lon));
+}
@@ -0,0 +1,38 @@
+@group(0) @binding(1)
+var source: texture_cube<f32>;
+@group(0) @binding(3)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_rotate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size) * 2.0 - 1.0;
+    let dir = (params.rotation * vec4<f32>(face_direction(id.z, uv), 0.0)).xyz;
+    textureStore(destination, vec2<i32>(id.xy), i32(id.z), textureSampleLevel(source, source_sampler, dir, 0.0));
+}
+
+// Four bilinear taps at the corners of the destination texel make a 4x4
+// tent over the previous level. The taps go through the cube sampler by
+// direction, so on face edges they pick up texels of the neighbouring face
+// instead of clamping, which is what keeps seams from showing up in the
+// blurrier mips.
+@compute @workgroup_size(8, 8, 1)
+fn cs_downsample(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let texel = 2.0 / vec2<f32>(size);
+    let uv = (vec2<f32>(id.xy) + 0.5) * texel - 1.0;
+    var color = vec4<f32>(0.0);
+    for (var i = 0u; i < 4u; i++) {
+        let corner = vec2<f32>(f32(i & 1u), f32(i >> 1u)) - 0.5;
+        color += textureSampleLevel(source, source_sampler, face_direction(id.z, uv + corner * texel), 0.0);
+    }
+    textureStore(destination, vec2<i32>(id.xy), i32(id.z), color * 0.25);
+}
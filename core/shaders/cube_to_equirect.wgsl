@@ -0,0 +1,17 @@
+@group(0) @binding(1)
+var source: texture_cube<f32>;
+@group(0) @binding(3)
+var destination: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size);
+    let dir = (params.rotation * vec4<f32>(equirect_to_direction(uv), 0.0)).xyz;
+    let color = textureSampleLevel(source, source_sampler, dir, 0.0);
+    textureStore(destination, vec2<i32>(id.xy), color);
+}
@@ -0,0 +1,16 @@
+// Pasted in front of particle shaders, see depth_fade.rs
+
+// View space distance of a depth buffer value from a perspective projection
+fn linearize_depth(depth: f32, near: f32, far: f32) -> f32 {
+    return near * far / (far - depth * (far - near));
+}
+
+// How visible a particle fragment at `position` (its @builtin(position))
+// is: 0 behind the scene, rising to 1 once it's `softness` units in front.
+// Multiply premultiplied color, or alpha, by it. `scene_depth` is the depth
+// texture bound as texture_2d<f32>, see SceneDepth::layout_entry.
+fn depth_fade(scene_depth: texture_2d<f32>, position: vec4<f32>, near: f32, far: f32, softness: f32) -> f32 {
+    let scene = linearize_depth(textureLoad(scene_depth, vec2<i32>(position.xy), 0).x, near, far);
+    let particle = linearize_depth(position.z, near, far);
+    return clamp((scene - particle) / softness, 0.0, 1.0);
+}
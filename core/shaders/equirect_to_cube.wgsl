@@ -0,0 +1,19 @@
+@group(0) @binding(1)
+var source: texture_2d<f32>;
+@group(0) @binding(3)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(size) * 2.0 - 1.0;
+    let dir = (params.rotation * vec4<f32>(face_direction(id.z, uv), 0.0)).xyz;
+    // The sampler repeats horizontally, so the seam where longitude wraps
+    // around filters like any other column
+    let color = textureSampleLevel(source, source_sampler, direction_to_equirect(dir), 0.0);
+    textureStore(destination, vec2<i32>(id.xy), i32(id.z), color);
+}
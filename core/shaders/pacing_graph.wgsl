@@ -0,0 +1,77 @@
+// Frame time graph drawn by core/pacing.rs: one column per frame, newest on
+// the right, bars as tall as the present interval and colored by what held
+// the frame up
+
+struct Graph {
+    // Milliseconds at the top of the graph and in one refresh period
+    scale: f32,
+    period: f32,
+    // Ring index of the newest frame, and how many frames are filled in
+    newest: u32,
+    count: u32,
+}
+
+@group(0) @binding(0) var<uniform> graph: Graph;
+// Present interval, CPU time, GPU time (negative when unknown) and the
+// class, all but the class in milliseconds
+@group(0) @binding(1) var<storage, read> frames: array<vec4<f32>>;
+
+const HISTORY: u32 = 240u;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(f32(i & 1u) * 4.0 - 1.0, f32(i >> 1u) * 4.0 - 1.0, 0.0, 1.0);
+    // Zero at the bottom left of the viewport
+    out.uv = out.position.xy * 0.5 + 0.5;
+    return out;
+}
+
+fn class_color(bound: f32) -> vec3<f32> {
+    // On time, CPU bound, GPU bound, presentation bound
+    if bound < 0.5 {
+        return vec3<f32>(0.3, 0.75, 0.3);
+    }
+    if bound < 1.5 {
+        return vec3<f32>(0.3, 0.5, 1.0);
+    }
+    if bound < 2.5 {
+        return vec3<f32>(1.0, 0.55, 0.15);
+    }
+    return vec3<f32>(0.75, 0.35, 0.9);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let column = min(u32(in.uv.x * f32(HISTORY)), HISTORY - 1u);
+    let ms = in.uv.y * graph.scale;
+    // About a pixel and a half, whatever the scale
+    let line = fwidth(ms) * 0.75;
+
+    var color = vec4<f32>(0.0, 0.0, 0.0, 0.6);
+    // One and two refresh periods
+    if abs(ms - graph.period) < line || abs(ms - 2.0 * graph.period) < line {
+        color = vec4<f32>(0.8, 0.8, 0.8, 0.9);
+    }
+
+    // Columns the ring hasn't filled yet stay empty
+    let age = HISTORY - 1u - column;
+    if age < graph.count {
+        let frame = frames[(graph.newest + HISTORY - age) % HISTORY];
+        if ms < frame.x {
+            color = vec4<f32>(class_color(frame.w), 0.85);
+        }
+        if abs(ms - frame.y) < line {
+            color = vec4<f32>(0.4, 0.9, 1.0, 1.0);
+        }
+        if frame.z >= 0.0 && abs(ms - frame.z) < line {
+            color = vec4<f32>(1.0, 0.9, 0.3, 1.0);
+        }
+    }
+    return vec4<f32>(pow(color.rgb, vec3<f32>(2.2)), color.a);
+}
@@ -0,0 +1,21 @@
+// Pasted in front of every post-processing shader, see post.rs. Stages
+// declare their own params at binding 0 and input textures from binding 2.
+
+struct FullscreenOutput {
+    @builtin(position) position: vec4<f32>,
+    // 0, 0 at the top left
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(1)
+var post_sampler: sampler;
+
+// One triangle covering the whole target
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: FullscreenOutput;
+    out.position = vec4<f32>(ndc, 0.0, 1.0);
+    out.uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    return out;
+}
@@ -0,0 +1,7 @@
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb, 1.0);
+}
@@ -0,0 +1,379 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::gpu::Gpu;
+
+// A sample's simulation state read back from the GPU, as named sections of
+// bytes. On disk, all little endian:
+//   "WGPUSNAP", format version (u32)
+//   sample name, sample's own state version (u32), section count (u32)
+//   per section: name, width, height (u32 each, 0 for buffers), data
+// with strings and data prefixed by their length (u64 for data, u32
+// otherwise). The sample's version lets it refuse snapshots from before a
+// layout change instead of loading garbage.
+
+const MAGIC: &[u8; 8] = b"WGPUSNAP";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    Format(String),
+    WrongSample { expected: String, found: String },
+    WrongVersion { expected: u32, found: u32 },
+    Missing(String),
+    // A section that doesn't fit what it's restored into
+    Size { name: String, expected: u64, found: u64 },
+    // A texture format without a fixed size per texel, e.g. Depth24Plus
+    UnsupportedFormat(wgpu::TextureFormat),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::Format(error) => write!(f, "not a snapshot: {}", error),
+            SnapshotError::WrongSample { expected, found } => write!(f, "saved by {}, not {}", found, expected),
+            SnapshotError::WrongVersion { expected, found } => {
+                write!(f, "state version {}, this build reads {}", found, expected)
+            }
+            SnapshotError::Missing(name) => write!(f, "no {} section", name),
+            SnapshotError::Size { name, expected, found } => {
+                write!(f, "{} is {} bytes, expected {}", name, found, expected)
+            }
+            SnapshotError::UnsupportedFormat(format) => write!(f, "can't snapshot {:?} textures", format),
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+struct Section {
+    name: String,
+    // Texels for textures, 0 for buffers and plain bytes
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+pub struct Snapshot {
+    pub sample: String,
+    pub version: u32,
+    sections: Vec<Section>,
+}
+
+impl Snapshot {
+    pub fn new(sample: &str, version: u32) -> Self {
+        Self {
+            sample: sample.to_string(),
+            version,
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    // CPU side state, e.g. which of a ping-pong pair is current
+    pub fn add_bytes(&mut self, name: &str, data: &[u8]) {
+        self.push(name, 0, 0, data.to_vec());
+    }
+
+    // The whole buffer, which needs COPY_SRC. Blocks until it's read back.
+    pub fn add_buffer(&mut self, gpu: &Gpu, name: &str, buffer: &wgpu::Buffer) {
+        let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Snapshot Readback"),
+            size: buffer.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Snapshot Encoder"),
+                },
+            );
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, buffer.size());
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let data = read_staging(gpu, &staging);
+        self.push(name, 0, 0, data);
+    }
+
+    // Mip 0 of a 2D texture with COPY_SRC, rows tightly packed. Blocks
+    // until it's read back.
+    pub fn add_texture(&mut self, gpu: &Gpu, name: &str, texture: &wgpu::Texture) -> Result<(), SnapshotError> {
+        let (width, height) = (texture.width(), texture.height());
+        let row_bytes = width * texel_size(texture.format())?;
+        let padded_row_bytes = wgpu::util::align_to(row_bytes, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Snapshot Readback"),
+            size: (padded_row_bytes * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Snapshot Encoder"),
+                },
+            );
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &staging,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let padded = read_staging(gpu, &staging);
+        let data = padded
+            .chunks(padded_row_bytes as usize)
+            .flat_map(|row| &row[..row_bytes as usize])
+            .copied()
+            .collect();
+        self.push(name, width, height, data);
+        Ok(())
+    }
+
+    pub fn bytes(&self, name: &str) -> Result<&[u8], SnapshotError> {
+        self.section(name).map(|section| section.data.as_slice())
+    }
+
+    // Writes the section back into a buffer of the same size with COPY_DST
+    pub fn restore_buffer(&self, gpu: &Gpu, name: &str, buffer: &wgpu::Buffer) -> Result<(), SnapshotError> {
+        let section = self.section(name)?;
+        check_size(name, buffer.size(), section.data.len() as u64)?;
+        gpu.queue.write_buffer(buffer, 0, &section.data);
+        Ok(())
+    }
+
+    // Writes the section back into a texture of the same size and texel
+    // size with COPY_DST
+    pub fn restore_texture(&self, gpu: &Gpu, name: &str, texture: &wgpu::Texture) -> Result<(), SnapshotError> {
+        let section = self.section(name)?;
+        let row_bytes = texture.width() * texel_size(texture.format())?;
+        check_size(name, (row_bytes * texture.height()) as u64, section.data.len() as u64)?;
+        if (section.width, section.height) != (texture.width(), texture.height()) {
+            return Err(SnapshotError::Format(format!(
+                "{} is {}x{}, expected {}x{}",
+                name,
+                section.width,
+                section.height,
+                texture.width(),
+                texture.height(),
+            )));
+        }
+        gpu.queue.write_texture(
+            texture.as_image_copy(),
+            &section.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(row_bytes),
+                rows_per_image: None,
+            },
+            texture.size(),
+        );
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_string(&mut out, &self.sample);
+        out.extend_from_slice(&self.version.to_le_bytes());
+        out.extend_from_slice(&(self.sections.len() as u32).to_le_bytes());
+        for section in &self.sections {
+            write_string(&mut out, &section.name);
+            out.extend_from_slice(&section.width.to_le_bytes());
+            out.extend_from_slice(&section.height.to_le_bytes());
+            out.extend_from_slice(&(section.data.len() as u64).to_le_bytes());
+            out.extend_from_slice(&section.data);
+        }
+        // Written next to it and renamed, so a crash mid-write leaves the
+        // last good checkpoint alone
+        let partial = path.with_extension("part");
+        std::fs::File::create(&partial)?.write_all(&out)?;
+        std::fs::rename(&partial, path)
+    }
+
+    // Checks it was saved by `sample` at `version`
+    pub fn load(path: &Path, sample: &str, version: u32) -> Result<Self, SnapshotError> {
+        let mut data = Vec::new();
+        std::fs::File::open(path)?.read_to_end(&mut data)?;
+        let mut reader = Reader(&data);
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::Format("wrong magic".to_string()));
+        }
+        let format_version = reader.u32()?;
+        if format_version != FORMAT_VERSION {
+            return Err(SnapshotError::Format(format!("format version {}", format_version)));
+        }
+        let found = reader.string()?;
+        if found != sample {
+            return Err(SnapshotError::WrongSample { expected: sample.to_string(), found });
+        }
+        let found_version = reader.u32()?;
+        if found_version != version {
+            return Err(SnapshotError::WrongVersion { expected: version, found: found_version });
+        }
+
+        let mut snapshot = Snapshot::new(sample, version);
+        for _ in 0..reader.u32()? {
+            let name = reader.string()?;
+            let width = reader.u32()?;
+            let height = reader.u32()?;
+            let length = reader.u64()? as usize;
+            let data = reader.take(length)?.to_vec();
+            snapshot.push(&name, width, height, data);
+        }
+        Ok(snapshot)
+    }
+
+    fn push(&mut self, name: &str, width: u32, height: u32, data: Vec<u8>) {
+        self.sections.retain(|section| section.name != name);
+        self.sections.push(Section {
+            name: name.to_string(),
+            width,
+            height,
+            data,
+        });
+    }
+
+    fn section(&self, name: &str) -> Result<&Section, SnapshotError> {
+        self.sections
+            .iter()
+            .find(|section| section.name == name)
+            .ok_or_else(|| SnapshotError::Missing(name.to_string()))
+    }
+}
+
+fn texel_size(format: wgpu::TextureFormat) -> Result<u32, SnapshotError> {
+    format.block_size(None).ok_or(SnapshotError::UnsupportedFormat(format))
+}
+
+fn check_size(name: &str, expected: u64, found: u64) -> Result<(), SnapshotError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(SnapshotError::Size { name: name.to_string(), expected, found })
+    }
+}
+
+fn read_staging(gpu: &Gpu, staging: &wgpu::Buffer) -> Vec<u8> {
+    let slice = staging.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    gpu.device.poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range().to_vec();
+    staging.unmap();
+    data
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < length {
+            return Err(SnapshotError::Format("truncated".to_string()));
+        }
+        let (taken, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, SnapshotError> {
+        let length = self.u32()? as usize;
+        String::from_utf8(self.take(length)?.to_vec()).map_err(|_| SnapshotError::Format("bad string".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // Saved to a file of its own per test, since they run in parallel
+    fn saved(test: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("wgpu-samples-{}-{}.state", test, std::process::id()));
+        let mut snapshot = Snapshot::new("particles", 2);
+        snapshot.add_bytes("current", &[1]);
+        snapshot.add_bytes("positions", &[0; 64]);
+        snapshot.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn round_trips() {
+        let path = saved("round-trips");
+        let snapshot = Snapshot::load(&path, "particles", 2).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(snapshot.bytes("current").unwrap(), &[1u8]);
+        assert_eq!(snapshot.bytes("positions").unwrap(), &[0u8; 64]);
+        assert!(matches!(snapshot.bytes("velocities"), Err(SnapshotError::Missing(_))));
+    }
+
+    #[test]
+    fn refuses_another_samples_snapshot() {
+        let path = saved("wrong-sample");
+        let loaded = Snapshot::load(&path, "jacobi", 2);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(SnapshotError::WrongSample { expected, found }) if expected == "jacobi" && found == "particles"));
+    }
+
+    #[test]
+    fn refuses_another_state_version() {
+        let path = saved("wrong-version");
+        let loaded = Snapshot::load(&path, "particles", 3);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(SnapshotError::WrongVersion { expected: 3, found: 2 })));
+    }
+
+    #[test]
+    fn refuses_a_truncated_file() {
+        let path = saved("truncated");
+        let length = std::fs::metadata(&path).unwrap().len();
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(length - 1).unwrap();
+        let loaded = Snapshot::load(&path, "particles", 2);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(SnapshotError::Format(error)) if error == "truncated"));
+    }
+
+    #[test]
+    fn refuses_formats_without_a_texel_size() {
+        assert_eq!(texel_size(wgpu::TextureFormat::Rgba8Unorm).unwrap(), 4);
+        assert!(matches!(
+            texel_size(wgpu::TextureFormat::Depth24Plus),
+            Err(SnapshotError::UnsupportedFormat(wgpu::TextureFormat::Depth24Plus))
+        ));
+    }
+}
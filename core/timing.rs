@@ -0,0 +1,22 @@
+use std::time::{Duration, Instant};
+
+use crate::Gpu;
+
+// How long the GPU takes over what `record` puts in one submission, by the
+// wall clock around a blocking submit since timestamp queries aren't
+// everywhere. Includes the submission overhead, so record enough work that
+// it doesn't matter, and take the fastest of a few calls: the others caught
+// something else running.
+pub fn time_submission(gpu: &Gpu, record: impl FnOnce(&mut wgpu::CommandEncoder)) -> Duration {
+    let mut encoder =
+        gpu.device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Timed Encoder"),
+            },
+        );
+    record(&mut encoder);
+    let start = Instant::now();
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+    gpu.device.poll(wgpu::Maintain::Wait);
+    start.elapsed()
+}
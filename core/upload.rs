@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wgpu::{Buffer, CommandEncoder, Device, Texture};
+
+// Staging buffers kept around at most. Each holds a whole texture, so this
+// bounds the memory as much as how far the CPU may run ahead.
+const MAX_SLOTS: usize = 3;
+
+struct Slot {
+    buffer: Buffer,
+    // The copy has been recorded but map_async can only be called once
+    // it's submitted
+    needs_map: bool,
+    // Mapped and ready to be written. Set by the map_async callback, or
+    // from the start for buffers mapped at creation.
+    mapped: Arc<AtomicBool>,
+}
+
+// Streams whole texture contents every frame through a small ring of
+// MAP_WRITE staging buffers. The caller fills the mapped memory directly,
+// so the data is written once and copied once by the GPU, where
+// write_texture copies it into wgpu's own staging memory first. The
+// texture-upload sample measures it against the alternatives.
+pub struct TextureStreamer {
+    slots: Vec<Slot>,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl TextureStreamer {
+    // For textures of the same size and format as this one. Staging buffers
+    // are only created once they're needed.
+    pub fn new(texture: &Texture) -> Self {
+        let bytes_per_row = texture.width() * texture.format().block_size(None).unwrap();
+        Self {
+            slots: Vec::new(),
+            height: texture.height(),
+            padded_bytes_per_row: wgpu::util::align_to(bytes_per_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+        }
+    }
+
+    // Rows in the slice passed to `fill` start this many bytes apart, with
+    // the padding after each row left alone
+    pub fn padded_bytes_per_row(&self) -> u32 {
+        self.padded_bytes_per_row
+    }
+
+    pub fn staging_buffers(&self) -> usize {
+        self.slots.len()
+    }
+
+    // Hands `fill` mapped staging memory for the whole texture and records
+    // its copy into `texture`. Only blocks when all staging buffers are
+    // still in use by the GPU.
+    pub fn upload(&mut self, device: &Device, encoder: &mut CommandEncoder, texture: &Texture, fill: impl FnOnce(&mut [u8])) {
+        let index = self.free_slot(device);
+        let slot = &mut self.slots[index];
+        slot.mapped.store(false, Ordering::Relaxed);
+
+        fill(&mut slot.buffer.slice(..).get_mapped_range_mut());
+        slot.buffer.unmap();
+
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &slot.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            texture.as_image_copy(),
+            texture.size(),
+        );
+        slot.needs_map = true;
+    }
+
+    // Call after submitting the encoder passed to upload
+    pub fn map_submitted(&mut self) {
+        for slot in self.slots.iter_mut().filter(|slot| slot.needs_map) {
+            slot.needs_map = false;
+            let mapped = slot.mapped.clone();
+            slot.buffer.slice(..).map_async(wgpu::MapMode::Write, move |result| {
+                result.unwrap();
+                mapped.store(true, Ordering::Release);
+            });
+        }
+    }
+
+    fn free_slot(&mut self, device: &Device) -> usize {
+        device.poll(wgpu::Maintain::Poll);
+        let find = |slots: &[Slot]| slots.iter().position(|slot| slot.mapped.load(Ordering::Acquire));
+        if let Some(index) = find(&self.slots) {
+            return index;
+        }
+
+        if self.slots.len() < MAX_SLOTS {
+            self.slots.push(Slot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Texture Staging"),
+                    size: (self.padded_bytes_per_row * self.height) as u64,
+                    usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                }),
+                needs_map: false,
+                mapped: Arc::new(AtomicBool::new(true)),
+            });
+            return self.slots.len() - 1;
+        }
+
+        assert!(
+            self.slots.iter().all(|slot| !slot.needs_map),
+            "map_submitted wasn't called after the last upload",
+        );
+        loop {
+            device.poll(wgpu::Maintain::Wait);
+            if let Some(index) = find(&self.slots) {
+                return index;
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, BindGroup, Buffer, Device, RenderPipeline, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+// Distance at which the two images line up, i.e. the depth of the screen
+const CONVERGENCE: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Anaglyph,
+    Mono,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Instance {
+    offset: [f32; 3],
+    spin: f32,
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![2 => Float32x3, 3 => Float32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    time: f32,
+    _padding: [f32; 3],
+}
+
+// A unit cube with flat normals, four vertices per face
+fn cube() -> (Vec<Vertex>, Vec<u16>) {
+    let faces = [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, up) in faces {
+        let right = up.cross(normal);
+        let base = vertices.len() as u16;
+        for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            vertices.push(Vertex {
+                position: ((normal + right * u + up * v) * 0.5).to_array(),
+                normal: normal.to_array(),
+            });
+        }
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    (vertices, indices)
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+struct Eye {
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+pub struct Renderer {
+    // Identical apart from the write mask
+    left_pipeline: RenderPipeline,
+    right_pipeline: RenderPipeline,
+    mono_pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    instance_buffer: Buffer,
+    instance_count: u32,
+    eyes: [Eye; 2],
+    depth_view: TextureView,
+    mode: Mode,
+    // Distance between the eyes in world units
+    separation: f32,
+    start: Instant,
+    // Set by --flythrough or a script, the fixed view in front of the
+    // cubes until then
+    camera: Option<CameraPose>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let (vertices, indices) = cube();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // A grid of cubes receding from in front of the screen to far
+        // behind it
+        let instances: Vec<Instance> = (0..5)
+            .flat_map(|z| {
+                (0..4).map(move |x| Instance {
+                    offset: [x as f32 * 2.2 - 3.3, (z % 2) as f32 - 0.5, 4.0 - z as f32 * 3.0],
+                    spin: 0.3 + (x + z) as f32 * 0.15,
+                })
+            })
+            .collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        // One set of uniforms per eye, both passes go into the same submit
+        let eyes = ["Left Eye", "Right Eye"].map(|label| {
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                }],
+            });
+            Eye {
+                uniform_buffer,
+                bind_group,
+            }
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_pipeline = |label, write_mask| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.config.format,
+                        blend: None,
+                        // Channels outside the mask keep whatever the
+                        // target held, here the other eye's image
+                        write_mask,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        // Red-cyan glasses: red filter over the left eye
+        let left_pipeline = create_pipeline("Left Eye Pipeline", wgpu::ColorWrites::RED);
+        let right_pipeline = create_pipeline("Right Eye Pipeline", wgpu::ColorWrites::GREEN | wgpu::ColorWrites::BLUE);
+        let mono_pipeline = create_pipeline("Mono Pipeline", wgpu::ColorWrites::ALL);
+
+        Self {
+            left_pipeline,
+            right_pipeline,
+            mono_pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer,
+            instance_count: instances.len() as u32,
+            eyes,
+            depth_view: create_depth_view(device, &gpu.config),
+            mode: Mode::Anaglyph,
+            separation: 0.3,
+            start: Instant::now(),
+            camera: None,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match key {
+                VirtualKeyCode::Space => {
+                    self.mode = match self.mode {
+                        Mode::Anaglyph => Mode::Mono,
+                        Mode::Mono => Mode::Anaglyph,
+                    };
+                }
+                VirtualKeyCode::Up => self.separation = (self.separation + 0.05).min(1.0),
+                VirtualKeyCode::Down => self.separation = (self.separation - 0.05).max(0.0),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{:?}, eye separation {:.2}", self.mode, self.separation))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let time = self.start.elapsed().as_secs_f32();
+        let proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), 0.1, 100.0);
+        let pose = self.camera.unwrap_or(CameraPose {
+            position: Vec3::new(0.0, 1.5, CONVERGENCE),
+            yaw: 0.0,
+            pitch: 0.0,
+        });
+        let direction = pose.direction();
+        let right = direction.cross(Vec3::Y).normalize();
+
+        // Parallel cameras with the images shifted back towards each other
+        // (an off-axis frustum), so objects at CONVERGENCE line up and
+        // there's no vertical parallax
+        let eye_matrices = [-0.5, 0.5].map(|side: f32| {
+            let offset = right * side * self.separation;
+            let view_matrix = Mat4::look_to_rh(pose.position + offset, direction, Vec3::Y);
+            let shift = -side * self.separation * proj.x_axis.x / CONVERGENCE;
+            Mat4::from_translation(Vec3::new(shift, 0.0, 0.0)) * proj * view_matrix
+        });
+
+        for (eye, view_proj) in self.eyes.iter().zip(eye_matrices) {
+            gpu.queue.write_buffer(
+                &eye.uniform_buffer,
+                0,
+                bytemuck::bytes_of(&Uniforms {
+                    view_proj: view_proj.to_cols_array_2d(),
+                    time,
+                    _padding: [0.0; 3],
+                }),
+            );
+        }
+
+        let passes: Vec<(&RenderPipeline, &Eye)> = match self.mode {
+            Mode::Anaglyph => vec![(&self.left_pipeline, &self.eyes[0]), (&self.right_pipeline, &self.eyes[1])],
+            Mode::Mono => vec![(&self.mono_pipeline, &self.eyes[0])],
+        };
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        for (index, (pipeline, eye)) in passes.iter().enumerate() {
+            // Only the first pass clears color, the second one has to add
+            // its channels to the first eye's
+            let load = if index == 0 {
+                wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.1,
+                    g: 0.1,
+                    b: 0.1,
+                    a: 1.0,
+                })
+            } else {
+                wgpu::LoadOp::Load
+            };
+
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load,
+                                store: true,
+                            },
+                        },
+                    )],
+                    // Every eye sees the scene from a different spot, so
+                    // depth starts over
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &eye.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,50 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(2) offset: vec3<f32>,
+    @location(3) spin: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+}
+
+// Rodrigues' rotation formula
+fn rotate(v: vec3<f32>, axis: vec3<f32>, angle: f32) -> vec3<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return v * c + cross(axis, v) * s + axis * dot(axis, v) * (1.0 - c);
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let axis = normalize(vec3<f32>(1.0, 1.0, 0.3));
+    let angle = uniforms.time * instance.spin;
+
+    var out: VertexOutput;
+    let world = rotate(vertex.position, axis, angle) + instance.offset;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world, 1.0);
+    out.normal = rotate(vertex.normal, axis, angle);
+    return out;
+}
+
+// Gray only: a saturated color would look different through the red and
+// the cyan filter and break the fusion
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light = normalize(vec3<f32>(0.4, 0.8, 0.6));
+    let shade = 0.15 + 0.85 * max(dot(normalize(in.normal), light), 0.0);
+    return vec4<f32>(vec3<f32>(shade), 1.0);
+}
@@ -0,0 +1,9 @@
+mod mesh;
+mod octree;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("asteroids");
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Lumps that make a sphere look like a rock. A function of the direction
+// only, so every level of detail gets the same silhouette.
+fn displacement(direction: Vec3) -> f32 {
+    1.0 + 0.18 * (direction.x * 3.1 + 0.7).sin() * (direction.y * 4.3 + 1.9).sin()
+        + 0.1 * (direction.z * 7.7 + direction.x * 2.3).sin()
+        - 0.08 * (direction.y * 9.1 - direction.z * 5.3).cos()
+}
+
+// A subdivided icosahedron pushed out by displacement, with normals
+// averaged from the faces around each vertex. 1 subdivision gives 80
+// triangles, 4 gives 5120.
+pub fn asteroid(subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut directions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges are shared by two triangles, both have to use the same midpoint
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                directions.push((directions[a as usize] + directions[b as usize]).normalize());
+                directions.len() as u32 - 1
+            })
+        };
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b);
+                let bc = midpoint(b, c);
+                let ca = midpoint(c, a);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let positions: Vec<Vec3> = directions.iter().map(|&direction| direction * displacement(direction)).collect();
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for &[a, b, c] in &triangles {
+        let [pa, pb, pc] = [a, b, c].map(|index| positions[index as usize]);
+        // Unnormalized, so bigger faces weigh more
+        let normal = (pb - pa).cross(pc - pa);
+        for index in [a, b, c] {
+            normals[index as usize] += normal;
+        }
+    }
+
+    let vertices = positions
+        .iter()
+        .zip(&normals)
+        .map(|(position, normal)| Vertex {
+            position: position.to_array(),
+            normal: normal.normalize().to_array(),
+        })
+        .collect();
+    (vertices, triangles.into_iter().flatten().collect())
+}
+
+// A camera facing square for the impostors, the shader turns it into a
+// shaded disc
+pub fn impostor_quad() -> (Vec<Vertex>, Vec<u32>) {
+    let vertices = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .iter()
+        .map(|&(x, y)| Vertex {
+            position: [x, y, 0.0],
+            normal: [0.0, 0.0, 1.0],
+        })
+        .collect();
+    (vertices, vec![0, 1, 2, 0, 2, 3])
+}
@@ -0,0 +1,168 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+pub const OBJECT_COUNT: usize = 100_000;
+// Leaves split once they hold more than this many asteroids, so a leaf is
+// small enough that one LOD fits all of it
+const LEAF_CAPACITY: usize = 256;
+const MAX_DEPTH: u32 = 8;
+// The belt around the origin
+const INNER_RADIUS: f32 = 400.0;
+const OUTER_RADIUS: f32 = 900.0;
+const THICKNESS: f32 = 60.0;
+
+// One asteroid as the shaders see it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Object {
+    pub position: [f32; 3],
+    pub scale: f32,
+    pub axis: [f32; 3],
+    // Radians per second around axis
+    pub spin: f32,
+    // Per axis scale on top of scale, so the same mesh makes many shapes
+    pub stretch: [f32; 3],
+    pub leaf: u32,
+}
+
+impl Object {
+    // Bounding radius, whatever the rotation
+    fn radius(&self) -> f32 {
+        self.scale * self.stretch.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+// A leaf's bounding sphere, which is all the selection shader needs
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Leaf {
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+// xorshift is plenty for scattering asteroids and keeps every run the same
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+// The asteroids sorted so each leaf owns a contiguous run of them, with
+// every asteroid knowing its leaf. Only leaves go to the GPU: the inner
+// nodes are how the space gets divided, the leaves are what LOD and culling
+// are decided for.
+pub struct Octree {
+    pub objects: Vec<Object>,
+    pub leaves: Vec<Leaf>,
+    pub node_count: usize,
+    pub depth: u32,
+}
+
+impl Octree {
+    pub fn generate() -> Self {
+        let mut rng = Rng(0x2545_f491);
+        let objects: Vec<Object> = (0..OBJECT_COUNT)
+            .map(|_| {
+                let angle = rng.range(0.0, std::f32::consts::TAU);
+                // Denser towards the middle of the belt
+                let t = (rng.next_f32() + rng.next_f32()) * 0.5;
+                let distance = INNER_RADIUS + (OUTER_RADIUS - INNER_RADIUS) * t;
+                let height = (rng.next_f32() + rng.next_f32() - 1.0) * THICKNESS;
+                // Mostly small rocks with the odd big one
+                let scale = 0.5 + rng.next_f32().powi(6) * 12.0;
+                let axis = Vec3::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), rng.range(-1.0, 1.0)).normalize_or_zero();
+                Object {
+                    position: [angle.cos() * distance, height, angle.sin() * distance],
+                    scale,
+                    axis: if axis == Vec3::ZERO { Vec3::Y } else { axis }.to_array(),
+                    spin: rng.range(-1.0, 1.0),
+                    stretch: [rng.range(0.6, 1.4), rng.range(0.5, 1.0), rng.range(0.6, 1.4)],
+                    leaf: 0,
+                }
+            })
+            .collect();
+
+        let (min, max) = objects.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), object| {
+            let position = Vec3::from(object.position);
+            (min.min(position), max.max(position))
+        });
+        // A cube, so every level halves all three axes alike
+        let center = (min + max) * 0.5;
+        let half_size = (max - min).max_element() * 0.5;
+
+        let mut octree = Self {
+            objects: Vec::with_capacity(OBJECT_COUNT),
+            leaves: Vec::new(),
+            node_count: 0,
+            depth: 0,
+        };
+        octree.build(objects, center, half_size, 0);
+        octree
+    }
+
+    fn build(&mut self, objects: Vec<Object>, center: Vec3, half_size: f32, depth: u32) {
+        self.node_count += 1;
+        self.depth = self.depth.max(depth);
+
+        if objects.len() <= LEAF_CAPACITY || depth == MAX_DEPTH {
+            self.add_leaf(objects);
+            return;
+        }
+
+        let mut children: [Vec<Object>; 8] = Default::default();
+        for object in objects {
+            let position = Vec3::from(object.position);
+            let octant = (position.x > center.x) as usize
+                | ((position.y > center.y) as usize) << 1
+                | ((position.z > center.z) as usize) << 2;
+            children[octant].push(object);
+        }
+
+        let quarter = half_size * 0.5;
+        for (octant, child) in children.into_iter().enumerate() {
+            // Empty octants don't get a node, most of the belt's cube is empty
+            if child.is_empty() {
+                continue;
+            }
+            let offset = Vec3::new(
+                if octant & 1 != 0 { quarter } else { -quarter },
+                if octant & 2 != 0 { quarter } else { -quarter },
+                if octant & 4 != 0 { quarter } else { -quarter },
+            );
+            self.build(child, center + offset, quarter, depth + 1);
+        }
+    }
+
+    // Bounds the asteroids themselves rather than the cell, which is much
+    // tighter where a cell only clips the edge of the belt
+    fn add_leaf(&mut self, mut objects: Vec<Object>) {
+        let (min, max) = objects.iter().fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), object| {
+            let position = Vec3::from(object.position);
+            (min.min(position), max.max(position))
+        });
+        let center = (min + max) * 0.5;
+        let radius = objects
+            .iter()
+            .map(|object| Vec3::from(object.position).distance(center) + object.radius())
+            .fold(0.0, f32::max);
+
+        let leaf = self.leaves.len() as u32;
+        for object in &mut objects {
+            object.leaf = leaf;
+        }
+        self.objects.extend(objects);
+        self.leaves.push(Leaf {
+            center: center.to_array(),
+            radius,
+        });
+    }
+}
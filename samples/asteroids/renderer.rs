@@ -0,0 +1,700 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3, Vec4};
+use framework::{CameraPose, Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, ComputePipeline, Device, RenderPipeline, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::mesh::{self, Vertex};
+use crate::octree::{Octree, OBJECT_COUNT};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const WORKGROUP_SIZE: u32 = 64;
+const MOVE_SPEED: f32 = 80.0;
+const TURN_SPEED: f32 = 1.2;
+// Full mesh up to the first distance, low mesh up to the second,
+// impostors beyond. Scaled by lod_scale.
+const LOD_DISTANCES: [f32; 2] = [120.0, 450.0];
+const LEVELS: usize = 3;
+
+// Laid out like wgpu's DrawIndexedIndirect, which is also what the
+// selection shader sees
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DrawArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Selection {
+    planes: [[f32; 4]; 6],
+    camera: [f32; 4],
+    lod_distances: [f32; 4],
+    counts: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+    position: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    tint: [f32; 4],
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// Gribb-Hartmann: each plane is a sum or difference of the matrix's rows,
+// pointing into the frustum. wgpu's depth runs 0..1, so near is row 2 alone.
+fn frustum_planes(view_proj: Mat4) -> [[f32; 4]; 6] {
+    let [r0, r1, r2, r3] = [0, 1, 2, 3].map(|i| view_proj.row(i));
+    [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r2, r3 - r2].map(|plane: Vec4| (plane / plane.truncate().length()).to_array())
+}
+
+fn storage_entry(binding: u32, visibility: wgpu::ShaderStages, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// The instance counts of the last frame whose readback has landed, so the
+// status can show them without ever waiting for the GPU
+struct CountReadback {
+    buffer: Buffer,
+    pending: bool,
+    needs_map: bool,
+    mapped: Arc<AtomicBool>,
+    counts: [u32; LEVELS],
+}
+
+impl CountReadback {
+    fn new(device: &Device) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Count Readback"),
+                size: std::mem::size_of::<[DrawArgs; LEVELS]>() as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            counts: [0; LEVELS],
+        }
+    }
+
+    fn request(&mut self, encoder: &mut wgpu::CommandEncoder, draws: &Buffer) {
+        if self.pending {
+            return;
+        }
+        encoder.copy_buffer_to_buffer(draws, 0, &self.buffer, 0, self.buffer.size());
+        self.pending = true;
+        self.needs_map = true;
+    }
+
+    // Call after submitting the encoder passed to request
+    fn map_submitted(&mut self) {
+        if !std::mem::take(&mut self.needs_map) {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            result.unwrap();
+            mapped.store(true, Ordering::Release);
+        });
+    }
+
+    fn poll(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+        {
+            let data = self.buffer.slice(..).get_mapped_range();
+            let draws: &[DrawArgs] = bytemuck::cast_slice(&data);
+            for (count, draw) in self.counts.iter_mut().zip(draws) {
+                *count = draw.instance_count;
+            }
+        }
+        self.buffer.unmap();
+        self.pending = false;
+    }
+}
+
+pub struct Renderer {
+    leaves_pipeline: ComputePipeline,
+    objects_pipeline: ComputePipeline,
+    select_bind_group: BindGroup,
+    mesh_pipeline: RenderPipeline,
+    impostor_pipeline: RenderPipeline,
+    camera_bind_group: BindGroup,
+    // One per level, each binding that level's instance list
+    lod_bind_groups: Vec<BindGroup>,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    // Reset to these every frame before the selection counts instances
+    draw_args: [DrawArgs; LEVELS],
+    draw_buffer: Buffer,
+    selection_buffer: Buffer,
+    camera_buffer: Buffer,
+    readback: CountReadback,
+    depth_view: TextureView,
+    leaf_count: u32,
+    node_count: usize,
+    depth: u32,
+    // u32s between the starts of the levels' instance lists
+    list_stride: u32,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+    lod_scale: f32,
+    tint: bool,
+    // Selection keeps using this frustum and position while set, so the
+    // culling can be looked at from outside
+    frozen: Option<([[f32; 4]; 6], Vec3)>,
+    toggle_freeze: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    start: Instant,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let octree = Octree::generate();
+        let leaf_count = octree.leaves.len() as u32;
+
+        // All three meshes share one vertex and one index buffer, the draw
+        // arguments say which part each level uses
+        let meshes = [mesh::asteroid(3), mesh::asteroid(1), mesh::impostor_quad()];
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut draw_args = [DrawArgs::zeroed(); LEVELS];
+        for ((mesh_vertices, mesh_indices), args) in meshes.into_iter().zip(&mut draw_args) {
+            *args = DrawArgs {
+                index_count: mesh_indices.len() as u32,
+                instance_count: 0,
+                first_index: indices.len() as u32,
+                base_vertex: vertices.len() as i32,
+                first_instance: 0,
+            };
+            vertices.extend(mesh_vertices);
+            indices.extend(mesh_indices);
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let object_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Objects"),
+            contents: bytemuck::cast_slice(&octree.objects),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let leaf_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Leaves"),
+            contents: bytemuck::cast_slice(&octree.leaves),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let leaf_lod_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Leaf LODs"),
+            size: leaf_count as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let draw_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Draw Arguments"),
+            contents: bytemuck::cast_slice(&draw_args),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        });
+
+        // Room for every asteroid in every level's list. Each list starts at
+        // an offset storage bindings accept, so the draws can bind just
+        // their own and index it by instance_index.
+        let alignment = device.limits().min_storage_buffer_offset_alignment;
+        let list_bytes = wgpu::util::align_to(OBJECT_COUNT as u32 * 4, alignment);
+        let visible_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible"),
+            size: list_bytes as u64 * LEVELS as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let selection_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Selection"),
+            contents: bytemuck::bytes_of(&Selection::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera"),
+            contents: bytemuck::bytes_of(&CameraUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute = wgpu::ShaderStages::COMPUTE;
+        let select_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Selection Layout"),
+            entries: &[
+                uniform_entry(0, compute),
+                storage_entry(1, compute, true),
+                storage_entry(2, compute, false),
+                storage_entry(3, compute, true),
+                storage_entry(4, compute, false),
+                storage_entry(5, compute, false),
+            ],
+        });
+        let select_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Selection Bind Group"),
+            layout: &select_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: selection_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: leaf_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: leaf_lod_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: object_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: draw_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: visible_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let select_shader = device.create_shader_module(include_wgsl!("shaders/select.wgsl"));
+        let select_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Selection Pipeline Layout"),
+            bind_group_layouts: &[&select_layout],
+            push_constant_ranges: &[],
+        });
+        let compute_pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&select_pipeline_layout),
+                module: &select_shader,
+                entry_point,
+            })
+        };
+        let leaves_pipeline = compute_pipeline("Select Leaves", "cs_leaves");
+        let objects_pipeline = compute_pipeline("Select Objects", "cs_objects");
+
+        let vertex_stages = wgpu::ShaderStages::VERTEX;
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Layout"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                storage_entry(1, vertex_stages, true),
+            ],
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: object_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let lod_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("LOD Layout"),
+            entries: &[
+                storage_entry(0, vertex_stages, true),
+                uniform_entry(1, vertex_stages),
+            ],
+        });
+        let lod_bind_groups = (0..LEVELS)
+            .map(|level| Self::create_lod_bind_group(device, &lod_layout, &visible_buffer, list_bytes, level))
+            .collect();
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/asteroid.wgsl"));
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&camera_layout, &lod_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+        let render_pipeline = |label, vs_entry, fs_entry, cull_mode| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: vs_entry,
+                    buffers: &[Vertex::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fs_entry,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let mesh_pipeline = render_pipeline("Mesh Pipeline", "vs_mesh", "fs_mesh", Some(wgpu::Face::Back));
+        let impostor_pipeline = render_pipeline("Impostor Pipeline", "vs_impostor", "fs_impostor", None);
+
+        Self {
+            leaves_pipeline,
+            objects_pipeline,
+            select_bind_group,
+            mesh_pipeline,
+            impostor_pipeline,
+            camera_bind_group,
+            lod_bind_groups,
+            vertex_buffer,
+            index_buffer,
+            draw_args,
+            draw_buffer,
+            selection_buffer,
+            camera_buffer,
+            readback: CountReadback::new(device),
+            depth_view: create_depth_view(device, &gpu.config),
+            leaf_count,
+            node_count: octree.node_count,
+            depth: octree.depth,
+            list_stride: list_bytes / 4,
+            position: Vec3::new(0.0, 40.0, 700.0),
+            yaw: 0.0,
+            pitch: -0.1,
+            lod_scale: 1.0,
+            tint: false,
+            frozen: None,
+            toggle_freeze: false,
+            held_keys: HashSet::new(),
+            start: Instant::now(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Space => self.tint = !self.tint,
+                // The frustum is only known in render
+                VirtualKeyCode::F => self.toggle_freeze = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.position = pose.position;
+        self.yaw = pose.yaw;
+        self.pitch = pose.pitch;
+    }
+
+    fn status(&self) -> Option<String> {
+        let counts = self.readback.counts;
+        Some(format!(
+            "{} asteroids in {} leaves of {} nodes, depth {}, {} full, {} low, {} impostors, lod scale {:.2}{}",
+            OBJECT_COUNT,
+            self.leaf_count,
+            self.node_count,
+            self.depth,
+            counts[0],
+            counts[1],
+            counts[2],
+            self.lod_scale,
+            if self.frozen.is_some() { ", culling frozen" } else { "" },
+        ))
+    }
+
+    fn ui(&mut self, ui: &mut framework::egui::Ui) {
+        ui.add(framework::egui::Slider::new(&mut self.lod_scale, 0.05..=10.0).logarithmic(true).text("lod scale"));
+        ui.checkbox(&mut self.tint, "tint by level");
+        let freeze = if self.frozen.is_some() { "unfreeze culling" } else { "freeze culling" };
+        if ui.button(freeze).clicked() {
+            self.toggle_freeze = true;
+        }
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.update_camera();
+        {
+            framework::profile_scope!("count readback");
+            self.readback.poll(&gpu.device);
+        }
+
+        let direction = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        );
+        let view_matrix = Mat4::look_to_rh(self.position, direction, Vec3::Y);
+        let proj = Mat4::perspective_rh(60f32.to_radians(), gpu.aspect_ratio(), 0.5, 4000.0);
+        let view_proj = proj * view_matrix;
+
+        let planes = frustum_planes(view_proj);
+        if std::mem::take(&mut self.toggle_freeze) {
+            self.frozen = match self.frozen {
+                Some(_) => None,
+                None => Some((planes, self.position)),
+            };
+        }
+        let (planes, selection_position) = self.frozen.unwrap_or((planes, self.position));
+
+        gpu.queue.write_buffer(&self.draw_buffer, 0, bytemuck::cast_slice(&self.draw_args));
+        gpu.queue.write_buffer(
+            &self.selection_buffer,
+            0,
+            bytemuck::bytes_of(&Selection {
+                planes,
+                camera: selection_position.extend(1.0).to_array(),
+                lod_distances: [LOD_DISTANCES[0] * self.lod_scale, LOD_DISTANCES[1] * self.lod_scale, 0.0, 0.0],
+                counts: [self.leaf_count, OBJECT_COUNT as u32, self.list_stride, 0],
+            }),
+        );
+        // The view's rows are the camera's axes in world space
+        gpu.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&CameraUniform {
+                view_proj: view_proj.to_cols_array_2d(),
+                position: self.position.extend(self.start.elapsed().as_secs_f32()).to_array(),
+                right: view_matrix.row(0).truncate().extend(0.0).to_array(),
+                up: view_matrix.row(1).truncate().extend(0.0).to_array(),
+                tint: [if self.tint { 1.0 } else { 0.0 }, 0.0, 0.0, 0.0],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Selection Pass"),
+            });
+            compute_pass.set_bind_group(0, &self.select_bind_group, &[]);
+            compute_pass.set_pipeline(&self.leaves_pipeline);
+            compute_pass.dispatch_workgroups((self.leaf_count + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE, 1, 1);
+            compute_pass.set_pipeline(&self.objects_pipeline);
+            compute_pass.dispatch_workgroups((OBJECT_COUNT as u32 + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE, 1, 1);
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.01,
+                                    g: 0.01,
+                                    b: 0.02,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            // Everything in three draws, whatever the counts came out as
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            let args_size = std::mem::size_of::<DrawArgs>() as u64;
+            for (level, bind_group) in self.lod_bind_groups.iter().enumerate() {
+                let pipeline = if level == LEVELS - 1 { &self.impostor_pipeline } else { &self.mesh_pipeline };
+                render_pass.set_pipeline(pipeline);
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.draw_indexed_indirect(&self.draw_buffer, level as u64 * args_size);
+            }
+        }
+        self.readback.request(&mut encoder, &self.draw_buffer);
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+        self.readback.map_submitted();
+    }
+}
+
+impl Renderer {
+    fn create_lod_bind_group(device: &Device, layout: &BindGroupLayout, visible_buffer: &Buffer, list_bytes: u32, level: usize) -> BindGroup {
+        let level_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("LOD"),
+            contents: bytemuck::bytes_of(&[level as u32, 0, 0, 0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("LOD Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: visible_buffer,
+                        offset: list_bytes as u64 * level as u64,
+                        size: wgpu::BufferSize::new(list_bytes as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: level_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    fn update_camera(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let forward = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        );
+        let right = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+        let held = |key| self.held_keys.contains(&key);
+
+        let mut movement = Vec3::ZERO;
+        if held(VirtualKeyCode::W) { movement += forward; }
+        if held(VirtualKeyCode::S) { movement -= forward; }
+        if held(VirtualKeyCode::D) { movement += right; }
+        if held(VirtualKeyCode::A) { movement -= right; }
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        let mut tilt = 0.0;
+        if held(VirtualKeyCode::Up) { tilt += 1.0; }
+        if held(VirtualKeyCode::Down) { tilt -= 1.0; }
+        let mut lod_change = 0.0;
+        if held(VirtualKeyCode::X) { lod_change += 1.0; }
+        if held(VirtualKeyCode::Z) { lod_change -= 1.0; }
+
+        self.position += movement * MOVE_SPEED * dt;
+        self.yaw += turn * TURN_SPEED * dt;
+        self.pitch = (self.pitch + tilt * TURN_SPEED * dt).clamp(-1.5, 1.5);
+        self.lod_scale = (self.lod_scale * (lod_change * dt).exp()).clamp(0.05, 10.0);
+    }
+}
@@ -0,0 +1,125 @@
+struct Camera {
+    view_proj: mat4x4<f32>,
+    // w is the time in seconds
+    position: vec4<f32>,
+    // The view's axes in world space, for facing the impostors
+    right: vec4<f32>,
+    up: vec4<f32>,
+    // x is 1 to tint everything by its level of detail
+    tint: vec4<f32>,
+}
+
+struct Object {
+    position: vec3<f32>,
+    scale: f32,
+    axis: vec3<f32>,
+    spin: f32,
+    stretch: vec3<f32>,
+    leaf: u32,
+}
+
+struct Lod {
+    // x: 0 full mesh, 1 low mesh, 2 impostor
+    index: vec4<u32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+@group(0) @binding(1)
+var<storage, read> objects: array<Object>;
+// This level's part of the instance lists
+@group(1) @binding(0)
+var<storage, read> visible: array<u32>;
+@group(1) @binding(1)
+var<uniform> lod: Lod;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+    // The quad's corner, impostors only
+    @location(3) corner: vec2<f32>,
+}
+
+fn rotate(v: vec3<f32>, axis: vec3<f32>, angle: f32) -> vec3<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return v * c + cross(axis, v) * s + axis * dot(axis, v) * (1.0 - c);
+}
+
+// Greys and browns, varied per asteroid or tinted by level
+fn object_color(index: u32) -> vec3<f32> {
+    if camera.tint.x > 0.5 {
+        var tints = array<vec3<f32>, 3>(vec3<f32>(0.2, 0.9, 0.3), vec3<f32>(0.9, 0.8, 0.2), vec3<f32>(0.9, 0.3, 0.2));
+        return tints[lod.index.x];
+    }
+    let hash = fract(sin(f32(index) * 12.9898) * 43758.5453);
+    return mix(vec3<f32>(0.35, 0.33, 0.32), vec3<f32>(0.5, 0.4, 0.3), hash);
+}
+
+// The sun sits at the origin, in the middle of the belt
+fn shade(world_position: vec3<f32>, normal: vec3<f32>, color: vec3<f32>) -> vec3<f32> {
+    let light = normalize(-world_position);
+    let diffuse = max(dot(normal, light), 0.0);
+    return color * (0.08 + 0.92 * diffuse);
+}
+
+@vertex
+fn vs_mesh(vertex: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let index = visible[instance];
+    let object = objects[index];
+    let angle = object.spin * camera.position.w;
+
+    let local = vertex.position * object.stretch * object.scale;
+    let world = rotate(local, object.axis, angle) + object.position;
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(world, 1.0);
+    out.world_position = world;
+    // Non-uniform scale: normals scale by the inverse
+    out.normal = rotate(normalize(vertex.normal / object.stretch), object.axis, angle);
+    out.color = object_color(index);
+    out.corner = vec2<f32>(0.0);
+    return out;
+}
+
+@fragment
+fn fs_mesh(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(shade(in.world_position, normalize(in.normal), in.color), 1.0);
+}
+
+@vertex
+fn vs_impostor(vertex: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let index = visible[instance];
+    let object = objects[index];
+    let size = object.scale * (object.stretch.x + object.stretch.y + object.stretch.z) / 3.0;
+
+    let world = object.position + (camera.right.xyz * vertex.position.x + camera.up.xyz * vertex.position.y) * size;
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(world, 1.0);
+    out.world_position = object.position;
+    out.normal = vec3<f32>(0.0);
+    out.color = object_color(index);
+    out.corner = vertex.position.xy;
+    return out;
+}
+
+// A lit sphere painted on the quad, which at a few pixels across is all a
+// rock looks like anyway
+@fragment
+fn fs_impostor(in: VertexOutput) -> @location(0) vec4<f32> {
+    let r2 = dot(in.corner, in.corner);
+    if r2 > 1.0 {
+        discard;
+    }
+    let towards_camera = cross(camera.right.xyz, camera.up.xyz);
+    let normal = camera.right.xyz * in.corner.x + camera.up.xyz * in.corner.y + towards_camera * sqrt(1.0 - r2);
+    return vec4<f32>(shade(in.world_position, normal, in.color), 1.0);
+}
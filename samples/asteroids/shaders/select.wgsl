@@ -0,0 +1,93 @@
+// Picks a level of detail for every octree leaf, then sorts every asteroid
+// into the instance list of its leaf's level. The instance counts go
+// straight into the indirect draw arguments.
+
+struct Object {
+    position: vec3<f32>,
+    scale: f32,
+    axis: vec3<f32>,
+    spin: f32,
+    stretch: vec3<f32>,
+    leaf: u32,
+}
+
+struct Leaf {
+    center: vec3<f32>,
+    radius: f32,
+}
+
+struct DrawArgs {
+    index_count: u32,
+    instance_count: atomic<u32>,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+struct Selection {
+    // Frustum planes pointing inwards
+    planes: array<vec4<f32>, 6>,
+    camera: vec4<f32>,
+    // x: full mesh up to here, y: low mesh up to here, impostors beyond
+    lod_distances: vec4<f32>,
+    // x: leaves, y: objects, z: stride between the instance lists
+    counts: vec4<u32>,
+}
+
+const CULLED: u32 = 3u;
+
+@group(0) @binding(0)
+var<uniform> selection: Selection;
+@group(0) @binding(1)
+var<storage, read> leaves: array<Leaf>;
+@group(0) @binding(2)
+var<storage, read_write> leaf_lods: array<u32>;
+@group(0) @binding(3)
+var<storage, read> objects: array<Object>;
+@group(0) @binding(4)
+var<storage, read_write> draws: array<DrawArgs, 3>;
+@group(0) @binding(5)
+var<storage, read_write> visible: array<u32>;
+
+@compute @workgroup_size(64)
+fn cs_leaves(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= selection.counts.x {
+        return;
+    }
+    let leaf = leaves[index];
+
+    for (var i = 0u; i < 6u; i++) {
+        let plane = selection.planes[i];
+        if dot(plane.xyz, leaf.center) + plane.w < -leaf.radius {
+            leaf_lods[index] = CULLED;
+            return;
+        }
+    }
+
+    // From the camera to the nearest point of the leaf, so the whole leaf
+    // is at least as detailed as its closest asteroid needs
+    let distance = max(length(leaf.center - selection.camera.xyz) - leaf.radius, 0.0);
+    var lod = 2u;
+    if distance < selection.lod_distances.x {
+        lod = 0u;
+    } else if distance < selection.lod_distances.y {
+        lod = 1u;
+    }
+    leaf_lods[index] = lod;
+}
+
+@compute @workgroup_size(64)
+fn cs_objects(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= selection.counts.y {
+        return;
+    }
+
+    let lod = leaf_lods[objects[index].leaf];
+    if lod == CULLED {
+        return;
+    }
+    let slot = atomicAdd(&draws[lod].instance_count, 1u);
+    visible[lod * selection.counts.z + slot] = index;
+}
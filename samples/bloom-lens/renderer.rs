@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use framework::post::{Bloom, PostChain, Tonemap, HDR_FORMAT};
+use framework::framegraph::FrameGraph;
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::stages::{Anamorphic, LensDirt};
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    chain: PostChain,
+    held_keys: HashSet<VirtualKeyCode>,
+    start: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::cast_slice(&[0.0f32; 4]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                // The scene goes into the chain, not the surface
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // The sample's own stages slot in between the framework's
+        let mut chain = PostChain::new(device, &gpu.config);
+        chain.push(device, Bloom::new(device));
+        chain.push(device, LensDirt::new(device, &gpu.queue));
+        chain.push(device, Anamorphic::new(device));
+        chain.push(device, Tonemap::new(device));
+
+        Self {
+            scene_pipeline,
+            uniform_buffer,
+            bind_group,
+            chain,
+            held_keys: HashSet::new(),
+            start: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.chain.resize(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Key1 => self.chain.toggle(0),
+                VirtualKeyCode::Key2 => self.chain.toggle(1),
+                VirtualKeyCode::Key3 => self.chain.toggle(2),
+                VirtualKeyCode::Key4 => self.chain.toggle(3),
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let stages: Vec<String> = self
+            .chain
+            .stages()
+            .enumerate()
+            .map(|(index, (name, enabled))| format!("{} {} {}", index + 1, name, if enabled { "on" } else { "off" }))
+            .collect();
+        Some(stages.join(", "))
+    }
+
+    fn frame_graph(&self) -> Option<&FrameGraph> {
+        Some(self.chain.graph())
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let uniforms = [self.start.elapsed().as_secs_f32(), gpu.aspect_ratio(), 0.0, 0.0];
+        gpu.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&uniforms));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view: self.chain.input(),
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        self.chain.run(&gpu.device, &gpu.queue, &mut encoder, view);
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,38 @@
+struct Params {
+    intensity: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+@group(0) @binding(3)
+var bloom: texture_2d<f32>;
+@group(0) @binding(4)
+var dirt: texture_2d<f32>;
+
+// How much bright light reaches the lens overall, averaged over a grid of
+// the bloom right here on the GPU. A light going behind a building takes
+// the dirt with it, with no readback to the CPU.
+fn exposure_to_light() -> f32 {
+    var sum = vec3<f32>(0.0);
+    for (var y = 0; y < 6; y++) {
+        for (var x = 0; x < 6; x++) {
+            let uv = (vec2<f32>(f32(x), f32(y)) + 0.5) / 6.0;
+            sum += textureSampleLevel(bloom, post_sampler, uv, 0.0).rgb;
+        }
+    }
+    let average = sum / 36.0;
+    return dot(average, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let scene = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb;
+    let glow = textureSampleLevel(bloom, post_sampler, in.uv, 0.0).rgb;
+    let smudges = textureSampleLevel(dirt, post_sampler, in.uv, 0.0).rgb;
+    // Local glow lights up the smudges near a light, the overall level
+    // the faint ones everywhere else
+    let light = glow + vec3<f32>(exposure_to_light() * 4.0);
+    return vec4<f32>(scene + smudges * light * params.intensity, 1.0);
+}
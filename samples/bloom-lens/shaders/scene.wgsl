@@ -0,0 +1,55 @@
+struct Uniforms {
+    time: f32,
+    aspect: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -aspect to aspect across, -1 to 1 up
+    @location(0) position: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.position = vec2<f32>(ndc.x * uniforms.aspect, ndc.y);
+    return out;
+}
+
+// A light's glow: a tight hot core and a faint halo
+fn light(position: vec2<f32>, center: vec2<f32>, radius: f32) -> f32 {
+    let distance = length(position - center);
+    return smoothstep(radius, radius * 0.7, distance) * 60.0 + 0.02 / (distance * distance + 0.01);
+}
+
+// Night skyline in HDR: a moon drifting behind the buildings, a blinking
+// beacon, lit windows and a pair of headlights on the road in front
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position;
+    let t = uniforms.time;
+
+    var color = mix(vec3<f32>(0.02, 0.02, 0.04), vec3<f32>(0.05, 0.04, 0.1), p.y * 0.5 + 0.5);
+    color += vec3<f32>(0.8, 0.85, 1.0) * light(p, vec2<f32>(sin(t * 0.15) * 1.4, -0.05 + cos(t * 0.15) * 0.3), 0.06);
+    color += vec3<f32>(1.0, 0.2, 0.1) * light(p, vec2<f32>(0.35, 0.3), 0.015) * step(0.5, fract(t * 0.7));
+
+    // Buildings hide the sky
+    let block = floor((p.x + 4.0) * 5.0);
+    let height = -0.2 + fract(sin(block * 12.9898) * 43758.547) * 0.5;
+    if p.y < height {
+        let window = fract(sin(dot(floor(p * vec2<f32>(60.0, 40.0)), vec2<f32>(12.9898, 78.233))) * 43758.547);
+        color = vec3<f32>(0.01) + vec3<f32>(1.5, 1.1, 0.6) * step(0.93, window);
+    }
+    // The road, and the headlights on it
+    if p.y < -0.5 {
+        color = vec3<f32>(0.015, 0.015, 0.02);
+    }
+    let car = sin(t * 0.4) * 1.6;
+    color += vec3<f32>(1.0, 0.9, 0.7) * (light(p, vec2<f32>(car - 0.06, -0.6), 0.02) + light(p, vec2<f32>(car + 0.06, -0.6), 0.02));
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,59 @@
+struct Params {
+    // Of the texture being read
+    texel_size: vec2<f32>,
+    threshold: f32,
+    intensity: f32,
+    // rgb tints the streak
+    tint: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+// Only bound for the composite
+@group(0) @binding(3)
+var streak: texture_2d<f32>;
+
+// Squashes a tall block of rows into one, keeping only what's over the
+// threshold: only the brightest lights streak
+@fragment
+fn fs_prefilter(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    var sum = vec3<f32>(0.0);
+    for (var i = -3; i <= 3; i++) {
+        let uv = in.uv + vec2<f32>(0.0, f32(i) * params.texel_size.y);
+        let color = textureSampleLevel(source, post_sampler, uv, 0.0).rgb;
+        let brightness = max(color.r, max(color.g, color.b));
+        sum += color * max(brightness - params.threshold, 0.0) / max(brightness, 0.0001);
+    }
+    return vec4<f32>(sum / 7.0, 1.0);
+}
+
+// Halves the width only
+@fragment
+fn fs_downsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let o = params.texel_size.x;
+    let sum = textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-1.5 * o, 0.0), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-0.5 * o, 0.0), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(0.5 * o, 0.0), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(1.5 * o, 0.0), 0.0).rgb;
+    return vec4<f32>(sum * 0.25, 1.0);
+}
+
+@fragment
+fn fs_upsample(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let o = params.texel_size.x;
+    let sum = textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(-o, 0.0), 0.0).rgb
+        + textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb * 2.0
+        + textureSampleLevel(source, post_sampler, in.uv + vec2<f32>(o, 0.0), 0.0).rgb;
+    return vec4<f32>(sum * 0.25, 1.0);
+}
+
+@fragment
+fn fs_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let scene = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb;
+    let line = textureSampleLevel(streak, post_sampler, in.uv, 0.0).rgb;
+    // Cameras with anamorphic lenses streak blue whatever the light's color
+    let tinted = dot(line, vec3<f32>(0.2126, 0.7152, 0.0722)) * params.tint.rgb;
+    return vec4<f32>(scene + tinted * params.intensity, 1.0);
+}
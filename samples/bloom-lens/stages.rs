@@ -0,0 +1,224 @@
+use bytemuck::{Pod, Zeroable};
+use framework::post::{self, FullscreenPass, PostFrame, PostStage, HDR_FORMAT};
+use wgpu::{Device, Queue, TextureView};
+
+const DIRT_SIZE: u32 = 512;
+const STREAK_LEVELS: usize = 6;
+// The streak chain is this many times shorter than the screen
+const STREAK_SQUASH: u32 = 8;
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+// Smudges, fingerprints' worth of soft blobs and a few drying streaks,
+// mostly dark so only lit parts of it show
+fn generate_dirt() -> Vec<u8> {
+    let size = DIRT_SIZE as usize;
+    let mut dirt = vec![0.0f32; size * size];
+    let mut rng = Rng(0x2545f491);
+    let splat = |dirt: &mut Vec<f32>, cx: f32, cy: f32, radius: f32, strength: f32| {
+        let min_x = ((cx - radius).floor().max(0.0)) as usize;
+        let max_x = ((cx + radius).ceil().min(size as f32 - 1.0)) as usize;
+        let min_y = ((cy - radius).floor().max(0.0)) as usize;
+        let max_y = ((cy + radius).ceil().min(size as f32 - 1.0)) as usize;
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let distance = ((x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)).sqrt() / radius;
+                if distance < 1.0 {
+                    // Dried droplets are darker at the rim
+                    let falloff = 1.0 - distance * distance;
+                    dirt[y * size + x] += strength * (falloff * 0.6 + distance.powi(6) * 0.8);
+                }
+            }
+        }
+    };
+
+    for _ in 0..90 {
+        let (x, y) = (rng.range(0.0, size as f32), rng.range(0.0, size as f32));
+        let radius = rng.range(6.0, 40.0);
+        let strength = rng.range(0.1, 0.5);
+        splat(&mut dirt, x, y, radius, strength);
+    }
+    // Streaks are a line of small splats
+    for _ in 0..12 {
+        let (mut x, mut y) = (rng.range(0.0, size as f32), rng.range(0.0, size as f32));
+        let angle = rng.range(0.0, std::f32::consts::TAU);
+        let length = rng.range(40.0, 160.0) as usize;
+        for _ in 0..length {
+            splat(&mut dirt, x, y, 3.0, 0.05);
+            x += angle.cos();
+            y += angle.sin();
+        }
+    }
+
+    dirt.iter()
+        .flat_map(|&value| {
+            let value = (value.min(1.0) * 255.0) as u8;
+            // A little warm, like grease
+            [value, (value as f32 * 0.92) as u8, (value as f32 * 0.8) as u8, 255]
+        })
+        .collect()
+}
+
+// Dirt on the lens lit up by the bloom from the stage before it. The dirt
+// follows how much light actually reaches the lens, worked out from the
+// bloom on the GPU, so an occluded light leaves the lens clean.
+pub struct LensDirt {
+    pub intensity: f32,
+    pass: FullscreenPass,
+    dirt: TextureView,
+}
+
+impl LensDirt {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let size = wgpu::Extent3d {
+            width: DIRT_SIZE,
+            height: DIRT_SIZE,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Lens Dirt"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            texture.as_image_copy(),
+            &generate_dirt(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * DIRT_SIZE),
+                rows_per_image: Some(DIRT_SIZE),
+            },
+            size,
+        );
+
+        Self {
+            intensity: 1.0,
+            pass: FullscreenPass::new(device, "Lens Dirt", include_str!("shaders/lens_dirt.wgsl"), "fs_main", 3, HDR_FORMAT, None),
+            dirt: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        }
+    }
+}
+
+impl PostStage for LensDirt {
+    fn name(&self) -> &str {
+        "lens dirt"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        // With the bloom off there's nothing to light the dirt; the input
+        // stands in for it so the bindings stay the same, at no intensity
+        let (bloom, intensity) = match frame.shared.get("bloom") {
+            Some(bloom) => (bloom, self.intensity),
+            None => (frame.input, 0.0),
+        };
+        let params = [intensity, 0.0, 0.0, 0.0];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input, bloom, &self.dirt], frame.output);
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct StreakParams {
+    texel_size: [f32; 2],
+    threshold: f32,
+    intensity: f32,
+    tint: [f32; 4],
+}
+
+// The horizontal flares of an anamorphic lens: the same down and up chain
+// as the bloom, but squashed flat and only ever blurred sideways
+pub struct Anamorphic {
+    pub threshold: f32,
+    pub intensity: f32,
+    pub tint: [f32; 3],
+    prefilter: FullscreenPass,
+    downsample: FullscreenPass,
+    upsample: FullscreenPass,
+    composite: FullscreenPass,
+    levels: Vec<(TextureView, [u32; 2])>,
+}
+
+impl Anamorphic {
+    pub fn new(device: &Device) -> Self {
+        let source = include_str!("shaders/streak.wgsl");
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        };
+        Self {
+            threshold: 2.0,
+            intensity: 0.5,
+            tint: [0.3, 0.5, 1.0],
+            prefilter: FullscreenPass::new(device, "Streak Prefilter", source, "fs_prefilter", 1, HDR_FORMAT, None),
+            downsample: FullscreenPass::new(device, "Streak Downsample", source, "fs_downsample", 1, HDR_FORMAT, None),
+            upsample: FullscreenPass::new(device, "Streak Upsample", source, "fs_upsample", 1, HDR_FORMAT, Some(additive)),
+            composite: FullscreenPass::new(device, "Streak Composite", source, "fs_composite", 2, HDR_FORMAT, None),
+            levels: Vec::new(),
+        }
+    }
+
+    fn params(&self, size: [u32; 2]) -> StreakParams {
+        StreakParams {
+            texel_size: [1.0 / size[0] as f32, 1.0 / size[1] as f32],
+            threshold: self.threshold,
+            intensity: self.intensity,
+            tint: [self.tint[0], self.tint[1], self.tint[2], 0.0],
+        }
+    }
+}
+
+impl PostStage for Anamorphic {
+    fn name(&self) -> &str {
+        "anamorphic"
+    }
+
+    fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        // Every level keeps the squashed height, only the width halves
+        let rows = (height / STREAK_SQUASH).max(1);
+        self.levels = (1..=STREAK_LEVELS)
+            .map(|level| {
+                let size = [(width >> level).max(1), rows];
+                (post::create_target(device, size[0], size[1], "Streak Level"), size)
+            })
+            .collect();
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let device = frame.device;
+        let params = self.params([frame.width, frame.height]);
+        self.prefilter.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[frame.input], &self.levels[0].0);
+
+        for level in 1..self.levels.len() {
+            let params = self.params(self.levels[level - 1].1);
+            self.downsample.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[&self.levels[level - 1].0], &self.levels[level].0);
+        }
+        for level in (0..self.levels.len() - 1).rev() {
+            let params = self.params(self.levels[level + 1].1);
+            self.upsample.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[&self.levels[level + 1].0], &self.levels[level].0);
+        }
+
+        self.composite.draw(device, frame.encoder, bytemuck::bytes_of(&params), &[frame.input, &self.levels[0].0], frame.output);
+    }
+}
@@ -0,0 +1,59 @@
+use bytemuck::{Pod, Zeroable};
+
+// World size in chunks on each axis. At 256KiB per chunk the whole dataset is
+// 4TiB, far beyond what a single buffer (or the GPU) could ever hold.
+pub const WORLD_CHUNKS: i32 = 4096;
+pub const POINTS_PER_CHUNK: u32 = 16384;
+pub const CHUNK_BYTES: u64 = POINTS_PER_CHUNK as u64 * std::mem::size_of::<Point>() as u64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Point {
+    position: [f32; 2],
+    value: f32,
+    _padding: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkCoord {
+    pub fn is_in_world(&self) -> bool {
+        (0..WORLD_CHUNKS).contains(&self.x) && (0..WORLD_CHUNKS).contains(&self.y)
+    }
+}
+
+// xorshift is plenty for scattering points and keeps every chunk reproducible
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+}
+
+// Stands in for reading the chunk from disk or the network: the data only
+// exists while the chunk is resident
+pub fn load_chunk(coord: ChunkCoord) -> Vec<Point> {
+    let seed = (coord.x as u32).wrapping_mul(73_856_093) ^ (coord.y as u32).wrapping_mul(19_349_663);
+    let mut rng = Rng(seed | 1);
+
+    (0..POINTS_PER_CHUNK)
+        .map(|_| {
+            let x = coord.x as f32 + rng.next_f32();
+            let y = coord.y as f32 + rng.next_f32();
+            let value = ((x * 0.05).sin() * (y * 0.07).cos() * 0.5 + 0.5) * 0.8 + rng.next_f32() * 0.2;
+            Point {
+                position: [x, y],
+                value,
+                _padding: 0.0,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,9 @@
+mod dataset;
+mod renderer;
+mod residency;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("buffer-streaming");
+}
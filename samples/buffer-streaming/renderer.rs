@@ -0,0 +1,243 @@
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, RenderPipeline, Buffer, BindGroup};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::dataset::{ChunkCoord, Point, POINTS_PER_CHUNK, WORLD_CHUNKS};
+use crate::residency::{ChunkPool, ResidencyStats, POOL_SIZE};
+
+const MIN_ZOOM: f32 = 0.5;
+// Zoomed out any further the visible chunks wouldn't fit into the pool anymore
+const MAX_ZOOM: f32 = 3.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CameraUniform {
+    center: [f32; 2],
+    extent: [f32; 2],
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    pool: ChunkPool,
+    // In chunks, the world spans 0..WORLD_CHUNKS on both axes
+    center: [f32; 2],
+    // Half the visible height in chunks
+    zoom: f32,
+    aspect: f32,
+    stats: ResidencyStats,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera"),
+            contents: bytemuck::bytes_of(&CameraUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/points.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&camera_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Point>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::PointList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let pool = ChunkPool::new(device);
+
+        Self {
+            render_pipeline,
+            camera_buffer,
+            camera_bind_group,
+            pool,
+            center: [WORLD_CHUNKS as f32 / 2.0; 2],
+            zoom: 1.5,
+            aspect: gpu.aspect_ratio(),
+            stats: ResidencyStats::default(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.aspect = gpu.aspect_ratio();
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.key_pressed(*key);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} visible, {}/{} resident, {} uploaded, {} evicted",
+            self.stats.visible, self.stats.resident, POOL_SIZE, self.stats.uploaded, self.stats.evicted,
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let visible = self.visible_chunks();
+        self.stats = self.pool.update(&gpu.queue, &visible);
+
+        gpu.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&CameraUniform {
+                center: self.center,
+                extent: self.extent(),
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            // Chunks that are still streaming in are simply skipped this frame
+            for chunk in &visible {
+                if let Some(buffer) = self.pool.buffer_for(chunk) {
+                    render_pass.set_vertex_buffer(0, buffer.slice(..));
+                    render_pass.draw(0..POINTS_PER_CHUNK, 0..1);
+                }
+            }
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn key_pressed(&mut self, key: VirtualKeyCode) {
+        let step = self.zoom * 0.25;
+        match key {
+            VirtualKeyCode::W | VirtualKeyCode::Up => self.center[1] += step,
+            VirtualKeyCode::S | VirtualKeyCode::Down => self.center[1] -= step,
+            VirtualKeyCode::A | VirtualKeyCode::Left => self.center[0] -= step,
+            VirtualKeyCode::D | VirtualKeyCode::Right => self.center[0] += step,
+            VirtualKeyCode::Q => self.zoom = (self.zoom * 1.25).min(MAX_ZOOM),
+            VirtualKeyCode::E => self.zoom = (self.zoom / 1.25).max(MIN_ZOOM),
+            _ => {}
+        }
+    }
+
+    fn extent(&self) -> [f32; 2] {
+        [self.zoom * self.aspect, self.zoom]
+    }
+
+    // Every chunk touching the view, nearest to the center first so those
+    // get streamed in before the ones at the edges
+    fn visible_chunks(&self) -> Vec<ChunkCoord> {
+        let [extent_x, extent_y] = self.extent();
+        let min_x = (self.center[0] - extent_x).floor() as i32;
+        let max_x = (self.center[0] + extent_x).floor() as i32;
+        let min_y = (self.center[1] - extent_y).floor() as i32;
+        let max_y = (self.center[1] + extent_y).floor() as i32;
+
+        let mut chunks: Vec<ChunkCoord> = (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| ChunkCoord { x, y }))
+            .filter(ChunkCoord::is_in_world)
+            .collect();
+
+        let distance = |chunk: &ChunkCoord| {
+            let dx = chunk.x as f32 + 0.5 - self.center[0];
+            let dy = chunk.y as f32 + 0.5 - self.center[1];
+            dx * dx + dy * dy
+        };
+        chunks.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        chunks
+    }
+}
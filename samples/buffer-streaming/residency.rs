@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use wgpu::{Buffer, Device, Queue};
+
+use crate::dataset::{load_chunk, ChunkCoord, CHUNK_BYTES};
+
+// Fixed GPU memory budget: POOL_SIZE * CHUNK_BYTES, no matter how big the dataset is
+pub const POOL_SIZE: usize = 96;
+// Generating and uploading a chunk isn't free, spread the work over several frames
+const UPLOADS_PER_FRAME: usize = 4;
+
+struct Slot {
+    chunk: ChunkCoord,
+    last_used: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResidencyStats {
+    pub visible: usize,
+    pub resident: usize,
+    pub uploaded: usize,
+    pub evicted: usize,
+}
+
+// A fixed set of equally sized buffers, each holding one chunk at a time
+pub struct ChunkPool {
+    buffers: Vec<Buffer>,
+    slots: Vec<Option<Slot>>,
+    resident: HashMap<ChunkCoord, usize>,
+    frame: u64,
+}
+
+impl ChunkPool {
+    pub fn new(device: &Device) -> Self {
+        let buffers = (0..POOL_SIZE)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Chunk Slot"),
+                    size: CHUNK_BYTES,
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        Self {
+            buffers,
+            slots: (0..POOL_SIZE).map(|_| None).collect(),
+            resident: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    // `visible` has to be sorted by priority, the most important chunk first
+    pub fn update(&mut self, queue: &Queue, visible: &[ChunkCoord]) -> ResidencyStats {
+        self.frame += 1;
+        let mut stats = ResidencyStats {
+            visible: visible.len(),
+            ..Default::default()
+        };
+
+        for chunk in visible {
+            if let Some(&slot) = self.resident.get(chunk) {
+                self.slots[slot].as_mut().unwrap().last_used = self.frame;
+            }
+        }
+
+        // Collected up front, the loop below changes what's resident
+        let missing: Vec<ChunkCoord> = visible
+            .iter()
+            .copied()
+            .filter(|chunk| !self.resident.contains_key(chunk))
+            .take(UPLOADS_PER_FRAME)
+            .collect();
+        for chunk in missing {
+            let Some(slot) = self.find_slot() else {
+                // Everything resident is in view, the pool is simply too small for this zoom level
+                break;
+            };
+
+            if let Some(old) = self.slots[slot].take() {
+                self.resident.remove(&old.chunk);
+                stats.evicted += 1;
+            }
+
+            let points = load_chunk(chunk);
+            queue.write_buffer(&self.buffers[slot], 0, bytemuck::cast_slice(&points));
+
+            self.slots[slot] = Some(Slot {
+                chunk,
+                last_used: self.frame,
+            });
+            self.resident.insert(chunk, slot);
+            stats.uploaded += 1;
+        }
+
+        stats.resident = self.resident.len();
+        stats
+    }
+
+    // A free slot if there is one, otherwise the least recently used chunk
+    // that isn't needed this frame
+    fn find_slot(&self) -> Option<usize> {
+        if let Some(free) = self.slots.iter().position(Option::is_none) {
+            return Some(free);
+        }
+
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| slot.as_ref().map(|slot| (index, slot.last_used)))
+            .filter(|&(_, last_used)| last_used < self.frame)
+            .min_by_key(|&(_, last_used)| last_used)
+            .map(|(index, _)| index)
+    }
+
+    pub fn buffer_for(&self, chunk: &ChunkCoord) -> Option<&Buffer> {
+        self.resident.get(chunk).map(|&slot| &self.buffers[slot])
+    }
+}
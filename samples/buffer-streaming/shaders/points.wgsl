@@ -0,0 +1,27 @@
+struct Camera {
+  center: vec2<f32>,
+  // Half the visible world size on each axis
+  extent: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) color: vec3<f32>,
+}
+
+// xy is the world position, z the measured value, w is unused
+@vertex
+fn vs_main(@location(0) point: vec4<f32>) -> VertexOutput {
+  let clip = (point.xy - camera.center) / camera.extent;
+  let value = point.z;
+  let color = mix(vec3(0.1, 0.3, 0.9), vec3(1.0, 0.8, 0.2), value);
+  return VertexOutput(vec4(clip, 0.0, 1.0), color);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return vec4(in.color, 1.0);
+}
@@ -0,0 +1,366 @@
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use framework::timing::time_submission;
+use framework::Gpu;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, CommandEncoder, ComputePipeline};
+
+const ROWS: usize = 4096;
+const LENGTH: usize = 256;
+const VALUES: usize = 1 << 20;
+const BINS: usize = 16;
+const GROUP_SIZE: u32 = 64;
+// Runs per timed submission, and timed submissions per variant; the
+// fastest submission counts
+const RUNS: u32 = 8;
+const ROUNDS: u32 = 5;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    rows: u32,
+    length: u32,
+    values: u32,
+    _padding: u32,
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn next_signed(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// Whether the adapter offers each optional extension. Only reported: wgpu
+// 0.16 lets a device ask for SHADER_F16, but naga 0.12's WGSL frontend
+// doesn't parse `enable f16;` yet, and there's no 64 bit integer feature at
+// all, so there are no native kernels and both are emulated everywhere.
+pub struct Capabilities {
+    pub adapter_f16: bool,
+}
+
+impl Capabilities {
+    pub fn detect(adapter: &wgpu::Adapter) -> Self {
+        Self {
+            adapter_f16: adapter.features().contains(wgpu::Features::SHADER_F16),
+        }
+    }
+}
+
+pub struct Outcome {
+    pub name: &'static str,
+    pub milliseconds: f32,
+    // Largest error against the CPU for the dot products, or whether the
+    // totals came out exact for the counts
+    pub error: f32,
+    pub exact: bool,
+}
+
+// Two pairs of kernels over the same inputs: a matrix times a vector in
+// f32 and in emulated f16, and a weighted histogram with 32 bit atomics
+// and emulated 64 bit ones. Each is timed and checked against the CPU, the
+// f16 one for how much precision it gives up and the 32 bit one for how
+// badly it wraps.
+pub struct Kernels {
+    pub capabilities: Capabilities,
+    dot_f32: ComputePipeline,
+    dot_f16: ComputePipeline,
+    count_u32: ComputePipeline,
+    count_u64: ComputePipeline,
+    bind_group: BindGroup,
+    products: Buffer,
+    counts: Buffer,
+    readback: Buffer,
+    expected_products: Vec<f64>,
+    expected_counts: Vec<u64>,
+}
+
+impl Kernels {
+    pub fn new(gpu: &Gpu, seed: u32) -> Self {
+        let device = &gpu.device;
+        let mut rng = Rng(seed.wrapping_mul(0x9e3779b9) | 1);
+        let matrix: Vec<f32> = (0..ROWS * LENGTH).map(|_| rng.next_signed()).collect();
+        let vector: Vec<f32> = (0..LENGTH).map(|_| rng.next_signed()).collect();
+        // Up to 2^28 each, so a bin passes 2^32 after a few dozen
+        let values: Vec<u32> = (0..VALUES).map(|_| rng.next_u32()).collect();
+
+        let expected_products: Vec<f64> = matrix
+            .chunks(LENGTH)
+            .map(|row| row.iter().zip(&vector).map(|(a, b)| *a as f64 * *b as f64).sum())
+            .collect();
+        let mut expected_counts = vec![0u64; BINS];
+        for value in &values {
+            expected_counts[(value & 15) as usize] += (value >> 4) as u64;
+        }
+        // Halves as the shader's unpack2x16float expects them, low first
+        let matrix_f16: Vec<u32> = matrix
+            .chunks(2)
+            .map(|pair| to_f16(pair[0]) as u32 | (to_f16(pair[1]) as u32) << 16)
+            .collect();
+
+        let storage = |label: &str, contents: &[u8], usage: wgpu::BufferUsages| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE | usage,
+            })
+        };
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Params"),
+            contents: bytemuck::bytes_of(&Params {
+                rows: ROWS as u32,
+                length: LENGTH as u32,
+                values: VALUES as u32,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let matrix_f32 = storage("Matrix f32", bytemuck::cast_slice(&matrix), wgpu::BufferUsages::empty());
+        let matrix_f16 = storage("Matrix f16", bytemuck::cast_slice(&matrix_f16), wgpu::BufferUsages::empty());
+        let vector = storage("Vector", bytemuck::cast_slice(&vector), wgpu::BufferUsages::empty());
+        let products = storage("Products", &vec![0; ROWS * 4], wgpu::BufferUsages::COPY_SRC);
+        let values = storage("Values", bytemuck::cast_slice(&values), wgpu::BufferUsages::empty());
+        let counts = storage("Counts", &[0; BINS * 8], wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST);
+
+        // Every kernel shares the one layout and bind group, whichever
+        // bindings it actually uses
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Kernels Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, true),
+                storage_entry(4, false),
+                storage_entry(5, true),
+                storage_entry(6, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Kernels Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(include_wgsl!("shaders/kernels.wgsl"));
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        let dot_f32 = pipeline("cs_dot_f32");
+        let dot_f16 = pipeline("cs_dot_f16_emulated");
+        let count_u32 = pipeline("cs_count_u32");
+        let count_u64 = pipeline("cs_count_u64_emulated");
+        Self {
+            capabilities: Capabilities::detect(&gpu.adapter),
+            bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Kernels Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: matrix_f32.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: matrix_f16.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: vector.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: products.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: values.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: counts.as_entire_binding(),
+                    },
+                ],
+            }),
+            dot_f32,
+            dot_f16,
+            count_u32,
+            count_u64,
+            products,
+            counts,
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback"),
+                size: (ROWS * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            expected_products,
+            expected_counts,
+        }
+    }
+
+    fn record(&self, encoder: &mut CommandEncoder, pipeline: &ComputePipeline, invocations: usize) {
+        // The counts start from zero every run; it's a few bytes, so the
+        // dot products clear them too rather than special casing
+        encoder.clear_buffer(&self.counts, 0, None);
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Kernel Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups((invocations as u32 + GROUP_SIZE - 1) / GROUP_SIZE, 1, 1);
+    }
+
+    fn time(&self, gpu: &Gpu, pipeline: &ComputePipeline, invocations: usize, runs: u32) -> Duration {
+        time_submission(gpu, |encoder| {
+            for _ in 0..runs {
+                self.record(encoder, pipeline, invocations);
+            }
+        })
+    }
+
+    fn measure(&self, gpu: &Gpu, pipeline: &ComputePipeline, invocations: usize) -> f32 {
+        // The first submission pays for pipeline creation in the driver
+        self.time(gpu, pipeline, invocations, 1);
+        let fastest = (0..ROUNDS).map(|_| self.time(gpu, pipeline, invocations, RUNS)).min().unwrap();
+        fastest.as_secs_f32() * 1000.0 / RUNS as f32
+    }
+
+    // Blocks until the buffer's first `size` bytes are back on the CPU
+    fn read<T: Pod>(&self, gpu: &Gpu, buffer: &Buffer, size: wgpu::BufferAddress) -> Vec<T> {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                },
+            );
+        encoder.copy_buffer_to_buffer(buffer, 0, &self.readback, 0, size);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let slice = self.readback.slice(..size);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        gpu.device.poll(wgpu::Maintain::Wait);
+        let contents = bytemuck::cast_slice::<u8, T>(&slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        contents
+    }
+
+    fn dot(&self, gpu: &Gpu, name: &'static str, pipeline: &ComputePipeline) -> Outcome {
+        let milliseconds = self.measure(gpu, pipeline, ROWS);
+        let products: Vec<f32> = self.read(gpu, &self.products, self.products.size());
+        let error = products
+            .iter()
+            .zip(&self.expected_products)
+            .map(|(product, expected)| (*product as f64 - expected).abs() as f32)
+            .fold(0.0, f32::max);
+        Outcome {
+            name,
+            milliseconds,
+            error,
+            exact: error == 0.0,
+        }
+    }
+
+    fn count(&self, gpu: &Gpu, name: &'static str, pipeline: &ComputePipeline, wide: bool) -> Outcome {
+        let milliseconds = self.measure(gpu, pipeline, VALUES);
+        let words: Vec<u32> = self.read(gpu, &self.counts, self.counts.size());
+        let counts: Vec<u64> = if wide {
+            words.chunks(2).map(|pair| pair[0] as u64 | (pair[1] as u64) << 32).collect()
+        } else {
+            words[..BINS].iter().map(|count| *count as u64).collect()
+        };
+        let error = counts
+            .iter()
+            .zip(&self.expected_counts)
+            .map(|(count, expected)| count.abs_diff(*expected) as f32)
+            .fold(0.0, f32::max);
+        Outcome {
+            name,
+            milliseconds,
+            error,
+            exact: counts == self.expected_counts,
+        }
+    }
+
+    // Every variant in order, printing a table along the way
+    pub fn benchmark(&self, gpu: &Gpu) -> Vec<Outcome> {
+        let outcomes = vec![
+            self.dot(gpu, "dot f32", &self.dot_f32),
+            self.dot(gpu, "dot f16 emulated", &self.dot_f16),
+            self.count(gpu, "count u32 atomics", &self.count_u32, false),
+            self.count(gpu, "count u64 emulated", &self.count_u64, true),
+        ];
+
+        println!(
+            "SHADER_F16 {}",
+            if self.capabilities.adapter_f16 { "offered by the adapter" } else { "not offered" },
+        );
+        for outcome in &outcomes {
+            println!(
+                "{:<20} {:>8.3}ms  largest error {}",
+                outcome.name, outcome.milliseconds, outcome.error,
+            );
+        }
+        outcomes
+    }
+}
+
+// Round to nearest even, flushing what's too small for a half to zero and
+// what's too big to infinity
+fn to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    if exponent <= 0 {
+        return sign;
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    let mantissa = bits & 0x7f_ffff;
+    let mut half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let rest = mantissa & 0x1fff;
+    if rest > 0x1000 || (rest == 0x1000 && half & 1 == 1) {
+        // Carries into the exponent when the mantissa overflows, as it should
+        half += 1;
+    }
+    sign | half as u16
+}
@@ -0,0 +1,8 @@
+mod kernels;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("capabilities");
+}
@@ -0,0 +1,211 @@
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::kernels::{Kernels, Outcome};
+
+// The first two bars are dot products, the rest counts
+const DOTS: usize = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Display {
+    bars: [[f32; 4]; 4],
+    size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+pub struct Renderer {
+    kernels: Kernels,
+    outcomes: Vec<Outcome>,
+    pipeline: RenderPipeline,
+    display_buffer: Buffer,
+    bind_group: BindGroup,
+    seed: u32,
+    needs_benchmark: bool,
+}
+
+impl Renderer {
+    // Every bar against the slowest of its operation. Only the counts can
+    // be wrong; the dot products are off by their precision, shown in the
+    // status instead.
+    fn bars(&self) -> [[f32; 4]; 4] {
+        let mut bars = [[0.0; 4]; 4];
+        for (index, outcome) in self.outcomes.iter().enumerate() {
+            let count = index >= DOTS;
+            let slowest = self
+                .outcomes
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| (*other >= DOTS) == count)
+                .map(|(_, other)| other.milliseconds)
+                .fold(0.0, f32::max);
+            bars[index] = [
+                outcome.milliseconds / slowest.max(1e-6),
+                (!count || outcome.exact) as u32 as f32,
+                count as u32 as f32,
+                0.0,
+            ];
+        }
+        bars
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let kernels = Kernels::new(gpu, 1);
+
+        let display_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display"),
+            contents: bytemuck::bytes_of(&Display::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: display_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            kernels,
+            outcomes: Vec::new(),
+            pipeline,
+            display_buffer,
+            bind_group,
+            seed: 1,
+            needs_benchmark: true,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::B),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.seed += 1;
+            self.needs_benchmark = true;
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let outcomes: Vec<String> = self
+            .outcomes
+            .iter()
+            .enumerate()
+            .map(|(index, outcome)| {
+                if index < DOTS {
+                    format!("{} {:.3}ms error {:.1e}", outcome.name, outcome.milliseconds, outcome.error)
+                } else {
+                    format!("{} {:.3}ms {}", outcome.name, outcome.milliseconds, if outcome.exact { "exact" } else { "wrapped" })
+                }
+            })
+            .collect();
+        let f16 = if self.kernels.capabilities.adapter_f16 { "offered" } else { "not offered" };
+        Some(format!("SHADER_F16 {}; {}; B reruns", f16, outcomes.join(", ")))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        // Blocks for a second or so, fresh inputs each time after the first
+        if std::mem::take(&mut self.needs_benchmark) {
+            if self.seed > 1 {
+                self.kernels = Kernels::new(gpu, self.seed);
+            }
+            self.outcomes = self.kernels.benchmark(gpu);
+        }
+
+        gpu.queue.write_buffer(
+            &self.display_buffer,
+            0,
+            bytemuck::bytes_of(&Display {
+                bars: self.bars(),
+                size: [gpu.config.width as f32, gpu.config.height as f32],
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
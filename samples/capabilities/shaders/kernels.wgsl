@@ -0,0 +1,77 @@
+struct Params {
+    rows: u32,
+    length: u32,
+    values: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> matrix_f32: array<f32>;
+// The same matrix as pairs of halves, the way f16 storage would hold it
+@group(0) @binding(2) var<storage, read> matrix_f16: array<u32>;
+@group(0) @binding(3) var<storage, read> vector: array<f32>;
+@group(0) @binding(4) var<storage, read_write> products: array<f32>;
+@group(0) @binding(5) var<storage, read> values: array<u32>;
+// 32 bit counts use the first BINS, 64 bit ones a low and high word per bin
+@group(0) @binding(6) var<storage, read_write> counts: array<atomic<u32>>;
+
+// Rounds to the nearest half, for f16 arithmetic done in f32
+fn round_f16(x: f32) -> f32 {
+    return unpack2x16float(pack2x16float(vec2<f32>(x, 0.0))).x;
+}
+
+// A row of the matrix times the vector per invocation
+@compute @workgroup_size(64)
+fn cs_dot_f32(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.rows {
+        return;
+    }
+    var total = 0.0;
+    for (var i = 0u; i < params.length; i++) {
+        total += matrix_f32[id.x * params.length + i] * vector[i];
+    }
+    products[id.x] = total;
+}
+
+// As above with every product and partial sum rounded to a half, which is
+// what native f16 arithmetic would give up, while reading half the bytes
+@compute @workgroup_size(64)
+fn cs_dot_f16_emulated(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.rows {
+        return;
+    }
+    var total = 0.0;
+    for (var i = 0u; i < params.length; i += 2u) {
+        let pair = unpack2x16float(matrix_f16[(id.x * params.length + i) / 2u]);
+        total = round_f16(total + round_f16(pair.x * round_f16(vector[i])));
+        total = round_f16(total + round_f16(pair.y * round_f16(vector[i + 1u])));
+    }
+    products[id.x] = total;
+}
+
+// Low four bits pick the bin, the rest is the weight to add
+@compute @workgroup_size(64)
+fn cs_count_u32(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.values {
+        return;
+    }
+    let value = values[id.x];
+    atomicAdd(&counts[value & 15u], value >> 4u);
+}
+
+// A 64 bit add from two 32 bit ones: whoever wraps the low word carries
+// into the high one. Readers mid-dispatch can see the two halves out of
+// step, but once the dispatch is done the total is exact.
+@compute @workgroup_size(64)
+fn cs_count_u64_emulated(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.values {
+        return;
+    }
+    let value = values[id.x];
+    let bin = (value & 15u) * 2u;
+    let weight = value >> 4u;
+    let before = atomicAdd(&counts[bin], weight);
+    if before + weight < before {
+        atomicAdd(&counts[bin + 1u], 1u);
+    }
+}
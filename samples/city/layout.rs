@@ -0,0 +1,150 @@
+use glam::{Vec2, Vec3};
+
+// Blocks per side, each BLOCK_SIZE across with a street of STREET_WIDTH
+// between neighbours
+pub const BLOCKS: u32 = 8;
+pub const BLOCK_SIZE: f32 = 80.0;
+pub const STREET_WIDTH: f32 = 16.0;
+pub const PITCH: f32 = BLOCK_SIZE + STREET_WIDTH;
+// The city spans 0..EXTENT on x and z, streets around the outside included
+pub const EXTENT: f32 = BLOCKS as f32 * PITCH + STREET_WIDTH;
+pub const FLOOR_HEIGHT: f32 = 3.5;
+// Lots narrower than this aren't split any further
+const MIN_LOT: f32 = 18.0;
+// Gap between a lot's edge and its building
+const SETBACK: f32 = 1.5;
+const STREETLIGHT_SPACING: f32 = 24.0;
+const STREETLIGHT_HEIGHT: f32 = 6.0;
+
+// xorshift is plenty for a city and makes every seed reproducible
+pub struct Rng(u32);
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // Zero would stay zero forever
+        Self(seed.wrapping_mul(2_654_435_761) | 1)
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+// An axis aligned box standing on min.y
+#[derive(Clone, Copy, Debug)]
+pub struct Mass {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+// One or two stacked masses, the upper one set back from the lower
+pub struct Building {
+    pub masses: Vec<Mass>,
+    // Fraction of windows with the lights on
+    pub occupancy: f32,
+}
+
+pub struct StreetLight {
+    pub position: Vec3,
+}
+
+pub struct City {
+    pub buildings: Vec<Building>,
+    pub street_lights: Vec<StreetLight>,
+}
+
+impl City {
+    pub fn generate(seed: u32) -> Self {
+        let mut rng = Rng::new(seed);
+        let center = Vec2::splat(EXTENT / 2.0);
+
+        let mut buildings = Vec::new();
+        for bx in 0..BLOCKS {
+            for bz in 0..BLOCKS {
+                let min = Vec2::new(bx as f32, bz as f32) * PITCH + STREET_WIDTH;
+                let mut lots = Vec::new();
+                split_lot(&mut rng, min, min + BLOCK_SIZE, &mut lots);
+                for (lot_min, lot_max) in lots {
+                    // Downtown in the middle, low-rise at the edges
+                    let centrality = 1.0 - ((lot_min + lot_max) * 0.5).distance(center) / (EXTENT * 0.5);
+                    let max_height = 12.0 + 140.0 * centrality.max(0.0).powi(2);
+                    buildings.push(building(&mut rng, lot_min + SETBACK, lot_max - SETBACK, max_height));
+                }
+            }
+        }
+
+        // Both sides of every street, east-west and north-south
+        let mut street_lights = Vec::new();
+        for line in 0..=BLOCKS {
+            let street = line as f32 * PITCH;
+            for side in [1.5, STREET_WIDTH - 1.5] {
+                let mut along = STREETLIGHT_SPACING / 2.0;
+                while along < EXTENT {
+                    street_lights.push(StreetLight {
+                        position: Vec3::new(street + side, STREETLIGHT_HEIGHT, along),
+                    });
+                    street_lights.push(StreetLight {
+                        position: Vec3::new(along, STREETLIGHT_HEIGHT, street + side),
+                    });
+                    along += STREETLIGHT_SPACING;
+                }
+            }
+        }
+
+        Self {
+            buildings,
+            street_lights,
+        }
+    }
+}
+
+// Cuts the lot across its longer side at a random point until the pieces
+// get too small, the way real blocks end up with lots of varied widths
+fn split_lot(rng: &mut Rng, min: Vec2, max: Vec2, lots: &mut Vec<(Vec2, Vec2)>) {
+    let size = max - min;
+    let axis = if size.x > size.y { 0 } else { 1 };
+    if size[axis] < MIN_LOT * 2.0 || (size.max_element() < 40.0 && rng.next_f32() < 0.3) {
+        lots.push((min, max));
+        return;
+    }
+
+    let cut = min[axis] + size[axis] * rng.range(0.35, 0.65);
+    let mut first_max = max;
+    first_max[axis] = cut;
+    let mut second_min = min;
+    second_min[axis] = cut;
+    split_lot(rng, min, first_max, lots);
+    split_lot(rng, second_min, max, lots);
+}
+
+fn building(rng: &mut Rng, min: Vec2, max: Vec2, max_height: f32) -> Building {
+    // Whole floors, so the windows line up with the top
+    let floors = (rng.range(0.3, 1.0) * max_height / FLOOR_HEIGHT).max(2.0).floor();
+    let height = floors * FLOOR_HEIGHT;
+    let mut masses = vec![Mass {
+        min: Vec3::new(min.x, 0.0, min.y),
+        max: Vec3::new(max.x, height, max.y),
+    }];
+
+    // Tall buildings often get a narrower tower on top
+    if height > 40.0 && rng.next_f32() < 0.6 {
+        let inset = (max - min) * rng.range(0.15, 0.3);
+        let tower_floors = (floors * rng.range(0.3, 0.8)).floor().max(2.0);
+        masses.push(Mass {
+            min: Vec3::new(min.x + inset.x, height, min.y + inset.y),
+            max: Vec3::new(max.x - inset.x, height + tower_floors * FLOOR_HEIGHT, max.y - inset.y),
+        });
+    }
+
+    Building {
+        masses,
+        occupancy: rng.range(0.1, 0.6),
+    }
+}
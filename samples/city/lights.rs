@@ -0,0 +1,77 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+use crate::layout::{City, Rng, EXTENT};
+
+// The ground split into CELLS x CELLS columns, each listing the lights
+// that reach into it. Columns rather than view-space clusters because the
+// lights never move and a city is flat: they're binned once when the city
+// is built and every fragment just looks up the column it's in.
+pub const CELLS: u32 = 32;
+pub const CELL_SIZE: f32 = EXTENT / CELLS as f32;
+const STREETLIGHT_RADIUS: f32 = 18.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Light {
+    pub position: [f32; 3],
+    pub radius: f32,
+    // Premultiplied by intensity
+    pub color: [f32; 3],
+    pub _padding: f32,
+}
+
+pub struct LightGrid {
+    pub lights: Vec<Light>,
+    // offset and count into indices, row by row along z
+    pub cells: Vec<[u32; 2]>,
+    pub indices: Vec<u32>,
+    pub max_per_cell: u32,
+}
+
+impl LightGrid {
+    pub fn build(city: &City, seed: u32) -> Self {
+        let mut rng = Rng::new(seed ^ 0x68e3_1da4);
+        let lights: Vec<Light> = city
+            .street_lights
+            .iter()
+            .map(|street_light| {
+                // Sodium orange with the odd newer white one
+                let color = if rng.next_f32() < 0.2 { Vec3::new(0.8, 0.85, 1.0) } else { Vec3::new(1.0, 0.55, 0.2) };
+                Light {
+                    position: street_light.position.to_array(),
+                    radius: STREETLIGHT_RADIUS,
+                    color: (color * 40.0).to_array(),
+                    _padding: 0.0,
+                }
+            })
+            .collect();
+
+        let mut bins = vec![Vec::new(); (CELLS * CELLS) as usize];
+        for (index, light) in lights.iter().enumerate() {
+            // Every cell the light's bounding square touches
+            let cell = |coordinate: f32| ((coordinate / CELL_SIZE).floor().max(0.0) as u32).min(CELLS - 1);
+            let [x, _, z] = light.position;
+            for cz in cell(z - light.radius)..=cell(z + light.radius) {
+                for cx in cell(x - light.radius)..=cell(x + light.radius) {
+                    bins[(cz * CELLS + cx) as usize].push(index as u32);
+                }
+            }
+        }
+
+        let mut cells = Vec::with_capacity(bins.len());
+        let mut indices = Vec::new();
+        for bin in &bins {
+            cells.push([indices.len() as u32, bin.len() as u32]);
+            indices.extend(bin);
+        }
+        let max_per_cell = bins.iter().map(Vec::len).max().unwrap_or(0) as u32;
+
+        Self {
+            lights,
+            cells,
+            indices,
+            max_per_cell,
+        }
+    }
+}
@@ -0,0 +1,10 @@
+mod layout;
+mod lights;
+mod mesh;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("city");
+}
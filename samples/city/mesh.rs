@@ -0,0 +1,185 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+use crate::layout::{City, Mass, Rng, EXTENT, FLOOR_HEIGHT};
+
+pub const MATERIAL_GROUND: u32 = 0;
+pub const MATERIAL_WALL: u32 = 1;
+pub const MATERIAL_ROOF: u32 = 2;
+
+const WINDOW_SPACING: f32 = 3.0;
+const WINDOW_WIDTH: f32 = 1.4;
+const WINDOW_HEIGHT: f32 = 1.8;
+// Windows float just off the wall rather than fight it for depth
+const WINDOW_OFFSET: f32 = 0.05;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    // In meters, so facades tile the same on every building
+    pub uv: [f32; 2],
+    pub material: u32,
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Uint32];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Window {
+    // w is half the height
+    pub center: [f32; 4],
+    // Half the width along the wall, w is how brightly it's lit, 0 for off
+    pub right: [f32; 4],
+    // Out of the wall, w picks the light's warmth
+    pub normal: [f32; 4],
+}
+
+impl Window {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Window>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+pub struct CityMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub windows: Vec<Window>,
+}
+
+fn quad(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, corners: [Vec3; 4], normal: Vec3, uvs: [[f32; 2]; 4], material: u32) {
+    let base = vertices.len() as u32;
+    for (corner, uv) in corners.iter().zip(uvs) {
+        vertices.push(Vertex {
+            position: corner.to_array(),
+            normal: normal.to_array(),
+            uv,
+            material,
+        });
+    }
+    indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+}
+
+// Every building extruded into walls and a roof, the ground under all of
+// it, and a window instance for every floor along every wall
+pub fn build(city: &City, seed: u32) -> CityMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut windows = Vec::new();
+    let mut rng = Rng::new(seed ^ 0x5bd1_e995);
+
+    quad(
+        &mut vertices,
+        &mut indices,
+        [Vec3::ZERO, Vec3::new(0.0, 0.0, EXTENT), Vec3::new(EXTENT, 0.0, EXTENT), Vec3::new(EXTENT, 0.0, 0.0)],
+        Vec3::Y,
+        [[0.0, 0.0], [0.0, EXTENT], [EXTENT, EXTENT], [EXTENT, 0.0]],
+        MATERIAL_GROUND,
+    );
+
+    for building in &city.buildings {
+        for mass in &building.masses {
+            extrude(&mut vertices, &mut indices, mass);
+            add_windows(&mut windows, &mut rng, mass, building.occupancy);
+        }
+    }
+
+    CityMesh {
+        vertices,
+        indices,
+        windows,
+    }
+}
+
+// The four walls counter-clockwise seen from outside, each as its bottom
+// edge from left to right and its outward normal
+fn walls(mass: &Mass) -> [(Vec3, Vec3, Vec3); 4] {
+    let (min, max) = (mass.min, mass.max);
+    [
+        (Vec3::new(min.x, min.y, max.z), Vec3::new(max.x, min.y, max.z), Vec3::Z),
+        (Vec3::new(max.x, min.y, max.z), Vec3::new(max.x, min.y, min.z), Vec3::X),
+        (Vec3::new(max.x, min.y, min.z), Vec3::new(min.x, min.y, min.z), Vec3::NEG_Z),
+        (Vec3::new(min.x, min.y, min.z), Vec3::new(min.x, min.y, max.z), Vec3::NEG_X),
+    ]
+}
+
+fn extrude(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, mass: &Mass) {
+    let height = mass.max.y - mass.min.y;
+    let up = Vec3::Y * height;
+    // u runs on around the building so neighbouring walls continue each
+    // other's pattern, v is the height above the ground
+    let mut u = 0.0;
+    for (left, right, normal) in walls(mass) {
+        let length = left.distance(right);
+        let (v0, v1) = (mass.min.y, mass.max.y);
+        quad(
+            vertices,
+            indices,
+            [left, right, right + up, left + up],
+            normal,
+            [[u, v0], [u + length, v0], [u + length, v1], [u, v1]],
+            MATERIAL_WALL,
+        );
+        u += length;
+    }
+
+    let (min, max) = (mass.min, mass.max);
+    quad(
+        vertices,
+        indices,
+        [
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+            Vec3::new(max.x, max.y, min.z),
+        ],
+        Vec3::Y,
+        [[min.x, min.z], [min.x, max.z], [max.x, max.z], [max.x, min.z]],
+        MATERIAL_ROOF,
+    );
+}
+
+fn add_windows(windows: &mut Vec<Window>, rng: &mut Rng, mass: &Mass, occupancy: f32) {
+    let floors = ((mass.max.y - mass.min.y) / FLOOR_HEIGHT).round() as u32;
+    for (left, right, normal) in walls(mass) {
+        let length = left.distance(right);
+        let along = (right - left) / length;
+        let count = ((length - 1.0) / WINDOW_SPACING).floor() as u32;
+        if count == 0 {
+            continue;
+        }
+        // Centred on the wall
+        let start = (length - (count - 1) as f32 * WINDOW_SPACING) / 2.0;
+        for floor in 0..floors {
+            let y = mass.min.y + (floor as f32 + 0.55) * FLOOR_HEIGHT;
+            for i in 0..count {
+                let position = left + along * (start + i as f32 * WINDOW_SPACING) + normal * WINDOW_OFFSET;
+                let lit = if rng.next_f32() < occupancy { rng.range(0.4, 1.0) } else { 0.0 };
+                windows.push(Window {
+                    center: [position.x, y, position.z, WINDOW_HEIGHT / 2.0],
+                    right: (along * WINDOW_WIDTH / 2.0).extend(lit).to_array(),
+                    normal: normal.extend(rng.next_f32()).to_array(),
+                });
+            }
+        }
+    }
+}
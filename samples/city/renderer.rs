@@ -0,0 +1,412 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use framework::{CameraPose, Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::layout::{City, EXTENT};
+use crate::lights::{LightGrid, CELLS, CELL_SIZE};
+use crate::mesh::{self, Vertex, Window};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const MOVE_SPEED: f32 = 60.0;
+const TURN_SPEED: f32 = 1.2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    camera: [f32; 4],
+    grid: [f32; 4],
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// Everything generated from one seed, rebuilt as a whole for a new one
+struct CityBuffers {
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    window_buffer: Buffer,
+    window_count: u32,
+    bind_group: BindGroup,
+    buildings: usize,
+    lights: usize,
+    max_lights_per_cell: u32,
+}
+
+impl CityBuffers {
+    fn generate(device: &Device, layout: &BindGroupLayout, uniform_buffer: &Buffer, seed: u32) -> Self {
+        let city = City::generate(seed);
+        let mesh = mesh::build(&city, seed);
+        let grid = LightGrid::build(&city, seed);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("City Vertices"),
+            contents: bytemuck::cast_slice(&mesh.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("City Indices"),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let window_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Windows"),
+            contents: bytemuck::cast_slice(&mesh.windows),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let storage = |label, contents: &[u8]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        };
+        let light_buffer = storage("Lights", bytemuck::cast_slice(&grid.lights));
+        let cell_buffer = storage("Light Cells", bytemuck::cast_slice(&grid.cells));
+        // Never empty, an empty binding isn't allowed
+        let mut indices = grid.indices.clone();
+        if indices.is_empty() {
+            indices.push(0);
+        }
+        let index_list_buffer = storage("Light Indices", bytemuck::cast_slice(&indices));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("City Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: cell_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: index_list_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: mesh.indices.len() as u32,
+            window_buffer,
+            window_count: mesh.windows.len() as u32,
+            bind_group,
+            buildings: city.buildings.len(),
+            lights: grid.lights.len(),
+            max_lights_per_cell: grid.max_per_cell,
+        }
+    }
+}
+
+pub struct Renderer {
+    city_pipeline: RenderPipeline,
+    window_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    uniform_buffer: Buffer,
+    city: CityBuffers,
+    depth_view: TextureView,
+    seed: u32,
+    regenerate: bool,
+    heatmap: bool,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("City Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/city.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+        let render_pipeline = |label, vs_entry, fs_entry, buffer| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: vs_entry,
+                    buffers: &[buffer],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fs_entry,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let city_pipeline = render_pipeline("City Pipeline", "vs_main", "fs_main", Vertex::desc());
+        let window_pipeline = render_pipeline("Window Pipeline", "vs_window", "fs_window", Window::desc());
+
+        let seed = 1;
+        let city = CityBuffers::generate(device, &bind_group_layout, &uniform_buffer, seed);
+
+        Self {
+            city_pipeline,
+            window_pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            city,
+            depth_view: create_depth_view(device, &gpu.config),
+            seed,
+            regenerate: false,
+            heatmap: false,
+            // Over the south-west corner, looking across town
+            position: Vec3::new(-60.0, 90.0, EXTENT + 60.0),
+            yaw: 0.75,
+            pitch: -0.25,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::R => {
+                    self.seed += 1;
+                    self.regenerate = true;
+                }
+                VirtualKeyCode::H => self.heatmap = !self.heatmap,
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.position = pose.position;
+        self.yaw = pose.yaw;
+        self.pitch = pose.pitch;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "seed {}, {} buildings, {} windows, {} lights, at most {} per cell{}",
+            self.seed,
+            self.city.buildings,
+            self.city.window_count,
+            self.city.lights,
+            self.city.max_lights_per_cell,
+            if self.heatmap { ", lights per cell" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.update_camera();
+        if std::mem::take(&mut self.regenerate) {
+            framework::profile_scope!("generate city");
+            self.city = CityBuffers::generate(&gpu.device, &self.bind_group_layout, &self.uniform_buffer, self.seed);
+        }
+
+        let direction = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        );
+        let view_matrix = Mat4::look_to_rh(self.position, direction, Vec3::Y);
+        let proj = Mat4::perspective_rh(60f32.to_radians(), gpu.aspect_ratio(), 0.5, 3000.0);
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: (proj * view_matrix).to_cols_array_2d(),
+                camera: self.position.extend(1.0).to_array(),
+                grid: [CELL_SIZE, CELLS as f32, if self.heatmap { 1.0 } else { 0.0 }, 0.0],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.015,
+                                    g: 0.02,
+                                    b: 0.04,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_bind_group(0, &self.city.bind_group, &[]);
+            render_pass.set_pipeline(&self.city_pipeline);
+            render_pass.set_vertex_buffer(0, self.city.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.city.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..self.city.index_count, 0, 0..1);
+
+            render_pass.set_pipeline(&self.window_pipeline);
+            render_pass.set_vertex_buffer(0, self.city.window_buffer.slice(..));
+            render_pass.draw(0..6, 0..self.city.window_count);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn update_camera(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let forward = Vec3::new(self.yaw.sin(), 0.0, -self.yaw.cos());
+        let right = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+        let held = |key| self.held_keys.contains(&key);
+
+        let mut movement = Vec3::ZERO;
+        if held(VirtualKeyCode::W) { movement += forward; }
+        if held(VirtualKeyCode::S) { movement -= forward; }
+        if held(VirtualKeyCode::D) { movement += right; }
+        if held(VirtualKeyCode::A) { movement -= right; }
+        if held(VirtualKeyCode::E) { movement += Vec3::Y; }
+        if held(VirtualKeyCode::Q) { movement -= Vec3::Y; }
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        let mut tilt = 0.0;
+        if held(VirtualKeyCode::Up) { tilt += 1.0; }
+        if held(VirtualKeyCode::Down) { tilt -= 1.0; }
+
+        self.position += movement * MOVE_SPEED * dt;
+        self.position.y = self.position.y.max(1.7);
+        self.yaw += turn * TURN_SPEED * dt;
+        self.pitch = (self.pitch + tilt * TURN_SPEED * dt).clamp(-1.5, 1.5);
+    }
+}
@@ -0,0 +1,177 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    camera: vec4<f32>,
+    // x: cell size, y: cells per side, z: 1 to show lights per cell
+    grid: vec4<f32>,
+}
+
+struct Light {
+    position: vec3<f32>,
+    radius: f32,
+    color: vec3<f32>,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var<storage, read> lights: array<Light>;
+// offset and count into light_indices per cell
+@group(0) @binding(2)
+var<storage, read> cells: array<vec2<u32>>;
+@group(0) @binding(3)
+var<storage, read> light_indices: array<u32>;
+
+// Match layout.rs
+const PITCH: f32 = 96.0;
+const STREET_WIDTH: f32 = 16.0;
+const FLOOR_HEIGHT: f32 = 3.5;
+
+const MATERIAL_GROUND: u32 = 0u;
+const MATERIAL_WALL: u32 = 1u;
+
+fn cell_range(world: vec3<f32>) -> vec2<u32> {
+    let cells_per_side = i32(uniforms.grid.y);
+    let cell = clamp(vec2<i32>(floor(world.xz / uniforms.grid.x)), vec2<i32>(0), vec2<i32>(cells_per_side - 1));
+    return cells[cell.y * cells_per_side + cell.x];
+}
+
+// Only the lights binned into this fragment's cell, a couple of dozen at
+// most rather than the city's thousand
+fn point_lights(world: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    let range = cell_range(world);
+    var total = vec3<f32>(0.0);
+    for (var i = 0u; i < range.y; i++) {
+        let light = lights[light_indices[range.x + i]];
+        let to_light = light.position - world;
+        let distance = length(to_light);
+        if distance < light.radius {
+            // Inverse square, windowed to reach exactly zero at the radius
+            let x = distance / light.radius;
+            let window = (1.0 - x * x) * (1.0 - x * x);
+            let diffuse = max(dot(normal, to_light / distance), 0.0);
+            total += light.color * diffuse * window / (distance * distance + 1.0);
+        }
+    }
+    return total;
+}
+
+// A faint blue moon and a little sky
+fn moonlight(normal: vec3<f32>) -> vec3<f32> {
+    let moon = normalize(vec3<f32>(-0.4, 0.8, 0.3));
+    return vec3<f32>(0.02, 0.025, 0.05) * max(dot(normal, moon), 0.0) + vec3<f32>(0.008, 0.01, 0.018);
+}
+
+fn finish(color: vec3<f32>, world: vec3<f32>) -> vec4<f32> {
+    let fog = 1.0 - exp(-length(world - uniforms.camera.xyz) * 0.0012);
+    let fogged = mix(color, vec3<f32>(0.015, 0.02, 0.04), fog);
+    // Reinhard, the streetlights go well past 1 right underneath
+    return vec4<f32>(fogged / (1.0 + fogged), 1.0);
+}
+
+fn heatmap(world: vec3<f32>) -> vec4<f32> {
+    let count = f32(cell_range(world).y);
+    return vec4<f32>(count / 24.0, 1.0 - abs(count / 12.0 - 1.0), 1.0 - count / 12.0, 1.0);
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) material: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) @interpolate(flat) material: u32,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(vertex.position, 1.0);
+    out.world = vertex.position;
+    out.normal = vertex.normal;
+    out.uv = vertex.uv;
+    out.material = vertex.material;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if uniforms.grid.z > 0.5 {
+        return heatmap(in.world);
+    }
+
+    var albedo = vec3<f32>(0.25, 0.25, 0.26);
+    if in.material == MATERIAL_GROUND {
+        let local = in.world.xz - floor(in.world.xz / PITCH) * PITCH;
+        let street = local.x < STREET_WIDTH || local.y < STREET_WIDTH;
+        albedo = select(vec3<f32>(0.3, 0.3, 0.29), vec3<f32>(0.08, 0.08, 0.09), street);
+    } else if in.material == MATERIAL_WALL {
+        // A darker band at every floor slab
+        let slab = fract(in.uv.y / FLOOR_HEIGHT) < 0.1;
+        albedo = select(vec3<f32>(0.35, 0.33, 0.3), vec3<f32>(0.2, 0.19, 0.18), slab);
+    }
+
+    let normal = normalize(in.normal);
+    let light = point_lights(in.world, normal) + moonlight(normal);
+    return finish(albedo * light, in.world);
+}
+
+struct WindowInput {
+    @location(0) center: vec4<f32>,
+    @location(1) right: vec4<f32>,
+    @location(2) normal: vec4<f32>,
+}
+
+struct WindowOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) corner: vec2<f32>,
+    // x: brightness, y: warmth
+    @location(3) lit: vec2<f32>,
+}
+
+// Two triangles, counter-clockwise seen from outside the wall
+@vertex
+fn vs_window(@builtin(vertex_index) vertex_index: u32, window: WindowInput) -> WindowOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0)
+    );
+    let corner = corners[vertex_index];
+    let world = window.center.xyz + window.right.xyz * corner.x + vec3<f32>(0.0, window.center.w * corner.y, 0.0);
+
+    var out: WindowOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world, 1.0);
+    out.world = world;
+    out.normal = window.normal.xyz;
+    out.corner = corner;
+    out.lit = vec2<f32>(window.right.w, window.normal.w);
+    return out;
+}
+
+@fragment
+fn fs_window(in: WindowOutput) -> @location(0) vec4<f32> {
+    if uniforms.grid.z > 0.5 {
+        return heatmap(in.world);
+    }
+
+    let frame = any(abs(in.corner) > vec2<f32>(0.88, 0.9));
+    if frame {
+        let light = point_lights(in.world, in.normal) + moonlight(in.normal);
+        return finish(vec3<f32>(0.1) * light, in.world);
+    }
+    if in.lit.x > 0.0 {
+        let tint = mix(vec3<f32>(1.0, 0.75, 0.45), vec3<f32>(0.85, 0.9, 1.0), in.lit.y);
+        return finish(tint * in.lit.x * 2.0, in.world);
+    }
+    // Dark glass that still catches some of the street below
+    let light = point_lights(in.world, in.normal) + moonlight(in.normal);
+    return finish(vec3<f32>(0.04, 0.05, 0.06) * light, in.world);
+}
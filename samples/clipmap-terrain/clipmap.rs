@@ -0,0 +1,160 @@
+use wgpu::{Device, Queue, Texture};
+
+use crate::terrain::ground_color;
+
+// Must match the constants in shaders/terrain.wgsl
+pub const LEVELS: usize = 6;
+pub const CLIP_SIZE: i32 = 256;
+pub const TEXELS_PER_UNIT: f32 = 4.0;
+
+// Nested square windows onto an endless texture, all centered on the camera.
+// Each level is stored toroidally: texel (x, y) of a level always lives at
+// (x mod CLIP_SIZE, y mod CLIP_SIZE), so when the window moves only the
+// rows and columns that scrolled in have to be written.
+pub struct Clipmap {
+    pub texture: Texture,
+    // Lower left corner of every level's window, in that level's texels
+    origins: [Option<[i32; 2]>; LEVELS],
+    texels_written: usize,
+}
+
+impl Clipmap {
+    pub fn new(device: &Device) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Clipmap"),
+            size: wgpu::Extent3d {
+                width: CLIP_SIZE as u32,
+                height: CLIP_SIZE as u32,
+                depth_or_array_layers: LEVELS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self {
+            texture,
+            origins: [None; LEVELS],
+            texels_written: 0,
+        }
+    }
+
+    pub fn origins(&self) -> [[f32; 4]; LEVELS] {
+        self.origins.map(|origin| {
+            let [x, y] = origin.unwrap_or_default();
+            [x as f32, y as f32, 0.0, 0.0]
+        })
+    }
+
+    // How many texels the last update had to generate
+    pub fn texels_written(&self) -> usize {
+        self.texels_written
+    }
+
+    // `center` is the camera position in world units on the ground plane
+    pub fn update(&mut self, queue: &Queue, center: [f32; 2]) {
+        self.texels_written = 0;
+
+        for level in 0..LEVELS {
+            let scale = TEXELS_PER_UNIT / (1 << level) as f32;
+            let new = [
+                (center[0] * scale).floor() as i32 - CLIP_SIZE / 2,
+                (center[1] * scale).floor() as i32 - CLIP_SIZE / 2,
+            ];
+
+            match self.origins[level] {
+                Some(old) if old == new => continue,
+                Some(old) if (new[0] - old[0]).abs() < CLIP_SIZE && (new[1] - old[1]).abs() < CLIP_SIZE => {
+                    // Columns that entered on the left or the right
+                    let columns = if new[0] > old[0] {
+                        old[0] + CLIP_SIZE..new[0] + CLIP_SIZE
+                    } else {
+                        new[0]..old[0]
+                    };
+                    for x in columns {
+                        self.write_column(queue, level, new, x);
+                    }
+
+                    // Rows that entered at the top or the bottom
+                    let rows = if new[1] > old[1] {
+                        old[1] + CLIP_SIZE..new[1] + CLIP_SIZE
+                    } else {
+                        new[1]..old[1]
+                    };
+                    for y in rows {
+                        self.write_row(queue, level, new, y);
+                    }
+                }
+                // First frame or a teleport: nothing of the old window is reusable
+                _ => self.write_level(queue, level, new),
+            }
+
+            self.origins[level] = Some(new);
+        }
+    }
+
+    // Inverse of the toroidal mapping: the texel inside the window that is
+    // stored at `wrapped`
+    fn unwrap(origin: i32, wrapped: i32) -> i32 {
+        origin + (wrapped - origin).rem_euclid(CLIP_SIZE)
+    }
+
+    fn texel(level: usize, x: i32, y: i32) -> [u8; 4] {
+        let size = (1 << level) as f32;
+        ground_color((x as f32 + 0.5) * size, (y as f32 + 0.5) * size, level as u32)
+    }
+
+    fn write_column(&mut self, queue: &Queue, level: usize, origin: [i32; 2], x: i32) {
+        let texels: Vec<u8> = (0..CLIP_SIZE)
+            .flat_map(|wrapped_y| Self::texel(level, x, Self::unwrap(origin[1], wrapped_y)))
+            .collect();
+        self.write(queue, level, [x.rem_euclid(CLIP_SIZE), 0], [1, CLIP_SIZE], &texels);
+    }
+
+    fn write_row(&mut self, queue: &Queue, level: usize, origin: [i32; 2], y: i32) {
+        let texels: Vec<u8> = (0..CLIP_SIZE)
+            .flat_map(|wrapped_x| Self::texel(level, Self::unwrap(origin[0], wrapped_x), y))
+            .collect();
+        self.write(queue, level, [0, y.rem_euclid(CLIP_SIZE)], [CLIP_SIZE, 1], &texels);
+    }
+
+    fn write_level(&mut self, queue: &Queue, level: usize, origin: [i32; 2]) {
+        let texels: Vec<u8> = (0..CLIP_SIZE)
+            .flat_map(|wrapped_y| {
+                let y = Self::unwrap(origin[1], wrapped_y);
+                (0..CLIP_SIZE).flat_map(move |wrapped_x| Self::texel(level, Self::unwrap(origin[0], wrapped_x), y))
+            })
+            .collect();
+        self.write(queue, level, [0, 0], [CLIP_SIZE, CLIP_SIZE], &texels);
+    }
+
+    fn write(&mut self, queue: &Queue, level: usize, offset: [i32; 2], size: [i32; 2], texels: &[u8]) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: offset[0] as u32,
+                    y: offset[1] as u32,
+                    z: level as u32,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size[0] as u32),
+                rows_per_image: Some(size[1] as u32),
+            },
+            wgpu::Extent3d {
+                width: size[0] as u32,
+                height: size[1] as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.texels_written += (size[0] * size[1]) as usize;
+    }
+}
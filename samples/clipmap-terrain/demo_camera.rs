@@ -0,0 +1,56 @@
+use framework::animation::{Easing, Keyframe, Playback, Timeline, Track, TransformTracks};
+use glam::{Quat, Vec3};
+
+// The camera's orientation for a yaw, where yaw 0 looks down -z
+fn facing(yaw: f32) -> Quat {
+    Quat::from_rotation_y(-yaw)
+}
+
+// A looping flight over the terrain, long enough to make every clipmap
+// level scroll. Kept as a node's transform tracks, the same as an
+// animated glTF node, so the overlay's scrubber seeks it like one.
+pub struct DemoCamera {
+    pub timeline: Timeline,
+    tracks: TransformTracks,
+}
+
+impl Default for DemoCamera {
+    fn default() -> Self {
+        let translation = Track::new(vec![
+            Keyframe::new(0.0, Vec3::new(0.0, 12.0, 0.0), Easing::EaseInOut),
+            Keyframe::new(8.0, Vec3::new(600.0, 40.0, -400.0), Easing::Linear),
+            Keyframe::new(14.0, Vec3::new(1200.0, 90.0, 0.0), Easing::Linear),
+            Keyframe::new(20.0, Vec3::new(600.0, 25.0, 500.0), Easing::EaseInOut),
+            Keyframe::new(28.0, Vec3::new(0.0, 12.0, 0.0), Easing::Linear),
+        ]);
+        // Rotations take the short way round between keyframes, so each
+        // step stays under half a turn; the last one closes the full turn
+        let rotation = Track::new(vec![
+            Keyframe::new(0.0, facing(0.9), Easing::EaseInOut),
+            Keyframe::new(8.0, facing(1.6), Easing::Linear),
+            Keyframe::new(14.0, facing(3.1), Easing::Linear),
+            Keyframe::new(20.0, facing(4.7), Easing::EaseInOut),
+            Keyframe::new(28.0, facing(0.9), Easing::Linear),
+        ]);
+        let tracks = TransformTracks {
+            translation: Some(translation),
+            rotation: Some(rotation),
+            scale: None,
+        };
+
+        Self {
+            timeline: Timeline::new(tracks.duration(), Playback::Loop),
+            tracks,
+        }
+    }
+}
+
+impl DemoCamera {
+    // Advances the timeline and returns the pose to use this frame
+    pub fn update(&mut self, delta_time: f32) -> (Vec3, f32) {
+        self.timeline.advance(delta_time);
+        let (_, rotation, position) = self.tracks.sample(self.timeline.time()).to_scale_rotation_translation();
+        let forward = rotation * Vec3::NEG_Z;
+        (position, forward.x.atan2(-forward.z))
+    }
+}
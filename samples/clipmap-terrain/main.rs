@@ -0,0 +1,10 @@
+mod clipmap;
+mod demo_camera;
+mod renderer;
+mod terrain;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("clipmap-terrain");
+}
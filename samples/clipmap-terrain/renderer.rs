@@ -0,0 +1,323 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use framework::{CameraPose, Gpu, Sample};
+use wgpu::{include_wgsl, RenderPipeline, Buffer, BindGroup};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::clipmap::{Clipmap, LEVELS};
+use crate::demo_camera::DemoCamera;
+
+const CAMERA_HEIGHT: f32 = 12.0;
+const MOVE_SPEED: f32 = 60.0;
+const TURN_SPEED: f32 = 1.5;
+// Looking slightly down at the ground
+const DEFAULT_PITCH: f32 = -0.34;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    camera: [f32; 4],
+    origins: [[f32; 4]; LEVELS],
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    clipmap: Clipmap,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+    held_keys: HashSet<VirtualKeyCode>,
+    // Flies the camera along a keyframed path instead of WASD, toggled with C
+    demo_camera: Option<DemoCamera>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let clipmap = Clipmap::new(device);
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Clipmap Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Terrain Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Terrain Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&clipmap.texture.create_view(
+                        &wgpu::TextureViewDescriptor {
+                            dimension: Some(wgpu::TextureViewDimension::D2Array),
+                            ..Default::default()
+                        },
+                    )),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/terrain.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // The ground is seen from above only, no need to care about winding
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+            uniform_buffer,
+            bind_group,
+            clipmap,
+            position: Vec3::new(0.0, CAMERA_HEIGHT, 0.0),
+            yaw: 0.0,
+            pitch: DEFAULT_PITCH,
+            held_keys: HashSet::new(),
+            demo_camera: None,
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Pressed {
+                // Key repeat keeps sending presses, only the first one toggles
+                if self.held_keys.insert(*key) && *key == VirtualKeyCode::C {
+                    self.toggle_demo_camera();
+                }
+            } else {
+                self.held_keys.remove(key);
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.position = pose.position;
+        self.yaw = pose.yaw;
+        self.pitch = pose.pitch;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "({:.0}, {:.0}), {} texels streamed last frame",
+            self.position.x,
+            self.position.z,
+            self.clipmap.texels_written(),
+        ))
+    }
+
+    // A scrubber for the demo flight: dragging seeks, and with playing off
+    // the camera stays wherever it was left
+    fn ui(&mut self, ui: &mut framework::egui::Ui) {
+        match &mut self.demo_camera {
+            Some(demo_camera) => {
+                let timeline = &mut demo_camera.timeline;
+                let mut time = timeline.time();
+                let scrubber = framework::egui::Slider::new(&mut time, 0.0..=timeline.duration).text("flight").suffix("s");
+                if ui.add(scrubber).changed() {
+                    timeline.seek(time);
+                }
+                ui.checkbox(&mut timeline.playing, "playing");
+            }
+            None => {
+                if ui.button("fly the demo path (C)").clicked() {
+                    self.toggle_demo_camera();
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.update_camera();
+        self.clipmap.update(&gpu.queue, [self.position.x, self.position.z]);
+
+        let aspect = gpu.aspect_ratio();
+        let direction = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        );
+        let view_matrix = Mat4::look_to_rh(self.position, direction, Vec3::Y);
+        let proj = Mat4::perspective_rh(60f32.to_radians(), aspect, 0.1, 5000.0);
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: (proj * view_matrix).to_cols_array_2d(),
+                camera: self.position.extend(1.0).to_array(),
+                origins: self.clipmap.origins(),
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.55,
+                                    g: 0.65,
+                                    b: 0.8,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn update_camera(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        if let Some(demo_camera) = &mut self.demo_camera {
+            (self.position, self.yaw) = demo_camera.update(dt);
+            return;
+        }
+
+        let forward = Vec3::new(self.yaw.sin(), 0.0, -self.yaw.cos());
+        let right = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+        let held = |key| self.held_keys.contains(&key);
+
+        let mut movement = Vec3::ZERO;
+        if held(VirtualKeyCode::W) { movement += forward; }
+        if held(VirtualKeyCode::S) { movement -= forward; }
+        if held(VirtualKeyCode::D) { movement += right; }
+        if held(VirtualKeyCode::A) { movement -= right; }
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::E) { turn += 1.0; }
+        if held(VirtualKeyCode::Q) { turn -= 1.0; }
+
+        self.position += movement * MOVE_SPEED * dt;
+        self.yaw += turn * TURN_SPEED * dt;
+    }
+
+    fn toggle_demo_camera(&mut self) {
+        if self.demo_camera.take().is_none() {
+            self.demo_camera = Some(DemoCamera::default());
+        } else {
+            // Back to manual control from wherever the flight left off
+            self.position.y = CAMERA_HEIGHT;
+        }
+    }
+}
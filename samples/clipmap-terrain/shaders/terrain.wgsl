@@ -0,0 +1,77 @@
+// Must match the constants in clipmap.rs
+const LEVELS: u32 = 6u;
+const CLIP_SIZE: f32 = 256.0;
+const TEXELS_PER_UNIT: f32 = 4.0;
+// Half the size of the ground quad that follows the camera
+const GROUND_EXTENT: f32 = 2000.0;
+// How close to a level's edge we start fading into the next coarser one
+const BLEND_WIDTH: f32 = 24.0;
+const FOG_COLOR: vec3<f32> = vec3<f32>(0.55, 0.65, 0.8);
+
+struct Uniforms {
+  view_proj: mat4x4<f32>,
+  camera: vec4<f32>,
+  // xy is the level's origin in that level's texels, zw unused
+  origins: array<vec4<f32>, 6>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+// One layer per level, all the same size; level n texels are 2^n times bigger
+@group(0) @binding(1)
+var clipmap: texture_2d_array<f32>;
+// Repeat addressing does the toroidal wrap-around for us
+@group(0) @binding(2)
+var clipmap_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) world: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var corners = array<vec2<f32>, 6>(
+    vec2(-1.0, -1.0),
+    vec2(1.0, 1.0),
+    vec2(1.0, -1.0),
+    vec2(-1.0, -1.0),
+    vec2(-1.0, 1.0),
+    vec2(1.0, 1.0)
+  );
+  let corner = corners[index] * GROUND_EXTENT + uniforms.camera.xz;
+  let world = vec3(corner.x, 0.0, corner.y);
+  return VertexOutput(uniforms.view_proj * vec4(world, 1.0), world);
+}
+
+fn sample_level(texels: vec2<f32>, level: u32) -> vec3<f32> {
+  let uv = texels / CLIP_SIZE;
+  return textureSampleLevel(clipmap, clipmap_sampler, uv, i32(level), 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let dist = length(in.world - uniforms.camera.xyz);
+  let fog = 1.0 - exp(-dist * 0.0015);
+  let world_texels = in.world.xz * TEXELS_PER_UNIT;
+
+  // The finest level that covers this point wins
+  for (var level = 0u; level < LEVELS; level++) {
+    let texels = world_texels / f32(1u << level);
+    let local = texels - uniforms.origins[level].xy;
+    let edge = min(min(local.x, local.y), min(CLIP_SIZE - local.x, CLIP_SIZE - local.y));
+    if edge <= 1.0 {
+      continue;
+    }
+
+    var color = sample_level(texels, level);
+    let blend = clamp((edge - 1.0) / BLEND_WIDTH, 0.0, 1.0);
+    if blend < 1.0 && level + 1u < LEVELS {
+      let coarser = sample_level(world_texels / f32(2u << level), level + 1u);
+      color = mix(coarser, color, blend);
+    }
+    return vec4(mix(color, FOG_COLOR, fog), 1.0);
+  }
+
+  return vec4(FOG_COLOR, 1.0);
+}
@@ -0,0 +1,48 @@
+// Procedural ground colors; the clipmap only ever asks for the texels that
+// just scrolled into view, so this could just as well read from a huge file
+
+fn hash(x: i32, y: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(374_761_393) ^ (y as u32).wrapping_mul(668_265_263);
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    (h ^ (h >> 16)) as f32 / u32::MAX as f32
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn value_noise(x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let (x0, y0) = (x0 as i32, y0 as i32);
+
+    let top = hash(x0, y0) + (hash(x0 + 1, y0) - hash(x0, y0)) * tx;
+    let bottom = hash(x0, y0 + 1) + (hash(x0 + 1, y0 + 1) - hash(x0, y0 + 1)) * tx;
+    top + (bottom - top) * ty
+}
+
+// Coarser levels skip the octaves that would be smaller than one of their
+// texels, which is a cheap way of prefiltering them
+fn height(x: f32, y: f32, level: u32) -> f32 {
+    let octaves = 9u32.saturating_sub(level).max(2);
+    let (mut sum, mut amplitude, mut frequency) = (0.0, 0.5, 1.0 / 512.0);
+    for _ in 0..octaves {
+        sum += value_noise(x * frequency, y * frequency) * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+// `x` and `y` are in level 0 texels
+pub fn ground_color(x: f32, y: f32, level: u32) -> [u8; 4] {
+    let h = height(x, y, level);
+    let [r, g, b] = match h {
+        h if h < 0.38 => [30.0, 70.0, 140.0],
+        h if h < 0.42 => [200.0, 190.0, 140.0],
+        h if h < 0.58 => [60.0 + h * 60.0, 120.0 + h * 40.0, 50.0],
+        h if h < 0.7 => [110.0, 100.0, 90.0],
+        _ => [240.0, 240.0, 245.0],
+    };
+    [r as u8, g as u8, b as u8, 255]
+}
@@ -0,0 +1,8 @@
+mod renderer;
+mod roundtrip;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("color-test");
+}
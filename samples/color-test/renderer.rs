@@ -0,0 +1,185 @@
+use bytemuck::{Pod, Zeroable};
+use framework::color::{self, Encoding};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, RenderPipeline};
+
+use crate::roundtrip::{Outcome, RoundTrips};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Display {
+    encoding: u32,
+    _padding: [u32; 3],
+}
+
+pub struct Renderer {
+    outcomes: Vec<Outcome>,
+    encoding: Encoding,
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let round_trips = RoundTrips::run(gpu);
+
+        println!("rows, top to bottom: the reference colors, then");
+        for outcome in &round_trips.outcomes {
+            let error = if outcome.steps {
+                format!("{:.0} steps", outcome.error)
+            } else {
+                format!("{:.1e}", outcome.error)
+            };
+            let verdict = match (outcome.matches(), outcome.expect_match) {
+                (true, true) => "matches",
+                (false, false) => "off, as expected",
+                (false, true) => "OFF",
+                (true, false) => "matches, though it shouldn't",
+            };
+            println!("  {}: max error {}, {}", outcome.name, error, verdict);
+        }
+        println!("the Display P3 row is more saturated, striped where sRGB can't show it");
+
+        // The surface decides whether the display shader encodes
+        let encoding = Encoding::of(gpu.config.format);
+        let display_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display"),
+            contents: bytemuck::bytes_of(&Display {
+                encoding: encoding.shader_value(),
+                _padding: [0; 3],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut entries = vec![wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }];
+        entries.extend((1..=round_trips.views.len() as u32).map(|binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        }));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &entries,
+        });
+
+        let mut bind_group_entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: display_buffer.as_entire_binding(),
+        }];
+        bind_group_entries.extend(round_trips.views.iter().zip(1..).map(|(view, binding)| wgpu::BindGroupEntry {
+            binding,
+            resource: wgpu::BindingResource::TextureView(view),
+        }));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &bind_group_entries,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}", color::WGSL, include_str!("shaders/display.wgsl")).into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            outcomes: round_trips.outcomes,
+            encoding,
+            pipeline,
+            bind_group,
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let expected = self.outcomes.iter().filter(|outcome| outcome.expect_match);
+        let total = expected.clone().count();
+        let matching = expected.filter(|outcome| outcome.matches()).count();
+        let encoding = match self.encoding {
+            Encoding::Hardware => "encodes itself",
+            Encoding::Shader => "gets encoded by the shader",
+            Encoding::Linear => "stays linear",
+        };
+        Some(format!(
+            "{} of {} round trips within tolerance; the surface {}",
+            matching, total, encoding,
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,349 @@
+use bytemuck::{Pod, Zeroable};
+use framework::color::{self, ColorSpace, Encoding};
+use framework::Gpu;
+use glam::Vec3;
+use wgpu::util::DeviceExt;
+use wgpu::{TextureFormat, TextureView};
+
+// Reference colors, one texel each
+pub const COUNT: usize = 16;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CaseUniform {
+    decode_curve: u32,
+    from_p3: u32,
+    to_p3: u32,
+    encoding: u32,
+}
+
+// One way of getting the reference colors from a texture into a target
+struct Case {
+    name: &'static str,
+    input: TextureFormat,
+    target: TextureFormat,
+    decode_curve: bool,
+    p3: bool,
+    // Only the double gamma case is meant to be off
+    expect_match: bool,
+}
+
+// In the order display.wgsl shows them
+const CASES: [Case; 5] = [
+    Case {
+        name: "sRGB texture to sRGB target",
+        input: TextureFormat::Rgba8UnormSrgb,
+        target: TextureFormat::Rgba8UnormSrgb,
+        decode_curve: false,
+        p3: false,
+        expect_match: true,
+    },
+    Case {
+        name: "unorm texture to unorm target, converted in the shader",
+        input: TextureFormat::Rgba8Unorm,
+        target: TextureFormat::Rgba8Unorm,
+        decode_curve: true,
+        p3: false,
+        expect_match: true,
+    },
+    Case {
+        name: "sRGB texture to float target",
+        input: TextureFormat::Rgba8UnormSrgb,
+        target: TextureFormat::Rgba16Float,
+        decode_curve: false,
+        p3: false,
+        expect_match: true,
+    },
+    Case {
+        name: "Display P3 through linear sRGB and back",
+        input: TextureFormat::Rgba8UnormSrgb,
+        target: TextureFormat::Rgba8Unorm,
+        decode_curve: false,
+        p3: true,
+        expect_match: true,
+    },
+    Case {
+        name: "double gamma, sRGB texture decoded again",
+        input: TextureFormat::Rgba8UnormSrgb,
+        target: TextureFormat::Rgba8UnormSrgb,
+        decode_curve: true,
+        p3: false,
+        expect_match: false,
+    },
+];
+
+pub struct Outcome {
+    pub name: &'static str,
+    // In 8 bit steps for unorm targets, linear values for float ones
+    pub error: f32,
+    pub tolerance: f32,
+    pub steps: bool,
+    pub expect_match: bool,
+}
+
+impl Outcome {
+    pub fn matches(&self) -> bool {
+        self.error <= self.tolerance
+    }
+}
+
+// The reference colors as 8 bit codes: ramps in each channel, so every
+// part of the curves gets hit
+fn reference_codes() -> [[u8; 4]; COUNT] {
+    std::array::from_fn(|i| {
+        let value = (i * 17) as u8;
+        [value, 255 - value, (i * 7 % COUNT * 17) as u8, 255]
+    })
+}
+
+// The round trips run once; the textures stay around for display.wgsl,
+// reference first
+pub struct RoundTrips {
+    pub views: Vec<TextureView>,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl RoundTrips {
+    pub fn run(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let codes = reference_codes();
+
+        let create_input = |format| {
+            device.create_texture_with_data(
+                &gpu.queue,
+                &wgpu::TextureDescriptor {
+                    label: Some("Reference Colors"),
+                    size: wgpu::Extent3d {
+                        width: COUNT as u32,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                bytemuck::cast_slice(&codes),
+            )
+        };
+        // sRGB and linear are the same bytes, only how sampling reads them differs
+        let srgb_texture = create_input(ColorSpace::Srgb.texture_format());
+        let srgb_input = srgb_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let unorm_input = create_input(ColorSpace::LinearSrgb.texture_format()).create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Round Trip Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Round Trip Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Round Trip Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}", color::WGSL, include_str!("shaders/roundtrip.wgsl")).into(),
+            ),
+        });
+
+        let mut views = vec![srgb_texture.create_view(&wgpu::TextureViewDescriptor::default())];
+        let mut outcomes = Vec::new();
+        for case in &CASES {
+            let target = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(case.name),
+                size: wgpu::Extent3d {
+                    width: COUNT as u32,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: case.target,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Round Trip Case"),
+                contents: bytemuck::bytes_of(&CaseUniform {
+                    decode_curve: case.decode_curve as u32,
+                    from_p3: case.p3 as u32,
+                    to_p3: case.p3 as u32,
+                    encoding: Encoding::of(case.target).shader_value(),
+                }),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let input = if case.input == TextureFormat::Rgba8Unorm { &unorm_input } else { &srgb_input };
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Round Trip Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(input),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Round Trip Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(case.target.into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+            let mut encoder =
+                gpu.device.create_command_encoder(
+                    &wgpu::CommandEncoderDescriptor {
+                        label: Some("Round Trip Encoder"),
+                    },
+                );
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Round Trip Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                render_pass.set_pipeline(&pipeline);
+                render_pass.set_bind_group(0, &bind_group, &[]);
+                render_pass.draw(0..3, 0..1);
+            }
+            gpu.queue.submit(std::iter::once(encoder.finish()));
+
+            outcomes.push(compare(case, &codes, &read_row(gpu, &target)));
+            views.push(target_view);
+        }
+
+        Self { views, outcomes }
+    }
+}
+
+fn compare(case: &Case, codes: &[[u8; 4]; COUNT], row: &[[f32; 4]]) -> Outcome {
+    let steps = case.target != TextureFormat::Rgba16Float;
+    let mut error: f32 = 0.0;
+    for (code, texel) in codes.iter().zip(row) {
+        let reference = Vec3::new(code[0] as f32, code[1] as f32, code[2] as f32) / 255.0;
+        let (expected, got) = if steps {
+            // What was written should decode to the codes it started as
+            (reference * 255.0, Vec3::new(texel[0], texel[1], texel[2]) * 255.0)
+        } else {
+            (ColorSpace::Srgb.to_linear_srgb(reference), Vec3::new(texel[0], texel[1], texel[2]))
+        };
+        error = error.max((expected - got).abs().max_element());
+    }
+    Outcome {
+        name: case.name,
+        error,
+        // A step of rounding either way for 8 bits, half precision otherwise
+        tolerance: if steps { 1.0 } else { 2e-3 },
+        steps,
+        expect_match: case.expect_match,
+    }
+}
+
+// The target's single row as stored, unorm values over 255
+fn read_row(gpu: &Gpu, texture: &wgpu::Texture) -> Vec<[f32; 4]> {
+    let texel_size = if texture.format() == TextureFormat::Rgba16Float { 8 } else { 4 };
+    // Texture to buffer copies need rows aligned to 256 bytes, one row fits
+    let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Round Trip Readback"),
+        size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder =
+        gpu.device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
+            },
+        );
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..(COUNT * texel_size) as u64);
+    slice.map_async(wgpu::MapMode::Read, |_| {});
+    gpu.device.poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range();
+    let row = if texel_size == 8 {
+        bytemuck::cast_slice::<u8, [u16; 4]>(&data).iter().map(|texel| texel.map(half_to_f32)).collect()
+    } else {
+        data.chunks(4).map(|texel| [0, 1, 2, 3].map(|channel| texel[channel] as f32 / 255.0)).collect()
+    };
+    drop(data);
+    buffer.unmap();
+    row
+}
+
+// IEEE half precision, which Rust has no type for
+fn half_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 => f32::INFINITY,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
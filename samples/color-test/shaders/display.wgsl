@@ -0,0 +1,70 @@
+// framework::color::WGSL is pasted in front of this. One row of patches per
+// texture: the reference colors on top, then what each round trip in
+// roundtrip.rs wrote, converted back to linear sRGB for display.
+
+struct Display {
+    // color::Encoding::shader_value of the surface
+    encoding: u32,
+    _padding_a: u32,
+    _padding_b: u32,
+    _padding_c: u32,
+}
+
+@group(0) @binding(0) var<uniform> display: Display;
+@group(0) @binding(1) var reference: texture_2d<f32>;
+@group(0) @binding(2) var srgb_target: texture_2d<f32>;
+@group(0) @binding(3) var unorm_target: texture_2d<f32>;
+@group(0) @binding(4) var float_target: texture_2d<f32>;
+@group(0) @binding(5) var p3_target: texture_2d<f32>;
+@group(0) @binding(6) var double_gamma_target: texture_2d<f32>;
+
+const COUNT: f32 = 16.0;
+const ROWS: f32 = 6.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(f32(i & 1u) * 4.0 - 1.0, f32(i >> 1u) * 4.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(out.position.x * 0.5 + 0.5, 0.5 - out.position.y * 0.5);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = in.uv * vec2<f32>(COUNT, ROWS);
+    let inside = fract(cell);
+    // Gaps between the patches
+    if inside.x < 0.06 || inside.y < 0.1 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let coords = vec2<i32>(i32(cell.x), 0);
+    let row = i32(cell.y);
+    var c = vec3<f32>(0.0);
+    if row == 0 {
+        c = textureLoad(reference, coords, 0).rgb;
+    } else if row == 1 {
+        c = textureLoad(srgb_target, coords, 0).rgb;
+    } else if row == 2 {
+        c = srgb_to_linear(textureLoad(unorm_target, coords, 0).rgb);
+    } else if row == 3 {
+        c = textureLoad(float_target, coords, 0).rgb;
+    } else if row == 4 {
+        // Holds Display P3, which sRGB can't show all of
+        c = linear_p3_to_linear_srgb(srgb_to_linear(textureLoad(p3_target, coords, 0).rgb));
+    } else {
+        c = textureLoad(double_gamma_target, coords, 0).rgb;
+    }
+
+    // Stripes over colors outside the surface's gamut, which clip
+    let outside = any(c < vec3<f32>(-0.002)) || any(c > vec3<f32>(1.002));
+    if outside && fract((in.position.x + in.position.y) / 12.0) < 0.25 {
+        c = vec3<f32>(0.5);
+    }
+    return vec4<f32>(encode_for_target(clamp(c, vec3<f32>(0.0), vec3<f32>(1.0)), display.encoding), 1.0);
+}
@@ -0,0 +1,39 @@
+// framework::color::WGSL is pasted in front of this. Converts one row of
+// reference colors the way a case in roundtrip.rs says, into a target of
+// that case's format.
+
+struct Case {
+    // Run srgb_to_linear on what the input texture gives; wrong for an
+    // Rgba8UnormSrgb input, which already decoded it
+    decode_curve: u32,
+    // The input is Display P3, converted to linear sRGB in between
+    from_p3: u32,
+    // And back to P3 before it's written
+    to_p3: u32,
+    // color::Encoding::shader_value of the target
+    encoding: u32,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> test: Case;
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    return vec4<f32>(f32(i & 1u) * 4.0 - 1.0, f32(i >> 1u) * 4.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    var c = textureLoad(input_texture, vec2<i32>(position.xy), 0).rgb;
+    if test.decode_curve == 1u {
+        c = srgb_to_linear(c);
+    }
+    if test.from_p3 == 1u {
+        c = linear_p3_to_linear_srgb(c);
+    }
+    // Linear sRGB here, whatever came in
+    if test.to_p3 == 1u {
+        c = linear_srgb_to_linear_p3(c);
+    }
+    return vec4<f32>(encode_for_target(c, test.encoding), 1.0);
+}
@@ -0,0 +1,7 @@
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("compute-particles");
+}
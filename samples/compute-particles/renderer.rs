@@ -0,0 +1,399 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::autotune::{self, Kernel};
+use framework::snapshot::{Snapshot, SnapshotError};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, Buffer, ComputePipeline, RenderPipeline};
+use wgpu::util::DeviceExt;
+
+const PARTICLE_COUNT: u32 = 65536;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+}
+
+impl Particle {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Particle>() as wgpu::BufferAddress,
+            // Every particle is one instance of a six vertex quad
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SimParams {
+    attractor: [f32; 2],
+    delta_time: f32,
+    aspect: f32,
+    particle_count: u32,
+    _padding: u32,
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+}
+
+pub struct Renderer {
+    compute_pipeline: ComputePipeline,
+    workgroup_size: [u32; 2],
+    render_pipeline: RenderPipeline,
+    params_buffer: Buffer,
+    // Ping-pong pair: each frame reads one and writes the other
+    particle_buffers: [Buffer; 2],
+    // compute_bind_groups[i] reads particle_buffers[i] and writes the other one
+    compute_bind_groups: [BindGroup; 2],
+    render_bind_group: BindGroup,
+    frame: usize,
+    // Simulated seconds, which run at time_scale times the real ones
+    time: f32,
+    time_scale: f32,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    // downlevel_webgl2_defaults() zeroes every compute limit, so ask for the
+    // smallest set that still has compute shaders even on the web
+    fn required_limits() -> wgpu::Limits {
+        wgpu::Limits::downlevel_defaults()
+    }
+
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        // WebGL2 adapters report no compute support at all; there is no
+        // fallback path here, so fail with something readable
+        let downlevel = gpu.adapter.get_downlevel_capabilities();
+        assert!(
+            downlevel.flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS),
+            "compute-particles needs compute shaders, which this adapter doesn't support",
+        );
+
+        let mut rng = Rng(0x9e37_79b9);
+        let particles: Vec<Particle> = (0..PARTICLE_COUNT)
+            .map(|_| {
+                let angle = rng.next_f32() * std::f32::consts::TAU;
+                let radius = rng.next_f32().sqrt() * 0.8;
+                let (sin, cos) = angle.sin_cos();
+                Particle {
+                    position: [cos * radius, sin * radius],
+                    // A slow spin to start with
+                    velocity: [-sin * 0.2, cos * 0.2],
+                }
+            })
+            .collect();
+
+        let particle_buffers = [0, 1].map(|index| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("Particles {}", index)),
+                contents: bytemuck::cast_slice(&particles),
+                // STORAGE for the simulation, VERTEX to draw straight from
+                // it, COPY_SRC and COPY_DST for snapshots
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::VERTEX
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sim Params"),
+            contents: bytemuck::bytes_of(&SimParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Simulation Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_bind_groups = [0, 1].map(|src| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Simulation Bind Group"),
+                layout: &compute_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: particle_buffers[src].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: particle_buffers[1 - src].as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let render_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Render Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Render Bind Group"),
+            layout: &render_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        let compute_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Compute Pipeline Layout"),
+                    bind_group_layouts: &[&compute_bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        // Tuning runs the simulation for real, which only ever writes the
+        // buffer it doesn't read; no time passing keeps the particles put
+        gpu.queue.write_buffer(
+            &params_buffer,
+            0,
+            bytemuck::bytes_of(&SimParams {
+                attractor: [0.0, 0.0],
+                delta_time: 0.0,
+                aspect: gpu.aspect_ratio(),
+                particle_count: PARTICLE_COUNT,
+                _padding: 0,
+            }),
+        );
+        let kernel = Kernel {
+            name: "compute-particles simulate",
+            source: include_str!("shaders/simulate.wgsl"),
+            entry_point: "cs_main",
+            layout: &compute_pipeline_layout,
+            bind_group: &compute_bind_groups[0],
+            invocations: [PARTICLE_COUNT, 1],
+        };
+        let workgroup_size = autotune::workgroup_size(gpu, &kernel);
+        let compute_pipeline = autotune::create_pipeline(device, &kernel, workgroup_size);
+
+        let particle_shader = device.create_shader_module(include_wgsl!("shaders/particle.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&render_bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &particle_shader,
+                entry_point: "vs_main",
+                buffers: &[Particle::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &particle_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    // Additive, so dense clusters glow
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            compute_pipeline,
+            workgroup_size,
+            render_pipeline,
+            params_buffer,
+            particle_buffers,
+            compute_bind_groups,
+            render_bind_group,
+            frame: 0,
+            time: 0.0,
+            time_scale: 1.0,
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{} particles", PARTICLE_COUNT))
+    }
+
+    // time_scale 0 freezes the particles, 0.2 is slow motion
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "time_scale" => self.time_scale = value.max(0.0),
+            _ => return false,
+        }
+        true
+    }
+
+    // 2 added the simulated time, which moves the attractor
+    const STATE_VERSION: u32 = 2;
+
+    // Only the buffer the next frame reads from, the other one gets
+    // overwritten before anything looks at it
+    fn save_state(&self, gpu: &Gpu, snapshot: &mut Snapshot) {
+        snapshot.add_buffer(gpu, "particles", &self.particle_buffers[self.frame % 2]);
+        snapshot.add_bytes("time", &self.time.to_le_bytes());
+    }
+
+    fn load_state(&mut self, gpu: &Gpu, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        snapshot.restore_buffer(gpu, "particles", &self.particle_buffers[self.frame % 2])?;
+        let time: [u8; 4] = snapshot
+            .bytes("time")?
+            .try_into()
+            .map_err(|_| SnapshotError::Format("time isn't an f32".to_string()))?;
+        self.time = f32::from_le_bytes(time);
+        Ok(())
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        // Clamped so a long hitch (e.g. dragging the window) doesn't blow
+        // the simulation apart
+        let delta_time = (now - self.last_frame).as_secs_f32().min(1.0 / 30.0) * self.time_scale;
+        self.last_frame = now;
+        self.time += delta_time;
+
+        let time = self.time;
+        gpu.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&SimParams {
+                attractor: [(time * 0.5).cos() * 0.5, (time * 0.7).sin() * 0.5],
+                delta_time,
+                aspect: gpu.aspect_ratio(),
+                particle_count: PARTICLE_COUNT,
+                _padding: 0,
+            }),
+        );
+
+        let src = self.frame % 2;
+        let dst = 1 - src;
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Simulation Pass"),
+            });
+            compute_pass.set_pipeline(&self.compute_pipeline);
+            compute_pass.set_bind_group(0, &self.compute_bind_groups[src], &[]);
+            // Rounded up, the shader skips the indices past the end
+            let [x, y] = autotune::workgroups([PARTICLE_COUNT, 1], self.workgroup_size);
+            compute_pass.dispatch_workgroups(x, y, 1);
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            // wgpu sees the storage write above and the vertex read here and
+            // orders them, no manual barrier needed
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.particle_buffers[dst].slice(..));
+            render_pass.draw(0..6, 0..PARTICLE_COUNT);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+
+        self.frame += 1;
+    }
+}
@@ -0,0 +1,51 @@
+struct SimParams {
+    attractor: vec2<f32>,
+    delta_time: f32,
+    aspect: f32,
+    particle_count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: SimParams;
+
+struct ParticleInput {
+    @location(0) position: vec2<f32>,
+    @location(1) velocity: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+const PARTICLE_SIZE: f32 = 0.004;
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    particle: ParticleInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0)
+    );
+    // Keep the quads square on wide windows
+    let corner = corners[vertex_index] * PARTICLE_SIZE * vec2<f32>(1.0 / params.aspect, 1.0);
+
+    // Slow particles are blue, fast ones orange
+    let speed = clamp(length(particle.velocity) * 1.5, 0.0, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(particle.position + corner, 0.0, 1.0);
+    out.color = mix(vec3<f32>(0.1, 0.2, 0.8), vec3<f32>(1.0, 0.5, 0.1), speed) * 0.3;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
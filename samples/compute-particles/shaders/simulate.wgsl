@@ -0,0 +1,43 @@
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+}
+
+struct SimParams {
+    attractor: vec2<f32>,
+    delta_time: f32,
+    aspect: f32,
+    particle_count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: SimParams;
+@group(0) @binding(1)
+var<storage, read> particles_src: array<Particle>;
+@group(0) @binding(2)
+var<storage, read_write> particles_dst: array<Particle>;
+
+// The size is filled in with whatever the autotuner found fastest
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    // The last workgroup usually runs past the end of the buffer
+    if index >= params.particle_count {
+        return;
+    }
+
+    var particle = particles_src[index];
+
+    // Pulled towards the attractor, softened so nothing gets flung away
+    // when it passes right through it
+    let offset = params.attractor - particle.position;
+    let dist_sq = dot(offset, offset) + 0.01;
+    particle.velocity += offset / dist_sq * 0.4 * params.delta_time;
+    particle.velocity *= 1.0 - 0.15 * params.delta_time;
+    particle.position += particle.velocity * params.delta_time;
+
+    // Wrap around the edges of the screen
+    particle.position = fract((particle.position + 1.0) * 0.5) * 2.0 - 1.0;
+
+    particles_dst[index] = particle;
+}
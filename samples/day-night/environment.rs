@@ -0,0 +1,364 @@
+use bytemuck::{Pod, Zeroable};
+use framework::envmap::{create_cubemap, cube_view, EnvironmentTools};
+use glam::Vec3;
+use wgpu::{BindGroupLayout, CommandEncoder, ComputePipeline, Device, Sampler, Texture, TextureView};
+use wgpu::util::DeviceExt;
+
+const SKY_SIZE: u32 = 128;
+const IRRADIANCE_SIZE: u32 = 16;
+const SPECULAR_SIZE: u32 = 128;
+// Roughness 0, 0.25, .. 1 in mips 0 to 4
+pub const SPECULAR_LEVELS: u32 = 5;
+const IRRADIANCE_SAMPLES: u32 = 128;
+const SPECULAR_SAMPLES: u32 = 64;
+const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    sun: [f32; 4],
+    face: u32,
+    roughness: f32,
+    source_size: f32,
+    samples: u32,
+}
+
+// One unit of regeneration work, small enough to slip into a frame
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    SkyFace(u32),
+    SkyMips,
+    Irradiance,
+    Specular(u32),
+}
+
+// Six sky faces, the sky's mips, irradiance, then every specular level
+pub const STEPS: usize = 6 + 1 + 1 + SPECULAR_LEVELS as usize;
+
+fn step(index: usize) -> Step {
+    match index {
+        0..=5 => Step::SkyFace(index as u32),
+        6 => Step::SkyMips,
+        7 => Step::Irradiance,
+        _ => Step::Specular(index as u32 - 8),
+    }
+}
+
+// The sky cubemap and the two filtered cubemaps PBR shading reads
+pub struct Environment {
+    sky: Texture,
+    irradiance: Texture,
+    specular: Texture,
+    // Sun direction the sky was generated for
+    pub sun: Vec3,
+}
+
+impl Environment {
+    fn new(device: &Device) -> Self {
+        Self {
+            sky: create_cubemap(device, SKY_SIZE, "Sky"),
+            irradiance: create_cubemap(device, IRRADIANCE_SIZE, "Irradiance"),
+            specular: create_cubemap(device, SPECULAR_SIZE, "Specular"),
+            sun: Vec3::Y,
+        }
+    }
+
+    pub fn irradiance_view(&self) -> TextureView {
+        cube_view(&self.irradiance)
+    }
+
+    pub fn specular_view(&self) -> TextureView {
+        cube_view(&self.specular)
+    }
+}
+
+fn face_array_view(cubemap: &Texture, mip: u32) -> TextureView {
+    cubemap.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        base_mip_level: mip,
+        mip_level_count: Some(1),
+        ..Default::default()
+    })
+}
+
+fn storage_texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format: framework::envmap::ENVIRONMENT_FORMAT,
+            view_dimension: wgpu::TextureViewDimension::D2Array,
+        },
+        count: None,
+    }
+}
+
+fn params_entry() -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// Two environments: one being shaded with while the other is rebuilt a few
+// steps per frame for the current sun. They trade places once the rebuild
+// is done, so shading always reads a complete, consistent set and the
+// regeneration cost is spread over as many frames as the budget says.
+pub struct ProgressiveEnvironment {
+    sky_layout: BindGroupLayout,
+    sky_pipeline: ComputePipeline,
+    filter_layout: BindGroupLayout,
+    irradiance_pipeline: ComputePipeline,
+    specular_pipeline: ComputePipeline,
+    sampler: Sampler,
+    tools: EnvironmentTools,
+    environments: [Environment; 2],
+    // Index of the one shading reads
+    current: usize,
+    next_step: usize,
+    generations: u32,
+}
+
+impl ProgressiveEnvironment {
+    pub fn new(device: &Device, encoder: &mut CommandEncoder, sun: Vec3) -> Self {
+        let sky_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Layout"),
+            entries: &[params_entry(), storage_texture_entry(3)],
+        });
+        let filter_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Filter Layout"),
+            entries: &[
+                params_entry(),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                storage_texture_entry(3),
+            ],
+        });
+
+        // WGSL has no includes, so the sky model is pasted in front
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Environment"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("shaders/sky.wgsl"), include_str!("shaders/environment.wgsl")).into(),
+            ),
+        });
+        let compute_pipeline = |label, layout: &BindGroupLayout, entry_point| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(label),
+                bind_group_layouts: &[layout],
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        let sky_pipeline = compute_pipeline("Sky", &sky_layout, "cs_sky");
+        let irradiance_pipeline = compute_pipeline("Irradiance", &filter_layout, "cs_irradiance");
+        let specular_pipeline = compute_pipeline("Specular", &filter_layout, "cs_specular");
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sky Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let mut environment = Self {
+            sky_layout,
+            sky_pipeline,
+            filter_layout,
+            irradiance_pipeline,
+            specular_pipeline,
+            sampler,
+            tools: EnvironmentTools::new(device),
+            environments: [Environment::new(device), Environment::new(device)],
+            current: 0,
+            next_step: 0,
+            generations: 0,
+        };
+
+        // The first one in full right away, there's nothing to shade with yet
+        environment.advance(device, encoder, sun, STEPS);
+        environment
+    }
+
+    pub fn current(&self) -> &Environment {
+        &self.environments[self.current]
+    }
+
+    pub fn generations(&self) -> u32 {
+        self.generations
+    }
+
+    // Steps done towards the next environment
+    pub fn progress(&self) -> usize {
+        self.next_step
+    }
+
+    // Records up to `budget` steps of the rebuild. A new rebuild takes the
+    // sun as it is when it starts. Returns true when the environments were
+    // swapped and bind groups using them need recreating.
+    pub fn advance(&mut self, device: &Device, encoder: &mut CommandEncoder, sun: Vec3, budget: usize) -> bool {
+        let mut swapped = false;
+        for _ in 0..budget {
+            let target = 1 - self.current;
+            if self.next_step == 0 {
+                self.environments[target].sun = sun;
+            }
+            self.record(device, encoder, target, step(self.next_step));
+
+            self.next_step += 1;
+            if self.next_step == STEPS {
+                self.next_step = 0;
+                self.current = target;
+                self.generations += 1;
+                swapped = true;
+            }
+        }
+        swapped
+    }
+
+    fn record(&self, device: &Device, encoder: &mut CommandEncoder, target: usize, step: Step) {
+        let environment = &self.environments[target];
+        let sun = environment.sun.extend(0.0).to_array();
+
+        match step {
+            Step::SkyFace(face) => {
+                let params = Params {
+                    sun,
+                    face,
+                    roughness: 0.0,
+                    source_size: 0.0,
+                    samples: 0,
+                };
+                let destination = face_array_view(&environment.sky, 0);
+                self.dispatch(device, encoder, &self.sky_pipeline, &self.sky_layout, params, None, &destination, [SKY_SIZE, SKY_SIZE, 1]);
+            }
+            Step::SkyMips => self.tools.generate_mips(device, encoder, &environment.sky),
+            Step::Irradiance => {
+                let params = Params {
+                    sun,
+                    face: 0,
+                    roughness: 1.0,
+                    source_size: SKY_SIZE as f32,
+                    samples: IRRADIANCE_SAMPLES,
+                };
+                let destination = face_array_view(&environment.irradiance, 0);
+                let source = cube_view(&environment.sky);
+                self.dispatch(
+                    device,
+                    encoder,
+                    &self.irradiance_pipeline,
+                    &self.filter_layout,
+                    params,
+                    Some(&source),
+                    &destination,
+                    [IRRADIANCE_SIZE, IRRADIANCE_SIZE, 6],
+                );
+            }
+            Step::Specular(level) => {
+                let size = SPECULAR_SIZE >> level;
+                let params = Params {
+                    sun,
+                    face: 0,
+                    roughness: level as f32 / (SPECULAR_LEVELS - 1) as f32,
+                    source_size: SKY_SIZE as f32,
+                    samples: SPECULAR_SAMPLES,
+                };
+                let destination = face_array_view(&environment.specular, level);
+                let source = cube_view(&environment.sky);
+                self.dispatch(
+                    device,
+                    encoder,
+                    &self.specular_pipeline,
+                    &self.filter_layout,
+                    params,
+                    Some(&source),
+                    &destination,
+                    [size, size, 6],
+                );
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn dispatch(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        pipeline: &ComputePipeline,
+        layout: &BindGroupLayout,
+        params: Params,
+        source: Option<&TextureView>,
+        destination: &TextureView,
+        size: [u32; 3],
+    ) {
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Environment Params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(destination),
+            },
+        ];
+        if let Some(source) = source {
+            entries.push(wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(source),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(&self.sampler),
+            });
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Environment Bind Group"),
+            layout,
+            entries: &entries,
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Environment Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            (size[0] + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            (size[1] + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            size[2],
+        );
+    }
+}
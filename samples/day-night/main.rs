@@ -0,0 +1,8 @@
+mod environment;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("day-night");
+}
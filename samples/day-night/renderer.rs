@@ -0,0 +1,546 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, Sampler, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::environment::{ProgressiveEnvironment, SPECULAR_LEVELS, STEPS};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+// Environment steps recorded per frame, cycled with Tab
+const BUDGETS: [usize; 4] = [1, 2, 4, STEPS];
+// Roughness across, one row per material
+const COLUMNS: usize = 7;
+const SPHERE_SPACING: f32 = 2.5;
+// Of the sun's path, tilted away from straight overhead
+const LATITUDE: f32 = 0.6;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Instance {
+    // w is the scale
+    position: [f32; 4],
+    // w is the roughness
+    albedo: [f32; 4],
+    // x is the metalness
+    material: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![2 => Float32x4, 3 => Float32x4, 4 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    inverse_view_proj: [[f32; 4]; 4],
+    camera: [f32; 4],
+    sun: [f32; 4],
+    environment: [f32; 4],
+}
+
+// A unit sphere of latitude rings
+fn sphere() -> (Vec<Vertex>, Vec<u16>) {
+    let (rings, segments) = (24u16, 48u16);
+    let mut vertices = Vec::new();
+    for ring in 0..=rings {
+        let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+        for segment in 0..=segments {
+            let phi = segment as f32 / segments as f32 * std::f32::consts::TAU;
+            let normal = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            vertices.push(Vertex {
+                position: normal.to_array(),
+                normal: normal.to_array(),
+            });
+        }
+    }
+
+    let mut indices = Vec::new();
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * (segments + 1) + segment;
+            let b = a + segments + 1;
+            indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+    (vertices, indices)
+}
+
+// Follows a tilted circle: rises in the east (+x) at 6, highest at 12,
+// sets in the west at 18 and stays below the horizon overnight
+fn sun_direction(hour: f32) -> Vec3 {
+    let angle = (hour - 6.0) / 12.0 * std::f32::consts::PI;
+    Vec3::new(angle.cos(), angle.sin() * LATITUDE.cos(), -angle.sin() * LATITUDE.sin())
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    sky_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    bind_group: BindGroup,
+    sampler: Sampler,
+    uniform_buffer: Buffer,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    instance_buffer: Buffer,
+    instance_count: u32,
+    depth_view: TextureView,
+    environment: ProgressiveEnvironment,
+    budget: usize,
+    hour: f32,
+    // Game hours per real second
+    speed: f32,
+    paused: bool,
+    orbit: f32,
+    // Set by --flythrough or a script, orbiting with the arrow keys until then
+    camera: Option<CameraPose>,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let hour = 16.5;
+
+        let mut encoder =
+            device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Environment Encoder"),
+                },
+            );
+        let environment = ProgressiveEnvironment::new(device, &mut encoder, sun_direction(hour));
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let (vertices, indices) = sphere();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // Rows of plastic, gold and rough stone, and one huge sphere whose top
+        // is the ground
+        let materials = [([0.8, 0.1, 0.1], 0.0), ([1.0, 0.78, 0.34], 1.0), ([0.5, 0.5, 0.5], 0.0)];
+        let mut instances = Vec::new();
+        for (row, (albedo, metalness)) in materials.iter().enumerate() {
+            for column in 0..COLUMNS {
+                let roughness = column as f32 / (COLUMNS - 1) as f32;
+                instances.push(Instance {
+                    position: [
+                        (column as f32 - (COLUMNS - 1) as f32 / 2.0) * SPHERE_SPACING,
+                        1.0,
+                        (row as f32 - 1.0) * SPHERE_SPACING,
+                        1.0,
+                    ],
+                    albedo: [albedo[0], albedo[1], albedo[2], roughness],
+                    material: [*metalness, 0.0, 0.0, 0.0],
+                });
+            }
+        }
+        instances.push(Instance {
+            position: [0.0, -1000.0, 0.0, 1000.0],
+            albedo: [0.3, 0.3, 0.28, 0.9],
+            material: [0.0; 4],
+        });
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Environment Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let cube_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::Cube,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                cube_entry(1),
+                cube_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = create_bind_group(device, &bind_group_layout, &uniform_buffer, &sampler, &environment);
+
+        // WGSL has no includes, so the sky model is pasted in front
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Scene"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("shaders/sky.wgsl"), include_str!("shaders/scene.wgsl")).into(),
+            ),
+        });
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), Instance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // Drawn last at the far plane, so only where nothing else is
+        let sky_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sky Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_sky",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_sky",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            scene_pipeline,
+            sky_pipeline,
+            bind_group_layout,
+            bind_group,
+            sampler,
+            uniform_buffer,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer,
+            instance_count: instances.len() as u32,
+            depth_view: create_depth_view(device, &gpu.config),
+            environment,
+            budget: 1,
+            hour,
+            speed: 0.5,
+            paused: false,
+            orbit: 0.3,
+            camera: None,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one counts
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                VirtualKeyCode::Up => self.speed = (self.speed * 2.0).min(8.0),
+                VirtualKeyCode::Down => self.speed = (self.speed / 2.0).max(1.0 / 32.0),
+                VirtualKeyCode::Tab => {
+                    let index = BUDGETS.iter().position(|&budget| budget == self.budget).unwrap_or(0);
+                    self.budget = BUDGETS[(index + 1) % BUDGETS.len()];
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        let sun = sun_direction(self.hour);
+        // How far the sun has moved since the shading environment's sky
+        let lag = sun.angle_between(self.environment.current().sun).to_degrees();
+        Some(format!(
+            "{:02}:{:02}{}, {:.2} h/s, {} steps/frame, {}/{} steps, {} environments, {:.2} degrees behind",
+            self.hour as u32,
+            (self.hour.fract() * 60.0) as u32,
+            if self.paused { " paused" } else { "" },
+            self.speed,
+            self.budget,
+            self.environment.progress(),
+            STEPS,
+            self.environment.generations(),
+            lag,
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        if !self.paused {
+            self.hour = (self.hour + self.speed * dt).rem_euclid(24.0);
+        }
+        let held = |key| self.held_keys.contains(&key);
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        self.orbit += turn * dt;
+
+        let sun = sun_direction(self.hour);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        if self.environment.advance(&gpu.device, &mut encoder, sun, self.budget) {
+            self.bind_group = create_bind_group(
+                &gpu.device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.sampler,
+                &self.environment,
+            );
+        }
+
+        let (eye, target) = match &self.camera {
+            Some(pose) => (pose.position, pose.position + pose.direction()),
+            None => (Vec3::new(self.orbit.sin() * 14.0, 4.0, self.orbit.cos() * 14.0), Vec3::new(0.0, 1.0, 0.0)),
+        };
+        let view_matrix = Mat4::look_at_rh(eye, target, Vec3::Y);
+        let proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), 0.1, 5000.0);
+        let view_proj = proj * view_matrix;
+        // Brighter at night, like eyes adjusting, without going fully
+        // automatic
+        let exposure = 1.0 + 5.0 * (1.0 - ((sun.y + 0.1) * 5.0).clamp(0.0, 1.0));
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: view_proj.to_cols_array_2d(),
+                inverse_view_proj: view_proj.inverse().to_cols_array_2d(),
+                camera: eye.extend(1.0).to_array(),
+                sun: sun.extend(exposure).to_array(),
+                environment: [(SPECULAR_LEVELS - 1) as f32, 0.0, 0.0, 0.0],
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+
+            render_pass.set_pipeline(&self.sky_pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniform_buffer: &Buffer,
+    sampler: &Sampler,
+    environment: &ProgressiveEnvironment,
+) -> BindGroup {
+    let current = environment.current();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Scene Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&current.irradiance_view()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&current.specular_view()),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
@@ -0,0 +1,122 @@
+// Regenerates the environment one step at a time: the sky itself per face,
+// then the irradiance and the specular levels filtered from it
+
+struct Params {
+    sun: vec4<f32>,
+    face: u32,
+    // Of the specular level being filtered
+    roughness: f32,
+    // Of the sky's top mip, for picking the mip to sample from
+    source_size: f32,
+    samples: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var source: texture_cube<f32>;
+@group(0) @binding(2)
+var source_sampler: sampler;
+@group(0) @binding(3)
+var destination: texture_storage_2d_array<rgba16float, write>;
+
+// Direction through `uv` (-1..1, v pointing down) on cube face `face`, in
+// the +X, -X, +Y, -Y, +Z, -Z layer order wgpu samples cubes with
+fn face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    var dir: vec3<f32>;
+    switch face {
+        case 0u: { dir = vec3<f32>(1.0, -uv.y, -uv.x); }
+        case 1u: { dir = vec3<f32>(-1.0, -uv.y, uv.x); }
+        case 2u: { dir = vec3<f32>(uv.x, 1.0, uv.y); }
+        case 3u: { dir = vec3<f32>(uv.x, -1.0, -uv.y); }
+        case 4u: { dir = vec3<f32>(uv.x, -uv.y, 1.0); }
+        default: { dir = vec3<f32>(-uv.x, -uv.y, -1.0); }
+    }
+    return normalize(dir);
+}
+
+fn texel_uv(id: vec2<u32>, size: vec2<u32>) -> vec2<f32> {
+    return (vec2<f32>(id) + 0.5) / vec2<f32>(size) * 2.0 - 1.0;
+}
+
+// Any vector perpendicular to n, and one perpendicular to both
+fn tangent_frame(n: vec3<f32>) -> mat3x3<f32> {
+    let up = select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), abs(n.y) > 0.99);
+    let tangent = normalize(cross(up, n));
+    return mat3x3<f32>(tangent, cross(n, tangent), n);
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064e-10);
+}
+
+// One face of the sky per dispatch
+@compute @workgroup_size(8, 8, 1)
+fn cs_sky(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let dir = face_direction(params.face, texel_uv(id.xy, size));
+    textureStore(destination, vec2<i32>(id.xy), i32(params.face), vec4<f32>(sky_radiance(dir, params.sun.xyz), 1.0));
+}
+
+// Cosine weighted average over the hemisphere around each direction. The
+// sky's small mips are already blurry enough that a coarse grid of
+// samples does.
+@compute @workgroup_size(8, 8, 1)
+fn cs_irradiance(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let frame = tangent_frame(face_direction(id.z, texel_uv(id.xy, size)));
+
+    var total = vec3<f32>(0.0);
+    for (var i = 0u; i < params.samples; i++) {
+        // Cosine weighted, so the weights cancel out of the average
+        let xi = hammersley(i, params.samples);
+        let radius = sqrt(xi.x);
+        let angle = 2.0 * PI * xi.y;
+        let local = vec3<f32>(radius * cos(angle), radius * sin(angle), sqrt(1.0 - xi.x));
+        total += textureSampleLevel(source, source_sampler, frame * local, 4.0).rgb;
+    }
+    textureStore(destination, vec2<i32>(id.xy), i32(id.z), vec4<f32>(total / f32(params.samples), 1.0));
+}
+
+// GGX importance sampled around each direction, assuming the view along
+// the normal as usual for the split sum. Each sample reads a mip blurry
+// enough to cover the solid angle it stands for, which keeps the few
+// samples from sparkling.
+@compute @workgroup_size(8, 8, 1)
+fn cs_specular(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let n = face_direction(id.z, texel_uv(id.xy, size));
+    let frame = tangent_frame(n);
+    let a = params.roughness * params.roughness;
+    let texel_solid_angle = 4.0 * PI / (6.0 * params.source_size * params.source_size);
+
+    var total = vec3<f32>(0.0);
+    var weight = 0.0;
+    for (var i = 0u; i < params.samples; i++) {
+        let xi = hammersley(i, params.samples);
+        let cos_theta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+        let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        let phi = 2.0 * PI * xi.x;
+        let h = frame * vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        let l = reflect(-n, h);
+        let n_dot_l = dot(n, l);
+        if n_dot_l > 0.0 {
+            let d = a * a / (PI * pow(cos_theta * cos_theta * (a * a - 1.0) + 1.0, 2.0));
+            let pdf = d / 4.0;
+            let sample_solid_angle = 1.0 / (f32(params.samples) * pdf + 0.0001);
+            let level = select(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0, params.roughness == 0.0);
+            total += textureSampleLevel(source, source_sampler, l, max(level, 0.0)).rgb * n_dot_l;
+            weight += n_dot_l;
+        }
+    }
+    textureStore(destination, vec2<i32>(id.xy), i32(id.z), vec4<f32>(total / max(weight, 0.0001), 1.0));
+}
@@ -0,0 +1,154 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    camera: vec4<f32>,
+    // w is the exposure
+    sun: vec4<f32>,
+    // x: specular mip for roughness 1
+    environment: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var irradiance: texture_cube<f32>;
+@group(0) @binding(2)
+var specular: texture_cube<f32>;
+@group(0) @binding(3)
+var environment_sampler: sampler;
+
+// Narkowicz's fit of the ACES filmic curve
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    let x = color * uniforms.sun.w;
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    // w is the scale
+    @location(2) position: vec4<f32>,
+    // w is the roughness
+    @location(3) albedo: vec4<f32>,
+    // x is the metalness
+    @location(4) material: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) albedo: vec3<f32>,
+    // x: roughness, y: metalness
+    @location(3) material: vec2<f32>,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let world = vertex.position * instance.position.w + instance.position.xyz;
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world, 1.0);
+    out.world = world;
+    out.normal = vertex.normal;
+    out.albedo = instance.albedo.rgb;
+    out.material = vec2<f32>(instance.albedo.w, instance.material.x);
+    return out;
+}
+
+fn ggx(n_dot_h: f32, a: f32) -> f32 {
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+fn smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    return n_dot_v / (n_dot_v * (1.0 - k) + k) * n_dot_l / (n_dot_l * (1.0 - k) + k);
+}
+
+fn fresnel(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+// Karis' analytic stand-in for the split sum's BRDF lookup table
+fn environment_brdf(f0: vec3<f32>, roughness: f32, n_dot_v: f32) -> vec3<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+    return f0 * ab.x + ab.y;
+}
+
+fn direct_light(n: vec3<f32>, v: vec3<f32>, l: vec3<f32>, radiance: vec3<f32>, albedo: vec3<f32>, f0: vec3<f32>, roughness: f32, metalness: f32) -> vec3<f32> {
+    let n_dot_l = dot(n, l);
+    if n_dot_l <= 0.0 {
+        return vec3<f32>(0.0);
+    }
+    let h = normalize(v + l);
+    let n_dot_v = max(dot(n, v), 0.001);
+    let f = fresnel(max(dot(h, v), 0.0), f0);
+    let spec = ggx(max(dot(n, h), 0.0), roughness * roughness) * smith(n_dot_v, n_dot_l, roughness) * f / (4.0 * n_dot_v * n_dot_l);
+    let diffuse = (1.0 - f) * (1.0 - metalness) * albedo / PI;
+    return (diffuse + spec) * radiance * n_dot_l;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let n = normalize(in.normal);
+    let v = normalize(uniforms.camera.xyz - in.world);
+    let roughness = max(in.material.x, 0.04);
+    let metalness = in.material.y;
+    let f0 = mix(vec3<f32>(0.04), in.albedo, metalness);
+    let sun = uniforms.sun.xyz;
+
+    var color = direct_light(n, v, sun, sun_color(sun), in.albedo, f0, roughness, metalness);
+    color += direct_light(n, v, -sun, moon_color(sun), in.albedo, f0, roughness, metalness);
+
+    // The environment as it was when its last regeneration started
+    let n_dot_v = max(dot(n, v), 0.001);
+    let diffuse = textureSampleLevel(irradiance, environment_sampler, n, 0.0).rgb * in.albedo * (1.0 - metalness);
+    let r = reflect(-v, n);
+    let prefiltered = textureSampleLevel(specular, environment_sampler, r, roughness * uniforms.environment.x).rgb;
+    color += diffuse + prefiltered * environment_brdf(f0, roughness, n_dot_v);
+
+    return vec4<f32>(tonemap(color), 1.0);
+}
+
+struct SkyOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+// One triangle covering the screen, at the far plane
+@vertex
+fn vs_sky(@builtin(vertex_index) in_vertex_index: u32) -> SkyOutput {
+    let ndc = vec2<f32>(f32(in_vertex_index & 1u) * 4.0 - 1.0, f32(in_vertex_index >> 1u) * 4.0 - 1.0);
+
+    var out: SkyOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+// Straight from the model rather than the cubemap, so the background
+// never lags behind. Only here do the sun and moon get their discs.
+@fragment
+fn fs_sky(in: SkyOutput) -> @location(0) vec4<f32> {
+    let far = uniforms.inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let dir = normalize(far.xyz / far.w - uniforms.camera.xyz);
+    let sun = uniforms.sun.xyz;
+
+    var color = sky_radiance(dir, sun);
+    if dot(dir, sun) > 0.9995 {
+        color += sun_color(sun) * 20.0;
+    }
+    if dot(dir, -sun) > 0.9996 {
+        color += moon_color(sun) * 60.0;
+    }
+    return vec4<f32>(tonemap(color), 1.0);
+}
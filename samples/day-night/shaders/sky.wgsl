@@ -0,0 +1,40 @@
+// The sky model, shared by the environment passes and the background so
+// both always agree. Pasted in front of environment.wgsl and scene.wgsl.
+
+const PI: f32 = 3.14159265;
+
+// Redder and dimmer the more air the light goes through near the horizon,
+// gone once the sun is down
+fn sun_color(sun: vec3<f32>) -> vec3<f32> {
+    let air_mass = 1.0 / (max(sun.y, 0.0) + 0.05);
+    let transmittance = exp(-vec3<f32>(0.1, 0.25, 0.6) * air_mass * 0.25);
+    return transmittance * 8.0 * smoothstep(-0.05, 0.05, sun.y);
+}
+
+// The moon rides opposite the sun, a faint blue fill for the night
+fn moon_color(sun: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(0.05, 0.06, 0.09) * smoothstep(-0.05, 0.1, -sun.y);
+}
+
+// Everything but the sun and moon discs, which would only turn into
+// noise in the filtered environment. The ground below the horizon stands
+// in for light bouncing off the world.
+fn sky_radiance(dir: vec3<f32>, sun: vec3<f32>) -> vec3<f32> {
+    let day = smoothstep(-0.2, 0.15, sun.y);
+    let night = vec3<f32>(0.004, 0.006, 0.014);
+
+    if dir.y < 0.0 {
+        let ground = vec3<f32>(0.25, 0.22, 0.18);
+        return ground * (sun_color(sun) * max(sun.y, 0.0) * 0.15 + day * 0.1 + moon_color(sun) * 0.3) + night * 0.5;
+    }
+
+    let height = sqrt(dir.y);
+    let zenith = vec3<f32>(0.12, 0.3, 0.75);
+    let horizon = mix(vec3<f32>(0.9, 0.45, 0.2), vec3<f32>(0.55, 0.7, 0.9), smoothstep(0.0, 0.35, sun.y));
+    var color = mix(horizon, zenith, height) * day * 1.2;
+
+    // Glow around the sun, strongest at sunset
+    let toward_sun = max(dot(dir, sun), 0.0);
+    color += sun_color(sun) * (pow(toward_sun, 12.0) * 0.15 + pow(toward_sun, 600.0) * 2.0);
+    return color + night;
+}
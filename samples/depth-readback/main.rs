@@ -0,0 +1,9 @@
+mod readback;
+mod renderer;
+mod terrain;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("depth-readback");
+}
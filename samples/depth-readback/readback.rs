@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use glam::{Mat4, Vec3, Vec4};
+use wgpu::{Buffer, CommandEncoder, Device, Texture};
+
+// Side of the square read back around the cursor, so a cursor right on a
+// silhouette edge can still fall back to a neighbouring pixel
+pub const REGION: u32 = 5;
+// Readbacks that may be in flight at once. The GPU usually runs a frame or
+// two behind, with fewer slots we'd just skip requests more often.
+const SLOTS: usize = 3;
+
+// Only Depth32Float can be copied out at all: Depth24Plus has no defined
+// memory layout, and of Depth24PlusStencil8 only the stencil aspect can be
+// copied. The depth aspect has to be named explicitly, even for formats
+// that have no other.
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const BYTES_PER_TEXEL: u32 = 4;
+
+// Everything needed to turn the depth values back into a position once
+// they arrive, which is a few frames after they were rendered
+#[derive(Clone, Copy)]
+struct Request {
+    view_proj: Mat4,
+    target_size: [u32; 2],
+    origin: [u32; 2],
+    cursor: [u32; 2],
+    frame: u64,
+}
+
+struct Slot {
+    buffer: Buffer,
+    request: Option<Request>,
+    // The copy has been recorded but map_async can only be called once
+    // it's submitted
+    needs_map: bool,
+    // Set by the map_async callback
+    mapped: Arc<AtomicBool>,
+}
+
+pub struct Pick {
+    pub position: Vec3,
+    // Frames between the copy being recorded and the result arriving
+    pub latency: u64,
+}
+
+pub struct DepthReadback {
+    slots: Vec<Slot>,
+}
+
+impl DepthReadback {
+    pub fn new(device: &Device) -> Self {
+        let slots = (0..SLOTS)
+            .map(|_| Slot {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Depth Readback"),
+                    size: (Self::padded_bytes_per_row() * REGION) as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                request: None,
+                needs_map: false,
+                mapped: Arc::new(AtomicBool::new(false)),
+            })
+            .collect();
+        Self { slots }
+    }
+
+    // Records a copy of the region around the cursor if a slot is free.
+    // The depth texture needs COPY_SRC and its pass has to store depth.
+    pub fn request(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        depth_texture: &Texture,
+        cursor: [u32; 2],
+        view_proj: Mat4,
+        frame: u64,
+    ) -> bool {
+        let Some(slot) = self.slots.iter_mut().find(|slot| slot.request.is_none()) else {
+            return false;
+        };
+
+        let width = depth_texture.width();
+        let height = depth_texture.height();
+        if width < REGION || height < REGION {
+            return false;
+        }
+        let cursor = [cursor[0].min(width - 1), cursor[1].min(height - 1)];
+        // Keep the whole region inside the texture, the copy may not go
+        // past its edges
+        let origin = [
+            cursor[0].saturating_sub(REGION / 2).min(width - REGION),
+            cursor[1].saturating_sub(REGION / 2).min(height - REGION),
+        ];
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: depth_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin[0],
+                    y: origin[1],
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::DepthOnly,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &slot.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(Self::padded_bytes_per_row()),
+                    rows_per_image: Some(REGION),
+                },
+            },
+            wgpu::Extent3d {
+                width: REGION,
+                height: REGION,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        slot.request = Some(Request {
+            view_proj,
+            target_size: [width, height],
+            origin,
+            cursor,
+            frame,
+        });
+        slot.needs_map = true;
+        true
+    }
+
+    // Call after submitting the encoder passed to request
+    pub fn map_submitted(&mut self) {
+        for slot in self.slots.iter_mut().filter(|slot| slot.needs_map) {
+            slot.needs_map = false;
+            let mapped = slot.mapped.clone();
+            slot.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                result.unwrap();
+                mapped.store(true, Ordering::Release);
+            });
+        }
+    }
+
+    // Never blocks: picks up whatever readbacks have finished since the
+    // last call and returns the newest one. None also means the cursor was
+    // over the sky.
+    pub fn poll(&mut self, device: &Device, frame: u64) -> Option<Option<Pick>> {
+        device.poll(wgpu::Maintain::Poll);
+
+        let mut finished = Vec::new();
+        for slot in &mut self.slots {
+            if !slot.mapped.swap(false, Ordering::Acquire) {
+                continue;
+            }
+            let request = slot.request.take().unwrap();
+
+            let depths: Vec<f32> = slot
+                .buffer
+                .slice(..)
+                .get_mapped_range()
+                .chunks(Self::padded_bytes_per_row() as usize)
+                .flat_map(|row| bytemuck::cast_slice::<u8, f32>(&row[..(REGION * BYTES_PER_TEXEL) as usize]).to_vec())
+                .collect();
+            slot.buffer.unmap();
+
+            let pick = Self::resolve(&request, &depths).map(|position| Pick {
+                position,
+                latency: frame - request.frame,
+            });
+            finished.push((request.frame, pick));
+        }
+        finished
+            .into_iter()
+            .max_by_key(|(frame, _)| *frame)
+            .map(|(_, pick)| pick)
+    }
+
+    // Even a 5 texel wide row takes up a full 256 bytes in the buffer
+    fn padded_bytes_per_row() -> u32 {
+        wgpu::util::align_to(REGION * BYTES_PER_TEXEL, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+    }
+
+    // The texel under the cursor, or the nearest surface around it if the
+    // cursor itself is over the cleared background
+    fn resolve(request: &Request, depths: &[f32]) -> Option<Vec3> {
+        let local_x = request.cursor[0] - request.origin[0];
+        let local_y = request.cursor[1] - request.origin[1];
+        let center = (local_y * REGION + local_x) as usize;
+
+        let (index, depth) = if depths[center] < 1.0 {
+            (center, depths[center])
+        } else {
+            depths
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|(_, depth)| *depth < 1.0)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))?
+        };
+
+        let pixel_x = request.origin[0] + index as u32 % REGION;
+        let pixel_y = request.origin[1] + index as u32 / REGION;
+        let ndc = Vec4::new(
+            (pixel_x as f32 + 0.5) / request.target_size[0] as f32 * 2.0 - 1.0,
+            1.0 - (pixel_y as f32 + 0.5) / request.target_size[1] as f32 * 2.0,
+            depth,
+            1.0,
+        );
+        let world = request.view_proj.inverse() * ndc;
+        Some(world.truncate() / world.w)
+    }
+}
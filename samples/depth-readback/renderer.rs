@@ -0,0 +1,278 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, BindGroup, Buffer, Device, RenderPipeline, Texture, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::WindowEvent;
+
+use crate::readback::{DepthReadback, Pick, DEPTH_FORMAT};
+use crate::terrain::{self, Terrain, Vertex};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    marker: [f32; 4],
+}
+
+fn create_depth_texture(device: &Device, config: &wgpu::SurfaceConfiguration) -> (Texture, TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        // COPY_SRC for the readback
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    terrain: Terrain,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    depth_texture: Texture,
+    depth_view: TextureView,
+    readback: DepthReadback,
+    // In physical pixels, None while the cursor is outside the window
+    cursor: Option<[u32; 2]>,
+    // The newest result, None when the cursor was over the sky
+    pick: Option<Pick>,
+    frame: u64,
+    start: Instant,
+    // Set by --flythrough or a script, a slow orbit until then
+    camera: Option<CameraPose>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let terrain = Terrain::new(device);
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Uniform Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/terrain.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (depth_texture, depth_view) = create_depth_texture(device, &gpu.config);
+
+        Self {
+            render_pipeline,
+            terrain,
+            uniform_buffer,
+            bind_group,
+            depth_texture,
+            depth_view,
+            readback: DepthReadback::new(device),
+            cursor: None,
+            pick: None,
+            frame: 0,
+            start: Instant::now(),
+            camera: None,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        // Readbacks still in flight copied from the old texture into their
+        // own buffers, so they arrive just fine
+        (self.depth_texture, self.depth_view) = create_depth_texture(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some([position.x.max(0.0) as u32, position.y.max(0.0) as u32]);
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            _ => {}
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.cursor.is_none() {
+            return Some("move the cursor over the terrain".to_string());
+        }
+        Some(match &self.pick {
+            Some(pick) => format!(
+                "({:.1}, {:.1}) height {:.2}, heightfield says {:.2}, {} frames old",
+                pick.position.x,
+                pick.position.z,
+                pick.position.y,
+                terrain::height(pick.position.x, pick.position.z),
+                pick.latency,
+            ),
+            None => "sky".to_string(),
+        })
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        if let Some(pick) = self.readback.poll(&gpu.device, self.frame) {
+            self.pick = pick;
+        }
+
+        let angle = self.start.elapsed().as_secs_f32() * 0.05;
+        let distance = terrain::SIZE * 0.6;
+        let view_matrix = match &self.camera {
+            Some(pose) => pose.view_matrix(),
+            None => Mat4::look_at_rh(
+                Vec3::new(angle.cos() * distance, 35.0, angle.sin() * distance),
+                Vec3::ZERO,
+                Vec3::Y,
+            ),
+        };
+        let proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), 0.5, 300.0);
+        let view_proj = proj * view_matrix;
+
+        let marker = match &self.pick {
+            Some(pick) => pick.position.extend(1.0).to_array(),
+            None => [0.0; 4],
+        };
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: view_proj.to_cols_array_2d(),
+                marker,
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.55,
+                                    g: 0.65,
+                                    b: 0.8,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        // Stored, or there'd be nothing left to copy
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: true,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.terrain.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.terrain.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..self.terrain.index_count, 0, 0..1);
+        }
+
+        // The marker drawn this frame comes from an older frame's depth, so
+        // it trails the cursor by the latency shown in the title
+        if let Some(cursor) = self.cursor {
+            self.readback.request(&mut encoder, &self.depth_texture, cursor, view_proj, self.frame);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+        self.readback.map_submitted();
+        self.frame += 1;
+    }
+}
@@ -0,0 +1,46 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    // xyz is the last picked point, w is 1 when there is one
+    marker: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(vertex.position, 1.0);
+    out.world_position = vertex.position;
+    out.normal = vertex.normal;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light = normalize(vec3<f32>(0.5, 0.8, 0.3));
+    let diffuse = max(dot(normalize(in.normal), light), 0.0);
+
+    // Grass in the valleys, rock further up
+    let grass = vec3<f32>(0.3, 0.5, 0.2);
+    let rock = vec3<f32>(0.55, 0.5, 0.45);
+    var color = mix(grass, rock, smoothstep(2.0, 6.0, in.world_position.y));
+
+    // A ring around the picked point
+    let ring = abs(distance(in.world_position.xz, uniforms.marker.xz) - 1.5);
+    if uniforms.marker.w > 0.0 && ring < 0.2 {
+        color = vec3<f32>(1.0, 0.2, 0.1);
+    }
+    return vec4<f32>(color * (0.3 + 0.7 * diffuse), 1.0);
+}
@@ -0,0 +1,93 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use wgpu::{Buffer, Device};
+use wgpu::util::DeviceExt;
+
+// Quads per side
+const RESOLUTION: u32 = 128;
+// World units per side, centered on the origin
+pub const SIZE: f32 = 100.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Rolling hills. Also used on the CPU to check the picked heights against.
+pub fn height(x: f32, z: f32) -> f32 {
+    3.0 * (x * 0.08).sin() * (z * 0.06).cos()
+        + 1.5 * (x * 0.21 + z * 0.13).sin()
+        + 0.5 * (z * 0.5).sin() * (x * 0.4).cos()
+        + 3.0
+}
+
+fn normal(x: f32, z: f32) -> Vec3 {
+    let e = 0.1;
+    let dx = height(x + e, z) - height(x - e, z);
+    let dz = height(x, z + e) - height(x, z - e);
+    Vec3::new(-dx, 2.0 * e, -dz).normalize()
+}
+
+pub struct Terrain {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub index_count: u32,
+}
+
+impl Terrain {
+    pub fn new(device: &Device) -> Self {
+        let step = SIZE / RESOLUTION as f32;
+        let mut vertices = Vec::new();
+        for j in 0..=RESOLUTION {
+            for i in 0..=RESOLUTION {
+                let x = i as f32 * step - SIZE / 2.0;
+                let z = j as f32 * step - SIZE / 2.0;
+                vertices.push(Vertex {
+                    position: [x, height(x, z), z],
+                    normal: normal(x, z).to_array(),
+                });
+            }
+        }
+
+        let row = RESOLUTION + 1;
+        let mut indices: Vec<u32> = Vec::new();
+        for j in 0..RESOLUTION {
+            for i in 0..RESOLUTION {
+                let corner = j * row + i;
+                indices.extend([corner, corner + row, corner + 1, corner + 1, corner + row, corner + row + 1]);
+            }
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain Vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain Indices"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use glam::Vec2;
+
+// Drawn at four times the network's resolution, so strokes come out smooth
+// once shrunk
+pub const SIZE: usize = 112;
+// Of the network's input, and the box the ink is fitted into, as in MNIST
+pub const INPUT_SIZE: usize = 28;
+const FIT_SIZE: f32 = 20.0;
+
+pub struct Canvas {
+    // Ink, 0-1, row by row
+    pub pixels: Vec<f32>,
+}
+
+impl Canvas {
+    pub fn new() -> Self {
+        Self { pixels: vec![0.0; SIZE * SIZE] }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0.0);
+    }
+
+    // A line of soft edged discs from one canvas point to another; `ink`
+    // 0 erases
+    pub fn stroke(&mut self, from: Vec2, to: Vec2, radius: f32, ink: f32) {
+        let min = from.min(to) - radius - 1.0;
+        let max = from.max(to) + radius + 1.0;
+        let segment = to - from;
+        for y in (min.y.max(0.0) as usize)..(max.y.min(SIZE as f32) as usize) {
+            for x in (min.x.max(0.0) as usize)..(max.x.min(SIZE as f32) as usize) {
+                let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let t = if segment == Vec2::ZERO { 0.0 } else { ((p - from).dot(segment) / segment.length_squared()).clamp(0.0, 1.0) };
+                let coverage = (radius + 0.5 - p.distance(from + segment * t)).clamp(0.0, 1.0);
+                let pixel = &mut self.pixels[y * SIZE + x];
+                *pixel = if ink > 0.0 { pixel.max(coverage * ink) } else { *pixel * (1.0 - coverage) };
+            }
+        }
+    }
+
+    // What the network sees, prepared the way MNIST's digits were: the ink
+    // scaled to fit a 20 pixel box, then moved so its center of mass sits
+    // in the middle of the 28 pixel image. None with nothing drawn.
+    pub fn to_input(&self) -> Option<Vec<f32>> {
+        let mut min = Vec2::splat(SIZE as f32);
+        let mut max = Vec2::ZERO;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if self.pixels[y * SIZE + x] > 0.1 {
+                    min = min.min(Vec2::new(x as f32, y as f32));
+                    max = max.max(Vec2::new(x as f32 + 1.0, y as f32 + 1.0));
+                }
+            }
+        }
+        if max.x <= min.x {
+            return None;
+        }
+
+        // Splatting each canvas pixel into the input pixel it lands in
+        // averages the area it covers; never scaled up, or that leaves holes
+        let scale = (FIT_SIZE / (max - min).max_element()).min(1.0);
+        let center = (min + max) * 0.5;
+        let mut shrunk = vec![0.0; INPUT_SIZE * INPUT_SIZE];
+        for y in min.y as usize..max.y as usize {
+            for x in min.x as usize..max.x as usize {
+                let p = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center) * scale + INPUT_SIZE as f32 * 0.5;
+                let (tx, ty) = (p.x as usize, p.y as usize);
+                if tx < INPUT_SIZE && ty < INPUT_SIZE {
+                    shrunk[ty * INPUT_SIZE + tx] += self.pixels[y * SIZE + x] * scale * scale;
+                }
+            }
+        }
+
+        let mut mass = 0.0;
+        let mut weighted = Vec2::ZERO;
+        for (index, value) in shrunk.iter_mut().enumerate() {
+            *value = value.min(1.0);
+            mass += *value;
+            weighted += Vec2::new((index % INPUT_SIZE) as f32 + 0.5, (index / INPUT_SIZE) as f32 + 0.5) * *value;
+        }
+        let shift = (Vec2::splat(INPUT_SIZE as f32 * 0.5) - weighted / mass).round();
+        let mut input = vec![0.0; INPUT_SIZE * INPUT_SIZE];
+        for y in 0..INPUT_SIZE as i32 {
+            for x in 0..INPUT_SIZE as i32 {
+                let (sx, sy) = (x - shift.x as i32, y - shift.y as i32);
+                if (0..INPUT_SIZE as i32).contains(&sx) && (0..INPUT_SIZE as i32).contains(&sy) {
+                    input[y as usize * INPUT_SIZE + x as usize] = shrunk[sy as usize * INPUT_SIZE + sx as usize];
+                }
+            }
+        }
+        Some(input)
+    }
+}
@@ -0,0 +1,10 @@
+mod canvas;
+mod model;
+mod network;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("digit-mlp");
+}
@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use glam::Vec2;
+use serde::Deserialize;
+
+use crate::canvas::{self, Canvas};
+
+// One fully connected layer, `weights` row by row, a row per output
+#[derive(Deserialize)]
+pub struct Layer {
+    pub weights: Vec<f32>,
+    pub biases: Vec<f32>,
+    #[serde(default)]
+    pub relu: bool,
+}
+
+impl Layer {
+    pub fn outputs(&self) -> usize {
+        self.biases.len()
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.weights.len() / self.biases.len()
+    }
+}
+
+// A stack of layers taking the 28x28 image and giving a score per digit,
+// turned into probabilities with a softmax. Load trained weights with
+// --weights model.json:
+// { "layers": [{ "weights": [...], "biases": [...], "relu": true }, ...] }
+#[derive(Deserialize)]
+pub struct Model {
+    pub layers: Vec<Layer>,
+    // Scale the input to unit length first
+    #[serde(default)]
+    pub unit_input: bool,
+}
+
+pub fn load() -> Model {
+    let mut args = std::env::args().skip_while(|arg| arg != "--weights");
+    if let Some(path) = args.nth(1) {
+        let json = std::fs::read_to_string(Path::new(&path)).unwrap();
+        let model: Model = serde_json::from_str(&json).unwrap();
+        assert_eq!(model.layers[0].inputs(), canvas::INPUT_SIZE * canvas::INPUT_SIZE);
+        assert_eq!(model.layers.last().unwrap().outputs(), 10);
+        return model;
+    }
+    built_in()
+}
+
+fn ring(center: [f32; 2], radius: [f32; 2], from: f32, to: f32) -> Vec<[f32; 2]> {
+    (0..=16)
+        .map(|step| {
+            let angle = (from + (to - from) * step as f32 / 16.0).to_radians();
+            [center[0] + angle.cos() * radius[0], center[1] + angle.sin() * radius[1]]
+        })
+        .collect()
+}
+
+// Each digit's strokes in a unit box, y down
+fn strokes(digit: usize) -> Vec<Vec<[f32; 2]>> {
+    match digit {
+        0 => vec![ring([0.5, 0.5], [0.4, 0.5], 0.0, 360.0)],
+        1 => vec![vec![[0.3, 0.2], [0.5, 0.0], [0.5, 1.0]]],
+        2 => vec![vec![[0.1, 0.25], [0.3, 0.02], [0.7, 0.02], [0.9, 0.25], [0.85, 0.45], [0.1, 1.0], [0.9, 1.0]]],
+        3 => vec![vec![[0.1, 0.1], [0.5, 0.0], [0.85, 0.15], [0.8, 0.4], [0.4, 0.5], [0.85, 0.6], [0.9, 0.85], [0.5, 1.0], [0.1, 0.9]]],
+        4 => vec![vec![[0.7, 1.0], [0.7, 0.0], [0.05, 0.7], [0.95, 0.7]]],
+        5 => vec![vec![[0.85, 0.0], [0.2, 0.0], [0.15, 0.45], [0.6, 0.4], [0.9, 0.6], [0.85, 0.9], [0.5, 1.0], [0.1, 0.9]]],
+        6 => vec![vec![[0.8, 0.05], [0.4, 0.1], [0.15, 0.5], [0.15, 0.85], [0.45, 1.0], [0.8, 0.9], [0.85, 0.65], [0.5, 0.5], [0.15, 0.65]]],
+        7 => vec![vec![[0.05, 0.0], [0.95, 0.0], [0.4, 1.0]]],
+        8 => vec![ring([0.5, 0.24], [0.3, 0.24], 0.0, 360.0), ring([0.5, 0.73], [0.36, 0.27], 0.0, 360.0)],
+        _ => vec![ring([0.45, 0.3], [0.32, 0.3], 0.0, 360.0), vec![[0.77, 0.3], [0.65, 1.0]]],
+    }
+}
+
+// There's no trained model to ship, so the built in one is made by hand:
+// the hidden layer holds a few slanted and narrowed renderings of every
+// digit, each neuron firing on how well the drawing correlates with its
+// template, and the output layer adds up each digit's neurons. Crude next
+// to a trained network, but the GPU side runs any model the same way.
+fn built_in() -> Model {
+    const SLANTS: [f32; 3] = [-0.2, 0.0, 0.2];
+    const WIDTHS: [f32; 2] = [0.65, 1.0];
+    // Correlation below this doesn't count for anything
+    const THRESHOLD: f32 = 0.3;
+    const SHARPNESS: f32 = 15.0;
+
+    let variants = SLANTS.len() * WIDTHS.len();
+    let mut hidden = Layer {
+        weights: Vec::new(),
+        biases: Vec::new(),
+        relu: true,
+    };
+    let mut output = Layer {
+        weights: vec![0.0; 10 * 10 * variants],
+        biases: vec![0.0; 10],
+        relu: false,
+    };
+    for digit in 0..10 {
+        for &slant in &SLANTS {
+            for &width in &WIDTHS {
+                let mut canvas = Canvas::new();
+                let to_canvas = |p: [f32; 2]| {
+                    let x = 0.5 + (p[0] - 0.5) * width + (0.5 - p[1]) * slant;
+                    Vec2::new(16.0 + x * 80.0, 16.0 + p[1] * 80.0)
+                };
+                for stroke in strokes(digit) {
+                    for pair in stroke.windows(2) {
+                        canvas.stroke(to_canvas(pair[0]), to_canvas(pair[1]), 5.0, 1.0);
+                    }
+                }
+                let template = canvas.to_input().unwrap();
+
+                // Zero mean and unit length, so with a unit length input
+                // the neuron's sum is the correlation
+                let mean = template.iter().sum::<f32>() / template.len() as f32;
+                let centered: Vec<f32> = template.iter().map(|value| value - mean).collect();
+                let length = centered.iter().map(|value| value * value).sum::<f32>().sqrt();
+                output.weights[digit * 10 * variants + hidden.biases.len()] = SHARPNESS;
+                hidden.weights.extend(centered.iter().map(|value| value / length));
+                hidden.biases.push(-THRESHOLD);
+            }
+        }
+    }
+    Model {
+        layers: vec![hidden, output],
+        unit_input: true,
+    }
+}
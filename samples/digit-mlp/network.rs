@@ -0,0 +1,254 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use framework::autotune::{self, Kernel};
+use framework::Gpu;
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+use crate::model::Model;
+
+pub const DIGITS: usize = 10;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct LayerParams {
+    inputs: u32,
+    outputs: u32,
+    relu: u32,
+    _padding: u32,
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// A model's weights uploaded once, a storage buffer per layer, and run as
+// a dispatch per layer. The first layer reads the input buffer, the rest
+// ping-pong between two activation buffers, and a softmax turns the last
+// one into probabilities.
+pub struct Network {
+    dense_pipeline: ComputePipeline,
+    workgroup_size: [u32; 2],
+    softmax_pipeline: ComputePipeline,
+    pub input: Buffer,
+    pub probabilities: Buffer,
+    // Per layer, with its output count for the dispatch size
+    layers: Vec<(BindGroup, u32)>,
+    softmax_bind_group: BindGroup,
+    pub unit_input: bool,
+    readback: Buffer,
+    // Inferred since the last copy to the readback
+    needs_copy: bool,
+    pending: bool,
+    needs_map: bool,
+    mapped: Arc<AtomicBool>,
+    // Of the last readback to land
+    pub latest: [f32; DIGITS],
+}
+
+impl Network {
+    pub fn new(gpu: &Gpu, model: &Model) -> Self {
+        let device = &gpu.device;
+        let storage = |label: &str, contents: &[f32]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(contents),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            })
+        };
+        let input = storage("Input", &vec![0.0; model.layers[0].inputs()]);
+        let widest = model.layers.iter().map(|layer| layer.outputs()).max().unwrap();
+        let activations = [storage("Activations 0", &vec![0.0; widest]), storage("Activations 1", &vec![0.0; widest])];
+        let probabilities = storage("Probabilities", &[0.0; DIGITS]);
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layer Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, true),
+                storage_entry(4, false),
+            ],
+        });
+        let bind_group = |params: LayerParams, weights: &Buffer, biases: &Buffer, source: &Buffer, destination: &Buffer| {
+            let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Layer Params"),
+                contents: bytemuck::bytes_of(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Layer Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: weights.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: biases.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: source.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: destination.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let mut layers = Vec::new();
+        let mut last_weights = None;
+        for (index, layer) in model.layers.iter().enumerate() {
+            let weights = storage(&format!("Layer {} Weights", index), &layer.weights);
+            let biases = storage(&format!("Layer {} Biases", index), &layer.biases);
+            let source = if index == 0 { &input } else { &activations[(index - 1) % 2] };
+            let params = LayerParams {
+                inputs: layer.inputs() as u32,
+                outputs: layer.outputs() as u32,
+                relu: layer.relu as u32,
+                _padding: 0,
+            };
+            layers.push((bind_group(params, &weights, &biases, source, &activations[index % 2]), params.outputs));
+            last_weights = Some((weights, biases));
+        }
+        // The softmax only reads the last layer's output; the weights are
+        // there to fill the layout
+        let (weights, biases) = last_weights.unwrap();
+        let softmax_params = LayerParams {
+            inputs: DIGITS as u32,
+            outputs: DIGITS as u32,
+            relu: 0,
+            _padding: 0,
+        };
+        let softmax_bind_group = bind_group(softmax_params, &weights, &biases, &activations[(model.layers.len() - 1) % 2], &probabilities);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Layer Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        // Tuned on the first layer, by far the biggest
+        let kernel = Kernel {
+            name: "digit-mlp dense",
+            source: include_str!("shaders/mlp.wgsl"),
+            entry_point: "cs_dense",
+            layout: &pipeline_layout,
+            bind_group: &layers[0].0,
+            invocations: [layers[0].1, 1],
+        };
+        let workgroup_size = autotune::workgroup_size(gpu, &kernel);
+        Self {
+            dense_pipeline: autotune::create_pipeline(device, &kernel, workgroup_size),
+            workgroup_size,
+            softmax_pipeline: autotune::create_pipeline(device, &Kernel { entry_point: "cs_softmax", ..kernel }, workgroup_size),
+            input,
+            probabilities,
+            layers,
+            softmax_bind_group,
+            unit_input: model.unit_input,
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Probability Readback"),
+                size: (DIGITS * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            needs_copy: false,
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            latest: [0.0; DIGITS],
+        }
+    }
+
+    // Uploads the input and records the whole network
+    pub fn infer(&mut self, queue: &Queue, encoder: &mut CommandEncoder, input: &[f32]) {
+        if self.unit_input {
+            let length = input.iter().map(|value| value * value).sum::<f32>().sqrt().max(1e-6);
+            let scaled: Vec<f32> = input.iter().map(|value| value / length).collect();
+            queue.write_buffer(&self.input, 0, bytemuck::cast_slice(&scaled));
+        } else {
+            queue.write_buffer(&self.input, 0, bytemuck::cast_slice(input));
+        }
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Inference Pass"),
+            });
+            compute_pass.set_pipeline(&self.dense_pipeline);
+            for (bind_group, outputs) in &self.layers {
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                let [x, y] = autotune::workgroups([*outputs, 1], self.workgroup_size);
+                compute_pass.dispatch_workgroups(x, y, 1);
+            }
+            compute_pass.set_pipeline(&self.softmax_pipeline);
+            compute_pass.set_bind_group(0, &self.softmax_bind_group, &[]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        self.needs_copy = true;
+        self.request(encoder);
+    }
+
+    // Copies the probabilities for reading back, unless a readback is still
+    // in flight; call every frame so inference during one isn't missed
+    pub fn request(&mut self, encoder: &mut CommandEncoder) {
+        if self.pending || !self.needs_copy {
+            return;
+        }
+        encoder.copy_buffer_to_buffer(&self.probabilities, 0, &self.readback, 0, self.readback.size());
+        self.needs_copy = false;
+        self.pending = true;
+        self.needs_map = true;
+    }
+
+    // Call after submitting the encoder passed to infer
+    pub fn map_submitted(&mut self) {
+        if !std::mem::take(&mut self.needs_map) {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.readback.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            result.unwrap();
+            mapped.store(true, Ordering::Release);
+        });
+    }
+
+    pub fn poll(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+        self.latest = *bytemuck::from_bytes(&self.readback.slice(..).get_mapped_range());
+        self.readback.unmap();
+        self.pending = false;
+    }
+}
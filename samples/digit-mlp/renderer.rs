@@ -0,0 +1,298 @@
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use glam::Vec2;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::canvas::{self, Canvas};
+use crate::model;
+use crate::network::{Network, DIGITS};
+
+// Canvas pixels
+const BRUSH_RADIUS: f32 = 4.5;
+// Window pixels around and between the panels
+const MARGIN: f32 = 24.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DisplayParams {
+    canvas: [f32; 4],
+    preview: [f32; 4],
+    bars: [f32; 4],
+}
+
+// The canvas on the left, on the right what the network sees above a bar
+// per digit
+fn layout(width: f32, height: f32) -> DisplayParams {
+    let size = (height - MARGIN * 2.0).min(width * 0.55).max(1.0);
+    let top = (height - size) * 0.5;
+    let right = MARGIN * 2.0 + size;
+    let column = (width - right - MARGIN).max(1.0);
+    let preview = column.min(size * 0.35);
+    DisplayParams {
+        canvas: [MARGIN, top, size, 0.0],
+        preview: [right, top, preview, 1.0],
+        bars: [right, top + preview + MARGIN, column, (size - preview - MARGIN).max(1.0)],
+    }
+}
+
+pub struct Renderer {
+    canvas: Canvas,
+    network: Network,
+    canvas_buffer: Buffer,
+    params_buffer: Buffer,
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    // As of the last frame, for mapping the cursor onto the canvas
+    params: DisplayParams,
+    cursor: Option<Vec2>,
+    // In canvas pixels, where the stroke has got to
+    last_point: Option<Vec2>,
+    drawing: bool,
+    erasing: bool,
+    // The canvas changed since the network last ran
+    dirty: bool,
+    empty: bool,
+    input_scale: f32,
+}
+
+impl Renderer {
+    fn to_canvas(&self, point: Vec2) -> Vec2 {
+        (point - Vec2::new(self.params.canvas[0], self.params.canvas[1])) / self.params.canvas[2] * canvas::SIZE as f32
+    }
+
+    fn paint(&mut self) {
+        let cursor = match self.cursor {
+            Some(cursor) if self.drawing || self.erasing => cursor,
+            _ => {
+                self.last_point = None;
+                return;
+            }
+        };
+        let point = self.to_canvas(cursor);
+        let from = self.last_point.unwrap_or(point);
+        let (radius, ink) = if self.drawing { (BRUSH_RADIUS, 1.0) } else { (BRUSH_RADIUS * 3.0, 0.0) };
+        self.canvas.stroke(from, point, radius, ink);
+        self.last_point = Some(point);
+        self.dirty = true;
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let canvas = Canvas::new();
+        let network = Network::new(gpu, &model::load());
+
+        let canvas_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Canvas"),
+            contents: bytemuck::cast_slice(&canvas.pixels),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display Params"),
+            contents: bytemuck::bytes_of(&DisplayParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: canvas_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: network.input.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: network.probabilities.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            canvas,
+            network,
+            canvas_buffer,
+            params_buffer,
+            pipeline,
+            bind_group,
+            params: layout(gpu.config.width as f32, gpu.config.height as f32),
+            cursor: None,
+            last_point: None,
+            drawing: false,
+            erasing: false,
+            dirty: true,
+            empty: true,
+            input_scale: 1.0,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some(Vec2::new(position.x as f32, position.y as f32));
+                self.paint();
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.last_point = None;
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left => self.drawing = pressed,
+                    MouseButton::Right => self.erasing = pressed,
+                    _ => {}
+                }
+                self.last_point = None;
+                self.paint();
+            }
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::C | VirtualKeyCode::Back),
+                    ..
+                },
+                ..
+            } => {
+                self.canvas.clear();
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.empty {
+            return Some("draw a digit, C clears".to_string());
+        }
+        let (digit, probability) = self
+            .network
+            .latest
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |best, (digit, &p)| if p > best.1 { (digit, p) } else { best });
+        Some(format!("looks like a {} ({:.0}%), bars 0 to {} left to right", digit, probability * 100.0, DIGITS - 1))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.network.poll(&gpu.device);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        // The network only runs when the drawing changes
+        if std::mem::take(&mut self.dirty) {
+            gpu.queue.write_buffer(&self.canvas_buffer, 0, bytemuck::cast_slice(&self.canvas.pixels));
+            let input = self.canvas.to_input();
+            self.empty = input.is_none();
+            let input = input.unwrap_or_else(|| vec![0.0; canvas::INPUT_SIZE * canvas::INPUT_SIZE]);
+            // Shown at full brightness, whatever scaling the model wants
+            let largest = input.iter().cloned().fold(0.0, f32::max);
+            let length = input.iter().map(|value| value * value).sum::<f32>().sqrt();
+            self.input_scale = if self.network.unit_input { length / largest.max(1e-6) } else { 1.0 };
+            self.network.infer(&gpu.queue, &mut encoder, &input);
+        }
+        self.network.request(&mut encoder);
+
+        self.params = layout(gpu.config.width as f32, gpu.config.height as f32);
+        self.params.preview[3] = self.input_scale;
+        gpu.queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params));
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.network.map_submitted();
+    }
+}
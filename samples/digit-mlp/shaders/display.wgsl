@@ -0,0 +1,71 @@
+struct Params {
+    // x, y, size in window pixels
+    canvas: vec4<f32>,
+    // x, y, size, and what to multiply the input by to show it
+    preview: vec4<f32>,
+    // x, y, width, height
+    bars: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read> canvas: array<f32>;
+// The network's input, 28x28
+@group(0) @binding(2)
+var<storage, read> network_input: array<f32>;
+@group(0) @binding(3)
+var<storage, read> probabilities: array<f32>;
+
+const CANVAS_SIZE: f32 = 112.0;
+const INPUT_SIZE: f32 = 28.0;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+// Where a window point falls in a square, 0-1, or outside it
+fn in_square(position: vec2<f32>, square: vec4<f32>) -> vec2<f32> {
+    return (position - square.xy) / square.z;
+}
+
+fn inside(uv: vec2<f32>) -> bool {
+    return all(uv >= vec2<f32>(0.0)) && all(uv < vec2<f32>(1.0));
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    var color = vec3<f32>(0.05);
+
+    let canvas_uv = in_square(position.xy, params.canvas);
+    if inside(canvas_uv) {
+        let pixel = vec2<u32>(canvas_uv * CANVAS_SIZE);
+        color = vec3<f32>(0.15 + canvas[pixel.y * 112u + pixel.x] * 0.85);
+    }
+
+    let preview_uv = in_square(position.xy, params.preview);
+    if inside(preview_uv) {
+        let pixel = vec2<u32>(preview_uv * INPUT_SIZE);
+        color = vec3<f32>(0.1, 0.1, 0.15) + vec3<f32>(network_input[pixel.y * 28u + pixel.x] * params.preview.w);
+    }
+
+    // A bar per digit, 0 on the left, the likeliest one highlighted
+    let bar_uv = (position.xy - params.bars.xy) / params.bars.zw;
+    if inside(bar_uv) {
+        let digit = u32(bar_uv.x * 10.0);
+        var likeliest = 0u;
+        for (var i = 1u; i < 10u; i++) {
+            if probabilities[i] > probabilities[likeliest] {
+                likeliest = i;
+            }
+        }
+        let within = fract(bar_uv.x * 10.0);
+        color = vec3<f32>(0.1);
+        if within > 0.15 && within < 0.85 && 1.0 - bar_uv.y < probabilities[digit] {
+            color = select(vec3<f32>(0.3, 0.45, 0.7), vec3<f32>(0.95, 0.7, 0.2), digit == likeliest);
+        }
+    }
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,57 @@
+struct Layer {
+    inputs: u32,
+    outputs: u32,
+    relu: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> layer: Layer;
+// Row by row, a row per output
+@group(0) @binding(1)
+var<storage, read> weights: array<f32>;
+@group(0) @binding(2)
+var<storage, read> biases: array<f32>;
+@group(0) @binding(3)
+var<storage, read> source: array<f32>;
+@group(0) @binding(4)
+var<storage, read_write> destination: array<f32>;
+
+// A matrix times a vector, an invocation per output. Batches of one leave
+// nothing to share between invocations, so there's no tiling. The size is
+// filled in with whatever the autotuner found fastest.
+@compute @workgroup_size(WORKGROUP_X, WORKGROUP_Y)
+fn cs_dense(@builtin(global_invocation_id) id: vec3<u32>) {
+    let row = id.x;
+    if row >= layer.outputs {
+        return;
+    }
+    var sum = biases[row];
+    let start = row * layer.inputs;
+    for (var i = 0u; i < layer.inputs; i++) {
+        sum += weights[start + i] * source[i];
+    }
+    if layer.relu != 0u {
+        sum = max(sum, 0.0);
+    }
+    destination[row] = sum;
+}
+
+// Scores to probabilities, over so few outputs that one invocation does it
+@compute @workgroup_size(1)
+fn cs_softmax() {
+    var largest = source[0];
+    for (var i = 1u; i < layer.outputs; i++) {
+        largest = max(largest, source[i]);
+    }
+    // Less the largest so exp can't overflow
+    var total = 0.0;
+    for (var i = 0u; i < layer.outputs; i++) {
+        let e = exp(source[i] - largest);
+        destination[i] = e;
+        total += e;
+    }
+    for (var i = 0u; i < layer.outputs; i++) {
+        destination[i] = destination[i] / total;
+    }
+}
@@ -0,0 +1,111 @@
+// Two flipbooks side by side, each GRID x GRID frames of FRAME_SIZE pixels
+// read left to right, top to bottom: a one-shot explosion on the left and
+// a looping fire on the right. Generated here rather than shipped as an
+// asset; any atlas laid out the same way would do.
+pub const FRAME_SIZE: u32 = 64;
+pub const GRID: u32 = 8;
+pub const FRAMES: u32 = GRID * GRID;
+pub const WIDTH: u32 = FRAME_SIZE * GRID * 2;
+pub const HEIGHT: u32 = FRAME_SIZE * GRID;
+
+// Value noise on a lattice that wraps every `period` cells vertically, so
+// the fire can scroll by exactly one period over its loop
+fn hash(x: i32, y: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0xffff) as f32 / 65535.0
+}
+
+fn noise(x: f32, y: f32, period: i32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (sx, sy) = (fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy));
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let corner = |dx: i32, dy: i32| hash(x0 + dx, (y0 + dy).rem_euclid(period));
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * sx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * sx;
+    top + (bottom - top) * sy
+}
+
+fn fractal(x: f32, y: f32, period: i32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut scale = 1;
+    for _ in 0..4 {
+        sum += noise(x * scale as f32, y * scale as f32, period * scale) * amplitude;
+        amplitude *= 0.5;
+        scale *= 2;
+    }
+    sum
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Hot white through orange to dark red
+fn fire_color(heat: f32) -> [f32; 3] {
+    [
+        (heat * 3.0).min(1.0),
+        (heat * 3.0 - 1.0).clamp(0.0, 1.0),
+        (heat * 3.0 - 2.0).clamp(0.0, 1.0),
+    ]
+}
+
+// Premultiplied: rgb is what the pixel adds, alpha how much it hides
+// what's behind. Flames are all glow and hide nothing, smoke the opposite.
+fn explosion(u: f32, v: f32, t: f32) -> [f32; 4] {
+    let (x, y) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+    let distance = (x * x + y * y).sqrt();
+    let radius = 0.25 + 0.65 * (1.0 - (1.0 - t).powi(3));
+    let billow = fractal(u * 4.0 + t * 2.0, v * 4.0 - t * 3.0, 1 << 16);
+    let shape = 1.0 - smoothstep(radius * 0.6, radius, distance + (billow - 0.5) * 0.5);
+
+    let heat = (1.0 - t * 1.6).max(0.0) * (1.0 - distance / radius).max(0.0) * 2.0 * billow;
+    let [r, g, b] = fire_color(heat);
+    let smoke = shape * smoothstep(0.1, 0.5, t) * (1.0 - smoothstep(0.7, 1.0, t)) * 0.8;
+    let glow = shape * (1.0 - smoothstep(0.5, 0.9, t));
+    let grey = 0.08 * smoke;
+    [r * glow + grey, g * glow + grey, b * glow + grey, smoke]
+}
+
+fn fire(u: f32, v: f32, t: f32) -> [f32; 4] {
+    let (x, y) = (u * 2.0 - 1.0, v);
+    // Scrolls up by one whole lattice period per loop so the last frame
+    // runs into the first
+    let period = 4;
+    let turbulence = fractal(u * 4.0, v * period as f32 + t * period as f32, period);
+    let width = 0.55 * (1.0 - (1.0 - y).powi(2) * 0.9);
+    let flame = smoothstep(width, width * 0.3, x.abs() + (turbulence - 0.5) * 0.6);
+    let heat = flame * y.powf(0.7) * (0.6 + turbulence);
+    let [r, g, b] = fire_color(heat);
+    [r, g, b, 0.0]
+}
+
+pub fn generate() -> Vec<u8> {
+    let mut pixels = vec![0u8; (WIDTH * HEIGHT * 4) as usize];
+    for (book, shade) in [(0, explosion as fn(f32, f32, f32) -> [f32; 4]), (1, fire)] {
+        for frame in 0..FRAMES {
+            let t = frame as f32 / FRAMES as f32;
+            let (column, row) = (frame % GRID, frame / GRID);
+            for y in 0..FRAME_SIZE {
+                for x in 0..FRAME_SIZE {
+                    // v = 1 at the bottom of the frame
+                    let u = (x as f32 + 0.5) / FRAME_SIZE as f32;
+                    let v = 1.0 - (y as f32 + 0.5) / FRAME_SIZE as f32;
+                    let texel = shade(u, v, t);
+                    let px = book * GRID * FRAME_SIZE + column * FRAME_SIZE + x;
+                    let py = row * FRAME_SIZE + y;
+                    let offset = ((py * WIDTH + px) * 4) as usize;
+                    for (channel, value) in texel.iter().enumerate() {
+                        pixels[offset + channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                }
+            }
+        }
+    }
+    pixels
+}
@@ -0,0 +1,8 @@
+mod atlas;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("flipbook");
+}
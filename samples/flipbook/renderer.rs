@@ -0,0 +1,709 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::depth_fade::{self, SceneDepth};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, ComputePipeline, Device, RenderPipeline, Sampler, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::atlas;
+
+const SPRITE_COUNT: u32 = 8192;
+// Must match @workgroup_size in shaders/animate.wgsl
+const WORKGROUP_SIZE: u32 = 64;
+// Half the width of the ground
+const EXTENT: f32 = 120.0;
+const NEAR: f32 = 0.1;
+const FAR: f32 = 1000.0;
+// How many units in front of the scene sprites start fading out
+const SOFTNESS: f32 = 2.0;
+const MOVE_SPEED: f32 = 30.0;
+const TURN_SPEED: f32 = 1.2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Sprite {
+    position: [f32; 3],
+    size: f32,
+    // Negative while waiting to start
+    frame: f32,
+    // Frames per second
+    rate: f32,
+    // 0 explosion, 1 fire
+    kind: u32,
+    seed: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    delta_time: f32,
+    sprite_count: u32,
+    frame_count: f32,
+    extent: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    depth: [f32; 4],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+// The ground and a scatter of crates for the sprites to sink into
+fn scene(rng: &mut Rng) -> (Vec<Vertex>, Vec<[f32; 4]>) {
+    let mut vertices = Vec::new();
+    let mut quad = |corners: [Vec3; 4], normal: Vec3, color: [f32; 3]| {
+        for index in [0, 1, 2, 0, 2, 3] {
+            vertices.push(Vertex {
+                position: corners[index].to_array(),
+                normal: normal.to_array(),
+                color,
+            });
+        }
+    };
+
+    quad(
+        [
+            Vec3::new(-EXTENT, 0.0, -EXTENT),
+            Vec3::new(-EXTENT, 0.0, EXTENT),
+            Vec3::new(EXTENT, 0.0, EXTENT),
+            Vec3::new(EXTENT, 0.0, -EXTENT),
+        ],
+        Vec3::Y,
+        [0.25, 0.22, 0.18],
+    );
+
+    // Box tops, xyz the middle of the top face and w half the width
+    let mut tops = Vec::new();
+    for _ in 0..60 {
+        let center = Vec3::new(rng.range(-EXTENT, EXTENT) * 0.9, 0.0, rng.range(-EXTENT, EXTENT) * 0.9);
+        let half = rng.range(1.5, 5.0);
+        let height = rng.range(1.0, 8.0);
+        let color = [0.4, 0.3, 0.2].map(|c| c * rng.range(0.7, 1.2));
+        let (min, max) = (center - Vec3::new(half, 0.0, half), center + Vec3::new(half, height, half));
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(if x { max.x } else { min.x }, if y { max.y } else { min.y }, if z { max.z } else { min.z })
+        };
+        quad([corner(false, false, true), corner(true, false, true), corner(true, true, true), corner(false, true, true)], Vec3::Z, color);
+        quad([corner(true, false, true), corner(true, false, false), corner(true, true, false), corner(true, true, true)], Vec3::X, color);
+        quad([corner(true, false, false), corner(false, false, false), corner(false, true, false), corner(true, true, false)], Vec3::NEG_Z, color);
+        quad([corner(false, false, false), corner(false, false, true), corner(false, true, true), corner(false, true, false)], Vec3::NEG_X, color);
+        quad([corner(false, true, false), corner(false, true, true), corner(true, true, true), corner(true, true, false)], Vec3::Y, color);
+        tops.push([center.x, height, center.z, half]);
+    }
+    (vertices, tops)
+}
+
+fn sprites(rng: &mut Rng, tops: &[[f32; 4]]) -> Vec<Sprite> {
+    (0..SPRITE_COUNT)
+        .map(|index| {
+            let seed = index.wrapping_mul(0x9e37_79b9) | 1;
+            if index % 2 == 0 {
+                // Fires burn on the crates and across the ground, sunk a
+                // little into whatever they sit on so the fade shows
+                let (x, y, z) = if index % 8 == 0 {
+                    let [x, y, z, half] = tops[(index as usize / 8) % tops.len()];
+                    (x + rng.range(-half, half), y, z + rng.range(-half, half))
+                } else {
+                    (rng.range(-EXTENT, EXTENT), 0.0, rng.range(-EXTENT, EXTENT))
+                };
+                let size = rng.range(1.5, 4.0);
+                Sprite {
+                    position: [x, y + size * 0.4, z],
+                    size,
+                    frame: rng.range(0.0, atlas::FRAMES as f32),
+                    rate: rng.range(20.0, 30.0),
+                    kind: 1,
+                    seed,
+                }
+            } else {
+                // Explosions pick their own spot when they first finish,
+                // start them all off waiting
+                Sprite {
+                    position: [0.0; 3],
+                    size: 0.0,
+                    frame: atlas::FRAMES as f32 - rng.range(0.0, 200.0),
+                    rate: 30.0,
+                    kind: 0,
+                    seed,
+                }
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_sprite_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniform_buffer: &Buffer,
+    sprite_buffer: &Buffer,
+    atlas_view: &TextureView,
+    sampler: &Sampler,
+    depth: &SceneDepth,
+) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Sprite Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: sprite_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(atlas_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: depth.binding(),
+            },
+        ],
+    })
+}
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    sprite_pipeline: RenderPipeline,
+    animate_pipeline: ComputePipeline,
+    scene_bind_group: BindGroup,
+    animate_bind_group: BindGroup,
+    sprite_layout: BindGroupLayout,
+    sprite_bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    params_buffer: Buffer,
+    sprite_buffer: Buffer,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    atlas_view: TextureView,
+    sampler: Sampler,
+    depth: SceneDepth,
+    paused: bool,
+    soft: bool,
+    blend_frames: bool,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let mut rng = Rng(0x2f6b_1c3d);
+
+        let (vertices, tops) = scene(&mut rng);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Scene Vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let sprite_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sprites"),
+            contents: bytemuck::cast_slice(&sprites(&mut rng, &tops)),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let size = wgpu::Extent3d {
+            width: atlas::WIDTH,
+            height: atlas::HEIGHT,
+            depth_or_array_layers: 1,
+        };
+        let atlas_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Flipbook Atlas"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // Premultiplied and made up in linear, not an sRGB image
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        gpu.queue.write_texture(
+            atlas_texture.as_image_copy(),
+            &atlas::generate(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * atlas::WIDTH),
+                rows_per_image: Some(atlas::HEIGHT),
+            },
+            size,
+        );
+        let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Animation Params"),
+            contents: bytemuck::bytes_of(&Params::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage_entry = |binding, visibility, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Layout"),
+            entries: &[uniform_entry(0, wgpu::ShaderStages::VERTEX)],
+        });
+        let scene_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &scene_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let animate_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Animate Layout"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::COMPUTE),
+                storage_entry(1, wgpu::ShaderStages::COMPUTE, false),
+            ],
+        });
+        let animate_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Animate Bind Group"),
+            layout: &animate_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: sprite_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let sprite_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite Layout"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                storage_entry(1, wgpu::ShaderStages::VERTEX, true),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                SceneDepth::layout_entry(4),
+            ],
+        });
+        let depth = SceneDepth::new(device, &gpu.config);
+        let sprite_bind_group = create_sprite_bind_group(
+            device,
+            &sprite_layout,
+            &uniform_buffer,
+            &sprite_buffer,
+            &atlas_view,
+            &sampler,
+            &depth,
+        );
+
+        let animate_shader = device.create_shader_module(include_wgsl!("shaders/animate.wgsl"));
+        let animate_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Animate Pipeline Layout"),
+            bind_group_layouts: &[&animate_layout],
+            push_constant_ranges: &[],
+        });
+        let animate_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Animate Pipeline"),
+            layout: Some(&animate_pipeline_layout),
+            module: &animate_shader,
+            entry_point: "cs_animate",
+        });
+
+        let scene_shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+        let scene_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&scene_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&scene_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &scene_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &scene_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_fade::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sprite_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}", depth_fade::WGSL, include_str!("shaders/sprite.wgsl")).into(),
+            ),
+        });
+        let sprite_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[&sprite_layout],
+            push_constant_ranges: &[],
+        });
+        let sprite_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&sprite_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &sprite_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &sprite_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    // Premultiplied, so glow adds and smoke covers in one
+                    // blend state. Sprites aren't sorted, overlapping smoke
+                    // can come out in the wrong order.
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // Tested against the scene's depth but never written, the
+            // texture is bound for the fade at the same time
+            depth_stencil: Some(depth_fade::particle_depth_state()),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            scene_pipeline,
+            sprite_pipeline,
+            animate_pipeline,
+            scene_bind_group,
+            animate_bind_group,
+            sprite_layout,
+            sprite_bind_group,
+            uniform_buffer,
+            params_buffer,
+            sprite_buffer,
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            atlas_view,
+            sampler,
+            depth,
+            paused: false,
+            soft: true,
+            blend_frames: true,
+            position: Vec3::new(0.0, 25.0, EXTENT * 0.8),
+            yaw: 0.0,
+            pitch: -0.25,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth = SceneDepth::new(&gpu.device, &gpu.config);
+        // Holds the old depth view, so it has to follow it
+        self.sprite_bind_group = create_sprite_bind_group(
+            &gpu.device,
+            &self.sprite_layout,
+            &self.uniform_buffer,
+            &self.sprite_buffer,
+            &self.atlas_view,
+            &self.sampler,
+            &self.depth,
+        );
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                VirtualKeyCode::F => self.soft = !self.soft,
+                VirtualKeyCode::B => self.blend_frames = !self.blend_frames,
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.position = pose.position;
+        self.yaw = pose.yaw;
+        self.pitch = pose.pitch;
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} sprites, {} edges, frame blending {}{}",
+            SPRITE_COUNT,
+            if self.soft { "soft" } else { "hard" },
+            if self.blend_frames { "on" } else { "off" },
+            if self.paused { ", paused" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let dt = self.update_camera();
+
+        let direction = Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        );
+        let view_matrix = Mat4::look_to_rh(self.position, direction, Vec3::Y);
+        let proj = Mat4::perspective_rh(60f32.to_radians(), gpu.aspect_ratio(), NEAR, FAR);
+        // The camera's own axes, so every sprite faces it square on
+        let right = direction.cross(Vec3::Y).normalize();
+        let up = right.cross(direction);
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: (proj * view_matrix).to_cols_array_2d(),
+                right: right.extend(0.0).to_array(),
+                up: up.extend(0.0).to_array(),
+                // Hard is the same fade squeezed into almost nothing, which
+                // leaves a plain depth test
+                depth: [NEAR, FAR, if self.soft { SOFTNESS } else { 0.001 }, if self.blend_frames { 1.0 } else { 0.0 }],
+            }),
+        );
+        gpu.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Params {
+                delta_time: if self.paused { 0.0 } else { dt },
+                sprite_count: SPRITE_COUNT,
+                frame_count: atlas::FRAMES as f32,
+                extent: EXTENT * 0.9,
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Animate Pass"),
+            });
+            compute_pass.set_pipeline(&self.animate_pipeline);
+            compute_pass.set_bind_group(0, &self.animate_bind_group, &[]);
+            compute_pass.dispatch_workgroups((SPRITE_COUNT + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE, 1, 1);
+        }
+
+        // The opaque scene first, keeping its depth for the sprites
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.05,
+                                    g: 0.06,
+                                    b: 0.09,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    // Stored for the sprite pass to read
+                    depth_stencil_attachment: Some(self.depth.attachment()),
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Sprite Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(self.depth.read_only_attachment()),
+                },
+            );
+            render_pass.set_pipeline(&self.sprite_pipeline);
+            render_pass.set_bind_group(0, &self.sprite_bind_group, &[]);
+            render_pass.draw(0..6, 0..SPRITE_COUNT);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    // Returns the frame's delta time for the animation too
+    fn update_camera(&mut self) -> f32 {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let forward = Vec3::new(self.yaw.sin(), 0.0, -self.yaw.cos());
+        let right = Vec3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+        let held = |key| self.held_keys.contains(&key);
+
+        let mut movement = Vec3::ZERO;
+        if held(VirtualKeyCode::W) { movement += forward; }
+        if held(VirtualKeyCode::S) { movement -= forward; }
+        if held(VirtualKeyCode::D) { movement += right; }
+        if held(VirtualKeyCode::A) { movement -= right; }
+        if held(VirtualKeyCode::E) { movement += Vec3::Y; }
+        if held(VirtualKeyCode::Q) { movement -= Vec3::Y; }
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        let mut tilt = 0.0;
+        if held(VirtualKeyCode::Up) { tilt += 1.0; }
+        if held(VirtualKeyCode::Down) { tilt -= 1.0; }
+
+        self.position += movement * MOVE_SPEED * dt;
+        self.position.y = self.position.y.max(1.0);
+        self.yaw += turn * TURN_SPEED * dt;
+        self.pitch = (self.pitch + tilt * TURN_SPEED * dt).clamp(-1.5, 1.5);
+        dt
+    }
+}
@@ -0,0 +1,62 @@
+struct Sprite {
+    position: vec3<f32>,
+    size: f32,
+    // Negative while waiting to start
+    frame: f32,
+    // Frames per second
+    rate: f32,
+    // 0 explosion, 1 fire
+    kind: u32,
+    seed: u32,
+}
+
+struct Params {
+    delta_time: f32,
+    sprite_count: u32,
+    frame_count: f32,
+    // Half the width of the square explosions respawn in
+    extent: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read_write> sprites: array<Sprite>;
+
+fn hash(value: u32) -> u32 {
+    var h = value * 747796405u + 2891336453u;
+    h = ((h >> ((h >> 28u) + 4u)) ^ h) * 277803737u;
+    return (h >> 22u) ^ h;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed >> 8u) / 16777216.0;
+}
+
+// Must match WORKGROUP_SIZE in renderer.rs
+@compute @workgroup_size(64)
+fn cs_animate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= params.sprite_count {
+        return;
+    }
+
+    var sprite = sprites[index];
+    sprite.frame += sprite.rate * params.delta_time;
+    if sprite.kind == 1u {
+        // Fires loop in place
+        sprite.frame = sprite.frame % params.frame_count;
+    } else if sprite.frame >= params.frame_count {
+        // A finished explosion goes off again somewhere else after a pause
+        var seed = sprite.seed;
+        let x = (random(&seed) * 2.0 - 1.0) * params.extent;
+        let z = (random(&seed) * 2.0 - 1.0) * params.extent;
+        sprite.size = 3.0 + random(&seed) * 5.0;
+        sprite.position = vec3<f32>(x, sprite.size * 0.4, z);
+        sprite.rate = 24.0 + random(&seed) * 16.0;
+        sprite.frame = -random(&seed) * 60.0;
+        sprite.seed = seed;
+    }
+    sprites[index] = sprite;
+}
@@ -0,0 +1,95 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    // Camera right and up for billboarding, w unused
+    right: vec4<f32>,
+    up: vec4<f32>,
+    // x near, y far, z how deep the depth fade is, w 1 to blend frames
+    depth: vec4<f32>,
+}
+
+struct Sprite {
+    position: vec3<f32>,
+    size: f32,
+    frame: f32,
+    rate: f32,
+    kind: u32,
+    seed: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var<storage, read> sprites: array<Sprite>;
+@group(0) @binding(2)
+var atlas: texture_2d<f32>;
+@group(0) @binding(3)
+var atlas_sampler: sampler;
+@group(0) @binding(4)
+var scene_depth: texture_2d<f32>;
+
+// Must match atlas.rs
+const GRID: f32 = 8.0;
+const FRAMES: f32 = 64.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Within the frame, 0 to 1
+    @location(0) uv: vec2<f32>,
+    // Which flipbook, then the frame to show and how far into the next
+    @location(1) @interpolate(flat) kind: u32,
+    @location(2) @interpolate(flat) frame: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0)
+    );
+    let corner = corners[vertex_index];
+    let sprite = sprites[instance_index];
+
+    var out: VertexOutput;
+    // Not started yet, collapse it to nothing
+    if sprite.frame < 0.0 {
+        out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+        return out;
+    }
+    let offset = (uniforms.right.xyz * (corner.x - 0.5) + uniforms.up.xyz * (corner.y - 0.5)) * sprite.size;
+    out.clip_position = uniforms.view_proj * vec4<f32>(sprite.position + offset, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    out.kind = sprite.kind;
+    out.frame = vec2<f32>(floor(sprite.frame), fract(sprite.frame));
+    return out;
+}
+
+// Where `uv` within `frame` lands in the atlas
+fn atlas_uv(kind: u32, frame: f32, uv: vec2<f32>) -> vec2<f32> {
+    let wrapped = frame % FRAMES;
+    let cell = vec2<f32>(wrapped % GRID, floor(wrapped / GRID));
+    // Both flipbooks side by side
+    return vec2<f32>((cell.x + uv.x + f32(kind) * GRID) / (GRID * 2.0), (cell.y + uv.y) / GRID);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Kept just inside the frame so its neighbours don't bleed in
+    let uv = clamp(in.uv, vec2<f32>(0.01), vec2<f32>(0.99));
+    let current = textureSample(atlas, atlas_sampler, atlas_uv(in.kind, in.frame.x, uv));
+    let next = textureSample(atlas, atlas_sampler, atlas_uv(in.kind, in.frame.x + 1.0, uv));
+    // Explosions don't loop, their last frame doesn't blend into the first
+    var blend = in.frame.y * uniforms.depth.w;
+    if in.kind == 0u && in.frame.x >= FRAMES - 1.0 {
+        blend = 0.0;
+    }
+    let color = mix(current, next, blend);
+
+    // Fades in over the last few units in front of the scene instead of
+    // clipping in a hard line
+    let fade = depth_fade(scene_depth, in.clip_position, uniforms.depth.x, uniforms.depth.y, uniforms.depth.z);
+    return color * fade;
+}
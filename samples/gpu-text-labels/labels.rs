@@ -0,0 +1,70 @@
+use bytemuck::{Pod, Zeroable};
+use framework::font::glyph_index;
+
+pub const WORLD_RADIUS: f32 = 1000.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Label {
+    position: [f32; 2],
+    // Uniform in 0..1, the layout pass drops everything below a threshold
+    // when zoomed out
+    rank: f32,
+    // First character in the text buffer in the upper 24 bits, length in
+    // the lower 8
+    text: u32,
+}
+
+pub struct Catalog {
+    pub labels: Vec<Label>,
+    // One glyph index per byte, padded to a whole number of u32s
+    pub text: Vec<u8>,
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.next_u32() as f32 / u32::MAX as f32
+    }
+}
+
+// A star catalog laid out as a three-armed spiral galaxy, with names like
+// "KX-40213"
+pub fn generate(count: usize) -> Catalog {
+    let mut rng = Rng(0x2545_f491);
+    let mut labels = Vec::with_capacity(count);
+    let mut text = Vec::with_capacity(count * 8);
+
+    for _ in 0..count {
+        let radius = rng.next_f32().sqrt() * WORLD_RADIUS;
+        let arm = (rng.next_u32() % 3) as f32 * std::f32::consts::TAU / 3.0;
+        let spread = (rng.next_f32() - 0.5) * 1.2;
+        let angle = arm + radius / WORLD_RADIUS * 4.0 + spread;
+
+        let name = format!(
+            "{}{}-{}",
+            (b'A' + (rng.next_u32() % 26) as u8) as char,
+            (b'A' + (rng.next_u32() % 26) as u8) as char,
+            rng.next_u32() % 100_000,
+        );
+        let first = text.len() as u32;
+        text.extend(name.chars().map(glyph_index));
+
+        labels.push(Label {
+            position: [radius * angle.cos(), radius * angle.sin()],
+            rank: rng.next_f32(),
+            text: first << 8 | name.len() as u32,
+        });
+    }
+
+    text.resize(wgpu::util::align_to(text.len(), 4), 0);
+    Catalog { labels, text }
+}
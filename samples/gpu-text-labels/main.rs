@@ -0,0 +1,8 @@
+mod labels;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("gpu-text-labels");
+}
@@ -0,0 +1,415 @@
+use bytemuck::{Pod, Zeroable};
+use framework::font::packed_glyphs;
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, Buffer, ComputePipeline, RenderPipeline};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::labels::{self, WORLD_RADIUS};
+
+const LABEL_COUNT: u32 = 250_000;
+// Must match MAX_GLYPHS in shaders/layout.wgsl
+const MAX_GLYPHS: u64 = 65536;
+// How many labels should roughly be on screen at any zoom level
+const TARGET_VISIBLE_LABELS: f32 = 3000.0;
+const MIN_ZOOM: f32 = 5.0;
+const MAX_ZOOM: f32 = 1200.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CameraUniform {
+    center: [f32; 2],
+    extent: [f32; 2],
+    viewport: [f32; 2],
+    min_rank: f32,
+    label_count: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Glyph {
+    anchor: [f32; 2],
+    glyph: u32,
+    column: u32,
+}
+
+impl Glyph {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Uint32, 2 => Uint32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Glyph>() as wgpu::BufferAddress,
+            // One quad per glyph, the vertex index picks the corner
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+pub struct Renderer {
+    layout_pipeline: ComputePipeline,
+    finalize_pipeline: ComputePipeline,
+    render_pipeline: RenderPipeline,
+    camera_buffer: Buffer,
+    glyph_buffer: Buffer,
+    counter_buffer: Buffer,
+    draw_args_buffer: Buffer,
+    layout_bind_group: BindGroup,
+    text_bind_group: BindGroup,
+    center: [f32; 2],
+    // Half the visible height in world units
+    zoom: f32,
+    viewport: [f32; 2],
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let catalog = labels::generate(LABEL_COUNT as usize);
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera"),
+            contents: bytemuck::bytes_of(&CameraUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let label_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Labels"),
+            contents: bytemuck::cast_slice(&catalog.labels),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let text_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Label Text"),
+            contents: &catalog.text,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        // Written by the layout pass, read as per-instance vertex data
+        let glyph_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Glyph Instances"),
+            size: MAX_GLYPHS * std::mem::size_of::<Glyph>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        let counter_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Glyph Counter"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Six vertices per glyph quad; cs_finalize fills in the instance count
+        let draw_args_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Draw Args"),
+            contents: bytemuck::cast_slice(&[6u32, 0, 0, 0]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+        });
+
+        let font_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Font"),
+            contents: bytemuck::cast_slice(&packed_glyphs()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let layout_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Layout Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, false),
+                storage_entry(4, false),
+                storage_entry(5, false),
+            ],
+        });
+
+        let layout_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Layout Bind Group"),
+            layout: &layout_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: label_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: text_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: glyph_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: counter_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: draw_args_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let text_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let text_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Text Bind Group"),
+            layout: &text_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: font_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let layout_shader = device.create_shader_module(include_wgsl!("shaders/layout.wgsl"));
+
+        let compute_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Layout Pipeline Layout"),
+                    bind_group_layouts: &[&layout_bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_compute_pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&compute_pipeline_layout),
+                module: &layout_shader,
+                entry_point,
+            })
+        };
+
+        let layout_pipeline = create_compute_pipeline("Layout Pipeline", "cs_layout");
+        let finalize_pipeline = create_compute_pipeline("Finalize Pipeline", "cs_finalize");
+
+        let text_shader = device.create_shader_module(include_wgsl!("shaders/text.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&text_bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &text_shader,
+                entry_point: "vs_main",
+                buffers: &[Glyph::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &text_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            layout_pipeline,
+            finalize_pipeline,
+            render_pipeline,
+            camera_buffer,
+            glyph_buffer,
+            counter_buffer,
+            draw_args_buffer,
+            layout_bind_group,
+            text_bind_group,
+            center: [0.0, 0.0],
+            zoom: MAX_ZOOM,
+            viewport: [gpu.config.width as f32, gpu.config.height as f32],
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.viewport = [gpu.config.width as f32, gpu.config.height as f32];
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.key_pressed(*key);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} labels, zoom {:.0}, rank >= {:.3}",
+            LABEL_COUNT,
+            self.zoom,
+            self.min_rank(),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        gpu.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::bytes_of(&CameraUniform {
+                center: self.center,
+                extent: self.extent(),
+                viewport: self.viewport,
+                min_rank: self.min_rank(),
+                label_count: LABEL_COUNT,
+            }),
+        );
+        gpu.queue.write_buffer(&self.counter_buffer, 0, bytemuck::bytes_of(&0u32));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Layout Pass"),
+            });
+            compute_pass.set_bind_group(0, &self.layout_bind_group, &[]);
+            compute_pass.set_pipeline(&self.layout_pipeline);
+            compute_pass.dispatch_workgroups((LABEL_COUNT + 255) / 256, 1, 1);
+            compute_pass.set_pipeline(&self.finalize_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.01,
+                                    g: 0.01,
+                                    b: 0.03,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            // The CPU never learns how many glyphs survived culling
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.text_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.glyph_buffer.slice(..));
+            render_pass.draw_indirect(&self.draw_args_buffer, 0);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn key_pressed(&mut self, key: VirtualKeyCode) {
+        let step = self.zoom * 0.25;
+        match key {
+            VirtualKeyCode::W | VirtualKeyCode::Up => self.center[1] += step,
+            VirtualKeyCode::S | VirtualKeyCode::Down => self.center[1] -= step,
+            VirtualKeyCode::A | VirtualKeyCode::Left => self.center[0] -= step,
+            VirtualKeyCode::D | VirtualKeyCode::Right => self.center[0] += step,
+            VirtualKeyCode::Q => self.zoom = (self.zoom * 1.25).min(MAX_ZOOM),
+            VirtualKeyCode::E => self.zoom = (self.zoom / 1.25).max(MIN_ZOOM),
+            _ => {}
+        }
+    }
+
+    fn extent(&self) -> [f32; 2] {
+        [self.zoom * self.viewport[0] / self.viewport[1], self.zoom]
+    }
+
+    // Ranks are uniform, so keeping the top fraction of labels that would
+    // otherwise be on screen holds the count near TARGET_VISIBLE_LABELS.
+    // Only roughly, the galaxy isn't evenly dense.
+    fn min_rank(&self) -> f32 {
+        let [extent_x, extent_y] = self.extent();
+        let world_area = std::f32::consts::PI * WORLD_RADIUS * WORLD_RADIUS;
+        let visible = LABEL_COUNT as f32 * (4.0 * extent_x * extent_y / world_area).min(1.0);
+        (1.0 - TARGET_VISIBLE_LABELS / visible).max(0.0)
+    }
+}
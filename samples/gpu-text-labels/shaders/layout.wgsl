@@ -0,0 +1,91 @@
+// Must match the constants in renderer.rs and text.wgsl
+const MAX_GLYPHS: u32 = 65536u;
+const CELL_SIZE: vec2<f32> = vec2<f32>(12.0, 16.0);
+const LABEL_OFFSET: f32 = 6.0;
+
+struct Camera {
+    center: vec2<f32>,
+    extent: vec2<f32>,
+    viewport: vec2<f32>,
+    min_rank: f32,
+    label_count: u32,
+}
+
+struct Label {
+    position: vec2<f32>,
+    rank: f32,
+    text: u32,
+}
+
+struct Glyph {
+    anchor: vec2<f32>,
+    glyph: u32,
+    column: u32,
+}
+
+struct Counter {
+    glyphs: atomic<u32>,
+}
+
+// Laid out like wgpu::util::DrawIndirect
+struct DrawArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+@group(0) @binding(1)
+var<storage, read> labels: array<Label>;
+// Four glyph indices per word
+@group(0) @binding(2)
+var<storage, read> text: array<u32>;
+@group(0) @binding(3)
+var<storage, read_write> glyphs: array<Glyph>;
+@group(0) @binding(4)
+var<storage, read_write> counter: Counter;
+@group(0) @binding(5)
+var<storage, read_write> draw_args: DrawArgs;
+
+// One thread per label: cull it, then reserve room for its glyphs with a
+// single atomic and write them out
+@compute @workgroup_size(256)
+fn cs_layout(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= camera.label_count {
+        return;
+    }
+    let label = labels[id.x];
+    if label.rank < camera.min_rank {
+        return;
+    }
+
+    let first = label.text >> 8u;
+    let len = label.text & 0xffu;
+
+    // The text hangs off to the right of the anchor, vertically centered
+    let anchor = (label.position - camera.center) / camera.extent;
+    let size = vec2<f32>(LABEL_OFFSET + f32(len) * CELL_SIZE.x, CELL_SIZE.y) * 2.0 / camera.viewport;
+    if anchor.x > 1.0 || anchor.x + size.x < -1.0 || abs(anchor.y) > 1.0 + size.y * 0.5 {
+        return;
+    }
+
+    let base = atomicAdd(&counter.glyphs, len);
+    for (var i = 0u; i < len; i++) {
+        // A label straddling the end of the buffer gets cut short
+        if base + i >= MAX_GLYPHS {
+            break;
+        }
+        let c = first + i;
+        let glyph = (text[c / 4u] >> (8u * (c % 4u))) & 0xffu;
+        glyphs[base + i] = Glyph(label.position, glyph, i);
+    }
+}
+
+// The counter keeps going past the end of the buffer, so clamp it before
+// the draw uses it
+@compute @workgroup_size(1)
+fn cs_finalize() {
+    draw_args.instance_count = min(atomicLoad(&counter.glyphs), MAX_GLYPHS);
+}
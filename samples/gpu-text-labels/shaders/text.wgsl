@@ -0,0 +1,72 @@
+// Must match the constants in layout.wgsl
+const CELL_SIZE: vec2<f32> = vec2<f32>(12.0, 16.0);
+const LABEL_OFFSET: f32 = 6.0;
+
+struct Camera {
+    center: vec2<f32>,
+    extent: vec2<f32>,
+    viewport: vec2<f32>,
+    min_rank: f32,
+    label_count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+// 5x7 glyphs, see core/font.rs for the packing
+@group(0) @binding(1)
+var<storage, read> font: array<vec2<u32>>;
+
+struct GlyphInput {
+    @location(0) anchor: vec2<f32>,
+    @location(1) glyph: u32,
+    @location(2) column: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // In font pixels, each cell is 6x8 with the glyph in the top left 5x7
+    @location(0) cell: vec2<f32>,
+    @location(1) @interpolate(flat) glyph: u32,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: GlyphInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0)
+    );
+    let corner = corners[vertex_index];
+
+    // Glyphs keep their size in pixels no matter the zoom
+    let anchor = (instance.anchor - camera.center) / camera.extent;
+    let pixels = vec2<f32>(LABEL_OFFSET + f32(instance.column) * CELL_SIZE.x, -0.5 * CELL_SIZE.y) + corner * CELL_SIZE;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(anchor + pixels * 2.0 / camera.viewport, 0.0, 1.0);
+    out.cell = vec2<f32>(corner.x, 1.0 - corner.y) * vec2<f32>(6.0, 8.0);
+    out.glyph = instance.glyph;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<u32>(in.cell);
+    if pixel.x >= 5u || pixel.y >= 7u {
+        discard;
+    }
+
+    let rows = font[in.glyph];
+    let word = select(rows.x, rows.y, pixel.y >= 4u);
+    let bits = word >> (8u * (pixel.y % 4u));
+    if ((bits >> (4u - pixel.x)) & 1u) == 0u {
+        discard;
+    }
+    return vec4<f32>(1.0, 0.9, 0.6, 1.0);
+}
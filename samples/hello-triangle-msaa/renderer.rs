@@ -0,0 +1,462 @@
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, TextureDescriptor, TextureFormat, TextureDimension, TextureUsages, TextureViewDescriptor, TextureView, BindGroup, BindGroupLayout, RenderPipeline, Device, ShaderModule};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+// Asked for, gpu.sample_count lowers it where the HDR format can't resolve it
+const SAMPLE_COUNT: u32 = 4;
+// The scene and the post chain work in HDR, only the last pass writes to the surface
+const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveMode {
+    // Let the render pass average the samples into the resolve target
+    Hardware,
+    // Average the samples ourselves, tonemapping each one first
+    Manual,
+}
+
+// The scene's multisampled color and the manual resolve's view of it
+struct MultisampledTarget {
+    view: TextureView,
+    manual_resolve_bind_group: BindGroup,
+}
+
+// Everything that depends on the surface size
+struct RenderTargets {
+    // None with a single sample, the scene is then drawn straight into post_views[0]
+    multisampled: Option<MultisampledTarget>,
+    // The post chain ping-pongs between these two, the scene resolves into the first one
+    post_views: [TextureView; 2],
+    post_bind_groups: [BindGroup; 2],
+}
+
+impl RenderTargets {
+    fn new(
+        device: &Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+        msaa_layout: &BindGroupLayout,
+        post_layout: &BindGroupLayout,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        // A single-sampled texture can't be bound as multisampled, so there's
+        // nothing for the manual resolve to read
+        let multisampled = (sample_count > 1).then(|| {
+            let view = device.create_texture(&TextureDescriptor {
+                size,
+                mip_level_count: 1,
+                sample_count,
+                dimension: TextureDimension::D2,
+                format: HDR_FORMAT,
+                // TEXTURE_BINDING so the manual resolve can read individual samples
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                label: Some("Multisampled Color"),
+                view_formats: &[],
+            }).create_view(&TextureViewDescriptor::default());
+
+            let manual_resolve_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Manual Resolve Bind Group"),
+                layout: msaa_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                }],
+            });
+
+            MultisampledTarget {
+                view,
+                manual_resolve_bind_group,
+            }
+        });
+
+        let post_views = ["Post A", "Post B"].map(|label| {
+            device.create_texture(&TextureDescriptor {
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: HDR_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                label: Some(label),
+                view_formats: &[],
+            }).create_view(&TextureViewDescriptor::default())
+        });
+
+        let post_bind_groups = [&post_views[0], &post_views[1]].map(|view| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Bind Group"),
+                layout: post_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                }],
+            })
+        });
+
+        Self {
+            multisampled,
+            post_views,
+            post_bind_groups,
+        }
+    }
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    manual_resolve_pipeline: RenderPipeline,
+    // HDR -> HDR passes, run in order
+    post_pipelines: Vec<RenderPipeline>,
+    // HDR -> surface, always last
+    tonemap_pipeline: RenderPipeline,
+    msaa_layout: BindGroupLayout,
+    post_layout: BindGroupLayout,
+    targets: RenderTargets,
+    sample_count: u32,
+    resolve_mode: ResolveMode,
+}
+
+fn create_fullscreen_pipeline(
+    device: &Device,
+    label: &str,
+    layout: &BindGroupLayout,
+    vertex_shader: &ShaderModule,
+    fragment_shader: &ShaderModule,
+    format: TextureFormat,
+) -> RenderPipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: vertex_shader,
+            entry_point: "main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: fragment_shader,
+            entry_point: "main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+impl Renderer {
+    pub fn toggle_resolve_mode(&mut self) -> ResolveMode {
+        self.resolve_mode = match self.resolve_mode {
+            ResolveMode::Hardware => ResolveMode::Manual,
+            ResolveMode::Manual => ResolveMode::Hardware,
+        };
+        self.resolve_mode
+    }
+
+    fn fullscreen_pass(
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        pipeline: &RenderPipeline,
+        bind_group: &BindGroup,
+        target: &TextureView,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let surface_config = &gpu.config;
+
+        let msaa_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Multisampled Texture Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    // Multisampled textures can't be filtered
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: true,
+                },
+                count: None,
+            }],
+        });
+
+        let post_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Texture Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        // 1 where HDR_FORMAT can't be multisampled at all, the scene is then
+        // drawn without MSAA and there's nothing to resolve
+        let sample_count = gpu.sample_count(HDR_FORMAT, SAMPLE_COUNT);
+        let targets = RenderTargets::new(
+            device,
+            surface_config.width,
+            surface_config.height,
+            sample_count,
+            &msaa_layout,
+            &post_layout,
+        );
+
+        let vertex_shader = device.create_shader_module(
+            include_wgsl!("shaders/triangle.vert.wgsl"),
+        );
+
+        let fragment_shader = device.create_shader_module(
+            include_wgsl!("shaders/red.frag.wgsl"),
+        );
+
+        let fullscreen_shader = device.create_shader_module(
+            include_wgsl!("shaders/fullscreen.vert.wgsl"),
+        );
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex_shader,
+                entry_point: "main", // 1.
+                buffers: &[], // 2.
+            },
+            fragment: Some(wgpu::FragmentState { // 3.
+                module: &fragment_shader,
+                entry_point: "main",
+                targets: &[Some(wgpu::ColorTargetState { // 4.
+                    format: HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw, // 2.
+                cull_mode: Some(wgpu::Face::Back),
+                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                polygon_mode: wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: None, // 1.
+            multisample: wgpu::MultisampleState {
+                count: sample_count, // 2.
+                mask: !0, // 3.
+                alpha_to_coverage_enabled: false, // 4.
+            },
+            multiview: None, // 5.
+        });
+
+        let manual_resolve_pipeline = create_fullscreen_pipeline(
+            device,
+            "Manual Resolve Pipeline",
+            &msaa_layout,
+            &fullscreen_shader,
+            &device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("shaders/manual_resolve.frag.wgsl"),
+                source: wgpu::ShaderSource::Wgsl(
+                    include_str!("shaders/manual_resolve.frag.wgsl")
+                        .replace("SAMPLE_COUNT", &sample_count.to_string())
+                        .into(),
+                ),
+            }),
+            HDR_FORMAT,
+        );
+
+        let post_pipelines = vec![
+            create_fullscreen_pipeline(
+                device,
+                "Vignette Pipeline",
+                &post_layout,
+                &fullscreen_shader,
+                &device.create_shader_module(include_wgsl!("shaders/vignette.frag.wgsl")),
+                HDR_FORMAT,
+            ),
+        ];
+
+        let tonemap_pipeline = create_fullscreen_pipeline(
+            device,
+            "Tonemap Pipeline",
+            &post_layout,
+            &fullscreen_shader,
+            &device.create_shader_module(include_wgsl!("shaders/tonemap.frag.wgsl")),
+            surface_config.format,
+        );
+
+        Self {
+            render_pipeline,
+            manual_resolve_pipeline,
+            post_pipelines,
+            tonemap_pipeline,
+            msaa_layout,
+            post_layout,
+            targets,
+            sample_count,
+            resolve_mode: ResolveMode::Hardware,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        // The multisampled texture and the post chain have to match the surface
+        self.targets = RenderTargets::new(
+            &gpu.device,
+            gpu.config.width,
+            gpu.config.height,
+            self.sample_count,
+            &self.msaa_layout,
+            &self.post_layout,
+        );
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::Space),
+                ..
+            },
+            ..
+        } = event
+        {
+            if self.sample_count > 1 {
+                println!("Resolve mode: {:?}", self.toggle_resolve_mode());
+            }
+        }
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            // With a manual resolve the samples have to stay in the multisampled
+            // texture, otherwise only the resolved image is needed afterwards.
+            // Without MSAA the scene goes straight to the post chain.
+            let (view, resolve_target, store) = match (&self.targets.multisampled, self.resolve_mode) {
+                (Some(msaa), ResolveMode::Hardware) => (&msaa.view, Some(&self.targets.post_views[0]), false),
+                (Some(msaa), ResolveMode::Manual) => (&msaa.view, None, true),
+                (None, _) => (&self.targets.post_views[0], None, true),
+            };
+
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline); // 2.
+            render_pass.draw(0..3, 0..1); // 3.
+        }
+
+        if let (Some(msaa), ResolveMode::Manual) = (&self.targets.multisampled, self.resolve_mode) {
+            Self::fullscreen_pass(
+                &mut encoder,
+                "Manual Resolve Pass",
+                &self.manual_resolve_pipeline,
+                &msaa.manual_resolve_bind_group,
+                &self.targets.post_views[0],
+            );
+        }
+
+        // Each post pass reads the previous result and writes the other texture
+        let mut source = 0;
+        for pipeline in &self.post_pipelines {
+            Self::fullscreen_pass(
+                &mut encoder,
+                "Post Pass",
+                pipeline,
+                &self.targets.post_bind_groups[source],
+                &self.targets.post_views[1 - source],
+            );
+            source = 1 - source;
+        }
+
+        Self::fullscreen_pass(
+            &mut encoder,
+            "Tonemap Pass",
+            &self.tonemap_pipeline,
+            &self.targets.post_bind_groups[source],
+            view,
+        );
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.sample_count > 1 {
+            Some(format!("{}x MSAA, {:?} resolve", self.sample_count, self.resolve_mode))
+        } else {
+            Some(format!("no MSAA, {:?} can't be multisampled here", HDR_FORMAT))
+        }
+    }
+}
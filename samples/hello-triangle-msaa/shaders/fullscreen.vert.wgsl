@@ -0,0 +1,13 @@
+// One oversized triangle that covers the whole screen
+@vertex
+fn main(
+  @builtin(vertex_index) VertexIndex : u32
+) -> @builtin(position) vec4<f32> {
+  var pos = array<vec2<f32>, 3>(
+    vec2(-1.0, -1.0),
+    vec2(3.0, -1.0),
+    vec2(-1.0, 3.0)
+  );
+
+  return vec4<f32>(pos[VertexIndex], 0.0, 1.0);
+}
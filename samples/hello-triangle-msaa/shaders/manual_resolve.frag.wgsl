@@ -0,0 +1,29 @@
+@group(0) @binding(0)
+var msaa_texture: texture_multisampled_2d<f32>;
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+  return color / (1.0 + max(color.r, max(color.g, color.b)));
+}
+
+fn inverse_tonemap(color: vec3<f32>) -> vec3<f32> {
+  return color / max(1.0 - max(color.r, max(color.g, color.b)), 0.0001);
+}
+
+// Averaging HDR samples lets a single very bright sample dominate the
+// pixel, so edges against dark backgrounds stay aliased after tonemapping.
+// Tonemapping every sample first, averaging, then undoing the tonemap keeps
+// the edge gradient while leaving the output in HDR for the post chain.
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+  let coords = vec2<i32>(position.xy);
+  // SAMPLE_COUNT is filled in by renderer.rs, not always the 4 it asks for.
+  // textureNumSamples would do, but GL ES 3.0 and WebGL2 don't have it.
+  var sum = vec4(0.0);
+  for (var i = 0; i < SAMPLE_COUNT; i++) {
+    let texel = textureLoad(msaa_texture, coords, i);
+    sum += vec4(tonemap(texel.rgb), texel.a);
+  }
+  let average = sum / f32(SAMPLE_COUNT);
+
+  return vec4(inverse_tonemap(average.rgb), average.a);
+}
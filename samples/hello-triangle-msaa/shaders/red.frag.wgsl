@@ -0,0 +1,6 @@
+@fragment
+fn main() -> @location(0) vec4<f32> {
+  // Deliberately brighter than 1.0 so the difference between resolving
+  // before and after tonemapping is visible on the triangle's edges
+  return vec4(8.0, 8.0, 0.0, 1.0);
+}
@@ -0,0 +1,12 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+  let color = textureLoad(source, vec2<i32>(position.xy), 0);
+
+  // Same curve as the manual resolve so both modes end up comparable
+  let mapped = color.rgb / (1.0 + max(color.r, max(color.g, color.b)));
+
+  return vec4(mapped, 1.0);
+}
@@ -0,0 +1,14 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@fragment
+fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+  let size = vec2<f32>(textureDimensions(source));
+  let uv = position.xy / size;
+  let color = textureLoad(source, vec2<i32>(position.xy), 0);
+
+  let offset = uv - vec2(0.5);
+  let falloff = 1.0 - dot(offset, offset) * 1.5;
+
+  return vec4(color.rgb * falloff, color.a);
+}
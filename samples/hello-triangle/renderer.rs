@@ -1,43 +1,13 @@
-use wgpu::{InstanceDescriptor, Instance, include_wgsl};
-use winit::window::Window;
+use framework::{Gpu, Sample};
+use wgpu::include_wgsl;
 
 pub struct Renderer {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
     render_pipeline: wgpu::RenderPipeline,
 }
 
-impl Renderer {
-    pub async fn new(window: &Window) -> Self {
-        let instance = Instance::new(InstanceDescriptor::default());
-        
-        let surface = unsafe { instance.create_surface(window) }.unwrap();
-
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference:
-                    wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter.request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    // WebGL doesn't support all of wgpu's features, so if
-                    // we're building for the web we'll have to disable some.
-                    limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
-                    label: None,
-                },
-                None, // Trace path
-            ).await.unwrap();
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
 
         let vertex_shader = device.create_shader_module(
             include_wgsl!("shaders/triangle.vert.wgsl"),
@@ -56,9 +26,6 @@ impl Renderer {
                 },
             );
 
-        let size =  window.inner_size();
-        let surface_config = surface.get_default_config(&adapter, size.width, size.height).unwrap();
-
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&render_pipeline_layout),
@@ -71,7 +38,7 @@ impl Renderer {
                 module: &fragment_shader,
                 entry_point: "main",
                 targets: &[Some(wgpu::ColorTargetState { // 4.
-                    format: surface_config.format,
+                    format: gpu.config.format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -97,23 +64,14 @@ impl Renderer {
             multiview: None, // 5.
         });
 
-        surface.configure(&device, &surface_config);
-
         Self {
-            surface,
-            device,
-            queue,
-            render_pipeline
+            render_pipeline,
         }
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output.texture.create_view(
-            &wgpu::TextureViewDescriptor::default(),
-        );
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
         let mut encoder =
-            self.device.create_command_encoder(
+            gpu.device.create_command_encoder(
                 &wgpu::CommandEncoderDescriptor {
                     label: Some("Render Encoder"),
                 },
@@ -124,7 +82,7 @@ impl Renderer {
                     label: Some("Render Pass"),
                     color_attachments: &[Some(
                         wgpu::RenderPassColorAttachment {
-                            view: &view,
+                            view,
                             resolve_target: None,
                             ops: wgpu::Operations {
                                 load: wgpu::LoadOp::Clear(
@@ -143,10 +101,7 @@ impl Renderer {
         }
     
         // submit will accept anything that implements IntoIter
-        self.queue
+        gpu.queue
             .submit(std::iter::once(encoder.finish()));
-        output.present();
-
-        Ok(())
     }
-}
\ No newline at end of file
+}
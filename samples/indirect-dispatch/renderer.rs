@@ -0,0 +1,464 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use glam::Vec2;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, ComputePipeline, Device, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+const PARTICLE_COUNT: u32 = 262144;
+const GROUP_SIZE: u32 = 64;
+const OCTAVES: u32 = 8;
+// Byte offset of the DrawIndirect in the workload buffer
+const DRAW_OFFSET: wgpu::BufferAddress = 16;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    glow: f32,
+    _padding: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SimParams {
+    spotlight: [f32; 2],
+    radius: f32,
+    delta_time: f32,
+    time: f32,
+    aspect: f32,
+    particle_count: u32,
+    octaves: u32,
+}
+
+// Written entirely by the GPU; the CPU only clears it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Workload {
+    dispatch: [u32; 3],
+    appended: u32,
+    draw: [u32; 4],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+}
+
+fn storage_entry(binding: u32, visibility: wgpu::ShaderStages, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &wgpu::BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// The visible count copied out for the title bar, never waited on; a
+// frame or two stale is fine there, and nothing else on the CPU needs it
+struct CountReadback {
+    buffer: Buffer,
+    pending: bool,
+    needs_map: bool,
+    mapped: Arc<AtomicBool>,
+    count: u32,
+}
+
+impl CountReadback {
+    fn new(device: &Device) -> Self {
+        Self {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Count Readback"),
+                size: std::mem::size_of::<Workload>() as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            count: 0,
+        }
+    }
+
+    fn request(&mut self, encoder: &mut wgpu::CommandEncoder, workload: &Buffer) {
+        if self.pending {
+            return;
+        }
+        encoder.copy_buffer_to_buffer(workload, 0, &self.buffer, 0, self.buffer.size());
+        self.pending = true;
+        self.needs_map = true;
+    }
+
+    // Call after submitting the encoder passed to request
+    fn map_submitted(&mut self) {
+        if !std::mem::take(&mut self.needs_map) {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            result.unwrap();
+            mapped.store(true, Ordering::Release);
+        });
+    }
+
+    fn poll(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+        self.count = bytemuck::from_bytes::<Workload>(&self.buffer.slice(..).get_mapped_range()).appended;
+        self.buffer.unmap();
+        self.pending = false;
+    }
+}
+
+pub struct Renderer {
+    cull_pipeline: ComputePipeline,
+    prepare_pipeline: ComputePipeline,
+    detail_pipeline: ComputePipeline,
+    all_pipeline: RenderPipeline,
+    visible_pipeline: RenderPipeline,
+    params_buffer: Buffer,
+    workload_buffer: Buffer,
+    cull_bind_group: BindGroup,
+    detail_bind_group: BindGroup,
+    render_bind_group: BindGroup,
+    readback: CountReadback,
+    // Window pixels, None while the cursor is outside the window
+    cursor: Option<Vec2>,
+    radius: f32,
+    // Dispatches cs_detail over every particle instead, for comparison
+    worst_case: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    start: Instant,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let downlevel = gpu.adapter.get_downlevel_capabilities();
+        assert!(
+            downlevel.flags.contains(wgpu::DownlevelFlags::COMPUTE_SHADERS | wgpu::DownlevelFlags::INDIRECT_EXECUTION),
+            "indirect-dispatch needs compute shaders and indirect execution, which this adapter doesn't support",
+        );
+
+        let mut rng = Rng(0x9e37_79b9);
+        let particles: Vec<Particle> = (0..PARTICLE_COUNT)
+            .map(|_| Particle {
+                position: [rng.next_f32() * 2.0 - 1.0, rng.next_f32() * 2.0 - 1.0],
+                velocity: [(rng.next_f32() - 0.5) * 0.02, (rng.next_f32() - 0.5) * 0.02],
+                glow: 0.0,
+                _padding: 0.0,
+            })
+            .collect();
+        let particle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particles"),
+            contents: bytemuck::cast_slice(&particles),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        // The count, then room for every particle's index
+        let visible_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Visible"),
+            size: (PARTICLE_COUNT as wgpu::BufferAddress + 1) * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let workload_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Workload"),
+            size: std::mem::size_of::<Workload>() as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sim Params"),
+            contents: bytemuck::bytes_of(&SimParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute = wgpu::ShaderStages::COMPUTE;
+        let cull_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cull Layout"),
+            entries: &[
+                uniform_entry(compute),
+                storage_entry(1, compute, false),
+                storage_entry(2, compute, false),
+                storage_entry(3, compute, false),
+            ],
+        });
+        // No workload here: a buffer can't be bound for writing while a
+        // dispatch reads its arguments from it
+        let detail_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Detail Layout"),
+            entries: &[
+                uniform_entry(compute),
+                storage_entry(1, compute, false),
+                storage_entry(2, compute, true),
+            ],
+        });
+        let vertex = wgpu::ShaderStages::VERTEX;
+        let render_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Render Layout"),
+            entries: &[uniform_entry(vertex), storage_entry(1, vertex, true), storage_entry(2, vertex, true)],
+        });
+
+        let bind_group = |label: &str, layout: &wgpu::BindGroupLayout, buffers: &[&Buffer]| {
+            let entries: Vec<wgpu::BindGroupEntry> = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &entries,
+            })
+        };
+        let cull_bind_group = bind_group("Cull Bind Group", &cull_layout, &[&params_buffer, &particle_buffer, &visible_buffer, &workload_buffer]);
+        let detail_bind_group = bind_group("Detail Bind Group", &detail_layout, &[&params_buffer, &particle_buffer, &visible_buffer]);
+        let render_bind_group = bind_group("Render Bind Group", &render_layout, &[&params_buffer, &particle_buffer, &visible_buffer]);
+
+        let simulate_shader = device.create_shader_module(include_wgsl!("shaders/simulate.wgsl"));
+        let detail_shader = device.create_shader_module(include_wgsl!("shaders/detail.wgsl"));
+        let particle_shader = device.create_shader_module(include_wgsl!("shaders/particles.wgsl"));
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&render_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = |entry_point: &str| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &particle_shader,
+                    entry_point,
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &particle_shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.config.format,
+                        // Additive, so dense clusters glow
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent {
+                                src_factor: wgpu::BlendFactor::One,
+                                dst_factor: wgpu::BlendFactor::One,
+                                operation: wgpu::BlendOperation::Add,
+                            },
+                            alpha: wgpu::BlendComponent::OVER,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        Self {
+            cull_pipeline: compute_pipeline(device, "Cull", &cull_layout, &simulate_shader, "cs_cull"),
+            prepare_pipeline: compute_pipeline(device, "Prepare", &cull_layout, &simulate_shader, "cs_prepare"),
+            detail_pipeline: compute_pipeline(device, "Detail", &detail_layout, &detail_shader, "cs_detail"),
+            all_pipeline: render_pipeline("vs_all"),
+            visible_pipeline: render_pipeline("vs_visible"),
+            params_buffer,
+            workload_buffer,
+            cull_bind_group,
+            detail_bind_group,
+            render_bind_group,
+            readback: CountReadback::new(device),
+            cursor: None,
+            radius: 0.25,
+            worst_case: false,
+            held_keys: HashSet::new(),
+            start: Instant::now(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some(Vec2::new(position.x as f32, position.y as f32));
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                if *state == ElementState::Released {
+                    self.held_keys.remove(key);
+                    return;
+                }
+                // Key repeat keeps sending presses, only the first one toggles
+                if !self.held_keys.insert(*key) {
+                    return;
+                }
+                match key {
+                    VirtualKeyCode::Up => self.radius = (self.radius * 1.25).min(1.5),
+                    VirtualKeyCode::Down => self.radius = (self.radius * 0.8).max(0.02),
+                    VirtualKeyCode::Tab => self.worst_case = !self.worst_case,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} of {} particles in the spotlight, {} dispatch, Up/Down resizes, Tab switches",
+            self.readback.count,
+            PARTICLE_COUNT,
+            if self.worst_case { "worst case" } else { "indirect" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.readback.poll(&gpu.device);
+
+        let now = Instant::now();
+        // Clamped so a long hitch (e.g. dragging the window) doesn't blow
+        // the simulation apart
+        let delta_time = (now - self.last_frame).as_secs_f32().min(1.0 / 30.0);
+        self.last_frame = now;
+        let time = (now - self.start).as_secs_f32();
+
+        // Follows the cursor, or wanders on its own without one
+        let spotlight = match self.cursor {
+            Some(cursor) => {
+                let ndc = cursor / Vec2::new(gpu.config.width as f32, gpu.config.height as f32) * 2.0 - 1.0;
+                [ndc.x, -ndc.y]
+            }
+            None => [(time * 0.3).cos() * 0.5, (time * 0.4).sin() * 0.5],
+        };
+        gpu.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&SimParams {
+                spotlight,
+                radius: self.radius,
+                delta_time,
+                time,
+                aspect: gpu.aspect_ratio(),
+                particle_count: PARTICLE_COUNT,
+                octaves: OCTAVES,
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        encoder.clear_buffer(&self.workload_buffer, 0, None);
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Simulation Pass"),
+            });
+            compute_pass.set_pipeline(&self.cull_pipeline);
+            compute_pass.set_bind_group(0, &self.cull_bind_group, &[]);
+            compute_pass.dispatch_workgroups((PARTICLE_COUNT + GROUP_SIZE - 1) / GROUP_SIZE, 1, 1);
+            compute_pass.set_pipeline(&self.prepare_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+
+            compute_pass.set_pipeline(&self.detail_pipeline);
+            compute_pass.set_bind_group(0, &self.detail_bind_group, &[]);
+            if self.worst_case {
+                // What it takes without the count on the GPU: enough
+                // workgroups for every particle, nearly all of them
+                // returning straight away
+                compute_pass.dispatch_workgroups((PARTICLE_COUNT + GROUP_SIZE - 1) / GROUP_SIZE, 1, 1);
+            } else {
+                compute_pass.dispatch_workgroups_indirect(&self.workload_buffer, 0);
+            }
+        }
+        self.readback.request(&mut encoder, &self.workload_buffer);
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_bind_group(0, &self.render_bind_group, &[]);
+            render_pass.set_pipeline(&self.all_pipeline);
+            render_pass.draw(0..6, 0..PARTICLE_COUNT);
+            render_pass.set_pipeline(&self.visible_pipeline);
+            render_pass.draw_indirect(&self.workload_buffer, DRAW_OFFSET);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.readback.map_submitted();
+    }
+}
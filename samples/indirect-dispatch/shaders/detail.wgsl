@@ -0,0 +1,73 @@
+// The expensive part: a curl noise flow field summed over several octaves,
+// run only for the particles cs_cull listed
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    glow: f32,
+    _padding: f32,
+}
+
+struct SimParams {
+    spotlight: vec2<f32>,
+    radius: f32,
+    delta_time: f32,
+    time: f32,
+    aspect: f32,
+    particle_count: u32,
+    octaves: u32,
+}
+
+struct Visible {
+    count: u32,
+    indices: array<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: SimParams;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read> visible: Visible;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let t = f * f * (3.0 - 2.0 * f);
+    let bottom = mix(hash(cell), hash(cell + vec2<f32>(1.0, 0.0)), t.x);
+    let top = mix(hash(cell + vec2<f32>(0.0, 1.0)), hash(cell + vec2<f32>(1.0, 1.0)), t.x);
+    return mix(bottom, top, t.y);
+}
+
+fn potential(p: vec2<f32>) -> f32 {
+    var total = 0.0;
+    var amplitude = 0.5;
+    var frequency = 3.0;
+    for (var octave = 0u; octave < params.octaves; octave++) {
+        total += value_noise(p * frequency + params.time * 0.3) * amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+    return total;
+}
+
+@compute @workgroup_size(64)
+fn cs_detail(@builtin(global_invocation_id) id: vec3<u32>) {
+    // Only the worst case dispatch gets here past the end; the indirect
+    // one is at most a workgroup over
+    if id.x >= visible.count {
+        return;
+    }
+    let index = visible.indices[id.x];
+    var particle = particles[index];
+
+    // Divergence free, so the particles swirl rather than bunch up
+    let e = 0.002;
+    let dx = potential(particle.position + vec2<f32>(e, 0.0)) - potential(particle.position - vec2<f32>(e, 0.0));
+    let dy = potential(particle.position + vec2<f32>(0.0, e)) - potential(particle.position - vec2<f32>(0.0, e));
+    let curl = vec2<f32>(dy, -dx) / (2.0 * e);
+    particle.velocity += curl * 0.6 * params.delta_time;
+    particle.glow = 1.0;
+    particles[index] = particle;
+}
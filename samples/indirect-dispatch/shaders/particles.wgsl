@@ -0,0 +1,72 @@
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    glow: f32,
+    _padding: f32,
+}
+
+struct SimParams {
+    spotlight: vec2<f32>,
+    radius: f32,
+    delta_time: f32,
+    time: f32,
+    aspect: f32,
+    particle_count: u32,
+    octaves: u32,
+}
+
+struct Visible {
+    count: u32,
+    indices: array<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: SimParams;
+@group(0) @binding(1) var<storage, read> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read> visible: Visible;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+const PARTICLE_SIZE: f32 = 0.003;
+
+fn quad(vertex_index: u32, particle: Particle, size: f32) -> vec4<f32> {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0)
+    );
+    // Keep the quads square on wide windows
+    let corner = corners[vertex_index] * size * vec2<f32>(1.0 / params.aspect, 1.0);
+    return vec4<f32>(particle.position + corner, 0.0, 1.0);
+}
+
+// Every particle, dim blue cooling from orange after the flow field
+// last touched it
+@vertex
+fn vs_all(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let particle = particles[instance];
+    var out: VertexOutput;
+    out.clip_position = quad(vertex_index, particle, PARTICLE_SIZE);
+    out.color = mix(vec3<f32>(0.05, 0.1, 0.4), vec3<f32>(1.0, 0.45, 0.1), particle.glow) * 0.25;
+    return out;
+}
+
+// Only the listed ones, drawn indirectly with the count cs_prepare wrote
+@vertex
+fn vs_visible(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let particle = particles[visible.indices[instance]];
+    var out: VertexOutput;
+    out.clip_position = quad(vertex_index, particle, PARTICLE_SIZE * 0.6);
+    out.color = vec3<f32>(0.3, 0.25, 0.15);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
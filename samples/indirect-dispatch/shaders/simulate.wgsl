@@ -0,0 +1,85 @@
+// Three passes a frame. cs_cull moves every particle and appends the ones
+// in the spotlight to a list, cs_prepare turns the list's length into
+// indirect dispatch and draw arguments, and cs_detail runs the expensive
+// flow field over only the listed particles, dispatched with those
+// arguments. The CPU never needs to know how many there were.
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    glow: f32,
+    _padding: f32,
+}
+
+struct SimParams {
+    spotlight: vec2<f32>,
+    radius: f32,
+    delta_time: f32,
+    time: f32,
+    aspect: f32,
+    particle_count: u32,
+    octaves: u32,
+}
+
+// A DispatchIndirect, the running count, then a DrawIndirect, in the
+// layout wgpu reads them in
+struct Workload {
+    dispatch_x: u32,
+    dispatch_y: u32,
+    dispatch_z: u32,
+    appended: atomic<u32>,
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+// The count is copied here for cs_detail, which can't bind the workload
+// while dispatching from it
+struct Visible {
+    count: u32,
+    indices: array<u32>,
+}
+
+const GROUP_SIZE: u32 = 64u;
+
+@group(0) @binding(0) var<uniform> params: SimParams;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> visible: Visible;
+@group(0) @binding(3) var<storage, read_write> workload: Workload;
+
+@compute @workgroup_size(64)
+fn cs_cull(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= params.particle_count {
+        return;
+    }
+    var particle = particles[index];
+
+    // A slow swirl around the middle everywhere, the flow field only
+    // where the spotlight is
+    particle.velocity += vec2<f32>(-particle.position.y, particle.position.x) * 0.05 * params.delta_time;
+    particle.velocity *= 1.0 - 0.5 * params.delta_time;
+    particle.position += particle.velocity * params.delta_time;
+    particle.position = fract((particle.position + 1.0) * 0.5) * 2.0 - 1.0;
+    particle.glow = max(particle.glow - params.delta_time * 1.5, 0.0);
+    particles[index] = particle;
+
+    let offset = (particle.position - params.spotlight) * vec2<f32>(params.aspect, 1.0);
+    if dot(offset, offset) < params.radius * params.radius {
+        visible.indices[atomicAdd(&workload.appended, 1u)] = index;
+    }
+}
+
+@compute @workgroup_size(1)
+fn cs_prepare() {
+    let count = atomicLoad(&workload.appended);
+    workload.dispatch_x = (count + GROUP_SIZE - 1u) / GROUP_SIZE;
+    workload.dispatch_y = 1u;
+    workload.dispatch_z = 1u;
+    workload.vertex_count = 6u;
+    workload.instance_count = count;
+    workload.first_vertex = 0u;
+    workload.first_instance = 0u;
+    visible.count = count;
+}
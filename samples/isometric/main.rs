@@ -0,0 +1,8 @@
+mod map;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("isometric");
+}
@@ -0,0 +1,131 @@
+use glam::Vec2;
+
+pub const SIZE: usize = 48;
+pub const MAX_ELEVATION: u8 = 6;
+
+// Screen pixels, at zoom 1
+pub const TILE_WIDTH: f32 = 64.0;
+pub const TILE_HEIGHT: f32 = 32.0;
+// How much a column grows per elevation layer
+pub const LAYER_HEIGHT: f32 = 16.0;
+// The dirt under layer 0, so even the lowest tiles have sides
+pub const BASE_HEIGHT: f32 = 12.0;
+
+// The top face center of a point on the map at an elevation. x runs down
+// and to the right on screen, y down and to the left.
+pub fn to_screen(x: f32, y: f32, elevation: f32) -> Vec2 {
+    Vec2::new((x - y) * TILE_WIDTH * 0.5, (x + y) * TILE_HEIGHT * 0.5 - elevation * LAYER_HEIGHT)
+}
+
+pub fn side_height(elevation: u8) -> f32 {
+    BASE_HEIGHT + elevation as f32 * LAYER_HEIGHT
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Face {
+    Top,
+    Left,
+    Right,
+}
+
+impl Face {
+    pub fn name(self) -> &'static str {
+        match self {
+            Face::Top => "top",
+            Face::Left => "left side",
+            Face::Right => "right side",
+        }
+    }
+}
+
+// Which face of a column a point hits, given in pixels from the top left
+// of the column's rectangle. The same shape the shader draws.
+pub fn column_face(local: Vec2, side_height: f32) -> Option<Face> {
+    let half_width = TILE_WIDTH * 0.5;
+    let half_height = TILE_HEIGHT * 0.5;
+    if local.x < 0.0 || local.x > TILE_WIDTH {
+        return None;
+    }
+    if (local.x - half_width).abs() / half_width + (local.y - half_height).abs() / half_height <= 1.0 {
+        return Some(Face::Top);
+    }
+    // The sides hang off the diamond's two lower edges
+    let (edge, face) = if local.x < half_width {
+        (half_height + local.x * half_height / half_width, Face::Left)
+    } else {
+        (TILE_HEIGHT - (local.x - half_width) * half_height / half_width, Face::Right)
+    };
+    if local.y >= edge && local.y <= edge + side_height {
+        Some(face)
+    } else {
+        None
+    }
+}
+
+pub struct Map {
+    // Row by row, y major
+    pub elevations: Vec<u8>,
+    // Tiles with a tree on them
+    pub trees: Vec<[usize; 2]>,
+}
+
+impl Map {
+    // Rolling hills out of a few sines, a lake where they dip lowest
+    pub fn generate() -> Self {
+        let mut elevations = Vec::with_capacity(SIZE * SIZE);
+        let mut trees = Vec::new();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let (fx, fy) = (x as f32, y as f32);
+                let height = (fx * 0.21).sin() * 1.4 + (fy * 0.17 + 1.0).sin() * 1.6 + ((fx + fy) * 0.09).cos() * 1.2 + 2.2;
+                let elevation = height.round().clamp(0.0, MAX_ELEVATION as f32) as u8;
+                elevations.push(elevation);
+                // Scattered on the grass, by a hash rather than an Rng so
+                // they don't shift when the terrain formula is tweaked
+                let hash = (x as u32).wrapping_mul(73856093) ^ (y as u32).wrapping_mul(19349663);
+                if (2..=3).contains(&elevation) && hash % 100 < 12 {
+                    trees.push([x, y]);
+                }
+            }
+        }
+        Self { elevations, trees }
+    }
+
+    pub fn elevation(&self, x: usize, y: usize) -> u8 {
+        self.elevations[y * SIZE + x]
+    }
+
+    pub fn set_elevation(&mut self, x: usize, y: usize, elevation: u8) {
+        self.elevations[y * SIZE + x] = elevation.min(MAX_ELEVATION);
+    }
+
+    // The top left of a column's rectangle and its size in pixels
+    pub fn column_rect(&self, x: usize, y: usize) -> [f32; 4] {
+        let elevation = self.elevation(x, y);
+        let center = to_screen(x as f32 + 0.5, y as f32 + 0.5, elevation as f32);
+        [
+            center.x - TILE_WIDTH * 0.5,
+            center.y - TILE_HEIGHT * 0.5,
+            TILE_WIDTH,
+            TILE_HEIGHT + side_height(elevation),
+        ]
+    }
+
+    // The tile under a point in screen pixels at zoom 1, and which face
+    // of it. Columns nearer the viewer cover the ones behind them, so
+    // they're tested first, the same order the painter's algorithm draws
+    // them in reverse.
+    pub fn pick(&self, point: Vec2) -> Option<([usize; 2], Face)> {
+        for diagonal in (0..SIZE * 2 - 1).rev() {
+            for x in diagonal.saturating_sub(SIZE - 1)..=diagonal.min(SIZE - 1) {
+                let y = diagonal - x;
+                let rect = self.column_rect(x, y);
+                let local = point - Vec2::new(rect[0], rect[1]);
+                if let Some(face) = column_face(local, side_height(self.elevation(x, y))) {
+                    return Some(([x, y], face));
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,471 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use glam::Vec2;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+use crate::map::{self, Face, Map, MAX_ELEVATION, SIZE, TILE_HEIGHT, TILE_WIDTH};
+
+const WALKERS: usize = 40;
+// Tiles per second
+const WALK_SPEED: f32 = 1.2;
+// Screen pixels per second at zoom 1
+const PAN_SPEED: f32 = 600.0;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+// Every sprite and column there could be, with room for the highlight
+const MAX_INSTANCES: usize = SIZE * SIZE * 2 + WALKERS + 1;
+
+const KIND_COLUMN: f32 = 0.0;
+const KIND_HIGHLIGHT: f32 = 1.0;
+const KIND_TREE: f32 = 2.0;
+const KIND_WALKER: f32 = 3.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Instance {
+    rect: [f32; 4],
+    params: [f32; 4],
+    color: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    center: [f32; 2],
+    zoom: f32,
+    time: f32,
+    screen_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+// Where an instance goes in the painter's order: further back diagonals
+// first, and on one tile its column, then the highlight, then whatever
+// stands on it, nearest last
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+struct DrawOrder {
+    diagonal: usize,
+    layer: u8,
+    depth: f32,
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+// Someone wandering from tile to tile, never into water or up a step
+// higher than one layer
+struct Walker {
+    from: [usize; 2],
+    to: [usize; 2],
+    // 0 to 1 from one tile's center to the next
+    progress: f32,
+    color: [f32; 4],
+    seed: f32,
+}
+
+impl Walker {
+    fn position(&self) -> Vec2 {
+        let from = Vec2::new(self.from[0] as f32, self.from[1] as f32);
+        let to = Vec2::new(self.to[0] as f32, self.to[1] as f32);
+        from.lerp(to, self.progress) + 0.5
+    }
+
+    fn elevation(&self, map: &Map) -> f32 {
+        let from = map.elevation(self.from[0], self.from[1]) as f32;
+        let to = map.elevation(self.to[0], self.to[1]) as f32;
+        from + (to - from) * self.progress
+    }
+
+    fn pick_next(&mut self, map: &Map, rng: &mut Rng) {
+        self.from = self.to;
+        self.progress = 0.0;
+        let [x, y] = self.from;
+        let here = map.elevation(x, y);
+        let neighbours = [[x.wrapping_sub(1), y], [x + 1, y], [x, y.wrapping_sub(1)], [x, y + 1]];
+        let walkable: Vec<[usize; 2]> = neighbours
+            .into_iter()
+            .filter(|&[nx, ny]| nx < SIZE && ny < SIZE)
+            .filter(|&[nx, ny]| {
+                let there = map.elevation(nx, ny);
+                there > 0 && there.abs_diff(here) <= 1
+            })
+            .collect();
+        // Stuck, say by an edit raising everything around; stay put
+        if !walkable.is_empty() {
+            self.to = walkable[(rng.next_f32() * walkable.len() as f32) as usize % walkable.len()];
+        }
+    }
+}
+
+fn elevation_color(elevation: u8) -> [f32; 4] {
+    match elevation {
+        0 => [0.2, 0.45, 0.8, 1.0],
+        1 => [0.85, 0.78, 0.5, 1.0],
+        2 => [0.35, 0.65, 0.3, 1.0],
+        3 => [0.28, 0.55, 0.25, 1.0],
+        4 => [0.5, 0.48, 0.45, 1.0],
+        _ => [0.92, 0.94, 0.98, 1.0],
+    }
+}
+
+pub struct Renderer {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    instance_buffer: Buffer,
+    map: Map,
+    walkers: Vec<Walker>,
+    rng: Rng,
+    // Screen pixels at zoom 1 of the window's center
+    center: Vec2,
+    zoom: f32,
+    screen_size: Vec2,
+    cursor: Option<Vec2>,
+    hovered: Option<([usize; 2], Face)>,
+    held_keys: HashSet<VirtualKeyCode>,
+    start: Instant,
+    last_frame: Instant,
+}
+
+impl Renderer {
+    // From window pixels to the map's screen pixels at zoom 1
+    fn unproject(&self, cursor: Vec2) -> Vec2 {
+        self.center + (cursor - self.screen_size * 0.5) / self.zoom
+    }
+
+    fn edit(&mut self, raise: bool) {
+        if let Some(([x, y], _)) = self.hovered {
+            let elevation = self.map.elevation(x, y);
+            let elevation = if raise { (elevation + 1).min(MAX_ELEVATION) } else { elevation.saturating_sub(1) };
+            self.map.set_elevation(x, y, elevation);
+        }
+    }
+
+    // Everything, sorted back to front. Rebuilt every frame since walkers
+    // move between diagonals and edits change columns' sizes.
+    fn instances(&self) -> Vec<Instance> {
+        let mut sorted = Vec::with_capacity(MAX_INSTANCES);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let elevation = self.map.elevation(x, y);
+                sorted.push((
+                    DrawOrder { diagonal: x + y, layer: 0, depth: 0.0 },
+                    Instance {
+                        rect: self.map.column_rect(x, y),
+                        params: [KIND_COLUMN, map::side_height(elevation), elevation as f32, 0.0],
+                        color: elevation_color(elevation),
+                    },
+                ));
+            }
+        }
+        if let Some(([x, y], face)) = self.hovered {
+            let top = self.map.column_rect(x, y);
+            sorted.push((
+                DrawOrder { diagonal: x + y, layer: 1, depth: 0.0 },
+                Instance {
+                    rect: [top[0], top[1], TILE_WIDTH, TILE_HEIGHT],
+                    params: [KIND_HIGHLIGHT, 0.0, 0.0, 0.0],
+                    // Hovering a side still edits the column, shown in a
+                    // different color
+                    color: if face == Face::Top { [1.0, 0.9, 0.2, 1.0] } else { [1.0, 0.5, 0.2, 1.0] },
+                },
+            ));
+        }
+        for (index, &[x, y]) in self.map.trees.iter().enumerate() {
+            let anchor = map::to_screen(x as f32 + 0.5, y as f32 + 0.5, self.map.elevation(x, y) as f32);
+            sorted.push((
+                DrawOrder { diagonal: x + y, layer: 2, depth: 0.0 },
+                Instance {
+                    rect: [anchor.x - 20.0, anchor.y - 60.0, 40.0, 64.0],
+                    params: [KIND_TREE, 0.0, 0.0, index as f32],
+                    color: [0.2, 0.55, 0.25, 1.0],
+                },
+            ));
+        }
+        for walker in &self.walkers {
+            let position = walker.position();
+            let anchor = map::to_screen(position.x, position.y, walker.elevation(&self.map));
+            // Sorted with the tile further in front of the two it's
+            // between, so it isn't hidden by the column it steps onto
+            let diagonal = (walker.from[0] + walker.from[1]).max(walker.to[0] + walker.to[1]);
+            sorted.push((
+                DrawOrder { diagonal, layer: 2, depth: position.x + position.y },
+                Instance {
+                    rect: [anchor.x - 10.0, anchor.y - 30.0, 20.0, 32.0],
+                    params: [KIND_WALKER, 0.0, 0.0, walker.seed],
+                    color: walker.color,
+                },
+            ));
+        }
+        sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        sorted.into_iter().map(|(_, instance)| instance).collect()
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(include_wgsl!("shaders/iso.wgsl"));
+
+        let map = Map::generate();
+        let mut rng = Rng(0x9e3779b9);
+        let mut walkers = Vec::with_capacity(WALKERS);
+        while walkers.len() < WALKERS {
+            let tile = [(rng.next_f32() * SIZE as f32) as usize % SIZE, (rng.next_f32() * SIZE as f32) as usize % SIZE];
+            if map.elevation(tile[0], tile[1]) == 0 {
+                continue;
+            }
+            let mut walker = Walker {
+                from: tile,
+                to: tile,
+                progress: 0.0,
+                color: [rng.range(0.2, 1.0), rng.range(0.2, 1.0), rng.range(0.2, 1.0), 1.0],
+                seed: rng.range(0.0, 100.0),
+            };
+            walker.pick_next(&map, &mut rng);
+            walkers.push(walker);
+        }
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprites"),
+            size: (MAX_INSTANCES * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        // No depth buffer: the instances arrive in the order they have to
+        // be painted in
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Instance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            instance_buffer,
+            map,
+            walkers,
+            rng,
+            center: map::to_screen(SIZE as f32 * 0.5, SIZE as f32 * 0.5, 0.0),
+            zoom: 1.0,
+            screen_size: Vec2::new(gpu.config.width as f32, gpu.config.height as f32),
+            cursor: None,
+            hovered: None,
+            held_keys: HashSet::new(),
+            start: Instant::now(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.screen_size = Vec2::new(gpu.config.width as f32, gpu.config.height as f32);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                if *state == ElementState::Pressed {
+                    self.held_keys.insert(*key);
+                } else {
+                    self.held_keys.remove(key);
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some(Vec2::new(position.x as f32, position.y as f32));
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => match button {
+                MouseButton::Left => self.edit(true),
+                MouseButton::Right => self.edit(false),
+                _ => {}
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                };
+                self.zoom = (self.zoom * 1.1f32.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+            }
+            _ => {}
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(match self.hovered {
+            Some(([x, y], face)) => format!(
+                "tile ({}, {}) elevation {}, {}, click to raise, right click to lower",
+                x,
+                y,
+                self.map.elevation(x, y),
+                face.name(),
+            ),
+            None => format!("{} walkers, zoom {:.1}", self.walkers.len(), self.zoom),
+        })
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut pan = Vec2::ZERO;
+        if held(VirtualKeyCode::W) { pan.y -= 1.0; }
+        if held(VirtualKeyCode::S) { pan.y += 1.0; }
+        if held(VirtualKeyCode::D) { pan.x += 1.0; }
+        if held(VirtualKeyCode::A) { pan.x -= 1.0; }
+        self.center += pan * PAN_SPEED * dt / self.zoom;
+
+        for walker in &mut self.walkers {
+            walker.progress += WALK_SPEED * dt;
+            if walker.progress >= 1.0 {
+                walker.pick_next(&self.map, &mut self.rng);
+            }
+        }
+        self.hovered = self.cursor.and_then(|cursor| self.map.pick(self.unproject(cursor)));
+
+        let instances = self.instances();
+        gpu.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                center: self.center.to_array(),
+                zoom: self.zoom,
+                time: self.start.elapsed().as_secs_f32(),
+                screen_size: self.screen_size.to_array(),
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.05,
+                                    g: 0.07,
+                                    b: 0.1,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+            render_pass.draw(0..6, 0..instances.len() as u32);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
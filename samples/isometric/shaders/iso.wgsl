@@ -0,0 +1,165 @@
+struct Uniforms {
+    // Screen pixels at zoom 1, of the window's center
+    center: vec2<f32>,
+    zoom: f32,
+    time: f32,
+    screen_size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct InstanceInput {
+    // Top left and size, in screen pixels at zoom 1
+    @location(0) rect: vec4<f32>,
+    // x kind, y side height for columns, z elevation, w a per sprite seed
+    @location(1) params: vec4<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Pixels from the rectangle's top left
+    @location(0) local: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) params: vec4<f32>,
+    @location(3) color: vec4<f32>,
+}
+
+const KIND_COLUMN: i32 = 0;
+const KIND_HIGHLIGHT: i32 = 1;
+const KIND_TREE: i32 = 2;
+const KIND_WALKER: i32 = 3;
+
+// What the kinds below return outside their shape, for fs_main to discard
+const CLEAR: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+
+const TILE_WIDTH: f32 = 64.0;
+const TILE_HEIGHT: f32 = 32.0;
+const LAYER_HEIGHT: f32 = 16.0;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0)
+    );
+    let local = corners[index] * instance.rect.zw;
+    let pixel = (instance.rect.xy + local - uniforms.center) * uniforms.zoom;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(pixel.x / uniforms.screen_size.x * 2.0, -pixel.y / uniforms.screen_size.y * 2.0, 0.0, 1.0);
+    out.local = local;
+    out.size = instance.rect.zw;
+    out.params = instance.params;
+    out.color = instance.color;
+    return out;
+}
+
+// How far inside the top diamond, 1 at its center and 0 on its edge
+fn diamond(local: vec2<f32>) -> f32 {
+    let half = vec2<f32>(TILE_WIDTH, TILE_HEIGHT) * 0.5;
+    return 1.0 - abs(local.x - half.x) / half.x - abs(local.y - half.y) / half.y;
+}
+
+// Mirrors column_face in map.rs; picking hits exactly what's drawn
+fn column(in: VertexOutput) -> vec4<f32> {
+    let top = diamond(in.local);
+    let elevation = in.params.z;
+    if top >= 0.0 {
+        var color = in.color.rgb;
+        if elevation < 0.5 {
+            // Water ripples
+            color += vec3<f32>(0.05) * sin(in.local.x * 0.3 + in.local.y * 0.5 + uniforms.time * 2.0);
+        }
+        // A darker rim so neighbouring tiles read as separate
+        return vec4<f32>(color * select(1.0, 0.85, top < 0.06), 1.0);
+    }
+
+    let half = TILE_WIDTH * 0.5;
+    let left = in.local.x < half;
+    var edge: f32;
+    if left {
+        edge = TILE_HEIGHT * 0.5 + in.local.x * 0.5;
+    } else {
+        edge = TILE_HEIGHT - (in.local.x - half) * 0.5;
+    }
+    let depth = in.local.y - edge;
+    if depth < 0.0 || depth > in.params.y {
+        return CLEAR;
+    }
+    // Layers of earth, one band per elevation step, counted from the top
+    let band = floor(depth / LAYER_HEIGHT);
+    var earth = mix(vec3<f32>(0.45, 0.32, 0.2), vec3<f32>(0.35, 0.25, 0.17), f32(i32(band) & 1));
+    if depth < 3.0 {
+        earth = in.color.rgb * 0.8;
+    }
+    return vec4<f32>(earth * select(0.6, 0.8, left), 1.0);
+}
+
+fn highlight(in: VertexOutput) -> vec4<f32> {
+    let top = diamond(in.local);
+    if top < 0.0 || top > 0.1 {
+        return CLEAR;
+    }
+    let pulse = 0.8 + 0.2 * sin(uniforms.time * 6.0);
+    return vec4<f32>(in.color.rgb * pulse, 1.0);
+}
+
+fn tree(in: VertexOutput) -> vec4<f32> {
+    let p = in.local / in.size;
+    // Trunk, then two blobs of leaves swaying a little
+    if abs(p.x - 0.5) < 0.08 && p.y > 0.55 {
+        return vec4<f32>(0.4, 0.26, 0.14, 1.0);
+    }
+    let sway = sin(uniforms.time * 1.5 + in.params.w) * 0.02;
+    let lower = length((p - vec2<f32>(0.5 + sway, 0.5)) * vec2<f32>(1.0, 1.4));
+    let upper = length((p - vec2<f32>(0.5 + sway * 2.0, 0.28)) * vec2<f32>(1.3, 1.6));
+    if lower >= 0.42 && upper >= 0.3 {
+        return CLEAR;
+    }
+    let shade = select(0.8, 1.0, p.x < 0.5 + sway);
+    return vec4<f32>(in.color.rgb * shade, 1.0);
+}
+
+fn walker(in: VertexOutput) -> vec4<f32> {
+    let p = in.local / in.size;
+    let step = sin(uniforms.time * 10.0 + in.params.w);
+    // Head, body and two legs taking turns
+    if length((p - vec2<f32>(0.5, 0.18)) * vec2<f32>(1.0, 1.6)) < 0.2 {
+        return vec4<f32>(0.95, 0.8, 0.65, 1.0);
+    }
+    if abs(p.x - 0.5) < 0.3 && p.y > 0.32 && p.y < 0.72 {
+        return vec4<f32>(in.color.rgb, 1.0);
+    }
+    let leg = select(0.35, 0.65, p.x > 0.5);
+    let lift = select(step, -step, p.x > 0.5) * 0.05;
+    if abs(p.x - leg) >= 0.1 || p.y < 0.72 || p.y >= 0.98 - max(lift, 0.0) {
+        return CLEAR;
+    }
+    return vec4<f32>(0.2, 0.2, 0.3, 1.0);
+}
+
+// The only discard: naga's GLSL output also puts helper functions in the
+// vertex shader, where discard doesn't compile
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let kind = i32(in.params.x);
+    var color: vec4<f32>;
+    if kind == KIND_COLUMN {
+        color = column(in);
+    } else if kind == KIND_HIGHLIGHT {
+        color = highlight(in);
+    } else if kind == KIND_TREE {
+        color = tree(in);
+    } else {
+        color = walker(in);
+    }
+    if color.a == 0.0 {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,8 @@
+mod renderer;
+mod solver;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("jacobi");
+}
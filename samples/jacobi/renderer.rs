@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+
+use bytemuck::{Pod, Zeroable};
+use framework::snapshot::{Snapshot, SnapshotError};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::solver::{Solver, Timing, SIZE, STRATEGIES};
+
+// A multiple of 8, see Solver::record
+const FRAME_ITERATIONS: u32 = 64;
+const RANGE: f32 = 50.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Display {
+    size: u32,
+    _padding: u32,
+    offset: [f32; 2],
+    scale: f32,
+    range: f32,
+    _padding2: [f32; 2],
+}
+
+pub struct Renderer {
+    solver: Solver,
+    timings: Vec<Timing>,
+    pipeline: RenderPipeline,
+    display_buffer: Buffer,
+    bind_group: BindGroup,
+    strategy: usize,
+    paused: bool,
+    needs_reset: bool,
+    needs_benchmark: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let solver = Solver::new(device);
+
+        let display_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display"),
+            contents: bytemuck::bytes_of(&Display::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: display_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: solver.grids[0].as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            solver,
+            timings: Vec::new(),
+            pipeline,
+            display_buffer,
+            bind_group,
+            strategy: 0,
+            paused: false,
+            needs_reset: false,
+            needs_benchmark: true,
+            held_keys: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Tab => self.strategy = (self.strategy + 1) % STRATEGIES.len(),
+                VirtualKeyCode::R => self.needs_reset = true,
+                VirtualKeyCode::B => self.needs_benchmark = true,
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let timing = match self.timings.get(self.strategy) {
+            Some(timing) => format!(
+                ", benchmarked at {:.3}ms in {} dispatches, differs by {}",
+                timing.milliseconds, timing.dispatches, timing.difference,
+            ),
+            None => String::new(),
+        };
+        Some(format!(
+            "{}{}, iteration {}{}, Tab switches, B benchmarks",
+            STRATEGIES[self.strategy],
+            timing,
+            self.solver.iterations,
+            if self.paused { ", paused" } else { "" },
+        ))
+    }
+
+    // The solution is always back in grids[0] between frames
+    fn save_state(&self, gpu: &Gpu, snapshot: &mut Snapshot) {
+        snapshot.add_buffer(gpu, "grid", &self.solver.grids[0]);
+        snapshot.add_bytes("iterations", &self.solver.iterations.to_le_bytes());
+    }
+
+    fn load_state(&mut self, gpu: &Gpu, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        snapshot.restore_buffer(gpu, "grid", &self.solver.grids[0])?;
+        let iterations = snapshot.bytes("iterations")?;
+        let iterations: [u8; 4] = iterations
+            .try_into()
+            .map_err(|_| SnapshotError::Format("iterations isn't a u32".to_string()))?;
+        self.solver.iterations = u32::from_le_bytes(iterations);
+        // The startup benchmark would start the solve over, B still runs it
+        self.needs_benchmark = false;
+        Ok(())
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        // Blocks for a moment, and starts the solve over
+        if std::mem::take(&mut self.needs_benchmark) {
+            self.timings = self.solver.benchmark(gpu);
+        }
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        if std::mem::take(&mut self.needs_reset) {
+            self.solver.reset(&mut encoder);
+        }
+        if !self.paused {
+            self.solver.set_iterations(&gpu.queue, FRAME_ITERATIONS);
+            self.solver.record(&mut encoder, self.strategy, FRAME_ITERATIONS);
+        }
+
+        // The grid as big as fits, centered
+        let (width, height) = (gpu.config.width as f32, gpu.config.height as f32);
+        let scale = width.min(height) / SIZE as f32;
+        gpu.queue.write_buffer(
+            &self.display_buffer,
+            0,
+            bytemuck::bytes_of(&Display {
+                size: SIZE,
+                _padding: 0,
+                offset: [(width - SIZE as f32 * scale) * 0.5, (height - SIZE as f32 * scale) * 0.5],
+                scale,
+                range: RANGE,
+                _padding2: [0.0; 2],
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,40 @@
+struct Display {
+    size: u32,
+    _padding: u32,
+    // Window pixels
+    offset: vec2<f32>,
+    scale: f32,
+    // Solution values at full color
+    range: f32,
+    _padding2: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> display: Display;
+@group(0) @binding(1) var<storage, read> grid: array<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+// Blue below zero, red above, through black
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = (in.position.xy - display.offset) / display.scale;
+    let size = f32(display.size);
+    if any(cell < vec2<f32>(0.0)) || any(cell >= vec2<f32>(size)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let value = grid[u32(cell.y) * display.size + u32(cell.x)] / display.range;
+    let strength = 1.0 - exp(-abs(value) * 3.0);
+    let color = select(vec3<f32>(0.2, 0.45, 1.0), vec3<f32>(1.0, 0.35, 0.1), value > 0.0) * strength;
+    // Contour lines every tenth of the range
+    let line = smoothstep(0.9, 1.0, fract(abs(value) * 10.0)) * 0.15;
+    return vec4<f32>(pow(color + line, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,124 @@
+// Jacobi iterations for the Poisson equation on a square grid, with zero
+// beyond the edges: each cell becomes the mean of its four neighbours less
+// a quarter of its source term. Three ways of running them, all giving the
+// same numbers.
+
+struct Params {
+    size: u32,
+    iterations: u32,
+    _padding: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> source: array<f32>;
+@group(0) @binding(2) var<storage, read_write> grid_a: array<f32>;
+@group(0) @binding(3) var<storage, read_write> grid_b: array<f32>;
+
+// Cells per side of a blocked workgroup's output, the iterations it runs
+// per dispatch, and the halo those iterations eat into
+const TILE: i32 = 16;
+const BLOCKED_ITERATIONS: i32 = 4;
+const SPAN: i32 = 24;
+
+var<workgroup> tiles: array<f32, 1152>;
+
+fn inside(x: i32, y: i32) -> bool {
+    let size = i32(params.size);
+    return x >= 0 && y >= 0 && x < size && y < size;
+}
+
+fn from_a(x: i32, y: i32) -> f32 {
+    if inside(x, y) {
+        return grid_a[y * i32(params.size) + x];
+    }
+    return 0.0;
+}
+
+fn from_b(x: i32, y: i32) -> f32 {
+    if inside(x, y) {
+        return grid_b[y * i32(params.size) + x];
+    }
+    return 0.0;
+}
+
+// One iteration per dispatch, the CPU ping-ponging the two buffers
+@compute @workgroup_size(16, 16)
+fn cs_step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = i32(id.x);
+    let y = i32(id.y);
+    if !inside(x, y) {
+        return;
+    }
+    let sum = from_a(x - 1, y) + from_a(x + 1, y) + from_a(x, y - 1) + from_a(x, y + 1);
+    grid_b[y * i32(params.size) + x] = (sum - source[y * i32(params.size) + x]) * 0.25;
+}
+
+// Several iterations per dispatch: a workgroup loads its tile plus a halo
+// into shared memory and iterates there, every iteration leaving one more
+// ring of the halo wrong, until only the tile itself is still right.
+// Fewer dispatches for some redundant work.
+@compute @workgroup_size(16, 16)
+fn cs_blocked(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let origin = vec2<i32>(group.xy) * TILE - BLOCKED_ITERATIONS;
+    let cells = SPAN * SPAN;
+    for (var i = i32(local); i < cells; i += 256) {
+        tiles[i] = from_a(origin.x + i % SPAN, origin.y + i / SPAN);
+    }
+
+    var read_half = 0;
+    for (var iteration = 0; iteration < BLOCKED_ITERATIONS; iteration++) {
+        workgroupBarrier();
+        for (var i = i32(local); i < cells; i += 256) {
+            let tx = i % SPAN;
+            let ty = i / SPAN;
+            let x = origin.x + tx;
+            let y = origin.y + ty;
+            var value = 0.0;
+            // The outermost ring has no neighbours to read; it's never
+            // needed again anyway
+            let edge = tx == 0 || ty == 0 || tx == SPAN - 1 || ty == SPAN - 1;
+            if inside(x, y) && !edge {
+                let sum = tiles[read_half + i - 1] + tiles[read_half + i + 1] + tiles[read_half + i - SPAN] + tiles[read_half + i + SPAN];
+                value = (sum - source[y * i32(params.size) + x]) * 0.25;
+            }
+            tiles[cells - read_half + i] = value;
+        }
+        read_half = cells - read_half;
+    }
+    workgroupBarrier();
+
+    let tx = i32(local) % TILE + BLOCKED_ITERATIONS;
+    let ty = i32(local) / TILE + BLOCKED_ITERATIONS;
+    let x = origin.x + tx;
+    let y = origin.y + ty;
+    if inside(x, y) {
+        grid_b[y * i32(params.size) + x] = tiles[read_half + ty * SPAN + tx];
+    }
+}
+
+// Every iteration in one dispatch of one workgroup, which loops over the
+// whole grid each time. Inside a single workgroup a barrier is enough to
+// order the iterations, where across workgroups only the end of a dispatch
+// is. No dispatch overhead at all, but one workgroup's worth of the GPU.
+@compute @workgroup_size(256)
+fn cs_persistent(@builtin(local_invocation_index) local: u32) {
+    let size = i32(params.size);
+    let cells = size * size;
+    for (var iteration = 0u; iteration < params.iterations; iteration++) {
+        // Even iterations go from grid_a to grid_b, odd ones back again
+        let forward = (iteration & 1u) == 0u;
+        for (var i = i32(local); i < cells; i += 256) {
+            let x = i % size;
+            let y = i / size;
+            if forward {
+                let sum = from_a(x - 1, y) + from_a(x + 1, y) + from_a(x, y - 1) + from_a(x, y + 1);
+                grid_b[i] = (sum - source[i]) * 0.25;
+            } else {
+                let sum = from_b(x - 1, y) + from_b(x + 1, y) + from_b(x, y - 1) + from_b(x, y + 1);
+                grid_a[i] = (sum - source[i]) * 0.25;
+            }
+        }
+        storageBarrier();
+        workgroupBarrier();
+    }
+}
@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use framework::timing::time_submission;
+use framework::Gpu;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+pub const SIZE: u32 = 256;
+// Cells per side of a cs_step or cs_blocked workgroup's output, and the
+// iterations cs_blocked does per dispatch; both as in the shader
+const TILE: u32 = 16;
+const BLOCKED_ITERATIONS: u32 = 4;
+const BENCHMARK_ITERATIONS: u32 = 512;
+// Timed submissions per strategy; the fastest counts
+const ROUNDS: u32 = 5;
+
+pub const STRATEGIES: [&str; 3] = ["dispatch per iteration", "4 iterations per dispatch", "one persistent workgroup"];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    size: u32,
+    iterations: u32,
+    _padding: [u32; 2],
+}
+
+pub struct Timing {
+    pub milliseconds: f32,
+    pub dispatches: u32,
+    // Against the first strategy, which should be zero or nearly
+    pub difference: f32,
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// Warm and cold spots for the solution to spread out from
+fn sources() -> Vec<f32> {
+    let spots = [(0.3, 0.3, -1.0), (0.7, 0.35, 1.0), (0.5, 0.7, -0.8), (0.2, 0.75, 0.6), (0.8, 0.8, -0.5)];
+    let mut source = vec![0.0; (SIZE * SIZE) as usize];
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (u, v) = (x as f32 / SIZE as f32, y as f32 / SIZE as f32);
+            source[(y * SIZE + x) as usize] = spots
+                .iter()
+                .map(|(cx, cy, strength)| strength * (-((u - cx) * (u - cx) + (v - cy) * (v - cy)) * 800.0).exp())
+                .sum();
+        }
+    }
+    source
+}
+
+// The same Jacobi solve run three ways: a dispatch per iteration, a few
+// iterations per dispatch with shared memory tiles, or every iteration in
+// one dispatch of a single looping workgroup. Where each one wins depends
+// on how much a dispatch costs on the backend against how much of the GPU
+// the work can fill, which is the point of timing them side by side.
+pub struct Solver {
+    pipelines: [ComputePipeline; 3],
+    params_buffer: Buffer,
+    // The solution always ends up back in grids[0]
+    pub grids: [Buffer; 2],
+    // bind_groups[i] reads grids[i] and writes the other one
+    bind_groups: [BindGroup; 2],
+    readback: Buffer,
+    // Since the last reset
+    pub iterations: u32,
+}
+
+impl Solver {
+    pub fn new(device: &Device) -> Self {
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Jacobi Params"),
+            contents: bytemuck::bytes_of(&Params {
+                size: SIZE,
+                iterations: 0,
+                _padding: [0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let source = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Source"),
+            contents: bytemuck::cast_slice(&sources()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let grids = [0, 1].map(|index| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("Grid {}", index)),
+                size: (SIZE * SIZE * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Jacobi Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, false),
+                storage_entry(3, false),
+            ],
+        });
+        let bind_groups = [0, 1].map(|read| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Jacobi Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: source.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: grids[read].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: grids[1 - read].as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/jacobi.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Jacobi Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipelines = ["cs_step", "cs_blocked", "cs_persistent"].map(|entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        });
+
+        Self {
+            pipelines,
+            params_buffer,
+            grids,
+            bind_groups,
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Grid Readback"),
+                size: (SIZE * SIZE * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            iterations: 0,
+        }
+    }
+
+    pub fn reset(&mut self, encoder: &mut CommandEncoder) {
+        for grid in &self.grids {
+            encoder.clear_buffer(grid, 0, None);
+        }
+        self.iterations = 0;
+    }
+
+    // The persistent kernel reads its iteration count from the params, so
+    // at most one count per submission
+    pub fn set_iterations(&self, queue: &Queue, iterations: u32) {
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Params {
+                size: SIZE,
+                iterations,
+                _padding: [0; 2],
+            }),
+        );
+    }
+
+    // Records `iterations` more, which has to be a multiple of twice the
+    // blocked iterations so every strategy ends on grids[0]. Returns the
+    // dispatches it took.
+    pub fn record(&mut self, encoder: &mut CommandEncoder, strategy: usize, iterations: u32) -> u32 {
+        assert_eq!(iterations % (BLOCKED_ITERATIONS * 2), 0);
+        self.iterations += iterations;
+        let groups = (SIZE + TILE - 1) / TILE;
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(STRATEGIES[strategy]),
+        });
+        compute_pass.set_pipeline(&self.pipelines[strategy]);
+        // wgpu orders every dispatch after the ones before it in the pass,
+        // which is the only global barrier compute has
+        let dispatches = match strategy {
+            0 => iterations,
+            1 => iterations / BLOCKED_ITERATIONS,
+            _ => {
+                compute_pass.set_bind_group(0, &self.bind_groups[0], &[]);
+                compute_pass.dispatch_workgroups(1, 1, 1);
+                return 1;
+            }
+        };
+        for dispatch in 0..dispatches {
+            compute_pass.set_bind_group(0, &self.bind_groups[dispatch as usize % 2], &[]);
+            compute_pass.dispatch_workgroups(groups, groups, 1);
+        }
+        dispatches
+    }
+
+    // Blocks until the solution is back on the CPU
+    fn read(&self, gpu: &Gpu) -> Vec<f32> {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                },
+            );
+        encoder.copy_buffer_to_buffer(&self.grids[0], 0, &self.readback, 0, self.readback.size());
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let slice = self.readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        gpu.device.poll(wgpu::Maintain::Wait);
+        let values = bytemuck::cast_slice::<u8, f32>(&slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        values
+    }
+
+    fn time(&mut self, gpu: &Gpu, strategy: usize) -> (Duration, u32) {
+        let mut dispatches = 0;
+        let elapsed = time_submission(gpu, |encoder| {
+            self.reset(encoder);
+            dispatches = self.record(encoder, strategy, BENCHMARK_ITERATIONS);
+        });
+        (elapsed, dispatches)
+    }
+
+    // Solves from scratch with every strategy, checking they agree and
+    // printing a table along the way; leaves the solver reset
+    pub fn benchmark(&mut self, gpu: &Gpu) -> Vec<Timing> {
+        let info = gpu.adapter.get_info();
+        println!("{} on {:?}, {} iterations of a {}x{} grid", info.name, info.backend, BENCHMARK_ITERATIONS, SIZE, SIZE);
+        self.set_iterations(&gpu.queue, BENCHMARK_ITERATIONS);
+        let mut timings = Vec::new();
+        let mut reference: Option<Vec<f32>> = None;
+        for (strategy, name) in STRATEGIES.iter().enumerate() {
+            // The first submission pays for pipeline creation in the driver
+            self.time(gpu, strategy);
+            let (fastest, dispatches) = (0..ROUNDS).map(|_| self.time(gpu, strategy)).min().unwrap();
+            let solution = self.read(gpu);
+            let difference = match &reference {
+                Some(reference) => solution.iter().zip(reference).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max),
+                None => 0.0,
+            };
+            if reference.is_none() {
+                reference = Some(solution);
+            }
+
+            let milliseconds = fastest.as_secs_f32() * 1000.0;
+            println!(
+                "{:<28} {:>4} dispatches {:>8.3}ms {:>7.2}us per iteration, differs by {}",
+                name,
+                dispatches,
+                milliseconds,
+                milliseconds * 1000.0 / BENCHMARK_ITERATIONS as f32,
+                difference,
+            );
+            timings.push(Timing {
+                milliseconds,
+                dispatches,
+                difference,
+            });
+        }
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Reset Encoder"),
+                },
+            );
+        self.reset(&mut encoder);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        timings
+    }
+}
@@ -0,0 +1,276 @@
+use bytemuck::{Pod, Zeroable};
+use framework::Gpu;
+use wgpu::{include_wgsl, BindGroupLayout, ComputePipeline, Device, Texture};
+use wgpu::util::DeviceExt;
+
+use crate::jpeg::Coefficients;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ComponentUniform {
+    block_offset: u32,
+    blocks_per_line: u32,
+    plane_offset: u32,
+    quant_table: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ColorParams {
+    size: [u32; 2],
+    component_count: u32,
+    _padding: u32,
+    max_sampling: [u32; 4],
+    planes: [[u32; 4]; 3],
+}
+
+fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// The GPU half of the decoder: one IDCT dispatch per component into float
+// sample planes, then one dispatch that upsamples chroma, converts to RGB
+// and writes the texture
+pub struct GpuDecoder {
+    idct_layout: BindGroupLayout,
+    idct_pipeline: ComputePipeline,
+    color_layout: BindGroupLayout,
+    color_pipeline: ComputePipeline,
+    // Whether the texture can be viewed as Rgba8UnormSrgb. GL can't
+    // reinterpret formats, there the display shader decodes sRGB itself.
+    srgb_view: bool,
+}
+
+impl GpuDecoder {
+    pub fn new(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let idct_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("IDCT Layout"),
+            entries: &[
+                uniform_entry(0),
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, false),
+            ],
+        });
+        let idct_shader = device.create_shader_module(include_wgsl!("shaders/idct.wgsl"));
+        let idct_pipeline = compute_pipeline(device, "IDCT", &idct_layout, &idct_shader, "cs_idct");
+
+        let color_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Color Layout"),
+            entries: &[
+                uniform_entry(0),
+                storage_entry(1, true),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let color_shader = device.create_shader_module(include_wgsl!("shaders/color.wgsl"));
+        let color_pipeline = compute_pipeline(device, "Color Conversion", &color_layout, &color_shader, "cs_color");
+
+        Self {
+            idct_layout,
+            idct_pipeline,
+            color_layout,
+            color_pipeline,
+            srgb_view: gpu
+                .adapter
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::VIEW_FORMATS),
+        }
+    }
+
+    pub fn srgb_view(&self) -> bool {
+        self.srgb_view
+    }
+
+    // Uploads the coefficients and records and submits the decode. The
+    // texture is Rgba8Unorm because storage textures can't be sRGB, view it
+    // as Rgba8UnormSrgb for sampling where srgb_view() allows.
+    pub fn decode(&self, gpu: &Gpu, jpeg: &Coefficients) -> Texture {
+        let device = &gpu.device;
+
+        let mut coefficients: Vec<i16> = Vec::new();
+        let mut uniforms = Vec::new();
+        let mut plane_size = 0;
+        let mut color_params = ColorParams::zeroed();
+        for (i, component) in jpeg.components.iter().enumerate() {
+            let plane_width = component.blocks_per_line * 8;
+            uniforms.push(ComponentUniform {
+                block_offset: (coefficients.len() / 64) as u32,
+                blocks_per_line: component.blocks_per_line,
+                plane_offset: plane_size,
+                quant_table: component.quant_table,
+            });
+            color_params.planes[i] = [plane_size, plane_width, component.h, component.v];
+            coefficients.extend_from_slice(&component.coefficients);
+            plane_size += plane_width * component.blocks_per_column * 8;
+        }
+        let (h_max, v_max) = jpeg.max_sampling();
+        color_params.size = [jpeg.width, jpeg.height];
+        color_params.component_count = jpeg.components.len() as u32;
+        color_params.max_sampling = [h_max, v_max, 0, 0];
+
+        let coefficient_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Coefficients"),
+            contents: bytemuck::cast_slice(&coefficients),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let quant_tables: Vec<f32> = jpeg.quant_tables.iter().flatten().map(|&q| q as f32).collect();
+        let quant_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Quantization Tables"),
+            contents: bytemuck::cast_slice(&quant_tables),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let plane_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sample Planes"),
+            size: plane_size as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let color_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Color Params"),
+            contents: bytemuck::bytes_of(&color_params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("GPU Decoded"),
+            size: wgpu::Extent3d {
+                width: jpeg.width,
+                height: jpeg.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: if self.srgb_view { &[wgpu::TextureFormat::Rgba8UnormSrgb] } else { &[] },
+        });
+
+        let idct_bind_groups: Vec<_> = uniforms
+            .iter()
+            .map(|uniform| {
+                let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Component"),
+                    contents: bytemuck::bytes_of(uniform),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("IDCT Bind Group"),
+                    layout: &self.idct_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: coefficient_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: quant_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: plane_buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let color_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Color Bind Group"),
+            layout: &self.color_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: color_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: plane_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+            ],
+        });
+
+        let mut encoder =
+            device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Decode Encoder"),
+                },
+            );
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Decode Pass"),
+            });
+            compute_pass.set_pipeline(&self.idct_pipeline);
+            for (component, bind_group) in jpeg.components.iter().zip(&idct_bind_groups) {
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(component.blocks_per_line, component.blocks_per_column, 1);
+            }
+            // wgpu puts a barrier between dispatches that write and read the
+            // same buffer, so the planes are complete here
+            compute_pass.set_pipeline(&self.color_pipeline);
+            compute_pass.set_bind_group(0, &color_bind_group, &[]);
+            compute_pass.dispatch_workgroups((jpeg.width + 7) / 8, (jpeg.height + 7) / 8, 1);
+        }
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        texture
+    }
+}
@@ -0,0 +1,546 @@
+// The CPU half of the decoder: marker parsing and Huffman decoding of
+// baseline JPEGs into quantized DCT coefficients. Everything after that,
+// dequantization, IDCT, upsampling and color conversion, runs on the GPU.
+
+// Bigger than any GPU will take as a texture, and a cap on what a bad
+// header can make us allocate
+const MAX_SIZE: u32 = 16384;
+
+// Maps the zigzag order coefficients arrive in to row-major order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21,
+    28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61,
+    54, 47, 55, 62, 63,
+];
+
+pub struct Component {
+    id: u8,
+    // Sampling factors, 1 or 2 in practice
+    pub h: u32,
+    pub v: u32,
+    pub quant_table: u32,
+    // Size of the padded block grid, a whole number of MCUs
+    pub blocks_per_line: u32,
+    pub blocks_per_column: u32,
+    // The blocks that actually hold image data, single component scans
+    // only visit these
+    blocks_wide: u32,
+    blocks_high: u32,
+    // 64 row-major coefficients per block, blocks in raster order
+    pub coefficients: Vec<i16>,
+    dc_table: usize,
+    ac_table: usize,
+    dc_prediction: i32,
+}
+
+pub struct Coefficients {
+    pub width: u32,
+    pub height: u32,
+    pub components: Vec<Component>,
+    // Row-major, 4 slots as JPEG allows, unused ones stay zero
+    pub quant_tables: [[u16; 64]; 4],
+}
+
+impl Coefficients {
+    pub fn max_sampling(&self) -> (u32, u32) {
+        let h = self.components.iter().map(|c| c.h).max().unwrap();
+        let v = self.components.iter().map(|c| c.v).max().unwrap();
+        (h, v)
+    }
+}
+
+#[derive(Clone, Default)]
+struct Huffman {
+    // Indexed by code length, 1 to 16
+    min_code: [u32; 17],
+    max_code: [i32; 17],
+    value_offset: [usize; 17],
+    values: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8], values: &[u8]) -> Self {
+        let mut table = Self {
+            values: values.to_vec(),
+            ..Default::default()
+        };
+        let mut code = 0u32;
+        let mut k = 0usize;
+        for length in 1..=16 {
+            let count = counts[length - 1] as usize;
+            table.value_offset[length] = k;
+            table.min_code[length] = code;
+            code += count as u32;
+            k += count;
+            table.max_code[length] = if count > 0 { code as i32 - 1 } else { -1 };
+            code <<= 1;
+        }
+        table
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, String> {
+        let bits = reader.peek(16);
+        for length in 1..=16 {
+            let code = bits >> (16 - length);
+            if code as i32 <= self.max_code[length] {
+                reader.consume(length as u32);
+                // Codes past the values the table was given, or any code
+                // from a table that was never defined
+                return self
+                    .values
+                    .get(self.value_offset[length] + (code - self.min_code[length]) as usize)
+                    .copied()
+                    .ok_or_else(|| "invalid Huffman code".to_string());
+            }
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+// MSB first, with 0xFF00 byte stuffing undone. Stops at markers and feeds
+// zeros from then on, which is what the spec asks for.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], position: usize) -> Self {
+        Self {
+            data,
+            position,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 24 {
+            let mut byte = 0;
+            if self.position < self.data.len() {
+                byte = self.data[self.position];
+                if byte == 0xFF {
+                    match self.data.get(self.position + 1) {
+                        Some(0x00) => self.position += 2,
+                        // A marker, leave it for whoever reads on
+                        _ => byte = 0,
+                    }
+                } else {
+                    self.position += 1;
+                }
+            }
+            self.bits |= (byte as u32) << (24 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, count: u32) -> u32 {
+        self.fill();
+        self.bits >> (32 - count)
+    }
+
+    fn consume(&mut self, count: u32) {
+        self.bits <<= count;
+        self.count -= count;
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let value = self.peek(count);
+        self.consume(count);
+        value
+    }
+
+    // The next `count` bits as a signed difference
+    fn receive_extend(&mut self, count: u32) -> i32 {
+        let value = self.bits(count) as i32;
+        if count > 0 && value < 1 << (count - 1) {
+            value - (1 << count) + 1
+        } else {
+            value
+        }
+    }
+
+    // Drops the leftover bits and steps over the RSTn marker
+    fn restart(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+        while self.position + 1 < self.data.len()
+            && !(self.data[self.position] == 0xFF && (0xD0..=0xD7).contains(&self.data[self.position + 1]))
+        {
+            self.position += 1;
+        }
+        self.position += 2;
+    }
+
+    // Where the entropy coded data ended, for picking up the next marker
+    fn end(&self) -> usize {
+        let mut position = self.position;
+        while position < self.data.len() && !(self.data[position] == 0xFF && self.data.get(position + 1) != Some(&0)) {
+            position += 1;
+        }
+        position
+    }
+}
+
+// The bytes in `range`, or an error for a segment that ends too soon
+fn bytes(data: &[u8], range: std::ops::Range<usize>) -> Result<&[u8], String> {
+    data.get(range).ok_or_else(|| "truncated".to_string())
+}
+
+fn read_u16(data: &[u8], position: usize) -> Result<usize, String> {
+    let pair = bytes(data, position..position + 2)?;
+    Ok(((pair[0] as usize) << 8) | pair[1] as usize)
+}
+
+// JPEG has four table slots of each kind, but four bits to name one with
+fn table_id(id: u8) -> Result<usize, String> {
+    if id < 4 {
+        Ok(id as usize)
+    } else {
+        Err(format!("table {} doesn't exist", id))
+    }
+}
+
+// Fails for anything but baseline and extended Huffman JPEGs with 1 or 3
+// components, e.g. progressive or arithmetic coded ones
+pub fn decode(data: &[u8]) -> Result<Coefficients, String> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err("not a JPEG".to_string());
+    }
+
+    let mut width = 0;
+    let mut height = 0;
+    let mut components: Vec<Component> = Vec::new();
+    let mut quant_tables = [[0u16; 64]; 4];
+    let mut dc_tables = vec![Huffman::default(); 4];
+    let mut ac_tables = vec![Huffman::default(); 4];
+    let mut restart_interval = 0;
+
+    let mut position = 2;
+    while position + 4 <= data.len() {
+        if data[position] != 0xFF {
+            return Err(format!("expected a marker at {}", position));
+        }
+        let marker = data[position + 1];
+        // Fill bytes in front of a marker
+        if marker == 0xFF {
+            position += 1;
+            continue;
+        }
+        if marker == 0xD9 {
+            break;
+        }
+        let length = read_u16(data, position + 2)?;
+        if length < 2 {
+            return Err(format!("segment length {} at {}", length, position));
+        }
+        let segment = bytes(data, position + 4..position + 2 + length)?;
+
+        match marker {
+            // DQT
+            0xDB => {
+                let mut offset = 0;
+                while offset < segment.len() {
+                    let precision = segment[offset] >> 4;
+                    let id = table_id(segment[offset] & 15)?;
+                    offset += 1;
+                    // 8 or 16 bit entries
+                    let size = if precision == 0 { 64 } else { 128 };
+                    let table = bytes(segment, offset..offset + size)?;
+                    for (i, &natural) in ZIGZAG.iter().enumerate() {
+                        quant_tables[id][natural] = if precision == 0 {
+                            table[i] as u16
+                        } else {
+                            read_u16(table, i * 2)? as u16
+                        };
+                    }
+                    offset += size;
+                }
+            }
+            // SOF0 baseline, SOF1 extended sequential
+            0xC0 | 0xC1 => {
+                if !components.is_empty() {
+                    return Err("more than one frame header".to_string());
+                }
+                height = read_u16(segment, 1)? as u32;
+                width = read_u16(segment, 3)? as u32;
+                // A height of 0 means it comes later in a DNL marker,
+                // which hardly anything writes
+                if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+                    return Err(format!("{}x{} images aren't supported", width, height));
+                }
+                let count = bytes(segment, 5..6)?[0] as usize;
+                if count != 1 && count != 3 {
+                    return Err(format!("{} components aren't supported", count));
+                }
+                for c in bytes(segment, 6..6 + count * 3)?.chunks(3) {
+                    let (h, v) = ((c[1] >> 4) as u32, (c[1] & 15) as u32);
+                    if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                        return Err(format!("sampling factors {}x{}", h, v));
+                    }
+                    components.push(Component {
+                        id: c[0],
+                        h,
+                        v,
+                        quant_table: table_id(c[2])? as u32,
+                        blocks_per_line: 0,
+                        blocks_per_column: 0,
+                        blocks_wide: 0,
+                        blocks_high: 0,
+                        coefficients: Vec::new(),
+                        dc_table: 0,
+                        ac_table: 0,
+                        dc_prediction: 0,
+                    });
+                }
+
+                let h_max = components.iter().map(|c| c.h).max().unwrap();
+                let v_max = components.iter().map(|c| c.v).max().unwrap();
+                let mcus_x = (width + 8 * h_max - 1) / (8 * h_max);
+                let mcus_y = (height + 8 * v_max - 1) / (8 * v_max);
+                for component in &mut components {
+                    component.blocks_per_line = mcus_x * component.h;
+                    component.blocks_per_column = mcus_y * component.v;
+                    component.blocks_wide = ((width * component.h + h_max - 1) / h_max + 7) / 8;
+                    component.blocks_high = ((height * component.v + v_max - 1) / v_max + 7) / 8;
+                    component.coefficients =
+                        vec![0; component.blocks_per_line as usize * component.blocks_per_column as usize * 64];
+                }
+            }
+            0xC2 | 0xC6 | 0xCA => return Err("progressive JPEGs aren't supported".to_string()),
+            0xC3 | 0xC5 | 0xC7 | 0xC9 | 0xCB | 0xCD | 0xCF => {
+                return Err("lossless, hierarchical and arithmetic coded JPEGs aren't supported".to_string())
+            }
+            // DHT
+            0xC4 => {
+                let mut offset = 0;
+                while offset < segment.len() {
+                    let class = segment[offset] >> 4;
+                    let id = table_id(segment[offset] & 15)?;
+                    let counts = bytes(segment, offset + 1..offset + 17)?;
+                    let total: usize = counts.iter().map(|&count| count as usize).sum();
+                    let values = bytes(segment, offset + 17..offset + 17 + total)?;
+                    let table = Huffman::new(counts, values);
+                    match class {
+                        0 => dc_tables[id] = table,
+                        1 => ac_tables[id] = table,
+                        _ => return Err(format!("Huffman table class {}", class)),
+                    }
+                    offset += 17 + total;
+                }
+            }
+            // DRI
+            0xDD => restart_interval = read_u16(segment, 0)?,
+            // SOS
+            0xDA => {
+                let count = bytes(segment, 0..1)?[0] as usize;
+                if count == 0 {
+                    return Err("empty scan".to_string());
+                }
+                let mut scan = Vec::new();
+                for pair in bytes(segment, 1..1 + count * 2)?.chunks(2) {
+                    let (id, tables) = (pair[0], pair[1]);
+                    let index = components
+                        .iter()
+                        .position(|c| c.id == id)
+                        .ok_or_else(|| format!("scan refers to unknown component {}", id))?;
+                    components[index].dc_table = table_id(tables >> 4)?;
+                    components[index].ac_table = table_id(tables & 15)?;
+                    components[index].dc_prediction = 0;
+                    scan.push(index);
+                }
+
+                let mut reader = BitReader::new(data, position + 2 + length);
+                decode_scan(&mut reader, &mut components, &scan, &dc_tables, &ac_tables, restart_interval, width, height)?;
+                position = reader.end();
+                continue;
+            }
+            // APPn, COM and anything else we don't need
+            _ => {}
+        }
+        position += 2 + length;
+    }
+
+    if components.is_empty() {
+        return Err("no frame header".to_string());
+    }
+    Ok(Coefficients {
+        width,
+        height,
+        components,
+        quant_tables,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn decode_scan(
+    reader: &mut BitReader,
+    components: &mut [Component],
+    scan: &[usize],
+    dc_tables: &[Huffman],
+    ac_tables: &[Huffman],
+    restart_interval: usize,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    // One component alone isn't interleaved: its MCU is a single block
+    // and only the blocks covering the image are coded
+    let blocks: Vec<(usize, u32, u32)> = if scan.len() == 1 {
+        let component = &components[scan[0]];
+        (0..component.blocks_high)
+            .flat_map(|y| (0..component.blocks_wide).map(move |x| (scan[0], x, y)))
+            .collect()
+    } else {
+        let h_max = components.iter().map(|c| c.h).max().unwrap();
+        let v_max = components.iter().map(|c| c.v).max().unwrap();
+        let mcus_x = (width + 8 * h_max - 1) / (8 * h_max);
+        let mcus_y = (height + 8 * v_max - 1) / (8 * v_max);
+        let mut blocks = Vec::new();
+        for mcu_y in 0..mcus_y {
+            for mcu_x in 0..mcus_x {
+                for &index in scan {
+                    let component = &components[index];
+                    for v in 0..component.v {
+                        for h in 0..component.h {
+                            blocks.push((index, mcu_x * component.h + h, mcu_y * component.v + v));
+                        }
+                    }
+                }
+            }
+        }
+        blocks
+    };
+
+    let blocks_per_mcu = if scan.len() == 1 {
+        1
+    } else {
+        scan.iter().map(|&index| (components[index].h * components[index].v) as usize).sum()
+    };
+
+    for (i, &(index, x, y)) in blocks.iter().enumerate() {
+        let mcu = i / blocks_per_mcu;
+        if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 && i % blocks_per_mcu == 0 {
+            reader.restart();
+            for &index in scan {
+                components[index].dc_prediction = 0;
+            }
+        }
+
+        let component = &mut components[index];
+        let offset = ((y * component.blocks_per_line + x) * 64) as usize;
+        let block = &mut component.coefficients[offset..offset + 64];
+
+        let size = dc_tables[component.dc_table].decode(reader)?;
+        if size > 16 {
+            return Err(format!("DC difference of {} bits", size));
+        }
+        // Wraps rather than overflows on garbage, like the i16 below
+        component.dc_prediction = component.dc_prediction.wrapping_add(reader.receive_extend(size as u32));
+        block[0] = component.dc_prediction as i16;
+
+        let ac_table = &ac_tables[component.ac_table];
+        let mut k = 1;
+        while k < 64 {
+            let symbol = ac_table.decode(reader)?;
+            let run = (symbol >> 4) as usize;
+            let size = (symbol & 15) as u32;
+            if size == 0 {
+                // End of block, or ZRL: sixteen zeros
+                if run != 15 {
+                    break;
+                }
+                k += 16;
+                continue;
+            }
+            k += run;
+            if k >= 64 {
+                break;
+            }
+            block[ZIGZAG[k]] = reader.receive_extend(size) as i16;
+            k += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::codecs::jpeg::JpegEncoder;
+
+    use super::*;
+
+    fn encoded(width: u32, height: u32) -> Vec<u8> {
+        let pixels: Vec<u8> = (0..width * height * 3).map(|i| (i * 7 % 251) as u8).collect();
+        let mut bytes = Vec::new();
+        JpegEncoder::new_with_quality(&mut bytes, 80)
+            .encode(&pixels, width, height, image::ColorType::Rgb8)
+            .unwrap();
+        bytes
+    }
+
+    // Where the frame header's first component's sampling factors are
+    fn sampling_byte(jpeg: &[u8]) -> usize {
+        let sof = jpeg.windows(2).position(|pair| pair == [0xFF, 0xC0]).unwrap();
+        sof + 2 + 2 + 6 + 1
+    }
+
+    #[test]
+    fn decodes_a_baseline_jpeg() {
+        let coefficients = decode(&encoded(40, 24)).unwrap();
+        assert_eq!((coefficients.width, coefficients.height), (40, 24));
+        assert_eq!(coefficients.components.len(), 3);
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_jpeg() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(b"GIF89a").is_err());
+        assert!(decode(&[0xFF, 0xD8, 0x00, 0x00, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn truncated_input_never_panics() {
+        let jpeg = encoded(40, 24);
+        for length in 0..jpeg.len() {
+            let _ = decode(&jpeg[..length]);
+        }
+    }
+
+    #[test]
+    fn corrupted_input_never_panics() {
+        let jpeg = encoded(40, 24);
+        let mut state = 0x2545_f491u32;
+        for _ in 0..2000 {
+            let mut corrupted = jpeg.clone();
+            for _ in 0..4 {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let index = state as usize % corrupted.len();
+                corrupted[index] = (state >> 24) as u8;
+            }
+            let _ = decode(&corrupted);
+        }
+    }
+
+    #[test]
+    fn rejects_a_zero_sampling_factor() {
+        let mut jpeg = encoded(16, 16);
+        let byte = sampling_byte(&jpeg);
+        jpeg[byte] = 0x01;
+        assert_eq!(decode(&jpeg).err().unwrap(), "sampling factors 0x1");
+    }
+
+    #[test]
+    fn rejects_progressive_jpegs() {
+        let mut jpeg = encoded(16, 16);
+        let sof = jpeg.windows(2).position(|pair| pair == [0xFF, 0xC0]).unwrap();
+        jpeg[sof + 1] = 0xC2;
+        assert_eq!(decode(&jpeg).err().unwrap(), "progressive JPEGs aren't supported");
+    }
+}
@@ -0,0 +1,9 @@
+mod gpu_decoder;
+mod jpeg;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("jpeg-decode");
+}
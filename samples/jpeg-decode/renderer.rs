@@ -0,0 +1,411 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{color, Gpu, Sample};
+use image::codecs::jpeg::JpegEncoder;
+use wgpu::{BindGroup, BindGroupLayout, Buffer, RenderPipeline, Sampler, Texture};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::gpu_decoder::GpuDecoder;
+use crate::jpeg;
+
+// Each path runs this often and the fastest run counts, so one-off costs
+// like the first pipeline use don't dominate
+const RUNS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum View {
+    Gpu,
+    Cpu,
+    Difference,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    scale: [f32; 2],
+    mode: u32,
+    // 1 when the GPU decoded texture is sampled without an sRGB view
+    decode_srgb: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Timings {
+    // The image crate, all on the CPU
+    cpu: f32,
+    // Our Huffman decode alone
+    entropy: f32,
+    // Huffman decode, upload, dispatches and waiting for the GPU
+    gpu_total: f32,
+}
+
+// The file given with --jpeg path.jpg or asset:name, or a synthetic
+// photo-sized image encoded right here. Files both decoders can't take,
+// e.g. progressive ones, are refused here so benchmark() can't fail.
+fn load_jpeg() -> Vec<u8> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--jpeg");
+    if let Some(path) = args.nth(1) {
+        return framework::assets::resolve(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| std::fs::read(file).map_err(|error| error.to_string()))
+            .and_then(|jpeg| {
+                jpeg::decode(&jpeg)?;
+                image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).map_err(|error| error.to_string())?;
+                Ok(jpeg)
+            })
+            .unwrap_or_else(|error| {
+                eprintln!("{}: {}", path, error);
+                std::process::exit(1);
+            });
+    }
+
+    let (width, height) = (2048u32, 1536u32);
+    let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let fx = x as f32 / width as f32;
+            let fy = y as f32 / height as f32;
+            let rings = ((fx - 0.5).hypot(fy - 0.5) * 60.0).sin() * 0.5 + 0.5;
+            pixels.extend([
+                (fx * 255.0) as u8,
+                (rings * 255.0) as u8,
+                (fy * 255.0) as u8,
+            ]);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, 90)
+        .encode(&pixels, width, height, image::ColorType::Rgb8)
+        .unwrap();
+    bytes
+}
+
+fn min_ms(runs: impl Iterator<Item = f32>) -> f32 {
+    runs.fold(f32::MAX, f32::min)
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    decoder: GpuDecoder,
+    jpeg: Vec<u8>,
+    image_size: [u32; 2],
+    timings: Timings,
+    view: View,
+    rerun: bool,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Image Sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                texture_entry(2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Display Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}", color::WGSL, include_str!("shaders/display.wgsl")).into(),
+            ),
+        });
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let decoder = GpuDecoder::new(gpu);
+        let jpeg = load_jpeg();
+        let (timings, gpu_decoded, cpu_decoded) = Self::benchmark(gpu, &decoder, &jpeg);
+        let bind_group = Self::create_bind_group(gpu, &bind_group_layout, &uniform_buffer, &sampler, &decoder, &gpu_decoded, &cpu_decoded);
+
+        Self {
+            render_pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            bind_group,
+            decoder,
+            jpeg,
+            image_size: [gpu_decoded.width(), gpu_decoded.height()],
+            timings,
+            view: View::Gpu,
+            rerun: false,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match key {
+                VirtualKeyCode::Tab => {
+                    self.view = match self.view {
+                        View::Gpu => View::Cpu,
+                        View::Cpu => View::Difference,
+                        View::Difference => View::Gpu,
+                    };
+                }
+                VirtualKeyCode::B => self.rerun = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{:?}, {}x{}, image crate {:.1} ms, ours {:.1} ms of which Huffman {:.1} ms",
+            self.view,
+            self.image_size[0],
+            self.image_size[1],
+            self.timings.cpu,
+            self.timings.gpu_total,
+            self.timings.entropy,
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        if std::mem::take(&mut self.rerun) {
+            let (timings, gpu_decoded, cpu_decoded) = Self::benchmark(gpu, &self.decoder, &self.jpeg);
+            self.timings = timings;
+            self.bind_group = Self::create_bind_group(
+                gpu,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.sampler,
+                &self.decoder,
+                &gpu_decoded,
+                &cpu_decoded,
+            );
+        }
+
+        // Fit the image into the window without stretching it
+        let image_aspect = self.image_size[0] as f32 / self.image_size[1] as f32;
+        let ratio = gpu.aspect_ratio() / image_aspect;
+        let scale = if ratio > 1.0 { [ratio, 1.0] } else { [1.0, 1.0 / ratio] };
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                scale,
+                mode: self.view as u32,
+                decode_srgb: !self.decoder.srgb_view() as u32,
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    // Decodes the JPEG both ways RUNS times and keeps the last results for
+    // display. The GPU path is timed until the device is idle again, since
+    // a decode that isn't finished isn't much use yet.
+    fn benchmark(gpu: &Gpu, decoder: &GpuDecoder, jpeg: &[u8]) -> (Timings, Texture, Texture) {
+        let mut cpu_image = None;
+        let cpu = min_ms((0..RUNS).map(|_| {
+            let start = Instant::now();
+            let image = image::load_from_memory_with_format(jpeg, image::ImageFormat::Jpeg)
+                .expect("checked by load_jpeg")
+                .to_rgba8();
+            let elapsed = start.elapsed().as_secs_f32() * 1000.0;
+            cpu_image = Some(image);
+            elapsed
+        }));
+
+        let mut gpu_texture = None;
+        let mut entropy_runs = Vec::new();
+        let gpu_total = min_ms((0..RUNS).map(|_| {
+            let start = Instant::now();
+            let coefficients = jpeg::decode(jpeg).expect("checked by load_jpeg");
+            entropy_runs.push(start.elapsed().as_secs_f32() * 1000.0);
+            let texture = decoder.decode(gpu, &coefficients);
+            gpu.device.poll(wgpu::Maintain::Wait);
+            let elapsed = start.elapsed().as_secs_f32() * 1000.0;
+            gpu_texture = Some(texture);
+            elapsed
+        }));
+
+        let cpu_image = cpu_image.unwrap();
+        let cpu_texture = gpu.device.create_texture_with_data(
+            &gpu.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("CPU Decoded"),
+                size: wgpu::Extent3d {
+                    width: cpu_image.width(),
+                    height: cpu_image.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &cpu_image,
+        );
+
+        let timings = Timings {
+            cpu,
+            entropy: min_ms(entropy_runs.into_iter()),
+            gpu_total,
+        };
+        (timings, gpu_texture.unwrap(), cpu_texture)
+    }
+
+    fn create_bind_group(
+        gpu: &Gpu,
+        layout: &BindGroupLayout,
+        uniform_buffer: &Buffer,
+        sampler: &Sampler,
+        decoder: &GpuDecoder,
+        gpu_decoded: &Texture,
+        cpu_decoded: &Texture,
+    ) -> BindGroup {
+        gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    // Decodes the sRGB bytes on sampling, like the CPU texture,
+                    // or leaves them to the shader where views can't
+                    resource: wgpu::BindingResource::TextureView(&gpu_decoded.create_view(
+                        &wgpu::TextureViewDescriptor {
+                            format: decoder.srgb_view().then_some(wgpu::TextureFormat::Rgba8UnormSrgb),
+                            ..Default::default()
+                        },
+                    )),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(
+                        &cpu_decoded.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+}
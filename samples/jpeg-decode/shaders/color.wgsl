@@ -0,0 +1,46 @@
+struct Params {
+    size: vec2<u32>,
+    component_count: u32,
+    _padding: u32,
+    // Largest sampling factors, the full resolution ones
+    max_sampling: vec4<u32>,
+    // Per component: plane offset, plane width, h and v sampling factors
+    planes: array<vec4<u32>, 3>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read> planes: array<f32>;
+@group(0) @binding(2)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+// Nearest neighbour upsampling for subsampled chroma
+fn fetch(c: u32, x: u32, y: u32) -> f32 {
+    let plane = params.planes[c];
+    let sx = x * plane.z / params.max_sampling.x;
+    let sy = y * plane.w / params.max_sampling.y;
+    return planes[plane.x + sy * plane.y + sx];
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_color(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.size.x || id.y >= params.size.y {
+        return;
+    }
+
+    let luma = fetch(0u, id.x, id.y);
+    var rgb = vec3<f32>(luma);
+    if params.component_count == 3u {
+        // JFIF's full range BT.601
+        let cb = fetch(1u, id.x, id.y) - 128.0;
+        let cr = fetch(2u, id.x, id.y) - 128.0;
+        rgb = vec3<f32>(
+            luma + 1.402 * cr,
+            luma - 0.344136 * cb - 0.714136 * cr,
+            luma + 1.772 * cb
+        );
+    }
+    // Still sRGB encoded, like the bytes the CPU decoder produces
+    textureStore(output, vec2<i32>(id.xy), vec4<f32>(clamp(rgb / 255.0, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+}
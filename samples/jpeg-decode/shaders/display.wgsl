@@ -0,0 +1,54 @@
+struct Uniforms {
+    // Screen to image scale that keeps the image's aspect ratio
+    scale: vec2<f32>,
+    // 0 GPU decode, 1 CPU decode, 2 their difference
+    mode: u32,
+    // 1 where gpu_decoded couldn't get an sRGB view
+    decode_srgb: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var gpu_decoded: texture_2d<f32>;
+@group(0) @binding(2)
+var cpu_decoded: texture_2d<f32>;
+@group(0) @binding(3)
+var image_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// One triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(in_vertex_index & 1u) * 4.0 - 1.0, f32(in_vertex_index >> 1u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.uv = ndc * uniforms.scale * vec2<f32>(0.5, -0.5) + 0.5;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var from_gpu = textureSample(gpu_decoded, image_sampler, in.uv);
+    // srgb_to_linear comes from framework::color::WGSL
+    if uniforms.decode_srgb == 1u {
+        from_gpu = vec4<f32>(srgb_to_linear(from_gpu.rgb), from_gpu.a);
+    }
+    let from_cpu = textureSample(cpu_decoded, image_sampler, in.uv);
+
+    var color = from_gpu;
+    if uniforms.mode == 1u {
+        color = from_cpu;
+    } else if uniforms.mode == 2u {
+        // Amplified, most differences are a step or two of 255
+        color = vec4<f32>(abs(from_gpu.rgb - from_cpu.rgb) * 16.0, 1.0);
+    }
+
+    let inside = all(in.uv >= vec2<f32>(0.0)) && all(in.uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.05, 0.05, 0.05, 1.0), color, inside);
+}
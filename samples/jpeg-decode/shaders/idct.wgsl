@@ -0,0 +1,62 @@
+struct Component {
+    // In blocks, into the coefficients of all components
+    block_offset: u32,
+    blocks_per_line: u32,
+    // In samples, into the planes of all components
+    plane_offset: u32,
+    quant_table: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> component: Component;
+// Two i16 coefficients per word, the even one in the low half
+@group(0) @binding(1)
+var<storage, read> coefficients: array<u32>;
+// Four tables of 64, row-major
+@group(0) @binding(2)
+var<storage, read> quant_tables: array<f32>;
+@group(0) @binding(3)
+var<storage, read_write> planes: array<f32>;
+
+var<workgroup> block: array<f32, 64>;
+var<workgroup> rows: array<f32, 64>;
+
+fn basis(x: u32, u: u32) -> f32 {
+    let scale = select(1.0, 0.70710678, u == 0u);
+    return scale * cos(f32((2u * x + 1u) * u) * 3.14159265 / 16.0);
+}
+
+// One workgroup per 8x8 block, one invocation per coefficient and later
+// per sample. The 2D IDCT is done as two 1D passes through workgroup
+// memory, rows first.
+@compute @workgroup_size(8, 8, 1)
+fn cs_idct(@builtin(workgroup_id) block_id: vec3<u32>, @builtin(local_invocation_id) local: vec3<u32>) {
+    let block_index = component.block_offset + block_id.y * component.blocks_per_line + block_id.x;
+    let i = local.y * 8u + local.x;
+
+    let index = block_index * 64u + i;
+    let word = coefficients[index / 2u];
+    // Shift the wanted half to the top, then back down with sign extension
+    let coefficient = bitcast<i32>(word << (16u - 16u * (index & 1u))) >> 16u;
+    block[i] = f32(coefficient) * quant_tables[component.quant_table * 64u + i];
+    workgroupBarrier();
+
+    // Row v of the block holds vertical frequency v
+    var row = 0.0;
+    for (var u = 0u; u < 8u; u++) {
+        row += block[local.y * 8u + u] * basis(local.x, u);
+    }
+    rows[i] = row;
+    workgroupBarrier();
+
+    var value = 0.0;
+    for (var v = 0u; v < 8u; v++) {
+        value += rows[v * 8u + local.x] * basis(local.y, v);
+    }
+
+    // 1/4 is the 2D IDCT's scale, 128 undoes the encoder's level shift
+    let plane_width = component.blocks_per_line * 8u;
+    let x = block_id.x * 8u + local.x;
+    let y = block_id.y * 8u + local.y;
+    planes[component.plane_offset + y * plane_width + x] = value / 4.0 + 128.0;
+}
@@ -0,0 +1,326 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+pub const MAX_CLUSTERS: usize = 16;
+const GROUP_SIZE: u32 = 256;
+// Enough workgroups to fill a GPU, each looping over a stride of the pixels
+const REDUCE_GROUPS: u32 = 64;
+// Colors are 0-1, so this is well under a byte step
+const CONVERGED_SHIFT: f32 = 0.0005;
+pub const MAX_ITERATIONS: u32 = 100;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    pixel_count: u32,
+    clusters: u32,
+    _padding: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct State {
+    // rgb the mean color in sRGB, w the share of the pixels nearest it
+    pub centroids: [[f32; 4]; MAX_CLUSTERS],
+    // The furthest any centroid moved in the last update
+    pub shift: f32,
+    _padding: [f32; 3],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn index(&mut self, len: usize) -> usize {
+        ((self.next_f32() * len as f32) as usize).min(len - 1)
+    }
+}
+
+fn storage_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: binding == 1 },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// K-means over an image's colors, one iteration being three dispatches:
+// assigning every pixel its nearest centroid, summing each cluster's
+// pixels, and moving the centroids to those means. The CPU decides when to
+// stop from the largest centroid shift, which it reads back without ever
+// waiting on the GPU, so it finds out an iteration or two late.
+pub struct KMeans {
+    pixel_count: u32,
+    // The seeding draws from these, rather than the whole image
+    samples: Vec<[f32; 3]>,
+    assign_pipeline: ComputePipeline,
+    reduce_pipeline: ComputePipeline,
+    update_pipeline: ComputePipeline,
+    params_buffer: Buffer,
+    pub pixels: Buffer,
+    pub assignments: Buffer,
+    sums: Buffer,
+    pub state: Buffer,
+    bind_group: BindGroup,
+    readback: Buffer,
+    pending: bool,
+    needs_map: bool,
+    mapped: Arc<AtomicBool>,
+    // The readback in flight is from before a restart
+    stale: bool,
+    pub clusters: usize,
+    pub iterations: u32,
+    // Of the last readback to land
+    pub latest: State,
+    pub converged: bool,
+}
+
+impl KMeans {
+    // `pixels` are RGBA8
+    pub fn new(device: &Device, pixels: &[u8]) -> Self {
+        let packed: Vec<u32> = pixels
+            .chunks_exact(4)
+            .map(|rgba| u32::from_le_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+            .collect();
+        let pixel_count = packed.len() as u32;
+        let mut rng = Rng(0x2545f491);
+        let samples = (0..4096)
+            .map(|_| {
+                let rgba = &pixels[rng.index(packed.len()) * 4..][..3];
+                [rgba[0], rgba[1], rgba[2]].map(|c| c as f32 / 255.0)
+            })
+            .collect();
+
+        let pixel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pixels"),
+            contents: bytemuck::cast_slice(&packed),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let assignments = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Assignments"),
+            size: pixel_count as wgpu::BufferAddress * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let sums = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cluster Sums"),
+            size: (MAX_CLUSTERS * 4 * 4) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let state = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Centroids"),
+            contents: bytemuck::bytes_of(&State::zeroed()),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Centroid Readback"),
+            size: std::mem::size_of::<State>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("K-Means Params"),
+            contents: bytemuck::bytes_of(&Params::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("K-Means Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("K-Means Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: pixel_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: assignments.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: sums.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: state.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/kmeans.wgsl"));
+        Self {
+            pixel_count,
+            samples,
+            assign_pipeline: compute_pipeline(device, "Assign", &layout, &shader, "cs_assign"),
+            reduce_pipeline: compute_pipeline(device, "Reduce", &layout, &shader, "cs_reduce"),
+            update_pipeline: compute_pipeline(device, "Update Centroids", &layout, &shader, "cs_update"),
+            params_buffer,
+            pixels: pixel_buffer,
+            assignments,
+            sums,
+            state,
+            bind_group,
+            readback,
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            stale: false,
+            clusters: 0,
+            iterations: 0,
+            latest: State::zeroed(),
+            converged: false,
+        }
+    }
+
+    // Starts over with `clusters` centroids seeded k-means++ style: each
+    // new one picked with odds growing with its squared distance from the
+    // ones so far, so they start spread over the colors actually used
+    pub fn restart(&mut self, queue: &Queue, encoder: &mut CommandEncoder, clusters: usize, seed: u32) {
+        let mut rng = Rng(seed.max(1));
+        let mut state = State::zeroed();
+        let first = self.samples[rng.index(self.samples.len())];
+        state.centroids[0] = [first[0], first[1], first[2], 0.0];
+        for k in 1..clusters {
+            let weights: Vec<f32> = self
+                .samples
+                .iter()
+                .map(|sample| {
+                    state.centroids[..k]
+                        .iter()
+                        .map(|centroid| (0..3).map(|c| (sample[c] - centroid[c]).powi(2)).sum::<f32>())
+                        .fold(f32::MAX, f32::min)
+                })
+                .collect();
+            let mut pick = rng.next_f32() * weights.iter().sum::<f32>();
+            let mut chosen = self.samples.len() - 1;
+            for (index, weight) in weights.iter().enumerate() {
+                pick -= weight;
+                if pick <= 0.0 {
+                    chosen = index;
+                    break;
+                }
+            }
+            let color = self.samples[chosen];
+            state.centroids[k] = [color[0], color[1], color[2], 0.0];
+        }
+        queue.write_buffer(&self.state, 0, bytemuck::bytes_of(&state));
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Params {
+                pixel_count: self.pixel_count,
+                clusters: clusters as u32,
+                _padding: [0; 2],
+            }),
+        );
+        encoder.clear_buffer(&self.sums, 0, None);
+        self.clusters = clusters;
+        self.iterations = 0;
+        self.latest = state;
+        self.converged = false;
+        self.stale = self.pending;
+    }
+
+    pub fn iterate(&mut self, encoder: &mut CommandEncoder) {
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("K-Means Pass"),
+            });
+            compute_pass.set_bind_group(0, &self.bind_group, &[]);
+            compute_pass.set_pipeline(&self.assign_pipeline);
+            compute_pass.dispatch_workgroups((self.pixel_count + GROUP_SIZE - 1) / GROUP_SIZE, 1, 1);
+            compute_pass.set_pipeline(&self.reduce_pipeline);
+            compute_pass.dispatch_workgroups(REDUCE_GROUPS, 1, 1);
+            compute_pass.set_pipeline(&self.update_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+        self.iterations += 1;
+
+        // Only one readback in flight; iterations carry on meanwhile
+        if !self.pending {
+            encoder.copy_buffer_to_buffer(&self.state, 0, &self.readback, 0, self.readback.size());
+            self.pending = true;
+            self.needs_map = true;
+        }
+    }
+
+    // Call after submitting the encoder passed to iterate
+    pub fn map_submitted(&mut self) {
+        if !std::mem::take(&mut self.needs_map) {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.readback.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            result.unwrap();
+            mapped.store(true, Ordering::Release);
+        });
+    }
+
+    pub fn poll(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+        let latest: State = *bytemuck::from_bytes(&self.readback.slice(..).get_mapped_range());
+        self.readback.unmap();
+        self.pending = false;
+        if std::mem::take(&mut self.stale) {
+            return;
+        }
+        self.latest = latest;
+        self.converged = self.latest.shift < CONVERGED_SHIFT;
+    }
+}
@@ -0,0 +1,73 @@
+use framework::assets;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 500;
+
+// RGBA8 rows: the file given with --image path.png or asset:name, or a
+// made up meadow at sunset, all smooth gradients and a handful of strong
+// flower colors
+pub fn load() -> (Vec<u8>, u32, u32) {
+    let mut args = std::env::args().skip_while(|arg| arg != "--image");
+    if let Some(path) = args.nth(1) {
+        let image = assets::resolve(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| image::open(file).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                eprintln!("{}: {}", path, error);
+                std::process::exit(1);
+            })
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        return (image.into_raw(), width, height);
+    }
+    (meadow(), WIDTH, HEIGHT)
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
+
+fn meadow() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((WIDTH * HEIGHT * 4) as usize);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (fx, fy) = (x as f32, y as f32);
+            // Sky from deep violet down to orange at the horizon
+            let t = (fy / 260.0).min(1.0);
+            let mut color = mix([0.25, 0.15, 0.45], [1.0, 0.55, 0.2], t * t);
+            let sun = (fx - 560.0).hypot(fy - 230.0);
+            if sun < 45.0 {
+                color = [1.0, 0.85, 0.45];
+            } else if sun < 120.0 {
+                color = mix([1.0, 0.75, 0.35], color, (sun - 45.0) / 75.0);
+            }
+
+            // Two rolling hills, the far one bluer
+            let far = 250.0 + (fx * 0.011).sin() * 25.0;
+            let near = 320.0 + (fx * 0.007 + 2.0).sin() * 40.0;
+            if fy > far {
+                color = mix([0.3, 0.35, 0.4], [0.2, 0.3, 0.25], ((fy - far) / 80.0).min(1.0));
+            }
+            if fy > near {
+                color = mix([0.35, 0.55, 0.2], [0.15, 0.3, 0.1], ((fy - near) / 180.0).min(1.0));
+                // Flowers on a jittered grid, bigger toward the front
+                let size = 4.0 + (fy - near) * 0.04;
+                let cell = [(fx / (size * 3.0)).floor(), (fy / (size * 3.0)).floor()];
+                let hash = (cell[0] as u32).wrapping_mul(73856093) ^ (cell[1] as u32).wrapping_mul(19349663);
+                let center = [
+                    (cell[0] + 0.3 + (hash % 7) as f32 * 0.06) * size * 3.0,
+                    (cell[1] + 0.3 + (hash / 7 % 7) as f32 * 0.06) * size * 3.0,
+                ];
+                let distance = (fx - center[0]).hypot(fy - center[1]);
+                if hash % 5 < 2 && distance < size {
+                    let petals = [[0.9, 0.15, 0.2], [0.95, 0.8, 0.1], [0.3, 0.35, 0.9]];
+                    let shade = 1.0 - distance / size * 0.35;
+                    color = petals[(hash / 49 % 3) as usize].map(|c| c * shade);
+                }
+            }
+            pixels.extend(color.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8));
+            pixels.push(255);
+        }
+    }
+    pixels
+}
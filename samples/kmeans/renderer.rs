@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::kmeans::{KMeans, MAX_CLUSTERS, MAX_ITERATIONS};
+use crate::picture;
+
+const MODES: [&str; 3] = ["quantized", "original", "original beside quantized"];
+// Window pixels
+const STRIP_HEIGHT: f32 = 32.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DisplayParams {
+    width: u32,
+    height: u32,
+    mode: u32,
+    clusters: u32,
+    scale: f32,
+    strip_top: f32,
+    offset: [f32; 2],
+    window_width: f32,
+    _padding: f32,
+}
+
+pub struct Renderer {
+    kmeans: KMeans,
+    width: u32,
+    height: u32,
+    pipeline: RenderPipeline,
+    params_buffer: Buffer,
+    bind_group: BindGroup,
+    clusters: usize,
+    seed: u32,
+    mode: usize,
+    // Paused runs one iteration per step
+    paused: bool,
+    step: bool,
+    needs_restart: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let (pixels, width, height) = picture::load();
+        let kmeans = KMeans::new(device, &pixels);
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display Params"),
+            contents: bytemuck::bytes_of(&DisplayParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: kmeans.pixels.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: kmeans.assignments.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: kmeans.state.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            kmeans,
+            width,
+            height,
+            pipeline,
+            params_buffer,
+            bind_group,
+            clusters: 8,
+            seed: 1,
+            mode: 0,
+            paused: false,
+            step: false,
+            needs_restart: true,
+            held_keys: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Up if self.clusters < MAX_CLUSTERS => {
+                    self.clusters += 1;
+                    self.needs_restart = true;
+                }
+                VirtualKeyCode::Down if self.clusters > 2 => {
+                    self.clusters -= 1;
+                    self.needs_restart = true;
+                }
+                VirtualKeyCode::R => {
+                    self.seed += 1;
+                    self.needs_restart = true;
+                }
+                VirtualKeyCode::Tab => self.mode = (self.mode + 1) % MODES.len(),
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                VirtualKeyCode::N => self.step = true,
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let progress = if self.kmeans.converged {
+            "converged".to_string()
+        } else {
+            format!("largest shift {:.4}", self.kmeans.latest.shift)
+        };
+        Some(format!(
+            "{} clusters, iteration {}, {}, {}{}",
+            self.clusters,
+            self.kmeans.iterations,
+            progress,
+            MODES[self.mode],
+            if self.paused { ", paused" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.kmeans.poll(&gpu.device);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        if std::mem::take(&mut self.needs_restart) {
+            self.kmeans.restart(&gpu.queue, &mut encoder, self.clusters, self.seed);
+            // Once, so there's something to show even when paused
+            self.step = true;
+        }
+        // One iteration a frame, so the palette can be watched settling
+        let running = !self.paused && !self.kmeans.converged && self.kmeans.iterations < MAX_ITERATIONS;
+        if std::mem::take(&mut self.step) || running {
+            self.kmeans.iterate(&mut encoder);
+        }
+
+        // Fit the image above the palette strip
+        let (width, height) = (gpu.config.width as f32, gpu.config.height as f32 - STRIP_HEIGHT);
+        let scale = (width / self.width as f32).min(height / self.height as f32);
+        gpu.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&DisplayParams {
+                width: self.width,
+                height: self.height,
+                mode: self.mode as u32,
+                clusters: self.clusters as u32,
+                scale,
+                strip_top: height,
+                offset: [
+                    (width - self.width as f32 * scale) * 0.5,
+                    (height - self.height as f32 * scale) * 0.5,
+                ],
+                window_width: width,
+                _padding: 0.0,
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.kmeans.map_submitted();
+    }
+}
@@ -0,0 +1,66 @@
+struct Params {
+    width: u32,
+    height: u32,
+    // 0 quantized, 1 the original, 2 original on the left, quantized right
+    mode: u32,
+    clusters: u32,
+    // Window pixels per image pixel, and where the image starts
+    scale: f32,
+    // Where the palette strip starts, in window pixels
+    strip_top: f32,
+    offset: vec2<f32>,
+    window_width: f32,
+    _padding: f32,
+}
+
+struct State {
+    centroids: array<vec4<f32>, 16>,
+    shift: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read> pixels: array<u32>;
+@group(0) @binding(2)
+var<storage, read> assignments: array<u32>;
+@group(0) @binding(3)
+var<storage, read> state: State;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+// The colors are sRGB and the surface expects linear
+fn output(color: vec3<f32>) -> vec4<f32> {
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // The palette along the bottom, each color as wide as its share
+    if position.y >= params.strip_top {
+        var start = 0.0;
+        let x = position.x / params.window_width;
+        for (var k = 0u; k < params.clusters; k++) {
+            start += state.centroids[k].w;
+            if x < start {
+                return output(state.centroids[k].rgb);
+            }
+        }
+        return output(vec3<f32>(0.1));
+    }
+
+    let pixel = (position.xy - params.offset) / params.scale;
+    if pixel.x < 0.0 || pixel.y < 0.0 || u32(pixel.x) >= params.width || u32(pixel.y) >= params.height {
+        return output(vec3<f32>(0.1));
+    }
+    let index = u32(pixel.y) * params.width + u32(pixel.x);
+    let original = params.mode == 1u || (params.mode == 2u && u32(pixel.x) < params.width / 2u);
+    if original {
+        return output(unpack4x8unorm(pixels[index]).rgb);
+    }
+    return output(state.centroids[assignments[index]].rgb);
+}
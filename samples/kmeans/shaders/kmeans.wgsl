@@ -0,0 +1,121 @@
+const MAX_CLUSTERS: u32 = 16u;
+const GROUP_SIZE: u32 = 256u;
+
+struct Params {
+    pixel_count: u32,
+    clusters: u32,
+    _padding: vec2<u32>,
+}
+
+struct State {
+    // rgb the mean color in sRGB, w the share of the pixels nearest it
+    centroids: array<vec4<f32>, 16>,
+    // The furthest any centroid moved in the last update
+    shift: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+// RGBA8
+@group(0) @binding(1)
+var<storage, read> pixels: array<u32>;
+// The nearest centroid to each pixel
+@group(0) @binding(2)
+var<storage, read_write> assignments: array<u32>;
+// Per cluster the red, green and blue totals in bytes, then the count
+@group(0) @binding(3)
+var<storage, read_write> sums: array<atomic<u32>>;
+@group(0) @binding(4)
+var<storage, read_write> state: State;
+
+// Distances are taken on the sRGB values directly; they're closer to how
+// different colors look than linear ones
+@compute @workgroup_size(256)
+fn cs_assign(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.pixel_count {
+        return;
+    }
+    let color = unpack4x8unorm(pixels[id.x]).rgb;
+    var nearest = 0u;
+    var nearest_distance = 1e9;
+    for (var k = 0u; k < params.clusters; k++) {
+        let offset = color - state.centroids[k].rgb;
+        let distance = dot(offset, offset);
+        if distance < nearest_distance {
+            nearest = k;
+            nearest_distance = distance;
+        }
+    }
+    assignments[id.x] = nearest;
+}
+
+var<workgroup> local_sums: array<atomic<u32>, 64>;
+
+// Each workgroup sums a stride of the pixels into shared memory, then adds
+// its totals to the global ones with one atomic per cluster channel, rather
+// than every pixel fighting over the same 64 words
+@compute @workgroup_size(256)
+fn cs_reduce(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local: u32,
+    @builtin(num_workgroups) groups: vec3<u32>
+) {
+    if local < MAX_CLUSTERS * 4u {
+        atomicStore(&local_sums[local], 0u);
+    }
+    workgroupBarrier();
+
+    for (var i = id.x; i < params.pixel_count; i += groups.x * GROUP_SIZE) {
+        let base = assignments[i] * 4u;
+        let color = pixels[i];
+        atomicAdd(&local_sums[base], color & 0xffu);
+        atomicAdd(&local_sums[base + 1u], (color >> 8u) & 0xffu);
+        atomicAdd(&local_sums[base + 2u], (color >> 16u) & 0xffu);
+        atomicAdd(&local_sums[base + 3u], 1u);
+    }
+    workgroupBarrier();
+
+    if local < MAX_CLUSTERS * 4u {
+        let total = atomicLoad(&local_sums[local]);
+        if total != 0u {
+            atomicAdd(&sums[local], total);
+        }
+    }
+}
+
+var<workgroup> shifts: array<f32, 16>;
+
+// Moves each centroid to the mean of its pixels and clears the sums for
+// the next iteration. A cluster nobody picked keeps its centroid.
+@compute @workgroup_size(16)
+fn cs_update(@builtin(local_invocation_index) k: u32) {
+    var shift = 0.0;
+    if k < params.clusters {
+        let count = atomicLoad(&sums[k * 4u + 3u]);
+        if count > 0u {
+            let total = vec3<f32>(
+                f32(atomicLoad(&sums[k * 4u])),
+                f32(atomicLoad(&sums[k * 4u + 1u])),
+                f32(atomicLoad(&sums[k * 4u + 2u]))
+            );
+            let mean = total / (f32(count) * 255.0);
+            shift = distance(mean, state.centroids[k].rgb);
+            state.centroids[k] = vec4<f32>(mean, f32(count) / f32(params.pixel_count));
+        } else {
+            state.centroids[k].w = 0.0;
+        }
+    }
+    for (var channel = 0u; channel < 4u; channel++) {
+        atomicStore(&sums[k * 4u + channel], 0u);
+    }
+    shifts[k] = shift;
+    workgroupBarrier();
+
+    if k == 0u {
+        var largest = 0.0;
+        for (var i = 0u; i < MAX_CLUSTERS; i++) {
+            largest = max(largest, shifts[i]);
+        }
+        state.shift = largest;
+    }
+}
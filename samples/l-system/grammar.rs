@@ -0,0 +1,108 @@
+// Expansion stops before a string grows past this, deep iteration counts
+// on bushy rules blow up exponentially
+const MAX_SYMBOLS: usize = 250_000;
+
+// An L-system in the turtle alphabet plant.rs interprets:
+//   F        a branch segment forward
+//   + -      turn left / right
+//   & ^      pitch down / up
+//   \ /      roll left / right
+//   [ ]      push / pop the turtle
+//   !        thinner branches from here on
+//   L        a leaf
+// Any other symbol only takes part in rewriting.
+#[derive(Clone, Debug)]
+pub struct Grammar {
+    pub axiom: String,
+    pub rules: Vec<(char, String)>,
+    // Degrees
+    pub angle: f32,
+    pub iterations: u32,
+}
+
+pub struct Preset {
+    pub name: &'static str,
+    pub text: &'static str,
+    pub angle: f32,
+    pub iterations: u32,
+}
+
+// Mostly after The Algorithmic Beauty of Plants
+pub const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "bush",
+        text: "A A=[&FL!A]/////[&FL!A]///////[&FL!A] F=S/////F S=FL",
+        angle: 22.5,
+        iterations: 6,
+    },
+    Preset {
+        name: "tree",
+        text: "FFA A=!F[&FLA]/////[&FLA]//////[&FLA]",
+        angle: 32.0,
+        iterations: 6,
+    },
+    Preset {
+        name: "weed",
+        text: "X X=F-[[XL]+XL]+F[+FXL]-XL F=FF",
+        angle: 22.5,
+        iterations: 5,
+    },
+    Preset {
+        name: "spiral",
+        text: "A A=F[&FL][^^!A]//F[&FL]/A",
+        angle: 30.0,
+        iterations: 10,
+    },
+];
+
+impl Grammar {
+    // Whitespace separated: the axiom, then one `symbol=replacement` per rule
+    pub fn parse(text: &str, angle: f32, iterations: u32) -> Result<Self, String> {
+        let mut tokens = text.split_whitespace();
+        let axiom = tokens.next().ok_or("no axiom")?;
+        if axiom.contains('=') {
+            return Err(format!("axiom \"{}\" looks like a rule", axiom));
+        }
+
+        let mut rules: Vec<(char, String)> = Vec::new();
+        for token in tokens {
+            let mut chars = token.chars();
+            let (symbol, equals) = (chars.next(), chars.next());
+            let symbol = match (symbol, equals) {
+                (Some(symbol), Some('=')) if symbol != '=' => symbol,
+                _ => return Err(format!("\"{}\" isn't symbol=replacement", token)),
+            };
+            if rules.iter().any(|(existing, _)| *existing == symbol) {
+                return Err(format!("two rules for {}", symbol));
+            }
+            rules.push((symbol, chars.collect()));
+        }
+
+        Ok(Self {
+            axiom: axiom.to_string(),
+            rules,
+            angle,
+            iterations,
+        })
+    }
+
+    // Rewrites the axiom `iterations` times, or as many as fit under
+    // MAX_SYMBOLS. Also returns how many were done.
+    pub fn expand(&self) -> (String, u32) {
+        let mut current = self.axiom.clone();
+        for iteration in 0..self.iterations {
+            let mut next = String::with_capacity(current.len() * 2);
+            for symbol in current.chars() {
+                match self.rules.iter().find(|(rule, _)| *rule == symbol) {
+                    Some((_, replacement)) => next.push_str(replacement),
+                    None => next.push(symbol),
+                }
+                if next.len() > MAX_SYMBOLS {
+                    return (current, iteration);
+                }
+            }
+            current = next;
+        }
+        (current, self.iterations)
+    }
+}
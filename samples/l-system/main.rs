@@ -0,0 +1,9 @@
+mod grammar;
+mod plant;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("l-system");
+}
@@ -0,0 +1,170 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Quat, Vec3};
+
+use crate::grammar::Grammar;
+
+const SEGMENT_LENGTH: f32 = 1.0;
+const TRUNK_RADIUS: f32 = 0.12;
+// What `!` multiplies the radius by
+const THINNING: f32 = 0.7;
+// Segments taper a little along their length on top of that
+const TAPER: f32 = 0.92;
+const LEAF_SIZE: f32 = 0.6;
+// Turns vary by up to this fraction so the plant doesn't look machined
+const JITTER: f32 = 0.15;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Branch {
+    // w is the radius at either end
+    pub start: [f32; 4],
+    pub end: [f32; 4],
+}
+
+impl Branch {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![1 => Float32x4, 2 => Float32x4];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Branch>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct Leaf {
+    // Where it's attached, w is its length
+    pub position: [f32; 4],
+    // Base to tip, w offsets its flutter so leaves don't move in lockstep
+    pub direction: [f32; 4],
+    pub normal: [f32; 4],
+}
+
+impl Leaf {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![1 => Float32x4, 2 => Float32x4, 3 => Float32x4];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Leaf>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Turtle {
+    position: Vec3,
+    // Heading along local +y, left along +x, up along +z
+    orientation: Quat,
+    radius: f32,
+}
+
+impl Turtle {
+    // About one of its own axes
+    fn turn(&mut self, axis: Vec3, angle: f32) {
+        self.orientation *= Quat::from_axis_angle(axis, angle);
+    }
+}
+
+pub struct Plant {
+    pub branches: Vec<Branch>,
+    pub leaves: Vec<Leaf>,
+    // Top of the highest branch or leaf
+    pub height: f32,
+    // Furthest anything reaches from the trunk sideways
+    pub spread: f32,
+    pub symbols: usize,
+    pub iterations: u32,
+}
+
+impl Plant {
+    pub fn grow(grammar: &Grammar) -> Self {
+        let (symbols, iterations) = grammar.expand();
+        let mut rng = Rng(0x2545_f491);
+        let angle = grammar.angle.to_radians();
+
+        let mut turtle = Turtle {
+            position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            radius: TRUNK_RADIUS,
+        };
+        let mut stack = Vec::new();
+        let mut branches = Vec::new();
+        let mut leaves = Vec::new();
+
+        for symbol in symbols.chars() {
+            let turn = angle * (1.0 + (rng.next_f32() * 2.0 - 1.0) * JITTER);
+            match symbol {
+                'F' => {
+                    let end = turtle.position + turtle.orientation * Vec3::Y * SEGMENT_LENGTH;
+                    branches.push(Branch {
+                        start: turtle.position.extend(turtle.radius).to_array(),
+                        end: end.extend(turtle.radius * TAPER).to_array(),
+                    });
+                    turtle.position = end;
+                    turtle.radius *= TAPER;
+                }
+                '+' => turtle.turn(Vec3::Z, turn),
+                '-' => turtle.turn(Vec3::Z, -turn),
+                '&' => turtle.turn(Vec3::X, turn),
+                '^' => turtle.turn(Vec3::X, -turn),
+                '\\' => turtle.turn(Vec3::Y, turn),
+                '/' => turtle.turn(Vec3::Y, -turn),
+                '[' => stack.push(turtle),
+                ']' => {
+                    if let Some(popped) = stack.pop() {
+                        turtle = popped;
+                    }
+                }
+                '!' => turtle.radius *= THINNING,
+                'L' => {
+                    // Angled off the twig rather than straight along it
+                    let tilt = Quat::from_rotation_x(0.8);
+                    let orientation = turtle.orientation * tilt;
+                    leaves.push(Leaf {
+                        position: turtle.position.extend(LEAF_SIZE).to_array(),
+                        direction: (orientation * Vec3::Y).extend(rng.next_f32() * 100.0).to_array(),
+                        normal: (orientation * Vec3::Z).extend(0.0).to_array(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let points = branches
+            .iter()
+            .map(|branch| Vec3::from_slice(&branch.end[..3]))
+            .chain(leaves.iter().map(|leaf| Vec3::from_slice(&leaf.position[..3])));
+        let (mut height, mut spread) = (SEGMENT_LENGTH, SEGMENT_LENGTH);
+        for point in points {
+            height = height.max(point.y);
+            spread = spread.max(Vec3::new(point.x, 0.0, point.z).length());
+        }
+
+        Self {
+            branches,
+            leaves,
+            height,
+            spread,
+            symbols: symbols.len(),
+            iterations,
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, Buffer, Device, RenderPipeline, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::grammar::{Grammar, PRESETS};
+use crate::plant::{Branch, Leaf, Plant};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const BRANCH_SIDES: u16 = 8;
+const MAX_ITERATIONS: u32 = 12;
+const ORBIT_SPEED: f32 = 0.8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    camera: [f32; 4],
+    wind: [f32; 4],
+    plant: [f32; 4],
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// An open tube from y = 0 to 1, vertices are (cos, sin, y) around it
+fn branch_mesh() -> (Vec<[f32; 3]>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    for y in 0..2 {
+        for side in 0..=BRANCH_SIDES {
+            let angle = side as f32 / BRANCH_SIDES as f32 * std::f32::consts::TAU;
+            vertices.push([angle.cos(), angle.sin(), y as f32]);
+        }
+    }
+    let mut indices = Vec::new();
+    for side in 0..BRANCH_SIDES {
+        let (bottom, top) = (side, side + BRANCH_SIDES + 1);
+        indices.extend([bottom, bottom + 1, top + 1, bottom, top + 1, top]);
+    }
+    (vertices, indices)
+}
+
+// Instances for one grown plant, rebuilt whenever the grammar changes
+struct PlantBuffers {
+    branch_buffer: Buffer,
+    branch_count: u32,
+    leaf_buffer: Buffer,
+    leaf_count: u32,
+    height: f32,
+    spread: f32,
+    symbols: usize,
+    iterations: u32,
+}
+
+impl PlantBuffers {
+    fn grow(device: &Device, grammar: &Grammar) -> Self {
+        let plant = Plant::grow(grammar);
+        // Never empty, a zero sized vertex buffer can't be bound
+        let instances = |label, contents: &[u8], size| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: if contents.is_empty() { &[0; 64][..size] } else { contents },
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        };
+
+        Self {
+            branch_buffer: instances("Branches", bytemuck::cast_slice(&plant.branches), std::mem::size_of::<Branch>()),
+            branch_count: plant.branches.len() as u32,
+            leaf_buffer: instances("Leaves", bytemuck::cast_slice(&plant.leaves), std::mem::size_of::<Leaf>()),
+            leaf_count: plant.leaves.len() as u32,
+            height: plant.height,
+            spread: plant.spread,
+            symbols: plant.symbols,
+            iterations: plant.iterations,
+        }
+    }
+}
+
+pub struct Renderer {
+    branch_pipeline: RenderPipeline,
+    leaf_pipeline: RenderPipeline,
+    ground_pipeline: RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: Buffer,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    plant: PlantBuffers,
+    depth_view: TextureView,
+    preset: usize,
+    // The grammar as typed so far, and the one the plant was grown from
+    text: String,
+    grammar: Grammar,
+    error: Option<String>,
+    regrow: bool,
+    wind: f32,
+    orbit: f32,
+    // Set by --flythrough or a script, orbiting with the arrow keys until then
+    camera: Option<CameraPose>,
+    // Smoothed towards the plant's size so a regrown plant doesn't jump
+    framing: f32,
+    start: Instant,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let (vertices, indices) = branch_mesh();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Branch Vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Branch Indices"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Plant Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Plant Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/plant.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+        let render_pipeline = |label, vs_entry, buffers: &[wgpu::VertexBufferLayout], cull_mode| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: vs_entry,
+                    buffers,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: gpu.config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+        let branch_vertex = wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+        };
+        let branch_pipeline = render_pipeline(
+            "Branch Pipeline",
+            "vs_branch",
+            &[branch_vertex, Branch::desc()],
+            Some(wgpu::Face::Back),
+        );
+        // Leaves are flat, so both of their sides are drawn
+        let leaf_pipeline = render_pipeline("Leaf Pipeline", "vs_leaf", &[Leaf::desc()], None);
+        let ground_pipeline = render_pipeline("Ground Pipeline", "vs_ground", &[], None);
+
+        let preset = 0;
+        let grammar = preset_grammar(preset);
+        let plant = PlantBuffers::grow(device, &grammar);
+        let framing = plant.height.max(plant.spread * 2.0);
+
+        Self {
+            branch_pipeline,
+            leaf_pipeline,
+            ground_pipeline,
+            bind_group,
+            uniform_buffer,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            plant,
+            depth_view: create_depth_view(device, &gpu.config),
+            preset,
+            text: PRESETS[preset].text.to_string(),
+            grammar,
+            error: None,
+            regrow: false,
+            wind: 0.5,
+            orbit: 0.0,
+            camera: None,
+            framing,
+            start: Instant::now(),
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            // Typing edits the grammar, so every other control is on keys
+            // that don't produce characters
+            WindowEvent::ReceivedCharacter(c) => match c {
+                '\u{8}' | '\u{7f}' => {
+                    self.text.pop();
+                }
+                '\r' | '\n' => self.apply_text(),
+                c if !c.is_control() => self.text.push(*c),
+                _ => {}
+            },
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                if *state == ElementState::Released {
+                    self.held_keys.remove(key);
+                    return;
+                }
+                // Key repeat keeps sending presses, only the first one counts
+                if !self.held_keys.insert(*key) {
+                    return;
+                }
+                match key {
+                    VirtualKeyCode::Tab => {
+                        self.preset = (self.preset + 1) % PRESETS.len();
+                        self.text = PRESETS[self.preset].text.to_string();
+                        self.grammar = preset_grammar(self.preset);
+                        self.error = None;
+                        self.regrow = true;
+                    }
+                    // Throws away an edit that hasn't been applied yet
+                    VirtualKeyCode::Escape => {
+                        self.text = grammar_text(&self.grammar);
+                        self.error = None;
+                    }
+                    VirtualKeyCode::PageUp => {
+                        self.grammar.iterations = (self.grammar.iterations + 1).min(MAX_ITERATIONS);
+                        self.regrow = true;
+                    }
+                    VirtualKeyCode::PageDown => {
+                        self.grammar.iterations = self.grammar.iterations.saturating_sub(1);
+                        self.regrow = true;
+                    }
+                    VirtualKeyCode::Home => {
+                        self.grammar.angle = (self.grammar.angle + 2.5).min(90.0);
+                        self.regrow = true;
+                    }
+                    VirtualKeyCode::End => {
+                        self.grammar.angle = (self.grammar.angle - 2.5).max(0.0);
+                        self.regrow = true;
+                    }
+                    VirtualKeyCode::Up => self.wind = (self.wind + 0.25).min(3.0),
+                    VirtualKeyCode::Down => self.wind = (self.wind - 0.25).max(0.0),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        let result = match &self.error {
+            Some(error) => format!("error: {}", error),
+            None => format!(
+                "{} iterations at {:.1} degrees, {} symbols, {} branches, {} leaves, wind {:.2}",
+                if self.plant.iterations < self.grammar.iterations {
+                    format!("{} of {}", self.plant.iterations, self.grammar.iterations)
+                } else {
+                    self.plant.iterations.to_string()
+                },
+                self.grammar.angle,
+                self.plant.symbols,
+                self.plant.branch_count,
+                self.plant.leaf_count,
+                self.wind,
+            ),
+        };
+        // Named until it's been edited
+        let name = if self.text == PRESETS[self.preset].text { PRESETS[self.preset].name } else { "custom" };
+        Some(format!("{}: {}_ - {}", name, self.text, result))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        if std::mem::take(&mut self.regrow) {
+            self.plant = PlantBuffers::grow(&gpu.device, &self.grammar);
+        }
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        self.orbit += turn * ORBIT_SPEED * dt;
+
+        let target_framing = self.plant.height.max(self.plant.spread * 2.0);
+        self.framing = self.framing * 0.95 + target_framing * 0.05;
+        let center = Vec3::new(0.0, self.framing * 0.45, 0.0);
+        let (eye, target) = match &self.camera {
+            Some(pose) => (pose.position, pose.position + pose.direction()),
+            None => (center + Vec3::new(self.orbit.sin(), 0.15, self.orbit.cos()) * (self.framing * 1.3 + 2.0), center),
+        };
+        let view_matrix = Mat4::look_at_rh(eye, target, Vec3::Y);
+        let proj = Mat4::perspective_rh(45f32.to_radians(), gpu.aspect_ratio(), 0.1, 1000.0);
+        let time = (now - self.start).as_secs_f32();
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: (proj * view_matrix).to_cols_array_2d(),
+                camera: eye.extend(1.0).to_array(),
+                wind: [0.8, 0.6, self.wind, time],
+                plant: [self.plant.height, 0.0, 0.0, 0.0],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                // Same as the fog in the shader
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.55,
+                                    g: 0.65,
+                                    b: 0.8,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_pipeline(&self.ground_pipeline);
+            render_pass.draw(0..6, 0..1);
+
+            render_pass.set_pipeline(&self.branch_pipeline);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.plant.branch_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..self.index_count, 0, 0..self.plant.branch_count);
+
+            render_pass.set_pipeline(&self.leaf_pipeline);
+            render_pass.set_vertex_buffer(0, self.plant.leaf_buffer.slice(..));
+            render_pass.draw(0..6, 0..self.plant.leaf_count);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    // Grows the typed grammar, or keeps the current plant and shows why not
+    fn apply_text(&mut self) {
+        match Grammar::parse(&self.text, self.grammar.angle, self.grammar.iterations) {
+            Ok(grammar) => {
+                self.grammar = grammar;
+                self.error = None;
+                self.regrow = true;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+fn preset_grammar(index: usize) -> Grammar {
+    let preset = &PRESETS[index];
+    Grammar::parse(preset.text, preset.angle, preset.iterations).unwrap()
+}
+
+fn grammar_text(grammar: &Grammar) -> String {
+    let mut text = grammar.axiom.clone();
+    for (symbol, replacement) in &grammar.rules {
+        text.push_str(&format!(" {}={}", symbol, replacement));
+    }
+    text
+}
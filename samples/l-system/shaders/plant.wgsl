@@ -0,0 +1,133 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    camera: vec4<f32>,
+    // xy is the direction across the ground, z the strength, w the time
+    wind: vec4<f32>,
+    // x is the plant's height
+    plant: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+const GROUND_EXTENT: f32 = 200.0;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+}
+
+// Bends everything downwind, more the higher up it is. It only depends on
+// the position, so where segments meet they still meet after swaying.
+fn sway(position: vec3<f32>) -> vec3<f32> {
+    let height = max(uniforms.plant.x, 0.001);
+    let bend = clamp(position.y / height, 0.0, 1.5);
+    let time = uniforms.wind.w;
+    let gust = sin(time * 1.3 + position.x * 0.2) + 0.4 * sin(time * 3.7 + position.z * 0.9);
+    let amount = uniforms.wind.z * bend * bend * (0.6 + 0.4 * gust) * height * 0.08;
+    return vec3<f32>(uniforms.wind.x, 0.0, uniforms.wind.y) * amount;
+}
+
+fn output(world: vec3<f32>, normal: vec3<f32>, color: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world, 1.0);
+    out.world = world;
+    out.normal = normal;
+    out.color = color;
+    return out;
+}
+
+struct BranchVertex {
+    // cos and sin around the branch, then 0 at the start and 1 at the end
+    @location(0) ring: vec3<f32>,
+}
+
+struct BranchInstance {
+    @location(1) start: vec4<f32>,
+    @location(2) end: vec4<f32>,
+}
+
+@vertex
+fn vs_branch(vertex: BranchVertex, instance: BranchInstance) -> VertexOutput {
+    let axis = instance.end.xyz - instance.start.xyz;
+    let direction = normalize(axis);
+    var helper = vec3<f32>(0.0, 0.0, 1.0);
+    if abs(direction.z) > 0.9 {
+        helper = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let u = normalize(cross(direction, helper));
+    let v = cross(direction, u);
+
+    let normal = u * vertex.ring.x + v * vertex.ring.y;
+    let radius = mix(instance.start.w, instance.end.w, vertex.ring.z);
+    let position = instance.start.xyz + axis * vertex.ring.z + normal * radius;
+    return output(position + sway(position), normal, vec3<f32>(0.32, 0.22, 0.14));
+}
+
+struct LeafInstance {
+    @location(1) position: vec4<f32>,
+    @location(2) direction: vec4<f32>,
+    @location(3) normal: vec4<f32>,
+}
+
+@vertex
+fn vs_leaf(@builtin(vertex_index) index: u32, instance: LeafInstance) -> VertexOutput {
+    // A diamond from the stem to the tip
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.3, 0.4),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(-0.3, 0.4)
+    );
+    let corner = corners[index];
+    let size = instance.position.w;
+    let direction = instance.direction.xyz;
+    let side = cross(direction, instance.normal.xyz);
+
+    // Flutters about its stem on top of swaying with the twig it's on
+    let phase = instance.direction.w;
+    let flutter = sin(uniforms.wind.w * 7.0 + phase) * 0.5 * uniforms.wind.z;
+    let position = instance.position.xyz
+        + (direction * corner.y + side * corner.x + instance.normal.xyz * corner.y * flutter) * size;
+    let world = position + sway(instance.position.xyz);
+
+    let color = mix(vec3<f32>(0.12, 0.35, 0.08), vec3<f32>(0.35, 0.5, 0.1), fract(phase * 0.137));
+    return output(world, instance.normal.xyz, color);
+}
+
+@vertex
+fn vs_ground(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(-1.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, -1.0)
+    );
+    let corner = corners[index] * GROUND_EXTENT;
+    return output(vec3<f32>(corner.x, 0.0, corner.y), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.3, 0.27, 0.2));
+}
+
+@fragment
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    // Leaves are seen from both sides
+    var normal = normalize(in.normal);
+    if !front_facing {
+        normal = -normal;
+    }
+    let sun = normalize(vec3<f32>(0.5, 0.8, 0.3));
+    let diffuse = max(dot(normal, sun), 0.0);
+    let sky = mix(vec3<f32>(0.25, 0.22, 0.18), vec3<f32>(0.45, 0.55, 0.7), normal.y * 0.5 + 0.5);
+    var color = in.color * (diffuse * vec3<f32>(1.0, 0.95, 0.85) + sky * 0.5);
+
+    // Fade into the clear color far away
+    let distance = length(in.world - uniforms.camera.xyz);
+    let fog = 1.0 - exp(-distance * 0.01);
+    color = mix(color, vec3<f32>(0.55, 0.65, 0.8), fog);
+    return vec4<f32>(color, 1.0);
+}
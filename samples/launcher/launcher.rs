@@ -0,0 +1,128 @@
+use framework::{scene, CameraPose, Gpu, Sample, SceneFactory, SceneStack};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::{compute_particles, hello_triangle, hello_triangle_msaa, msaa_depth};
+
+// Picked with the number keys, in this order
+const SCENES: &[(&str, SceneFactory)] = &[
+    ("hello-triangle", scene::<hello_triangle::Renderer>),
+    ("hello-triangle-msaa", scene::<hello_triangle_msaa::Renderer>),
+    ("msaa-depth", scene::<msaa_depth::Renderer>),
+    ("compute-particles", scene::<compute_particles::Renderer>),
+];
+
+// 1-4 replace the current scene, Tab pushes the next one on top of it and
+// Escape pops back to the one underneath. Everything else goes to the scene.
+pub struct Launcher {
+    stack: SceneStack,
+    current: usize,
+    // Stack operations need the device, but update() doesn't get one
+    pending: Option<Command>,
+}
+
+enum Command {
+    Switch(usize),
+    Push(usize),
+    Pop,
+}
+
+impl Sample for Launcher {
+    // The union of what the scenes ask for; compute-particles wants compute
+    fn required_limits() -> wgpu::Limits {
+        wgpu::Limits::downlevel_defaults()
+    }
+
+    fn init(_gpu: &Gpu) -> Self {
+        // Like every other stack operation, the first push happens in
+        // render() so the leak check compares like with like
+        Self {
+            stack: SceneStack::default(),
+            current: 0,
+            pending: Some(Command::Switch(0)),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.stack.reinit_surface_resources(gpu);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            let command = match key {
+                VirtualKeyCode::Key1 => Some(Command::Switch(0)),
+                VirtualKeyCode::Key2 => Some(Command::Switch(1)),
+                VirtualKeyCode::Key3 => Some(Command::Switch(2)),
+                VirtualKeyCode::Key4 => Some(Command::Switch(3)),
+                VirtualKeyCode::Tab => Some(Command::Push((self.current + 1) % SCENES.len())),
+                VirtualKeyCode::Escape => Some(Command::Pop),
+                _ => None,
+            };
+            if command.is_some() {
+                self.pending = command;
+                return;
+            }
+        }
+
+        if let Some(scene) = self.stack.top_mut() {
+            scene.update(event);
+        }
+    }
+
+    // --flythrough and parameter changes drive whichever scene is on top
+    fn set_camera(&mut self, pose: &CameraPose) {
+        if let Some(scene) = self.stack.top_mut() {
+            scene.set_camera(pose);
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        match self.stack.top_mut() {
+            Some(scene) => scene.set_parameter(name, value),
+            None => false,
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let name = self.stack.top_name()?;
+        let status = match self.stack.top().and_then(|scene| scene.status()) {
+            Some(status) => format!("{} - {}", name, status),
+            None => name.to_string(),
+        };
+        Some(format!("{} (stack depth {})", status, self.stack.len()))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        match self.pending.take() {
+            Some(Command::Switch(index)) => {
+                let (name, factory) = SCENES[index];
+                self.stack.switch(gpu, name, factory);
+                self.current = index;
+            }
+            Some(Command::Push(index)) => {
+                let (name, factory) = SCENES[index];
+                self.stack.push(gpu, name, factory);
+                self.current = index;
+            }
+            Some(Command::Pop) => {
+                // pop() refuses to take the last scene
+                if self.stack.pop(gpu) {
+                    let name = self.stack.top_name();
+                    self.current = SCENES.iter().position(|(scene, _)| Some(*scene) == name).unwrap();
+                }
+            }
+            None => {}
+        }
+
+        if let Some(scene) = self.stack.top_mut() {
+            scene.render(gpu, view);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+// Every sample whose renderer is a single file, hosted as scenes of one
+// process instead of one binary each
+#[path = "../compute-particles/renderer.rs"]
+mod compute_particles;
+#[path = "../hello-triangle/renderer.rs"]
+mod hello_triangle;
+#[path = "../hello-triangle-msaa/renderer.rs"]
+mod hello_triangle_msaa;
+mod launcher;
+#[path = "../msaa-depth/renderer.rs"]
+mod msaa_depth;
+
+use crate::launcher::Launcher;
+
+fn main() {
+    framework::run_sample::<Launcher>("launcher");
+}
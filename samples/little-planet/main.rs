@@ -0,0 +1,8 @@
+mod panorama;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("little-planet");
+}
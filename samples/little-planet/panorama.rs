@@ -0,0 +1,68 @@
+use std::f32::consts::PI;
+
+use framework::assets;
+use glam::Vec3;
+
+const WIDTH: u32 = 2048;
+
+// RGBA8 rows of an equirectangular 360 panorama: the file given with
+// --panorama path.png or asset:name, or a made up town square without one
+pub fn load() -> (Vec<u8>, u32, u32) {
+    let mut args = std::env::args().skip_while(|arg| arg != "--panorama");
+    if let Some(path) = args.nth(1) {
+        let image = assets::resolve(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| image::open(file).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                eprintln!("{}: {}", path, error);
+                std::process::exit(1);
+            })
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        return (image.into_raw(), width, height);
+    }
+    (town(), WIDTH, WIDTH / 2)
+}
+
+// Cheap hash of a column index, for building heights and colors
+fn hash(i: u32) -> f32 {
+    let x = (i as f32 * 12.9898).sin() * 43758.547;
+    x - x.floor()
+}
+
+fn town() -> Vec<u8> {
+    let height = WIDTH / 2;
+    let buildings = 48;
+
+    let mut pixels = Vec::with_capacity((WIDTH * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..WIDTH {
+            let u = (x as f32 + 0.5) / WIDTH as f32;
+            let lat = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+
+            let building = (u * buildings as f32) as u32;
+            let within = u * buildings as f32 - building as f32;
+            let roof = (0.05 + hash(building) * 0.45) * PI / 2.0;
+
+            let color = if lat < -0.35 {
+                // Cobblestones right under the camera
+                let stones = (((u * 400.0).floor() + (lat * 120.0).floor()) as i32).rem_euclid(2) == 0;
+                if stones { Vec3::new(0.45, 0.42, 0.4) } else { Vec3::new(0.38, 0.36, 0.34) }
+            } else if lat < 0.0 {
+                Vec3::new(0.3, 0.55, 0.25)
+            } else if lat < roof && within > 0.08 && within < 0.92 {
+                let wall = Vec3::new(0.6 + hash(building + 100) * 0.35, 0.45 + hash(building + 200) * 0.3, 0.35);
+                // A grid of windows
+                let window_column = (within * 6.0).fract() > 0.5;
+                let window_row = (lat * 40.0).fract() > 0.5;
+                if window_column && window_row && lat > 0.03 { Vec3::new(0.2, 0.25, 0.35) } else { wall }
+            } else {
+                Vec3::new(0.85, 0.9, 0.95).lerp(Vec3::new(0.25, 0.45, 0.85), (lat / (PI / 2.0)).max(0.0))
+            };
+
+            let color = (color * 255.0).to_array();
+            pixels.extend([color[0] as u8, color[1] as u8, color[2] as u8, 255]);
+        }
+    }
+    pixels
+}
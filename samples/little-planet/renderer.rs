@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::envmap::{cube_view, EnvironmentTools};
+use framework::{Gpu, Sample};
+use glam::{Mat4, Quat};
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+use crate::panorama;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 20.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    rotation: [[f32; 4]; 4],
+    aspect: f32,
+    zoom: f32,
+    invert: f32,
+    _padding: f32,
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    zoom: f32,
+    spin: f32,
+    tilt: f32,
+    // Eases towards 0 (little planet) or 1 (rabbit hole)
+    invert: f32,
+    inverted: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let (pixels, width, height) = panorama::load();
+        let equirect = device.create_texture_with_data(
+            &gpu.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Panorama"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &pixels,
+        );
+
+        // Into a cubemap with mips: the outer ring of the planet squeezes
+        // half the panorama into a few pixels and needs the blurrier levels
+        let tools = EnvironmentTools::new(device);
+        let mut encoder =
+            device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Panorama Encoder"),
+                },
+            );
+        let face_size = (width / 4).next_power_of_two().min(1024);
+        let cubemap = tools.equirect_to_cubemap(
+            device,
+            &mut encoder,
+            &equirect.create_view(&wgpu::TextureViewDescriptor::default()),
+            face_size,
+            Quat::IDENTITY,
+        );
+        tools.generate_mips(device, &mut encoder, &cubemap);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Panorama Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Planet Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Planet Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&cube_view(&cubemap)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/planet.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            render_pipeline,
+            uniform_buffer,
+            bind_group,
+            zoom: 2.5,
+            spin: 0.0,
+            tilt: 0.0,
+            invert: 0.0,
+            inverted: false,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => {
+                if *state == ElementState::Pressed {
+                    // Key repeat keeps sending presses, only the first one toggles
+                    if self.held_keys.insert(*key) && *key == VirtualKeyCode::Space {
+                        self.inverted = !self.inverted;
+                    }
+                } else {
+                    self.held_keys.remove(key);
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+                };
+                self.zoom = (self.zoom * 0.9f32.powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+            }
+            _ => {}
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{}, zoom {:.1}, tilt {:.0} degrees",
+            if self.inverted { "rabbit hole" } else { "little planet" },
+            self.zoom,
+            self.tilt.to_degrees(),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32();
+        self.last_frame = now;
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut spin_speed = 0.1;
+        if held(VirtualKeyCode::Left) { spin_speed -= 1.0; }
+        if held(VirtualKeyCode::Right) { spin_speed += 1.0; }
+        let mut tilt_speed = 0.0;
+        if held(VirtualKeyCode::Up) { tilt_speed += 0.8; }
+        if held(VirtualKeyCode::Down) { tilt_speed -= 0.8; }
+        let mut zoom_speed = 0.0;
+        if held(VirtualKeyCode::Q) { zoom_speed += 1.0; }
+        if held(VirtualKeyCode::E) { zoom_speed -= 1.0; }
+
+        self.spin += spin_speed * dt;
+        self.tilt = (self.tilt + tilt_speed * dt).clamp(-1.2, 1.2);
+        self.zoom = (self.zoom * (zoom_speed * dt).exp()).clamp(MIN_ZOOM, MAX_ZOOM);
+        let target = if self.inverted { 1.0 } else { 0.0 };
+        self.invert += (target - self.invert) * (dt * 4.0).min(1.0);
+
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                rotation: (Mat4::from_rotation_y(self.spin) * Mat4::from_rotation_x(self.tilt)).to_cols_array_2d(),
+                aspect: gpu.aspect_ratio(),
+                zoom: self.zoom,
+                invert: self.invert,
+                _padding: 0.0,
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
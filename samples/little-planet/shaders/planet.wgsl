@@ -0,0 +1,53 @@
+struct Uniforms {
+    // Turns the sphere before projecting, the planet's spin and tilt
+    rotation: mat4x4<f32>,
+    aspect: f32,
+    // Plane units per half screen height, larger shows more of the sphere
+    zoom: f32,
+    // 1 looks up instead of down: the rabbit hole
+    invert: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var panorama: texture_cube<f32>;
+@group(0) @binding(2)
+var panorama_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+// One triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(in_vertex_index & 1u) * 4.0 - 1.0, f32(in_vertex_index >> 1u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+// Inverse stereographic projection. The unit sphere sits on the plane at
+// its south pole and is projected from the north pole, so a point at
+// distance r from the center comes from the angle 2 * atan(r / 2) away from
+// straight down. The center of the screen is the ground under our feet and
+// the sky wraps around the outside.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let plane = in.ndc * vec2<f32>(uniforms.aspect, 1.0) * uniforms.zoom;
+    let theta = 2.0 * atan(length(plane) / 2.0);
+    let phi = atan2(plane.y, plane.x);
+
+    var dir = vec3<f32>(sin(theta) * cos(phi), -cos(theta), sin(theta) * sin(phi));
+    dir.y = mix(dir.y, -dir.y, uniforms.invert);
+    dir = (uniforms.rotation * vec4<f32>(dir, 0.0)).xyz;
+
+    // Derivatives explode towards the outside of the planet, the cube's
+    // mips keep the sky from sparkling there
+    return textureSample(panorama, panorama_sampler, dir);
+}
@@ -0,0 +1,8 @@
+mod renderer;
+mod track;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("mode7");
+}
@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use glam::Vec2;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::track;
+
+// Texels per second squared
+const ACCELERATION: f32 = 240.0;
+const TOP_SPEED: f32 = 320.0;
+// On the grass
+const TOP_SPEED_OFF_ROAD: f32 = 110.0;
+// Radians per second at full speed
+const STEERING: f32 = 1.8;
+// Snapped rows, the SNES's visible lines
+const SCANLINES: f32 = 224.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    camera: [f32; 2],
+    angle: f32,
+    height: f32,
+    horizon: f32,
+    focal: f32,
+    aspect: f32,
+    scanlines: f32,
+    filtered: f32,
+    lean: f32,
+    _padding: [f32; 2],
+}
+
+pub struct Renderer {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    // Kept to tell road from grass under the kart
+    texels: Vec<u8>,
+    position: Vec2,
+    angle: f32,
+    speed: f32,
+    // Eases toward the steering input, for the kart's lean
+    steer: f32,
+    height: f32,
+    filtered: bool,
+    snap_scanlines: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Renderer {
+    fn off_road(&self) -> bool {
+        let x = (self.position.x.max(0.0) as u32).min(track::SIZE - 1);
+        let y = (self.position.y.max(0.0) as u32).min(track::SIZE - 1);
+        let texel = &self.texels[((y * track::SIZE + x) * 4) as usize..][..3];
+        texel[1] as i32 > texel[0] as i32 + 30
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(include_wgsl!("shaders/mode7.wgsl"));
+
+        let texels = track::generate();
+        let size = wgpu::Extent3d {
+            width: track::SIZE,
+            height: track::SIZE,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Track"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        gpu.queue.write_texture(
+            texture.as_image_copy(),
+            &texels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * track::SIZE),
+                rows_per_image: Some(track::SIZE),
+            },
+            size,
+        );
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Clamped, so past the track's edge the grass goes on forever
+        let nearest_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Nearest Sampler"),
+            ..Default::default()
+        });
+        let linear_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Linear Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                texture_entry(1),
+                sampler_entry(2),
+                texture_entry(3),
+                sampler_entry(4),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&nearest_sampler),
+                },
+                // The same track again, see mode7.wgsl
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(&linear_sampler),
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mode 7 Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (start, angle) = track::start();
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            texels,
+            position: Vec2::from(start),
+            angle,
+            speed: 0.0,
+            steer: 0.0,
+            height: 24.0,
+            filtered: false,
+            snap_scanlines: true,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::F => self.filtered = !self.filtered,
+                VirtualKeyCode::L => self.snap_scanlines = !self.snap_scanlines,
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{:.0} km/h{}, camera {:.0} up, {}, {}",
+            self.speed * 0.5,
+            if self.off_road() { " on the grass" } else { "" },
+            self.height,
+            if self.filtered { "linear" } else { "nearest" },
+            if self.snap_scanlines { "224 scanlines" } else { "every row" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut throttle = 0.0;
+        let mut steering = 0.0;
+        let mut climb = 0.0;
+        if held(VirtualKeyCode::Up) { throttle += 1.0; }
+        if held(VirtualKeyCode::Down) { throttle -= 1.0; }
+        if held(VirtualKeyCode::Right) { steering += 1.0; }
+        if held(VirtualKeyCode::Left) { steering -= 1.0; }
+        if held(VirtualKeyCode::PageUp) { climb += 1.0; }
+        if held(VirtualKeyCode::PageDown) { climb -= 1.0; }
+
+        let top_speed = if self.off_road() { TOP_SPEED_OFF_ROAD } else { TOP_SPEED };
+        self.speed += throttle * ACCELERATION * dt;
+        // Rolling resistance; the grass's lower top speed does the rest
+        self.speed -= self.speed * 0.6 * dt;
+        self.speed = self.speed.clamp(-top_speed * 0.3, top_speed);
+        // Steering bites harder the faster the kart goes
+        self.steer = self.steer * 0.9 + steering * 0.1;
+        self.angle += self.steer * STEERING * (self.speed / TOP_SPEED) * dt;
+        self.position += Vec2::new(self.angle.cos(), self.angle.sin()) * self.speed * dt;
+        self.height = (self.height + climb * 30.0 * dt).clamp(4.0, 200.0);
+
+        // The camera trails the kart a little, as the kart sits low on
+        // the screen rather than under the camera
+        let camera = self.position - Vec2::new(self.angle.cos(), self.angle.sin()) * self.height * 1.25;
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                camera: camera.to_array(),
+                angle: self.angle,
+                height: self.height,
+                horizon: 0.25,
+                focal: 1.2,
+                aspect: gpu.aspect_ratio(),
+                scanlines: if self.snap_scanlines { SCANLINES } else { 0.0 },
+                filtered: if self.filtered { 1.0 } else { 0.0 },
+                lean: self.steer,
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
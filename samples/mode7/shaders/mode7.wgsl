@@ -0,0 +1,112 @@
+struct Uniforms {
+    // In texels of the track
+    camera: vec2<f32>,
+    // Which way the camera faces, radians from the track's x axis
+    angle: f32,
+    // Above the ground, in texels
+    height: f32,
+    // Where the ground meets the sky, in NDC
+    horizon: f32,
+    // Focal length in NDC units, bigger is narrower
+    focal: f32,
+    aspect: f32,
+    // Screen rows snapped to this many scanlines, 0 for every pixel
+    scanlines: f32,
+    // 1 for linear filtering, 0 for nearest
+    filtered: f32,
+    // How far the kart leans into a turn
+    lean: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+// The track is bound once per sampler, GL can't sample one texture with two
+@group(0) @binding(1)
+var track: texture_2d<f32>;
+@group(0) @binding(2)
+var nearest_sampler: sampler;
+@group(0) @binding(3)
+var track_filtered: texture_2d<f32>;
+@group(0) @binding(4)
+var linear_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+fn sky(ndc: vec2<f32>) -> vec3<f32> {
+    var color = mix(vec3<f32>(0.55, 0.75, 1.0), vec3<f32>(0.2, 0.4, 0.85), clamp((ndc.y - uniforms.horizon) * 1.5, 0.0, 1.0));
+    // Mountains far enough away to only turn with the camera, never move
+    let heading = uniforms.angle + atan2(ndc.x * uniforms.aspect, uniforms.focal);
+    let far_ridge = 0.08 + sin(heading * 5.0) * 0.04 + sin(heading * 13.0 + 1.0) * 0.02;
+    let near_ridge = 0.04 + sin(heading * 9.0 + 2.0) * 0.025 + sin(heading * 21.0) * 0.01;
+    let above = ndc.y - uniforms.horizon;
+    if above < far_ridge {
+        color = vec3<f32>(0.45, 0.5, 0.7);
+    }
+    if above < near_ridge {
+        color = vec3<f32>(0.3, 0.45, 0.35);
+    }
+    return color;
+}
+
+// A little kart seen from behind, leaning with the steering
+fn kart(ndc: vec2<f32>) -> vec4<f32> {
+    let p = vec2<f32>((ndc.x * uniforms.aspect - ndc.y * uniforms.lean * 0.3) / 0.12, (ndc.y + 0.72) / 0.12);
+    if abs(p.x) < 1.0 && abs(p.y) < 0.45 {
+        return vec4<f32>(0.85, 0.15, 0.1, 1.0);
+    }
+    if abs(abs(p.x) - 0.85) < 0.22 && p.y < -0.2 && p.y > -0.75 {
+        return vec4<f32>(0.1, 0.1, 0.1, 1.0);
+    }
+    if length(p - vec2<f32>(0.0, 0.75)) < 0.35 {
+        return vec4<f32>(0.95, 0.95, 0.95, 1.0);
+    }
+    return vec4<f32>(0.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var ndc = in.ndc;
+    // The SNES could only change its ground transform between scanlines;
+    // snapping rows to them gives the same stepped look in the distance
+    if uniforms.scanlines > 0.0 {
+        let line_height = 2.0 / uniforms.scanlines;
+        ndc.y = (floor(ndc.y / line_height) + 0.5) * line_height;
+    }
+
+    let driver = kart(in.ndc);
+    if driver.a > 0.0 {
+        return driver;
+    }
+    if ndc.y >= uniforms.horizon {
+        return vec4<f32>(sky(ndc), 1.0);
+    }
+
+    // Per row: how far away the ground is. Per pixel: how far to the side.
+    // The whole effect is these two lines.
+    let distance = uniforms.height * uniforms.focal / (uniforms.horizon - ndc.y);
+    let side = ndc.x * uniforms.aspect * distance / uniforms.focal;
+
+    let forward = vec2<f32>(cos(uniforms.angle), sin(uniforms.angle));
+    let right = vec2<f32>(-forward.y, forward.x);
+    let position = uniforms.camera + forward * distance + right * side;
+    let uv = position / vec2<f32>(textureDimensions(track));
+
+    let nearest = textureSampleLevel(track, nearest_sampler, uv, 0.0).rgb;
+    let linear = textureSampleLevel(track_filtered, linear_sampler, uv, 0.0).rgb;
+    let ground = mix(nearest, linear, uniforms.filtered);
+    // Haze toward the horizon, hiding the worst of the distant shimmer
+    let haze = clamp(distance / 1500.0, 0.0, 1.0);
+    return vec4<f32>(mix(ground, vec3<f32>(0.6, 0.75, 0.95), haze * haze), 1.0);
+}
@@ -0,0 +1,79 @@
+// A racing circuit painted into one texture, the whole world of the sample
+pub const SIZE: u32 = 1024;
+// Texels from the middle of the road to its edge
+const ROAD_HALF_WIDTH: f32 = 34.0;
+const CURB_WIDTH: f32 = 6.0;
+
+// The circuit's center line, a wobbly loop around the middle
+fn center_line(angle: f32) -> (f32, f32) {
+    let radius = 360.0 + (angle * 3.0).sin() * 70.0 + (angle * 2.0 + 1.0).cos() * 40.0;
+    (SIZE as f32 * 0.5 + angle.cos() * radius, SIZE as f32 * 0.5 + angle.sin() * radius * 0.8)
+}
+
+// Where the circuit starts, and which way it runs there, in texels
+pub fn start() -> ([f32; 2], f32) {
+    let (x, y) = center_line(0.0);
+    let (ahead_x, ahead_y) = center_line(0.01);
+    ([x, y], (ahead_y - y).atan2(ahead_x - x))
+}
+
+// Rgba8UnormSrgb texels, row by row
+pub fn generate() -> Vec<u8> {
+    const SAMPLES: usize = 720;
+    let line: Vec<(f32, f32)> = (0..SAMPLES).map(|i| center_line(i as f32 / SAMPLES as f32 * std::f32::consts::TAU)).collect();
+
+    // Distance to the nearest sampled point of the center line and which
+    // sample that was, stamped around each sample rather than searched for
+    // from every texel; only texels near the road need it
+    let reach = ROAD_HALF_WIDTH + CURB_WIDTH + 1.0;
+    let mut distances = vec![f32::MAX; (SIZE * SIZE) as usize];
+    let mut nearests = vec![0usize; (SIZE * SIZE) as usize];
+    for (i, &(lx, ly)) in line.iter().enumerate() {
+        let min_x = (lx - reach).max(0.0) as u32;
+        let max_x = ((lx + reach) as u32).min(SIZE - 1);
+        let min_y = (ly - reach).max(0.0) as u32;
+        let max_y = ((ly + reach) as u32).min(SIZE - 1);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let distance = (x as f32 + 0.5 - lx).hypot(y as f32 + 0.5 - ly);
+                let index = (y * SIZE + x) as usize;
+                if distance < distances[index] {
+                    distances[index] = distance;
+                    nearests[index] = i;
+                }
+            }
+        }
+    }
+
+    let mut texels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let index = (y * SIZE + x) as usize;
+            let (nearest, distance) = (nearests[index], distances[index]);
+
+            let color: [u8; 3] = if distance < ROAD_HALF_WIDTH {
+                // A checkered start line across the road at sample 0
+                let along = nearest.min(SAMPLES - nearest);
+                if along < 3 && ((x / 8 + y / 8) & 1) == 0 {
+                    [240, 240, 240]
+                } else if along < 3 {
+                    [20, 20, 20]
+                } else if distance < 1.5 && (nearest / 6) & 1 == 0 {
+                    // Dashes down the middle
+                    [230, 220, 120]
+                } else {
+                    [95, 95, 105]
+                }
+            } else if distance < ROAD_HALF_WIDTH + CURB_WIDTH {
+                // Red and white curbs
+                if (nearest / 4) & 1 == 0 { [220, 40, 40] } else { [240, 240, 240] }
+            } else {
+                // Mown grass in stripes
+                let stripe = ((x + y) / 32) & 1;
+                if stripe == 0 { [70, 150, 60] } else { [60, 135, 52] }
+            };
+            texels.extend([color[0], color[1], color[2], 255]);
+        }
+    }
+    texels
+}
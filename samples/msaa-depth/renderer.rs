@@ -0,0 +1,228 @@
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, Device, RenderPipeline, SurfaceConfiguration, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+// Asked for, gpu.sample_count lowers it where the surface format can't resolve it
+const SAMPLE_COUNT: u32 = 4;
+const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shading {
+    PerPixel,
+    PerSample,
+}
+
+pub struct Renderer {
+    per_pixel_pipeline: RenderPipeline,
+    per_sample_pipeline: RenderPipeline,
+    targets: SurfaceTargets,
+    sample_count: u32,
+    shading: Shading,
+}
+
+// Everything sized to the surface, rebuilt together whenever it changes
+struct SurfaceTargets {
+    // None with a single sample, the pass then draws straight into the surface
+    texture_view_for_multisampling: Option<TextureView>,
+    // Has to be multisampled too: every color sample gets its own depth value
+    depth_view: TextureView,
+}
+
+impl SurfaceTargets {
+    fn new(device: &Device, config: &SurfaceConfiguration, sample_count: u32) -> Self {
+        Self {
+            texture_view_for_multisampling: (sample_count > 1)
+                .then(|| create_attachment(device, "Multisampled Color", config, config.format, sample_count)),
+            depth_view: create_attachment(device, "Multisampled Depth", config, DEPTH_FORMAT, sample_count),
+        }
+    }
+}
+
+fn create_attachment(
+    device: &Device,
+    label: &str,
+    config: &SurfaceConfiguration,
+    format: TextureFormat,
+    sample_count: u32,
+) -> TextureView {
+    device.create_texture(&TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        // All attachments of a pass must share the same sample count
+        sample_count,
+        dimension: TextureDimension::D2,
+        format,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        label: Some(label),
+        view_formats: &[],
+    }).create_view(&TextureViewDescriptor::default())
+}
+
+impl Renderer {
+    pub fn toggle_shading(&mut self) -> Shading {
+        self.shading = match self.shading {
+            Shading::PerPixel => Shading::PerSample,
+            Shading::PerSample => Shading::PerPixel,
+        };
+        self.shading
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let surface_config = &gpu.config;
+        // 1 where the surface format can't be multisampled at all, the scene
+        // is then drawn without MSAA and both shadings look the same
+        let sample_count = gpu.sample_count(surface_config.format, SAMPLE_COUNT);
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_pipeline = |label, vertex_entry, fragment_entry| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: vertex_entry,
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fragment_entry,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    // The triangles are wound differently, keep both
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            })
+        };
+
+        let per_pixel_pipeline = create_pipeline("Per-Pixel Pipeline", "vs_main", "fs_main");
+        let per_sample_pipeline = create_pipeline("Per-Sample Pipeline", "vs_per_sample", "fs_per_sample");
+
+        Self {
+            per_pixel_pipeline,
+            per_sample_pipeline,
+            targets: SurfaceTargets::new(device, surface_config, sample_count),
+            sample_count,
+            shading: Shading::PerPixel,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.targets = SurfaceTargets::new(&gpu.device, &gpu.config, self.sample_count);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::Space),
+                ..
+            },
+            ..
+        } = event
+        {
+            println!("Shading: {:?}", self.toggle_shading());
+        }
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            // The resolved copy is all we present, without MSAA there's nothing to resolve
+            let (color_view, resolve_target) = match &self.targets.texture_view_for_multisampling {
+                Some(multisampled) => (multisampled, Some(view)),
+                None => (view, None),
+            };
+
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view: color_view,
+                            resolve_target,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: resolve_target.is_none(),
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.targets.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            // Multisampled depth can't be resolved, and nothing reads it later
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_pipeline(match self.shading {
+                Shading::PerPixel => &self.per_pixel_pipeline,
+                Shading::PerSample => &self.per_sample_pipeline,
+            });
+            render_pass.draw(0..6, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.sample_count > 1 {
+            Some(format!("{}x MSAA, {:?} shading", self.sample_count, self.shading))
+        } else {
+            Some("no MSAA, the surface format can't be multisampled here".to_string())
+        }
+    }
+}
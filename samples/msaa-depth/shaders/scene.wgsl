@@ -0,0 +1,85 @@
+// Two triangles that cut through each other, so the depth test has to
+// produce an edge in the middle of both of them
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  // When a pixel is only partially covered the pixel center can lie outside
+  // the triangle. Centroid interpolation evaluates the attribute inside the
+  // covered area instead, so it is never extrapolated past the vertices.
+  @location(0) @interpolate(perspective, centroid) color: vec3<f32>,
+  @location(1) @interpolate(perspective, centroid) stripe: f32,
+}
+
+// Same data as VertexOutput, but evaluated at every sample position. The
+// interpolation qualifiers have to match between the stages, hence the copy.
+struct PerSampleOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) @interpolate(perspective, sample) color: vec3<f32>,
+  @location(1) @interpolate(perspective, sample) stripe: f32,
+}
+
+struct SceneVertex {
+  position: vec3<f32>,
+  color: vec3<f32>,
+  stripe: f32,
+}
+
+fn scene_vertex(index: u32) -> SceneVertex {
+  var positions = array<vec3<f32>, 6>(
+    // Flat triangle in the middle of the depth range
+    vec3(-0.7, -0.5, 0.5),
+    vec3(0.7, -0.5, 0.5),
+    vec3(0.0, 0.6, 0.5),
+    // Tilted triangle going from near on the left to far on the right
+    vec3(-0.6, 0.4, 0.1),
+    vec3(0.0, -0.7, 0.5),
+    vec3(0.6, 0.4, 0.9)
+  );
+  var colors = array<vec3<f32>, 6>(
+    vec3(1.0, 0.8, 0.0),
+    vec3(1.0, 0.8, 0.0),
+    vec3(1.0, 0.8, 0.0),
+    vec3(0.1, 0.4, 1.0),
+    vec3(0.1, 0.4, 1.0),
+    vec3(0.1, 0.4, 1.0)
+  );
+  var stripes = array<f32, 6>(0.0, 12.0, 6.0, 0.0, 6.0, 12.0);
+
+  return SceneVertex(positions[index], colors[index], stripes[index]);
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let v = scene_vertex(index);
+  return VertexOutput(vec4(v.position, 1.0), v.color, v.stripe);
+}
+
+@vertex
+fn vs_per_sample(@builtin(vertex_index) index: u32) -> PerSampleOutput {
+  let v = scene_vertex(index);
+  return PerSampleOutput(vec4(v.position, 1.0), v.color, v.stripe);
+}
+
+fn shade(color: vec3<f32>, stripe: f32) -> vec4<f32> {
+  // High frequency detail inside the triangle: per-pixel shading can't
+  // antialias this, only per-sample shading can
+  let band = step(0.5, fract(stripe));
+  return vec4(color * mix(0.6, 1.0, band), 1.0);
+}
+
+// Runs once per pixel; MSAA only smooths the triangle and depth edges
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return shade(in.color, in.stripe);
+}
+
+// Reading sample_index (or using `sample` interpolation) makes the shader
+// run once per sample. Everything gets antialiased, at SAMPLE_COUNT times
+// the fragment cost.
+@fragment
+fn fs_per_sample(
+  in: PerSampleOutput,
+  @builtin(sample_index) sample_index: u32
+) -> @location(0) vec4<f32> {
+  return shade(in.color, in.stripe);
+}
@@ -0,0 +1,66 @@
+use std::f32::consts::PI;
+
+pub const SAMPLES: usize = 512;
+pub const NAMES: [&str; 4] = ["two spirals", "circles", "xor", "moons"];
+
+pub struct Rng(pub u32);
+
+impl Rng {
+    pub fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+// Points in -1 to 1 as x, y, label (0 or 1), and padding, half of each label
+pub fn generate(kind: usize, seed: u32) -> Vec<[f32; 4]> {
+    let mut rng = Rng(seed.max(1));
+    (0..SAMPLES)
+        .map(|index| {
+            let label = (index % 2) as f32;
+            let noise = [rng.range(-0.05, 0.05), rng.range(-0.05, 0.05)];
+            let [x, y] = match kind {
+                // Two arms winding out from the middle, half a turn apart
+                0 => {
+                    let t = rng.next_f32();
+                    let angle = t * 3.0 * PI + label * PI;
+                    [angle.cos() * t * 0.9, angle.sin() * t * 0.9]
+                }
+                // A disc inside a ring
+                1 => {
+                    let angle = rng.range(0.0, 2.0 * PI);
+                    let radius = if label == 0.0 { rng.range(0.0, 0.4) } else { rng.range(0.6, 0.9) };
+                    [angle.cos() * radius, angle.sin() * radius]
+                }
+                // Opposite quadrants share a label
+                2 => {
+                    let (x, y) = (rng.range(0.05, 0.9), rng.range(0.05, 0.9));
+                    let flip = rng.next_f32() < 0.5;
+                    if label == 0.0 {
+                        if flip { [x, y] } else { [-x, -y] }
+                    } else if flip {
+                        [-x, y]
+                    } else {
+                        [x, -y]
+                    }
+                }
+                // Two interlocking half circles
+                _ => {
+                    let angle = rng.range(0.0, PI);
+                    if label == 0.0 {
+                        [angle.cos() * 0.6 - 0.3, angle.sin() * 0.6 - 0.15]
+                    } else {
+                        [0.3 - angle.cos() * 0.6, 0.15 - angle.sin() * 0.6]
+                    }
+                }
+            };
+            [x + noise[0], y + noise[1], label, 0.0]
+        })
+        .collect()
+}
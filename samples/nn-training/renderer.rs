@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::dataset::{self, NAMES, SAMPLES};
+use crate::trainer::Trainer;
+
+const STEPS_PER_FRAME: u32 = 8;
+const MOMENTUM: f32 = 0.9;
+// The samples span -1 to 1; a little border around them
+const VIEW_EXTENT: f32 = 1.15;
+const DATASET_KEYS: [VirtualKeyCode; 4] = [VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3, VirtualKeyCode::Key4];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct View {
+    scale: [f32; 2],
+    point_radius: f32,
+    _padding: f32,
+}
+
+pub struct Renderer {
+    trainer: Trainer,
+    field_pipeline: RenderPipeline,
+    points_pipeline: RenderPipeline,
+    view_buffer: Buffer,
+    // One per parameter buffer the trainer can have as current
+    bind_groups: [BindGroup; 2],
+    dataset: usize,
+    seed: u32,
+    learning_rate: f32,
+    paused: bool,
+    needs_reset: bool,
+    needs_rates: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+}
+
+fn create_pipeline(gpu: &Gpu, layout: &BindGroupLayout, shader: &wgpu::ShaderModule, vertex: &str, fragment: &str) -> RenderPipeline {
+    let pipeline_layout = gpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Pipeline Layout"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(fragment),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: vertex,
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: gpu.config.format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let trainer = Trainer::new(device);
+
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("View"),
+            contents: bytemuck::bytes_of(&View::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let storage_entry = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, wgpu::ShaderStages::FRAGMENT),
+                storage_entry(2, wgpu::ShaderStages::VERTEX),
+            ],
+        });
+        let bind_groups = [0, 1].map(|current: usize| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Display Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: view_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: trainer.parameters[current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: trainer.samples.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        Self {
+            field_pipeline: create_pipeline(gpu, &bind_group_layout, &shader, "vs_field", "fs_field"),
+            points_pipeline: create_pipeline(gpu, &bind_group_layout, &shader, "vs_points", "fs_points"),
+            trainer,
+            view_buffer,
+            bind_groups,
+            dataset: 0,
+            seed: 1,
+            learning_rate: 0.1,
+            paused: false,
+            needs_reset: true,
+            needs_rates: true,
+            held_keys: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            if let Some(dataset) = DATASET_KEYS.iter().position(|k| k == key) {
+                self.dataset = dataset;
+                self.needs_reset = true;
+            }
+            match key {
+                VirtualKeyCode::R => {
+                    self.seed += 1;
+                    self.needs_reset = true;
+                }
+                VirtualKeyCode::Up => {
+                    self.learning_rate = (self.learning_rate * 2.0).min(3.2);
+                    self.needs_rates = true;
+                }
+                VirtualKeyCode::Down => {
+                    self.learning_rate = (self.learning_rate * 0.5).max(0.003125);
+                    self.needs_rates = true;
+                }
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{}, step {}, loss {:.4}, accuracy {:.1}%, learning rate {}{}",
+            NAMES[self.dataset],
+            self.trainer.steps,
+            self.trainer.loss,
+            self.trainer.accuracy * 100.0,
+            self.learning_rate,
+            if self.paused { ", paused" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.trainer.poll(&gpu.device);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        if std::mem::take(&mut self.needs_reset) {
+            self.trainer.set_samples(&gpu.queue, &dataset::generate(self.dataset, self.seed));
+            self.trainer.reset(&gpu.queue, &mut encoder, self.seed);
+        }
+        if std::mem::take(&mut self.needs_rates) {
+            self.trainer.set_rates(&gpu.queue, self.learning_rate, MOMENTUM);
+        }
+        if !self.paused {
+            self.trainer.train(&mut encoder, STEPS_PER_FRAME);
+        }
+
+        // Fit the samples' square in the window
+        let aspect = gpu.aspect_ratio();
+        let scale = if aspect > 1.0 { [1.0 / (VIEW_EXTENT * aspect), 1.0 / VIEW_EXTENT] } else { [1.0 / VIEW_EXTENT, aspect / VIEW_EXTENT] };
+        gpu.queue.write_buffer(
+            &self.view_buffer,
+            0,
+            bytemuck::bytes_of(&View {
+                scale,
+                point_radius: 0.018,
+                _padding: 0.0,
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_bind_group(0, &self.bind_groups[self.trainer.current], &[]);
+            render_pass.set_pipeline(&self.field_pipeline);
+            render_pass.draw(0..3, 0..1);
+            render_pass.set_pipeline(&self.points_pipeline);
+            render_pass.draw(0..6, 0..SAMPLES as u32);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.trainer.map_submitted();
+    }
+}
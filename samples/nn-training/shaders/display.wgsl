@@ -0,0 +1,103 @@
+struct View {
+    // Clip space per unit of the samples' space
+    scale: vec2<f32>,
+    // In the samples' space
+    point_radius: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(0) @binding(1)
+var<storage, read> parameters: array<f32>;
+@group(0) @binding(2)
+var<storage, read> samples: array<vec4<f32>>;
+
+// The trainer's 2, 16, 16, 1 network: each layer's weights a row per
+// output, then its biases
+const HIDDEN: u32 = 16u;
+const SECOND_LAYER: u32 = 48u;
+const OUTPUT_LAYER: u32 = 320u;
+
+fn evaluate(p: vec2<f32>) -> f32 {
+    var first: array<f32, 16>;
+    for (var n = 0u; n < HIDDEN; n++) {
+        first[n] = tanh(parameters[HIDDEN * 2u + n] + parameters[n * 2u] * p.x + parameters[n * 2u + 1u] * p.y);
+    }
+    var second: array<f32, 16>;
+    for (var n = 0u; n < HIDDEN; n++) {
+        var sum = parameters[SECOND_LAYER + HIDDEN * HIDDEN + n];
+        for (var i = 0u; i < HIDDEN; i++) {
+            sum += parameters[SECOND_LAYER + n * HIDDEN + i] * first[i];
+        }
+        second[n] = tanh(sum);
+    }
+    var sum = parameters[OUTPUT_LAYER + HIDDEN];
+    for (var i = 0u; i < HIDDEN; i++) {
+        sum += parameters[OUTPUT_LAYER + i] * second[i];
+    }
+    return 1.0 / (1.0 + exp(-sum));
+}
+
+const BLUE: vec3<f32> = vec3<f32>(0.15, 0.35, 0.75);
+const ORANGE: vec3<f32> = vec3<f32>(0.95, 0.55, 0.15);
+
+struct FieldOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) point: vec2<f32>,
+}
+
+@vertex
+fn vs_field(@builtin(vertex_index) index: u32) -> FieldOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: FieldOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.point = ndc / view.scale;
+    return out;
+}
+
+// The network's prediction everywhere, lighter where it's unsure, with
+// the decision boundary drawn where it crosses a half
+@fragment
+fn fs_field(in: FieldOutput) -> @location(0) vec4<f32> {
+    let prediction = evaluate(in.point);
+    let confidence = abs(prediction - 0.5) * 2.0;
+    var color = mix(vec3<f32>(0.9), mix(BLUE, ORANGE, step(0.5, prediction)), confidence * 0.6);
+    let boundary = 1.0 - smoothstep(0.0, fwidth(prediction) * 1.5, abs(prediction - 0.5));
+    color = mix(color, vec3<f32>(0.1), boundary);
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
+
+struct PointOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) label: f32,
+}
+
+@vertex
+fn vs_points(@builtin(vertex_index) index: u32, @builtin(instance_index) instance: u32) -> PointOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0)
+    );
+    let sample = samples[instance];
+    let corner = corners[index];
+    var out: PointOutput;
+    out.clip_position = vec4<f32>((sample.xy + corner * view.point_radius) * view.scale, 0.0, 1.0);
+    out.corner = corner;
+    out.label = sample.z;
+    return out;
+}
+
+@fragment
+fn fs_points(in: PointOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.corner);
+    if distance > 1.0 {
+        discard;
+    }
+    var color = mix(BLUE, ORANGE, in.label) * 0.8;
+    if distance > 0.7 {
+        color = vec3<f32>(1.0);
+    }
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,167 @@
+// Where one layer's numbers live in the flat buffers
+struct Layer {
+    inputs: u32,
+    outputs: u32,
+    // Into the parameters: the weights, a row per output, then the biases
+    parameters: u32,
+    // Into each sample's activations and deltas; the first layer's inputs
+    // come from the samples instead
+    input_offset: u32,
+    output_offset: u32,
+    // 1 on the output layer, which uses a sigmoid instead of tanh
+    last: u32,
+    // The layer after, for the backward pass
+    next_outputs: u32,
+    next_parameters: u32,
+}
+
+struct Training {
+    samples: u32,
+    // Per sample, across all layers
+    activations: u32,
+    parameters: u32,
+    learning_rate: f32,
+    momentum: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> layer: Layer;
+@group(0) @binding(1)
+var<uniform> training: Training;
+// x, y, label
+@group(0) @binding(2)
+var<storage, read> samples: array<vec4<f32>>;
+// Ping-ponged: every kernel reads the current parameters, and the update
+// writes the next ones into the other buffer
+@group(0) @binding(3)
+var<storage, read> parameters: array<f32>;
+@group(0) @binding(4)
+var<storage, read_write> next_parameters: array<f32>;
+@group(0) @binding(5)
+var<storage, read_write> velocity: array<f32>;
+@group(0) @binding(6)
+var<storage, read_write> activations: array<f32>;
+// The loss's derivative with respect to each neuron's sum
+@group(0) @binding(7)
+var<storage, read_write> deltas: array<f32>;
+@group(0) @binding(8)
+var<storage, read_write> gradients: array<f32>;
+// Per sample loss, then summed into the last two: mean loss and accuracy
+@group(0) @binding(9)
+var<storage, read_write> losses: array<f32>;
+
+fn layer_input(sample: u32, i: u32) -> f32 {
+    if layer.input_offset == 0xffffffffu {
+        return samples[sample][i];
+    }
+    return activations[sample * training.activations + layer.input_offset + i];
+}
+
+// An invocation per sample and neuron
+@compute @workgroup_size(64)
+fn cs_forward(@builtin(global_invocation_id) id: vec3<u32>) {
+    let sample = id.x / layer.outputs;
+    let neuron = id.x % layer.outputs;
+    if sample >= training.samples {
+        return;
+    }
+    let row = layer.parameters + neuron * layer.inputs;
+    var sum = parameters[layer.parameters + layer.outputs * layer.inputs + neuron];
+    for (var i = 0u; i < layer.inputs; i++) {
+        sum += parameters[row + i] * layer_input(sample, i);
+    }
+    var activation = tanh(sum);
+    if layer.last != 0u {
+        activation = 1.0 / (1.0 + exp(-sum));
+    }
+    activations[sample * training.activations + layer.output_offset + neuron] = activation;
+}
+
+// A sigmoid under binary cross entropy leaves prediction minus label as
+// the output's delta. Hidden layers take the next layer's deltas back
+// through its weights, times tanh's derivative.
+@compute @workgroup_size(64)
+fn cs_backward(@builtin(global_invocation_id) id: vec3<u32>) {
+    let sample = id.x / layer.outputs;
+    let neuron = id.x % layer.outputs;
+    if sample >= training.samples {
+        return;
+    }
+    let base = sample * training.activations;
+    let activation = activations[base + layer.output_offset + neuron];
+    if layer.last != 0u {
+        let label = samples[sample].z;
+        deltas[base + layer.output_offset + neuron] = activation - label;
+        let p = clamp(activation, 1e-6, 1.0 - 1e-6);
+        losses[sample] = -(label * log(p) + (1.0 - label) * log(1.0 - p));
+        return;
+    }
+    var sum = 0.0;
+    let next_offset = layer.output_offset + layer.outputs;
+    for (var k = 0u; k < layer.next_outputs; k++) {
+        sum += parameters[layer.next_parameters + k * layer.outputs + neuron] * deltas[base + next_offset + k];
+    }
+    deltas[base + layer.output_offset + neuron] = sum * (1.0 - activation * activation);
+}
+
+// An invocation per weight and bias, averaging over every sample, so no
+// two invocations ever write the same gradient
+@compute @workgroup_size(64)
+fn cs_gradient(@builtin(global_invocation_id) id: vec3<u32>) {
+    let weights = layer.outputs * layer.inputs;
+    if id.x >= weights + layer.outputs {
+        return;
+    }
+    var neuron = id.x - weights;
+    var input = 0xffffffffu;
+    if id.x < weights {
+        neuron = id.x / layer.inputs;
+        input = id.x % layer.inputs;
+    }
+    var sum = 0.0;
+    for (var sample = 0u; sample < training.samples; sample++) {
+        let delta = deltas[sample * training.activations + layer.output_offset + neuron];
+        if input == 0xffffffffu {
+            sum += delta;
+        } else {
+            sum += delta * layer_input(sample, input);
+        }
+    }
+    gradients[layer.parameters + id.x] = sum / f32(training.samples);
+}
+
+// Gradient descent with momentum over every parameter at once
+@compute @workgroup_size(64)
+fn cs_update(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= training.parameters {
+        return;
+    }
+    let v = velocity[id.x] * training.momentum - gradients[id.x] * training.learning_rate;
+    velocity[id.x] = v;
+    next_parameters[id.x] = parameters[id.x] + v;
+}
+
+var<workgroup> partial: array<vec2<f32>, 256>;
+
+// The mean loss and the share of samples on the right side of 0.5
+@compute @workgroup_size(256)
+fn cs_loss(@builtin(local_invocation_index) local: u32) {
+    var sum = vec2<f32>(0.0);
+    for (var sample = local; sample < training.samples; sample += 256u) {
+        let prediction = activations[sample * training.activations + training.activations - 1u];
+        let correct = (prediction > 0.5) == (samples[sample].z > 0.5);
+        sum += vec2<f32>(losses[sample], select(0.0, 1.0, correct));
+    }
+    partial[local] = sum;
+    workgroupBarrier();
+    for (var stride = 128u; stride > 0u; stride /= 2u) {
+        if local < stride {
+            partial[local] += partial[local + stride];
+        }
+        workgroupBarrier();
+    }
+    if local == 0u {
+        losses[training.samples] = partial[0].x / f32(training.samples);
+        losses[training.samples + 1u] = partial[0].y / f32(training.samples);
+    }
+}
@@ -0,0 +1,376 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+use crate::dataset::{Rng, SAMPLES};
+
+// Neurons per layer, input first. The display shader evaluates the same
+// shape, so they change together.
+pub const LAYERS: [usize; 4] = [2, 16, 16, 1];
+const GROUP_SIZE: u32 = 64;
+// The first layer reads the samples rather than activations
+const FROM_SAMPLES: u32 = u32::MAX;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct LayerParams {
+    inputs: u32,
+    outputs: u32,
+    parameters: u32,
+    input_offset: u32,
+    output_offset: u32,
+    last: u32,
+    next_outputs: u32,
+    next_parameters: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct TrainingParams {
+    samples: u32,
+    activations: u32,
+    parameters: u32,
+    learning_rate: f32,
+    momentum: f32,
+    _padding: [f32; 3],
+}
+
+fn parameter_count() -> usize {
+    LAYERS.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// Full batch training of a small tanh network, one step being a dispatch
+// per layer for the forward pass, the backward pass and the gradients, then
+// one for the update. Every buffer stays on the GPU; the parameters are two
+// buffers, the update reading one and writing the other, which then becomes
+// the one everything reads.
+pub struct Trainer {
+    forward_pipeline: ComputePipeline,
+    backward_pipeline: ComputePipeline,
+    gradient_pipeline: ComputePipeline,
+    update_pipeline: ComputePipeline,
+    loss_pipeline: ComputePipeline,
+    // A slot per layer, picked with a dynamic offset
+    layer_stride: u32,
+    training_buffer: Buffer,
+    pub samples: Buffer,
+    pub parameters: [Buffer; 2],
+    velocity: Buffer,
+    losses: Buffer,
+    // The first reads parameters[0] and writes parameters[1]
+    bind_groups: [BindGroup; 2],
+    // Which of parameters is current
+    pub current: usize,
+    pub steps: u32,
+    readback: Buffer,
+    pending: bool,
+    needs_map: bool,
+    mapped: Arc<AtomicBool>,
+    // Mean loss and accuracy of the last readback to land
+    pub loss: f32,
+    pub accuracy: f32,
+}
+
+impl Trainer {
+    pub fn new(device: &Device) -> Self {
+        let activations_per_sample: usize = LAYERS[1..].iter().sum();
+        let mut layers = Vec::new();
+        let (mut parameters, mut offset) = (0, 0);
+        for (index, pair) in LAYERS.windows(2).enumerate() {
+            let next = LAYERS.get(index + 2).copied().unwrap_or(0);
+            layers.push(LayerParams {
+                inputs: pair[0] as u32,
+                outputs: pair[1] as u32,
+                parameters,
+                input_offset: if index == 0 { FROM_SAMPLES } else { offset - pair[0] as u32 },
+                output_offset: offset,
+                last: (index == LAYERS.len() - 2) as u32,
+                next_outputs: next as u32,
+                next_parameters: parameters + ((pair[0] + 1) * pair[1]) as u32,
+            });
+            parameters += ((pair[0] + 1) * pair[1]) as u32;
+            offset += pair[1] as u32;
+        }
+
+        let layer_stride = device.limits().min_uniform_buffer_offset_alignment.max(std::mem::size_of::<LayerParams>() as u32);
+        let mut layer_bytes = vec![0; (layer_stride as usize) * layers.len()];
+        for (index, layer) in layers.iter().enumerate() {
+            let start = index * layer_stride as usize;
+            layer_bytes[start..start + std::mem::size_of::<LayerParams>()].copy_from_slice(bytemuck::bytes_of(layer));
+        }
+        let layer_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Layer Params"),
+            contents: &layer_bytes,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let training_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Training Params"),
+            contents: bytemuck::bytes_of(&TrainingParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let storage = |label: &str, floats: usize, usage: wgpu::BufferUsages| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (floats * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE | usage,
+                mapped_at_creation: false,
+            })
+        };
+        let count = parameter_count();
+        let samples = storage("Samples", SAMPLES * 4, wgpu::BufferUsages::COPY_DST);
+        let parameter_buffers = [
+            storage("Parameters 0", count, wgpu::BufferUsages::COPY_DST),
+            storage("Parameters 1", count, wgpu::BufferUsages::COPY_DST),
+        ];
+        let velocity = storage("Velocity", count, wgpu::BufferUsages::COPY_DST);
+        let activations = storage("Activations", SAMPLES * activations_per_sample, wgpu::BufferUsages::empty());
+        let deltas = storage("Deltas", SAMPLES * activations_per_sample, wgpu::BufferUsages::empty());
+        let gradients = storage("Gradients", count, wgpu::BufferUsages::empty());
+        // Per sample, then the mean loss and accuracy
+        let losses = storage("Losses", SAMPLES + 2, wgpu::BufferUsages::COPY_SRC);
+
+        let mut entries = vec![
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<LayerParams>() as u64),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ];
+        for binding in 2..=9 {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: binding <= 3 },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            });
+        }
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Training Layout"),
+            entries: &entries,
+        });
+        let bind_groups = [(0, 1), (1, 0)].map(|(source, destination): (usize, usize)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Training Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &layer_buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<LayerParams>() as u64),
+                        }),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: training_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: samples.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: parameter_buffers[source].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: parameter_buffers[destination].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: velocity.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: activations.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 7,
+                        resource: deltas.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 8,
+                        resource: gradients.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 9,
+                        resource: losses.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/train.wgsl"));
+        Self {
+            forward_pipeline: compute_pipeline(device, "Forward", &layout, &shader, "cs_forward"),
+            backward_pipeline: compute_pipeline(device, "Backward", &layout, &shader, "cs_backward"),
+            gradient_pipeline: compute_pipeline(device, "Gradient", &layout, &shader, "cs_gradient"),
+            update_pipeline: compute_pipeline(device, "Update", &layout, &shader, "cs_update"),
+            loss_pipeline: compute_pipeline(device, "Loss", &layout, &shader, "cs_loss"),
+            layer_stride,
+            training_buffer,
+            samples,
+            parameters: parameter_buffers,
+            velocity,
+            losses,
+            bind_groups,
+            current: 0,
+            steps: 0,
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Loss Readback"),
+                size: 8,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            pending: false,
+            needs_map: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            loss: 0.0,
+            accuracy: 0.0,
+        }
+    }
+
+    pub fn set_samples(&self, queue: &Queue, samples: &[[f32; 4]]) {
+        queue.write_buffer(&self.samples, 0, bytemuck::cast_slice(samples));
+    }
+
+    pub fn set_rates(&self, queue: &Queue, learning_rate: f32, momentum: f32) {
+        let params = TrainingParams {
+            samples: SAMPLES as u32,
+            activations: LAYERS[1..].iter().sum::<usize>() as u32,
+            parameters: parameter_count() as u32,
+            learning_rate,
+            momentum,
+            _padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.training_buffer, 0, bytemuck::bytes_of(&params));
+    }
+
+    // Fresh weights, scaled by each layer's fan in so tanh starts out of
+    // saturation, and zero biases and velocity
+    pub fn reset(&mut self, queue: &Queue, encoder: &mut CommandEncoder, seed: u32) {
+        let mut rng = Rng(seed.max(1));
+        let mut parameters = Vec::with_capacity(parameter_count());
+        for pair in LAYERS.windows(2) {
+            let scale = (1.0 / pair[0] as f32).sqrt() * 1.5;
+            parameters.extend((0..pair[0] * pair[1]).map(|_| rng.range(-scale, scale)));
+            parameters.resize(parameters.len() + pair[1], 0.0);
+        }
+        queue.write_buffer(&self.parameters[0], 0, bytemuck::cast_slice(&parameters));
+        encoder.clear_buffer(&self.velocity, 0, None);
+        self.current = 0;
+        self.steps = 0;
+    }
+
+    pub fn train(&mut self, encoder: &mut CommandEncoder, steps: u32) {
+        let layers = LAYERS.len() as u32 - 1;
+        let groups = |invocations: usize| (invocations as u32 + GROUP_SIZE - 1) / GROUP_SIZE;
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Training Pass"),
+            });
+            for _ in 0..steps {
+                let bind_group = &self.bind_groups[self.current];
+                let slot = |layer: u32| [layer * self.layer_stride];
+
+                compute_pass.set_pipeline(&self.forward_pipeline);
+                for layer in 0..layers {
+                    compute_pass.set_bind_group(0, bind_group, &slot(layer));
+                    compute_pass.dispatch_workgroups(groups(SAMPLES * LAYERS[layer as usize + 1]), 1, 1);
+                }
+                compute_pass.set_pipeline(&self.backward_pipeline);
+                for layer in (0..layers).rev() {
+                    compute_pass.set_bind_group(0, bind_group, &slot(layer));
+                    compute_pass.dispatch_workgroups(groups(SAMPLES * LAYERS[layer as usize + 1]), 1, 1);
+                }
+                compute_pass.set_pipeline(&self.gradient_pipeline);
+                for layer in 0..layers {
+                    let pair = &LAYERS[layer as usize..];
+                    compute_pass.set_bind_group(0, bind_group, &slot(layer));
+                    compute_pass.dispatch_workgroups(groups((pair[0] + 1) * pair[1]), 1, 1);
+                }
+                compute_pass.set_pipeline(&self.update_pipeline);
+                compute_pass.set_bind_group(0, bind_group, &slot(0));
+                compute_pass.dispatch_workgroups(groups(parameter_count()), 1, 1);
+
+                self.current = 1 - self.current;
+                self.steps += 1;
+            }
+            // Of the last step's forward pass
+            compute_pass.set_pipeline(&self.loss_pipeline);
+            compute_pass.set_bind_group(0, &self.bind_groups[self.current], &[0]);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+
+        if !self.pending {
+            encoder.copy_buffer_to_buffer(&self.losses, (SAMPLES * 4) as wgpu::BufferAddress, &self.readback, 0, 8);
+            self.pending = true;
+            self.needs_map = true;
+        }
+    }
+
+    // Call after submitting the encoder passed to train
+    pub fn map_submitted(&mut self) {
+        if !std::mem::take(&mut self.needs_map) {
+            return;
+        }
+        let mapped = self.mapped.clone();
+        self.readback.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            result.unwrap();
+            mapped.store(true, Ordering::Release);
+        });
+    }
+
+    pub fn poll(&mut self, device: &Device) {
+        device.poll(wgpu::Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+        {
+            let data = self.readback.slice(..).get_mapped_range();
+            let values: &[f32] = bytemuck::cast_slice(&data);
+            self.loss = values[0];
+            self.accuracy = values[1];
+        }
+        self.readback.unmap();
+        self.pending = false;
+    }
+}
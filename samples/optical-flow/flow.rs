@@ -0,0 +1,325 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{include_wgsl, BindGroup, Buffer, CommandEncoder, ComputePipeline, Device, Queue, RenderPipeline, TextureView};
+
+// Of the frames at the finest level; the flow doesn't care about the window
+pub const WIDTH: u32 = 640;
+pub const HEIGHT: u32 = 360;
+// Each one halves the size, so the coarsest sees motion an eighth as far
+pub const LEVELS: usize = 4;
+pub const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+const FLOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+const GROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    size: [f32; 2],
+    has_coarser: u32,
+    iterations: u32,
+}
+
+fn level_size(level: usize) -> (u32, u32) {
+    (WIDTH >> level, HEIGHT >> level)
+}
+
+fn create_texture(device: &Device, size: (u32, u32), format: wgpu::TextureFormat, usage: wgpu::TextureUsages, label: &str) -> TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: usage | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn texture_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn sampler_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    }
+}
+
+// Pyramidal Lucas-Kanade between the two most recent frames. Each frame is
+// rendered into the finest level of one of two image pyramids, which swap
+// roles every frame, so the previous frame's pyramid is already built. The
+// flow is solved coarsest level first, each level refining the one above.
+pub struct Flow {
+    // Two pyramids, finest level first
+    pub frames: [Vec<TextureView>; 2],
+    // The flow at each level, finest first, in that level's pixels. Points
+    // from a pixel of the current frame to where it was in the previous.
+    pub flows: Vec<TextureView>,
+    // Which of frames the newest frame goes in
+    pub current: usize,
+    downsample_pipeline: RenderPipeline,
+    // Per pyramid, per level past the finest, reading the level before
+    downsample_bind_groups: [Vec<BindGroup>; 2],
+    flow_pipeline: ComputePipeline,
+    // One slot per level, picked with a dynamic offset
+    params_buffer: Buffer,
+    params_stride: u32,
+    // Per current pyramid, per level
+    flow_bind_groups: [Vec<BindGroup>; 2],
+}
+
+impl Flow {
+    pub fn new(device: &Device) -> Self {
+        let frames = [0, 1].map(|set| {
+            (0..LEVELS)
+                .map(|level| {
+                    create_texture(
+                        device,
+                        level_size(level),
+                        FRAME_FORMAT,
+                        wgpu::TextureUsages::RENDER_ATTACHMENT,
+                        &format!("Frame {} Level {}", set, level),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+        let flows: Vec<_> = (0..LEVELS)
+            .map(|level| create_texture(device, level_size(level), FLOW_FORMAT, wgpu::TextureUsages::STORAGE_BINDING, &format!("Flow Level {}", level)))
+            .collect();
+        // Bound as the coarser flow of the coarsest level, never read
+        let no_flow = create_texture(device, (1, 1), FLOW_FORMAT, wgpu::TextureUsages::empty(), "No Coarser Flow");
+        let linear_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Linear Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let downsample_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Downsample Layout"),
+            entries: &[
+                texture_entry(0, wgpu::ShaderStages::FRAGMENT),
+                sampler_entry(1, wgpu::ShaderStages::FRAGMENT),
+            ],
+        });
+        let downsample_bind_groups = [0, 1].map(|set: usize| {
+            (1..LEVELS)
+                .map(|level| {
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Downsample Bind Group"),
+                        layout: &downsample_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::TextureView(&frames[set][level - 1]),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::Sampler(&linear_sampler),
+                            },
+                        ],
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let downsample_shader = device.create_shader_module(include_wgsl!("shaders/downsample.wgsl"));
+        let downsample_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Downsample Pipeline Layout"),
+            bind_group_layouts: &[&downsample_layout],
+            push_constant_ranges: &[],
+        });
+        let downsample_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Downsample Pipeline"),
+            layout: Some(&downsample_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &downsample_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &downsample_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FRAME_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let params_stride = device.limits().min_uniform_buffer_offset_alignment.max(std::mem::size_of::<Params>() as u32);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Flow Params"),
+            size: (params_stride * LEVELS as u32) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let flow_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Flow Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+                    },
+                    count: None,
+                },
+                texture_entry(1, wgpu::ShaderStages::COMPUTE),
+                texture_entry(2, wgpu::ShaderStages::COMPUTE),
+                texture_entry(3, wgpu::ShaderStages::COMPUTE),
+                sampler_entry(4, wgpu::ShaderStages::COMPUTE),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: FLOW_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let flow_bind_groups = [0, 1].map(|current: usize| {
+            (0..LEVELS)
+                .map(|level| {
+                    device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("Flow Bind Group"),
+                        layout: &flow_layout,
+                        entries: &[
+                            wgpu::BindGroupEntry {
+                                binding: 0,
+                                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                    buffer: &params_buffer,
+                                    offset: 0,
+                                    size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+                                }),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: wgpu::BindingResource::TextureView(&frames[current][level]),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: wgpu::BindingResource::TextureView(&frames[1 - current][level]),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 3,
+                                resource: wgpu::BindingResource::TextureView(flows.get(level + 1).unwrap_or(&no_flow)),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 4,
+                                resource: wgpu::BindingResource::Sampler(&linear_sampler),
+                            },
+                            wgpu::BindGroupEntry {
+                                binding: 5,
+                                resource: wgpu::BindingResource::TextureView(&flows[level]),
+                            },
+                        ],
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        let flow_shader = device.create_shader_module(include_wgsl!("shaders/lucas_kanade.wgsl"));
+        let flow_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Flow Pipeline Layout"),
+            bind_group_layouts: &[&flow_layout],
+            push_constant_ranges: &[],
+        });
+        let flow_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Lucas-Kanade"),
+            layout: Some(&flow_pipeline_layout),
+            module: &flow_shader,
+            entry_point: "cs_flow",
+        });
+
+        Self {
+            frames,
+            flows,
+            current: 0,
+            downsample_pipeline,
+            downsample_bind_groups,
+            flow_pipeline,
+            params_buffer,
+            params_stride,
+            flow_bind_groups,
+        }
+    }
+
+    // Makes the oldest pyramid the one for the next frame
+    pub fn advance(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    // The finest level of the current pyramid, for the frame to be
+    // rendered into
+    pub fn frame(&self) -> &TextureView {
+        &self.frames[self.current][0]
+    }
+
+    // Builds the rest of the current pyramid and solves the flow from it to
+    // the previous one. With `pyramid` off only the finest level is solved,
+    // starting from no motion, to show what the pyramid buys.
+    pub fn estimate(&self, queue: &Queue, encoder: &mut CommandEncoder, pyramid: bool, iterations: u32) {
+        for level in 1..LEVELS {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Downsample Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.frames[self.current][level],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(&self.downsample_pipeline);
+            render_pass.set_bind_group(0, &self.downsample_bind_groups[self.current][level - 1], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        let coarsest = if pyramid { LEVELS - 1 } else { 0 };
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Flow Pass"),
+        });
+        compute_pass.set_pipeline(&self.flow_pipeline);
+        for level in (0..=coarsest).rev() {
+            let (width, height) = level_size(level);
+            let params = Params {
+                size: [width as f32, height as f32],
+                has_coarser: (level < coarsest) as u32,
+                iterations,
+            };
+            let offset = level as u32 * self.params_stride;
+            queue.write_buffer(&self.params_buffer, offset as wgpu::BufferAddress, bytemuck::bytes_of(&params));
+            compute_pass.set_bind_group(0, &self.flow_bind_groups[self.current][level], &[offset]);
+            compute_pass.dispatch_workgroups((width + GROUP_SIZE - 1) / GROUP_SIZE, (height + GROUP_SIZE - 1) / GROUP_SIZE, 1);
+        }
+    }
+}
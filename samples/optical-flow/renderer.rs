@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{post, Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline, TextureView};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::flow::{self, Flow};
+
+const MODES: [&str; 5] = ["frame", "estimated flow", "true flow", "error", "flow over frame"];
+// Animation time per frame, rather than the wall clock, so the motion
+// between frames only depends on the speed setting
+const FRAME_STEP: f32 = 1.0 / 60.0;
+const ITERATIONS: u32 = 3;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SceneUniforms {
+    time: f32,
+    previous_time: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DisplayParams {
+    mode: u32,
+    max_motion: f32,
+}
+
+pub struct Renderer {
+    flow: Flow,
+    scene_pipeline: RenderPipeline,
+    scene_bind_group: BindGroup,
+    scene_buffer: Buffer,
+    // The scene's own motion, to check the estimate against
+    truth: TextureView,
+    display_pipeline: RenderPipeline,
+    display_buffer: Buffer,
+    // One per pyramid the newest frame can be in
+    display_bind_groups: [BindGroup; 2],
+    time: f32,
+    speed: f32,
+    mode: usize,
+    paused: bool,
+    pyramid: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let flow = Flow::new(device);
+        let truth = post::create_target(device, flow::WIDTH, flow::HEIGHT, "True Motion");
+
+        let scene_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Scene Uniforms"),
+            contents: bytemuck::bytes_of(&SceneUniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Layout"),
+            entries: &[uniform_entry],
+        });
+        let scene_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &scene_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: scene_buffer.as_entire_binding(),
+            }],
+        });
+        let scene_shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+        let scene_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&scene_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&scene_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &scene_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &scene_shader,
+                entry_point: "fs_main",
+                targets: &[
+                    Some(wgpu::ColorTargetState {
+                        format: flow::FRAME_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                    Some(wgpu::ColorTargetState {
+                        format: post::HDR_FORMAT,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let display_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display Params"),
+            contents: bytemuck::bytes_of(&DisplayParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let display_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                uniform_entry,
+                texture_entry(1),
+                texture_entry(2),
+                texture_entry(3),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Display Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let display_bind_groups = [0, 1].map(|current: usize| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Display Bind Group"),
+                layout: &display_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: display_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&flow.frames[current][0]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(&flow.flows[0]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&truth),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            })
+        });
+        let display_shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let display_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&display_layout],
+            push_constant_ranges: &[],
+        });
+        let display_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&display_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &display_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &display_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            flow,
+            scene_pipeline,
+            scene_bind_group,
+            scene_buffer,
+            truth,
+            display_pipeline,
+            display_buffer,
+            display_bind_groups,
+            time: 0.0,
+            speed: 1.0,
+            mode: 1,
+            paused: false,
+            pyramid: true,
+            held_keys: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::Tab => self.mode = (self.mode + 1) % MODES.len(),
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                VirtualKeyCode::P => self.pyramid = !self.pyramid,
+                VirtualKeyCode::Up => self.speed = (self.speed + 0.5).min(6.0),
+                VirtualKeyCode::Down => self.speed = (self.speed - 0.5).max(0.5),
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{}, {}x speed, {}{}",
+            MODES[self.mode],
+            self.speed,
+            if self.pyramid { "4 level pyramid" } else { "finest level only" },
+            if self.paused { ", paused" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        // Paused keeps the last two frames, so the estimate can still be
+        // compared with and without the pyramid
+        if !self.paused {
+            let previous_time = self.time;
+            self.time += FRAME_STEP * self.speed;
+            self.flow.advance();
+            gpu.queue.write_buffer(
+                &self.scene_buffer,
+                0,
+                bytemuck::bytes_of(&SceneUniforms {
+                    time: self.time,
+                    previous_time,
+                }),
+            );
+
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[
+                        Some(wgpu::RenderPassColorAttachment {
+                            view: self.flow.frame(),
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        }),
+                        Some(wgpu::RenderPassColorAttachment {
+                            view: &self.truth,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        }),
+                    ],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        self.flow.estimate(&gpu.queue, &mut encoder, self.pyramid, ITERATIONS);
+
+        gpu.queue.write_buffer(
+            &self.display_buffer,
+            0,
+            bytemuck::bytes_of(&DisplayParams {
+                mode: self.mode as u32,
+                // Faster animation moves further each frame
+                max_motion: 4.0 * self.speed,
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.display_pipeline);
+            render_pass.set_bind_group(0, &self.display_bind_groups[self.flow.current], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,62 @@
+struct Params {
+    // 0 the frame, 1 the estimated flow, 2 the true flow, 3 the error,
+    // 4 the frame with the flow over it
+    mode: u32,
+    // Motion in full resolution pixels that shows at full color
+    max_motion: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var frame: texture_2d<f32>;
+@group(0) @binding(2)
+var flow: texture_2d<f32>;
+@group(0) @binding(3)
+var truth: texture_2d<f32>;
+@group(0) @binding(4)
+var linear_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    return out;
+}
+
+// The usual flow coloring: hue is the direction, saturation the speed
+fn flow_color(motion: vec2<f32>) -> vec3<f32> {
+    let hue = atan2(motion.y, motion.x) / 6.2831853 + 0.5;
+    let rgb = clamp(abs(fract(hue + vec3<f32>(0.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0, vec3<f32>(0.0), vec3<f32>(1.0));
+    let strength = min(length(motion) / params.max_motion, 1.0);
+    return mix(vec3<f32>(1.0), rgb, strength);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let intensity = textureSampleLevel(frame, linear_sampler, in.uv, 0.0).r;
+    // The flow points back to where things were; motion is the other way
+    let estimate = -textureSampleLevel(flow, linear_sampler, in.uv, 0.0).xy;
+    let actual = textureSampleLevel(truth, linear_sampler, in.uv, 0.0).xy;
+
+    var color = vec3<f32>(intensity);
+    if params.mode == 1u {
+        color = flow_color(estimate);
+    } else if params.mode == 2u {
+        color = flow_color(actual);
+    } else if params.mode == 3u {
+        let error = min(length(estimate - actual) / params.max_motion, 1.0);
+        color = vec3<f32>(error, error * 0.3, 0.0) + vec3<f32>(intensity * 0.2);
+    } else if params.mode == 4u {
+        color = mix(vec3<f32>(intensity), flow_color(estimate), 0.5);
+    }
+    // The frames and colors are made up in sRGB
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,25 @@
+@group(0) @binding(0)
+var finer: texture_2d<f32>;
+@group(0) @binding(1)
+var linear_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    return out;
+}
+
+// Each pixel's center falls where four finer pixels meet, so one linear
+// sample averages them
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) f32 {
+    return textureSampleLevel(finer, linear_sampler, in.uv, 0.0).r;
+}
@@ -0,0 +1,91 @@
+struct Params {
+    // Of this pyramid level
+    size: vec2<f32>,
+    // 0 on the coarsest level, which starts from no motion
+    has_coarser: u32,
+    iterations: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+// The frame the flow is worked out for, and the one it's matched against
+@group(0) @binding(1)
+var current: texture_2d<f32>;
+@group(0) @binding(2)
+var previous: texture_2d<f32>;
+// The estimate from the level above, at half the size
+@group(0) @binding(3)
+var coarser: texture_2d<f32>;
+@group(0) @binding(4)
+var linear_sampler: sampler;
+// xy, in this level's pixels, from a pixel of the current frame to where
+// the same thing was in the previous one
+@group(0) @binding(5)
+var flow: texture_storage_2d<rgba16float, write>;
+
+const RADIUS: i32 = 3;
+
+fn current_at(pixel: vec2<f32>) -> f32 {
+    return textureSampleLevel(current, linear_sampler, pixel / params.size, 0.0).r;
+}
+
+fn previous_at(pixel: vec2<f32>) -> f32 {
+    return textureSampleLevel(previous, linear_sampler, pixel / params.size, 0.0).r;
+}
+
+// Lucas-Kanade: assume everything in a small window moves the same way,
+// and solve for the displacement that best explains the difference between
+// the frames with the window's gradients. A few iterations per level, each
+// resampling the previous frame at the better guess.
+@compute @workgroup_size(8, 8)
+fn cs_flow(@builtin(global_invocation_id) id: vec3<u32>) {
+    if f32(id.x) >= params.size.x || f32(id.y) >= params.size.y {
+        return;
+    }
+    let center = vec2<f32>(id.xy) + 0.5;
+
+    // Twice the coarser level's estimate, as its pixels are twice the size
+    var displacement = vec2<f32>(0.0);
+    if params.has_coarser != 0u {
+        displacement = textureSampleLevel(coarser, linear_sampler, center / params.size, 0.0).xy * 2.0;
+    }
+
+    // The current frame doesn't change between iterations, so its
+    // intensities and gradients are taken once along with the structure
+    // tensor they sum to
+    var window: array<vec3<f32>, 49>;
+    var g = vec3<f32>(0.0);
+    var i = 0;
+    for (var y = -RADIUS; y <= RADIUS; y++) {
+        for (var x = -RADIUS; x <= RADIUS; x++) {
+            let p = center + vec2<f32>(f32(x), f32(y));
+            let gradient = vec2<f32>(
+                current_at(p + vec2<f32>(1.0, 0.0)) - current_at(p - vec2<f32>(1.0, 0.0)),
+                current_at(p + vec2<f32>(0.0, 1.0)) - current_at(p - vec2<f32>(0.0, 1.0))
+            ) * 0.5;
+            window[i] = vec3<f32>(current_at(p), gradient);
+            g += vec3<f32>(gradient.x * gradient.x, gradient.x * gradient.y, gradient.y * gradient.y);
+            i++;
+        }
+    }
+    let determinant = g.x * g.z - g.y * g.y;
+
+    // Flat windows, or edges running one way only, can't say how they moved
+    if determinant > 1e-6 {
+        for (var iteration = 0u; iteration < params.iterations; iteration++) {
+            var mismatch = vec2<f32>(0.0);
+            i = 0;
+            for (var y = -RADIUS; y <= RADIUS; y++) {
+                for (var x = -RADIUS; x <= RADIUS; x++) {
+                    let p = center + vec2<f32>(f32(x), f32(y));
+                    let texel = window[i];
+                    mismatch += (texel.x - previous_at(p + displacement)) * texel.yz;
+                    i++;
+                }
+            }
+            // The 2x2 inverse of the tensor times the mismatch
+            displacement += vec2<f32>(g.z * mismatch.x - g.y * mismatch.y, g.x * mismatch.y - g.y * mismatch.x) / determinant;
+        }
+    }
+    textureStore(flow, vec2<i32>(id.xy), vec4<f32>(displacement, 0.0, 1.0));
+}
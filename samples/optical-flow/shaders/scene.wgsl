@@ -0,0 +1,96 @@
+struct Uniforms {
+    time: f32,
+    // Of the frame before, for the true motion
+    previous_time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    return out;
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.547);
+}
+
+fn noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let s = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(cell), hash(cell + vec2<f32>(1.0, 0.0)), s.x),
+        mix(hash(cell + vec2<f32>(0.0, 1.0)), hash(cell + vec2<f32>(1.0, 1.0)), s.x),
+        s.y
+    );
+}
+
+// Blotchy texture, plenty of gradient in every direction for the flow to
+// lock on to
+fn pattern(local: vec2<f32>, seed: f32) -> f32 {
+    return 0.2 + 0.45 * noise(local * 0.07 + seed) + 0.35 * noise(local * 0.19 + seed * 3.1);
+}
+
+fn rotate(v: vec2<f32>, angle: f32) -> vec2<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec2<f32>(c * v.x - s * v.y, s * v.x + c * v.y);
+}
+
+// Where an object is at a time: x, y center, z rotation, w scale
+fn placement(object: i32, t: f32) -> vec4<f32> {
+    if object == 0 {
+        return vec4<f32>(180.0, 190.0, t * 0.8, 1.0);
+    } else if object == 1 {
+        return vec4<f32>(390.0 + cos(t * 0.7) * 110.0, 180.0 + sin(t * 1.1) * 80.0, t * 0.3, 1.0);
+    }
+    return vec4<f32>(530.0, 100.0, 0.0, 1.0 + 0.35 * sin(t * 1.3));
+}
+
+fn inside(object: i32, local: vec2<f32>) -> bool {
+    if object == 0 {
+        return length(local) < 75.0;
+    }
+    return max(abs(local.x), abs(local.y)) < 50.0;
+}
+
+struct FragmentOutput {
+    @location(0) intensity: f32,
+    // xy how far this pixel's surface moved since the previous frame
+    @location(1) motion: vec4<f32>,
+}
+
+// Three objects over a panning background, each with an analytic motion,
+// so the true flow is known exactly and can be shown next to the estimate
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    let p = in.clip_position.xy;
+    var out: FragmentOutput;
+
+    let pan = vec2<f32>(25.0, 8.0);
+    let background = p + pan * uniforms.time;
+    out.intensity = pattern(background, 0.0) * 0.8;
+    out.motion = vec4<f32>(pan * (uniforms.time - uniforms.previous_time) * -1.0, 0.0, 1.0);
+
+    // Drawn back to front, so the last one that covers the pixel wins
+    for (var object = 0; object < 3; object++) {
+        let now = placement(object, uniforms.time);
+        let local = rotate(p - now.xy, -now.z) / now.w;
+        if inside(object, local) {
+            let before = placement(object, uniforms.previous_time);
+            let previous = before.xy + rotate(local * before.w, before.z);
+            out.intensity = pattern(local, f32(object + 1) * 17.0);
+            out.motion = vec4<f32>(p - previous, 0.0, 1.0);
+        }
+    }
+    return out;
+}
@@ -0,0 +1,82 @@
+// The most colors the present pass has room for
+pub const MAX_COLORS: usize = 64;
+
+pub struct Palette {
+    pub name: String,
+    // sRGB, 0 to 1
+    pub colors: Vec<[f32; 3]>,
+}
+
+impl Palette {
+    // The .hex format palette sites hand out: one RRGGBB per line, blank
+    // lines and ; comments ignored
+    pub fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let mut colors = Vec::new();
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap().trim().trim_start_matches('#');
+            if line.is_empty() {
+                continue;
+            }
+            if line.len() != 6 {
+                return Err(format!("'{}' isn't six digits", line));
+            }
+            let value = u32::from_str_radix(line, 16).map_err(|_| format!("'{}' isn't a hex color", line))?;
+            colors.push([(value >> 16) as f32 / 255.0, ((value >> 8) & 0xff) as f32 / 255.0, (value & 0xff) as f32 / 255.0]);
+        }
+        if colors.is_empty() {
+            return Err("no colors".to_string());
+        }
+        if colors.len() > MAX_COLORS {
+            return Err(format!("{} colors, at most {} fit", colors.len(), MAX_COLORS));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            colors,
+        })
+    }
+
+    // The file given with --palette path.hex, or PICO-8's. Errors start
+    // with the path.
+    pub fn load() -> Result<Self, String> {
+        let mut args = std::env::args().skip_while(|arg| arg != "--palette");
+        match args.nth(1) {
+            Some(path) => Self::load_file(&path).map_err(|error| format!("{}: {}", path, error)),
+            None => Ok(Self::parse("pico-8", include_str!("palettes/pico-8.hex")).expect("the built-in palette parses")),
+        }
+    }
+
+    fn load_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        // Named after the file, or the whole path for one like ".hex"
+        let name = std::path::Path::new(path)
+            .file_stem()
+            .map_or_else(|| path.to_string(), |stem| stem.to_string_lossy().into_owned());
+        Self::parse(&name, &text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_lines() {
+        let palette = Palette::parse("test", "; a comment\nff0000\n\n#00ff80 ; green\n").unwrap();
+        assert_eq!(palette.name, "test");
+        assert_eq!(palette.colors, vec![[1.0, 0.0, 0.0], [0.0, 1.0, 128.0 / 255.0]]);
+    }
+
+    #[test]
+    fn rejects_bad_palettes() {
+        assert!(Palette::parse("test", "fff").is_err());
+        assert!(Palette::parse("test", "gg0000").is_err());
+        assert!(Palette::parse("test", "; only a comment\n").is_err());
+        assert!(Palette::parse("test", &"000000\n".repeat(MAX_COLORS + 1)).is_err());
+        assert!(Palette::parse("test", &"000000\n".repeat(MAX_COLORS)).is_ok());
+    }
+
+    #[test]
+    fn built_in_palette_parses() {
+        assert_eq!(Palette::parse("pico-8", include_str!("palettes/pico-8.hex")).unwrap().colors.len(), 16);
+    }
+}
@@ -0,0 +1,583 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, TextureView};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::palette::{Palette, MAX_COLORS};
+
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+// Roughly how many pixels tall the picture is; the exact size is whatever
+// divides the window into whole pixels
+const DEFAULT_TARGET_HEIGHT: u32 = 180;
+// World units from the bottom of the picture to the top
+const VIEW_HEIGHT: f32 = 30.0;
+const PAN_SPEED: f32 = 10.0;
+const DRIFT_SPEED: f32 = 1.5;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct PresentParams {
+    offset: [f32; 2],
+    scale: f32,
+    colors: u32,
+    dither: f32,
+    _padding: [f32; 3],
+    palette: [[f32; 4]; MAX_COLORS],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+// A village of boxy houses and trees around a pond
+fn scene() -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    let mut quad = |corners: [Vec3; 4], color: [f32; 3]| {
+        let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize();
+        for index in [0, 1, 2, 0, 2, 3] {
+            vertices.push(Vertex {
+                position: corners[index].to_array(),
+                normal: normal.to_array(),
+                color,
+            });
+        }
+    };
+    let mut cuboid = |min: Vec3, max: Vec3, color: [f32; 3]| {
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(if x { max.x } else { min.x }, if y { max.y } else { min.y }, if z { max.z } else { min.z })
+        };
+        quad([corner(false, false, true), corner(true, false, true), corner(true, true, true), corner(false, true, true)], color);
+        quad([corner(true, false, true), corner(true, false, false), corner(true, true, false), corner(true, true, true)], color);
+        quad([corner(true, false, false), corner(false, false, false), corner(false, true, false), corner(true, true, false)], color);
+        quad([corner(false, false, false), corner(false, false, true), corner(false, true, true), corner(false, true, false)], color);
+        quad([corner(false, true, false), corner(false, true, true), corner(true, true, true), corner(true, true, false)], color);
+    };
+
+    cuboid(Vec3::new(-80.0, -1.0, -80.0), Vec3::new(80.0, 0.0, 80.0), [0.25, 0.5, 0.2]);
+    cuboid(Vec3::new(-6.0, -0.9, -5.0), Vec3::new(6.0, 0.05, 5.0), [0.2, 0.4, 0.8]);
+    // Paths along the grid lines
+    for line in -4..=4 {
+        let offset = line as f32 * 16.0;
+        cuboid(Vec3::new(offset - 1.0, 0.0, -80.0), Vec3::new(offset + 1.0, 0.02, 80.0), [0.6, 0.5, 0.35]);
+        cuboid(Vec3::new(-80.0, 0.0, offset - 1.0), Vec3::new(80.0, 0.02, offset + 1.0), [0.6, 0.5, 0.35]);
+    }
+
+    let mut rng = Rng(0x1f2e3d4c);
+    for cell_x in -5..5 {
+        for cell_z in -5..5 {
+            let center = Vec3::new(cell_x as f32 * 16.0 + 8.0, 0.0, cell_z as f32 * 16.0 + 8.0);
+            // The pond's block stays open
+            if center.x.abs() < 10.0 && center.z.abs() < 10.0 {
+                continue;
+            }
+            if rng.next_f32() < 0.5 {
+                let half = Vec3::new(rng.range(2.0, 4.0), 0.0, rng.range(2.0, 4.0));
+                let height = rng.range(2.5, 5.0);
+                let wall = [rng.range(0.7, 0.9), rng.range(0.6, 0.8), rng.range(0.5, 0.6)];
+                let roof = if rng.next_f32() < 0.5 { [0.7, 0.2, 0.15] } else { [0.3, 0.3, 0.45] };
+                cuboid(center - half, center + half + Vec3::Y * height, wall);
+                // A stepped roof, a pixel artist's idea of a slope
+                cuboid(center - half * 1.15 + Vec3::Y * height, center + half * 1.15 + Vec3::Y * (height + 0.8), roof);
+                cuboid(center - half * 0.6 + Vec3::Y * (height + 0.8), center + half * 0.6 + Vec3::Y * (height + 1.6), roof);
+            }
+            for _ in 0..3 {
+                let position = center + Vec3::new(rng.range(-7.0, 7.0), 0.0, rng.range(-7.0, 7.0));
+                if (position - center).abs().max_element() < 5.0 {
+                    continue;
+                }
+                let size = rng.range(0.8, 1.6);
+                cuboid(position - Vec3::new(0.25, 0.0, 0.25), position + Vec3::new(0.25, 1.5, 0.25), [0.45, 0.3, 0.15]);
+                cuboid(
+                    position + Vec3::new(-size, 1.5, -size),
+                    position + Vec3::new(size, 1.5 + size * 2.0, size),
+                    [0.15, rng.range(0.4, 0.55), 0.15],
+                );
+            }
+        }
+    }
+    vertices
+}
+
+// The picture at its real, tiny size, and how it's blown up to the window
+struct LowRes {
+    color: TextureView,
+    depth: TextureView,
+    size: [u32; 2],
+    scale: u32,
+    // Of the scaled picture on the surface, centering it
+    offset: [u32; 2],
+}
+
+impl LowRes {
+    // The biggest whole scale that keeps the picture at least
+    // target_height tall, with the size then picked to fill the window.
+    // Whatever's left over, less than one scaled pixel, is a black border.
+    fn new(device: &Device, config: &wgpu::SurfaceConfiguration, target_height: u32) -> Self {
+        let scale = (config.height / target_height).max(1);
+        let size = [(config.width / scale).max(1), (config.height / scale).max(1)];
+        let offset = [(config.width - size[0] * scale) / 2, (config.height - size[1] * scale) / 2];
+        let create_view = |format, label| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width: size[0],
+                        height: size[1],
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        Self {
+            color: create_view(COLOR_FORMAT, "Low Res Color"),
+            depth: create_view(DEPTH_FORMAT, "Low Res Depth"),
+            size,
+            scale,
+            offset,
+        }
+    }
+}
+
+fn create_present_bind_group(device: &Device, layout: &BindGroupLayout, params_buffer: &Buffer, low_res: &LowRes) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Present Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&low_res.color),
+            },
+        ],
+    })
+}
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    present_pipeline: RenderPipeline,
+    scene_bind_group: BindGroup,
+    present_layout: BindGroupLayout,
+    present_bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    params_buffer: Buffer,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    low_res: LowRes,
+    palette: Palette,
+    target_height: u32,
+    // The point in the middle of the screen, on the ground
+    focus: Vec3,
+    snap: bool,
+    dither: bool,
+    drift: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Renderer {
+    // Looking down at the classic three-quarter angle, from far enough back
+    // that nothing gets clipped
+    fn camera_axes() -> (Vec3, Vec3, Vec3) {
+        let (yaw, pitch) = (45f32.to_radians(), -35f32.to_radians());
+        let forward = Vec3::new(yaw.sin() * pitch.cos(), pitch.sin(), -yaw.cos() * pitch.cos());
+        let right = forward.cross(Vec3::Y).normalize();
+        let up = right.cross(forward);
+        (forward, right, up)
+    }
+
+    // Moves the focus so the camera sits on a whole low resolution pixel,
+    // measured along the screen's own axes. With a fixed orientation,
+    // every pixel of the scene then lands on the same pixel it did last
+    // frame, just shifted, instead of edges crawling as the camera slides.
+    fn snapped_focus(&self) -> Vec3 {
+        if !self.snap {
+            return self.focus;
+        }
+        let (forward, right, up) = Self::camera_axes();
+        let texel = VIEW_HEIGHT / self.low_res.size[1] as f32;
+        let snap = |value: f32| (value / texel).round() * texel;
+        right * snap(self.focus.dot(right)) + up * snap(self.focus.dot(up)) + forward * self.focus.dot(forward)
+    }
+
+    fn params(&self) -> PresentParams {
+        let mut palette = [[0.0; 4]; MAX_COLORS];
+        for (entry, color) in palette.iter_mut().zip(&self.palette.colors) {
+            *entry = [color[0], color[1], color[2], 1.0];
+        }
+        PresentParams {
+            offset: [self.low_res.offset[0] as f32, self.low_res.offset[1] as f32],
+            scale: self.low_res.scale as f32,
+            colors: self.palette.colors.len() as u32,
+            // Fewer colors are further apart, so the dither has to reach further
+            dither: if self.dither { 1.0 / (self.palette.colors.len() as f32).sqrt() } else { 0.0 },
+            _padding: [0.0; 3],
+            palette,
+        }
+    }
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let palette = Palette::load().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        });
+        let device = &gpu.device;
+        let scene_shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+        let present_shader = device.create_shader_module(include_wgsl!("shaders/present.wgsl"));
+
+        let vertices = scene();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Village"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Mat4::IDENTITY.to_cols_array_2d()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Present Params"),
+            contents: bytemuck::bytes_of(&PresentParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_entry = |visibility| wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Bind Group Layout"),
+            entries: &[uniform_entry(wgpu::ShaderStages::VERTEX)],
+        });
+        let present_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Present Bind Group Layout"),
+            entries: &[
+                uniform_entry(wgpu::ShaderStages::FRAGMENT),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        // Only ever loaded, never filtered
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let scene_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &scene_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let low_res = LowRes::new(device, &gpu.config, DEFAULT_TARGET_HEIGHT);
+        let present_bind_group = create_present_bind_group(device, &present_layout, &params_buffer, &low_res);
+
+        let scene_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&scene_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&scene_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &scene_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &scene_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            // Multisampling would blend edges into colors the palette
+            // doesn't have, and blur the pixels anyway
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let present_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Present Pipeline Layout"),
+            bind_group_layouts: &[&present_layout],
+            push_constant_ranges: &[],
+        });
+        let present_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Present Pipeline"),
+            layout: Some(&present_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &present_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &present_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            scene_pipeline,
+            present_pipeline,
+            scene_bind_group,
+            present_layout,
+            present_bind_group,
+            uniform_buffer,
+            params_buffer,
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            low_res,
+            palette,
+            target_height: DEFAULT_TARGET_HEIGHT,
+            focus: Vec3::ZERO,
+            snap: true,
+            dither: true,
+            drift: true,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.low_res = LowRes::new(&gpu.device, &gpu.config, self.target_height);
+        self.present_bind_group = create_present_bind_group(&gpu.device, &self.present_layout, &self.params_buffer, &self.low_res);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::G => self.snap = !self.snap,
+                VirtualKeyCode::T => self.dither = !self.dither,
+                VirtualKeyCode::Space => self.drift = !self.drift,
+                VirtualKeyCode::Up => self.target_height = (self.target_height + 30).min(480),
+                VirtualKeyCode::Down => self.target_height = (self.target_height - 30).max(60),
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{}x{} at {}x, {} ({} colors){}, {}",
+            self.low_res.size[0],
+            self.low_res.size[1],
+            self.low_res.scale,
+            self.palette.name,
+            self.palette.colors.len(),
+            if self.dither { " dithered" } else { "" },
+            if self.snap { "snapped to pixels" } else { "not snapped" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        // The scale only changes between whole numbers, so the targets only
+        // need rebuilding when the height asked for gives a different one
+        if self.low_res.scale != (gpu.config.height / self.target_height).max(1) {
+            self.reinit_surface_resources(gpu);
+        }
+
+        let (forward, right, up) = Self::camera_axes();
+        let ground_forward = Vec3::new(forward.x, 0.0, forward.z).normalize();
+        let ground_right = Vec3::new(right.x, 0.0, right.z).normalize();
+        let held = |key| self.held_keys.contains(&key);
+        let mut movement = Vec3::ZERO;
+        if held(VirtualKeyCode::W) { movement += ground_forward; }
+        if held(VirtualKeyCode::S) { movement -= ground_forward; }
+        if held(VirtualKeyCode::D) { movement += ground_right; }
+        if held(VirtualKeyCode::A) { movement -= ground_right; }
+        self.focus += movement.normalize_or_zero() * PAN_SPEED * dt;
+        // A slow diagonal drift, where unsnapped edges crawl the most
+        if self.drift {
+            self.focus += Vec3::new(1.0, 0.0, 0.6) * DRIFT_SPEED * dt;
+        }
+
+        let focus = self.snapped_focus();
+        let eye = focus - forward * 100.0;
+        let half_height = VIEW_HEIGHT * 0.5;
+        let half_width = half_height * self.low_res.size[0] as f32 / self.low_res.size[1] as f32;
+        let proj = Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, 1.0, 250.0);
+        let view_proj = proj * Mat4::look_at_rh(eye, focus, up);
+        gpu.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&view_proj.to_cols_array_2d()));
+        gpu.queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&self.params()));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view: &self.low_res.color,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.1,
+                                    g: 0.1,
+                                    b: 0.15,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.low_res.depth,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Present Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            // Only the scaled picture; the border keeps the clear color
+            let low_res = &self.low_res;
+            render_pass.set_viewport(
+                low_res.offset[0] as f32,
+                low_res.offset[1] as f32,
+                (low_res.size[0] * low_res.scale) as f32,
+                (low_res.size[1] * low_res.scale) as f32,
+                0.0,
+                1.0,
+            );
+            render_pass.set_pipeline(&self.present_pipeline);
+            render_pass.set_bind_group(0, &self.present_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,50 @@
+struct Params {
+    // Where the scaled image starts on the surface, in surface pixels
+    offset: vec2<f32>,
+    // Whole surface pixels per low resolution pixel
+    scale: f32,
+    colors: u32,
+    dither: f32,
+    // sRGB
+    palette: array<vec4<f32>, 64>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var image: texture_2d<f32>;
+
+// Covers the viewport, which is set to the scaled image
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+// 4x4 ordered dither thresholds
+fn bayer(texel: vec2<u32>) -> f32 {
+    var thresholds = array<f32, 16>(0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0);
+    return (thresholds[(texel.x & 3u) + (texel.y & 3u) * 4u] + 0.5) / 16.0 - 0.5;
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // Nearest neighbour by hand: every surface pixel of a block loads the
+    // same texel, so nothing gets blurred however big the scale
+    let texel = vec2<u32>((position.xy - params.offset) / params.scale);
+    let linear = textureLoad(image, vec2<i32>(texel), 0).rgb;
+
+    // Matched in sRGB, where the palette was picked
+    let color = pow(linear, vec3<f32>(1.0 / 2.2)) + bayer(texel) * params.dither;
+    var nearest = params.palette[0].rgb;
+    var nearest_distance = 1000.0;
+    for (var i = 0u; i < min(params.colors, 64u); i++) {
+        let candidate = params.palette[i].rgb;
+        let distance = dot(color - candidate, color - candidate);
+        if distance < nearest_distance {
+            nearest = candidate;
+            nearest_distance = distance;
+        }
+    }
+    return vec4<f32>(pow(nearest, vec3<f32>(2.2)), 1.0);
+}
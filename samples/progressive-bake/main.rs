@@ -0,0 +1,8 @@
+mod renderer;
+mod scheduler;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("progressive-bake");
+}
@@ -0,0 +1,252 @@
+use std::time::Instant;
+
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, RenderPipeline, ComputePipeline, BindGroup, Buffer, BufferUsages};
+
+use crate::scheduler::{BakeScheduler, Job, LIGHTMAP_SIZE, MAX_JOBS_PER_FRAME, TILE_SIZE};
+
+// vec2<u32> tile, u32 pass index and padding
+const JOB_SIZE: usize = 16;
+
+pub struct Renderer {
+    bake_pipeline: ComputePipeline,
+    bake_bind_group: BindGroup,
+    // One job per slot, each slot aligned for use as a dynamic offset
+    job_buffer: Buffer,
+    job_stride: u32,
+    display_pipeline: RenderPipeline,
+    display_bind_group: BindGroup,
+    scheduler: BakeScheduler,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let lightmap_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lightmap"),
+            size: (LIGHTMAP_SIZE * LIGHTMAP_SIZE) as u64 * 16,
+            usage: BufferUsages::STORAGE,
+            // Buffers start out zeroed, which is exactly "nothing baked yet"
+            mapped_at_creation: false,
+        });
+
+        let job_stride = device.limits().min_uniform_buffer_offset_alignment;
+        let job_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Bake Jobs"),
+            size: (job_stride * MAX_JOBS_PER_FRAME) as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bake_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bake Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        // Lets every dispatch of a frame pick its own job from one buffer
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(JOB_SIZE as u64),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let bake_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Bake Bind Group"),
+            layout: &bake_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &job_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(JOB_SIZE as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: lightmap_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let bake_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Bake Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Bake Pipeline Layout"),
+                bind_group_layouts: &[&bake_layout],
+                push_constant_ranges: &[],
+            })),
+            module: &device.create_shader_module(include_wgsl!("shaders/bake.wgsl")),
+            entry_point: "main",
+        });
+
+        let display_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let display_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &display_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: lightmap_buffer.as_entire_binding(),
+            }],
+        });
+
+        let display_shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let display_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Display Pipeline Layout"),
+                bind_group_layouts: &[&display_layout],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &display_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &display_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            bake_pipeline,
+            bake_bind_group,
+            job_buffer,
+            job_stride,
+            display_pipeline,
+            display_bind_group,
+            scheduler: BakeScheduler::default(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.scheduler.is_done() {
+            Some("Bake finished".to_string())
+        } else {
+            Some(format!(
+                "Baking {:.1}% ({} dispatches per frame)",
+                self.scheduler.progress() * 100.0,
+                self.scheduler.jobs_per_frame(),
+            ))
+        }
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.bake(gpu);
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.display_pipeline);
+            render_pass.set_bind_group(0, &self.display_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn bake(&mut self, gpu: &Gpu) {
+        let now = Instant::now();
+        self.scheduler.adapt(now - self.last_frame);
+        self.last_frame = now;
+
+        let jobs: Vec<Job> = self.scheduler.next_jobs();
+        if jobs.is_empty() {
+            return;
+        }
+
+        let mut job_data = vec![0u8; jobs.len() * self.job_stride as usize];
+        for (slot, job) in job_data.chunks_mut(self.job_stride as usize).zip(&jobs) {
+            slot[0..4].copy_from_slice(&job.tile[0].to_ne_bytes());
+            slot[4..8].copy_from_slice(&job.tile[1].to_ne_bytes());
+            slot[8..12].copy_from_slice(&job.pass_index.to_ne_bytes());
+        }
+        gpu.queue.write_buffer(&self.job_buffer, 0, &job_data);
+
+        let mut encoder = gpu.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Bake Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Bake Pass"),
+            });
+            compute_pass.set_pipeline(&self.bake_pipeline);
+            for slot in 0..jobs.len() as u32 {
+                compute_pass.set_bind_group(0, &self.bake_bind_group, &[slot * self.job_stride]);
+                compute_pass.dispatch_workgroups(TILE_SIZE / 8, TILE_SIZE / 8, 1);
+            }
+        }
+
+        // A submission of its own, so the bake never sits in the same command
+        // buffer as the frame and the queue can interleave the two
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
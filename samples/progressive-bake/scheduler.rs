@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+// Must match the constants in shaders/bake.wgsl
+pub const LIGHTMAP_SIZE: u32 = 512;
+pub const TILE_SIZE: u32 = 32;
+pub const PASSES: u32 = 64;
+
+const TILES_PER_ROW: u32 = LIGHTMAP_SIZE / TILE_SIZE;
+const TILE_COUNT: u32 = TILES_PER_ROW * TILES_PER_ROW;
+
+// Upper bound so the per-frame uniform buffer has a fixed size
+pub const MAX_JOBS_PER_FRAME: u32 = 256;
+
+// A little above 60Hz: with vsync every frame takes at least 16.6ms, only
+// going over this means we actually missed a present
+const FRAME_BUDGET: Duration = Duration::from_micros(18_000);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Job {
+    pub tile: [u32; 2],
+    pub pass_index: u32,
+}
+
+// Hands out small units of baking work, as many per frame as the frame time allows
+pub struct BakeScheduler {
+    next_job: u32,
+    jobs_per_frame: u32,
+}
+
+impl Default for BakeScheduler {
+    fn default() -> Self {
+        Self {
+            next_job: 0,
+            jobs_per_frame: 1,
+        }
+    }
+}
+
+impl BakeScheduler {
+    pub fn total_jobs(&self) -> u32 {
+        TILE_COUNT * PASSES
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_job >= self.total_jobs()
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.next_job as f32 / self.total_jobs() as f32
+    }
+
+    pub fn jobs_per_frame(&self) -> u32 {
+        self.jobs_per_frame
+    }
+
+    // Additive increase, multiplicative decrease: creep up while frames are on
+    // time, back off hard as soon as one isn't
+    pub fn adapt(&mut self, frame_time: Duration) {
+        if frame_time > FRAME_BUDGET {
+            self.jobs_per_frame = (self.jobs_per_frame / 2).max(1);
+        } else {
+            self.jobs_per_frame = (self.jobs_per_frame + 1).min(MAX_JOBS_PER_FRAME);
+        }
+    }
+
+    // Jobs are ordered pass-major, so the whole lightmap converges together
+    // instead of one tile at a time
+    pub fn next_jobs(&mut self) -> Vec<Job> {
+        let end = (self.next_job + self.jobs_per_frame).min(self.total_jobs());
+        let jobs = (self.next_job..end)
+            .map(|job| {
+                let tile = job % TILE_COUNT;
+                Job {
+                    tile: [tile % TILES_PER_ROW, tile / TILES_PER_ROW],
+                    pass_index: job / TILE_COUNT,
+                }
+            })
+            .collect();
+        self.next_job = end;
+        jobs
+    }
+}
@@ -0,0 +1,93 @@
+// Must match the constants in renderer.rs
+const LIGHTMAP_SIZE: u32 = 512u;
+const TILE_SIZE: u32 = 32u;
+const RAYS_PER_PASS: u32 = 16u;
+const OCCLUDER_COUNT: u32 = 5u;
+
+struct Job {
+  tile: vec2<u32>,
+  pass_index: u32,
+  _padding: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> job: Job;
+
+// rgb is the summed radiance, a is the number of rays that went into it
+@group(0) @binding(1)
+var<storage, read_write> lightmap: array<vec4<f32>>;
+
+// xy is the center, z the radius, all in lightmap texels
+fn occluder(index: u32) -> vec3<f32> {
+  var occluders = array<vec3<f32>, 5>(
+    vec3(140.0, 150.0, 40.0),
+    vec3(360.0, 120.0, 25.0),
+    vec3(250.0, 300.0, 60.0),
+    vec3(100.0, 400.0, 30.0),
+    vec3(420.0, 380.0, 45.0)
+  );
+  return occluders[index];
+}
+
+const LAMP: vec3<f32> = vec3<f32>(300.0, 60.0, 12.0);
+const LAMP_COLOR: vec3<f32> = vec3<f32>(6.0, 4.5, 2.5);
+const SKY_COLOR: vec3<f32> = vec3<f32>(0.05, 0.07, 0.1);
+
+fn pcg_hash(input: u32) -> u32 {
+  let state = input * 747796405u + 2891336453u;
+  let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+  return (word >> 22u) ^ word;
+}
+
+// Distance along the ray to the circle, or -1 if it misses
+fn intersect(origin: vec2<f32>, direction: vec2<f32>, circle: vec3<f32>) -> f32 {
+  let to_center = circle.xy - origin;
+  let along = dot(to_center, direction);
+  let closest = dot(to_center, to_center) - along * along;
+  let radius_squared = circle.z * circle.z;
+  if along < 0.0 || closest > radius_squared {
+    return -1.0;
+  }
+  return along - sqrt(radius_squared - closest);
+}
+
+fn trace(origin: vec2<f32>, direction: vec2<f32>) -> vec3<f32> {
+  var nearest = 1e9;
+  for (var i = 0u; i < OCCLUDER_COUNT; i++) {
+    let t = intersect(origin, direction, occluder(i));
+    if t >= 0.0 && t < nearest {
+      nearest = t;
+    }
+  }
+
+  let lamp_t = intersect(origin, direction, LAMP);
+  if lamp_t >= 0.0 && lamp_t < nearest {
+    return LAMP_COLOR;
+  }
+  if nearest < 1e9 {
+    return vec3(0.0);
+  }
+  return SKY_COLOR;
+}
+
+// Each dispatch only covers one tile for one pass, so a single dispatch is
+// cheap and the CPU decides how many of them fit into a frame
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+  let texel = job.tile * TILE_SIZE + id.xy;
+  if texel.x >= LIGHTMAP_SIZE || texel.y >= LIGHTMAP_SIZE {
+    return;
+  }
+  let origin = vec2<f32>(texel) + vec2(0.5);
+  let index = texel.y * LIGHTMAP_SIZE + texel.x;
+
+  var radiance = vec3(0.0);
+  var seed = pcg_hash(index ^ pcg_hash(job.pass_index));
+  for (var ray = 0u; ray < RAYS_PER_PASS; ray++) {
+    seed = pcg_hash(seed);
+    let angle = f32(seed) / 4294967295.0 * 6.2831853;
+    radiance += trace(origin, vec2(cos(angle), sin(angle)));
+  }
+
+  lightmap[index] += vec4(radiance, f32(RAYS_PER_PASS));
+}
@@ -0,0 +1,36 @@
+// Must match LIGHTMAP_SIZE in renderer.rs
+const LIGHTMAP_SIZE: u32 = 512u;
+
+@group(0) @binding(0)
+var<storage, read> lightmap: array<vec4<f32>>;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var pos = array<vec2<f32>, 3>(
+    vec2(-1.0, -1.0),
+    vec2(3.0, -1.0),
+    vec2(-1.0, 3.0)
+  );
+  let p = pos[index];
+  return VertexOutput(vec4(p, 0.0, 1.0), vec2(p.x * 0.5 + 0.5, 0.5 - p.y * 0.5));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let texel = min(vec2<u32>(in.uv * f32(LIGHTMAP_SIZE)), vec2(LIGHTMAP_SIZE - 1u));
+  let sum = lightmap[texel.y * LIGHTMAP_SIZE + texel.x];
+
+  // Texels nobody has baked yet show up as a dark checkerboard
+  if sum.a == 0.0 {
+    let checker = f32((texel.x / 16u + texel.y / 16u) % 2u);
+    return vec4(vec3(0.02 + checker * 0.02), 1.0);
+  }
+
+  let color = sum.rgb / sum.a;
+  return vec4(color / (1.0 + color), 1.0);
+}
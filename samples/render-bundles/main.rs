@@ -0,0 +1,8 @@
+mod renderer;
+mod scene;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("render-bundles");
+}
@@ -0,0 +1,208 @@
+use std::time::Instant;
+
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{Device, RenderBundle, TextureView};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::scene::{Scene, DEPTH_FORMAT, GRID};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    // Replay the draws recorded once at startup
+    Bundle,
+    // Record every draw into the render pass again each frame
+    Direct,
+}
+
+// What a bundle does and doesn't survive:
+// - It holds on to the pipeline, bind groups and buffers it was recorded
+//   with. Writing new contents into those buffers is fine, that's how the
+//   camera moves here. Replacing any of them means recording a new bundle.
+// - It can only run in passes whose color formats, depth format and sample
+//   count match its RenderBundleEncoderDescriptor. A new surface format
+//   means a new bundle, a new surface size doesn't.
+// - Viewport, scissor, blend constant and stencil reference can't be set
+//   inside a bundle, it uses whatever the pass has.
+// - The pass's pipeline, bind groups and buffers are unset after
+//   execute_bundles, so direct draws following it have to set them again.
+fn record_bundle(device: &Device, scene: &Scene, format: wgpu::TextureFormat) -> RenderBundle {
+    let mut encoder = device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+        label: Some("Scene Bundle Encoder"),
+        color_formats: &[Some(format)],
+        depth_stencil: Some(wgpu::RenderBundleDepthStencil {
+            format: DEPTH_FORMAT,
+            depth_read_only: false,
+            stencil_read_only: true,
+        }),
+        sample_count: 1,
+        multiview: None,
+    });
+    scene.record(&mut encoder);
+    encoder.finish(&wgpu::RenderBundleDescriptor {
+        label: Some("Scene Bundle"),
+    })
+}
+
+fn create_depth_view(device: &Device, config: &wgpu::SurfaceConfiguration) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+pub struct Renderer {
+    scene: Scene,
+    bundle: RenderBundle,
+    // The format scene and bundle were built for
+    format: wgpu::TextureFormat,
+    depth_view: TextureView,
+    mode: Mode,
+    // Smoothed CPU time spent recording the pass, per mode, in milliseconds
+    encode_ms: [Option<f32>; 2],
+    start: Instant,
+    // Set by --flythrough or a script, an orbit until then
+    camera: Option<CameraPose>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let format = gpu.config.format;
+        let scene = Scene::new(&gpu.device, format);
+        let bundle = record_bundle(&gpu.device, &scene, format);
+
+        Self {
+            scene,
+            bundle,
+            format,
+            depth_view: create_depth_view(&gpu.device, &gpu.config),
+            mode: Mode::Bundle,
+            encode_ms: [None; 2],
+            start: Instant::now(),
+            camera: None,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(&gpu.device, &gpu.config);
+        if gpu.config.format != self.format {
+            self.format = gpu.config.format;
+            self.scene = Scene::new(&gpu.device, self.format);
+            self.bundle = record_bundle(&gpu.device, &self.scene, self.format);
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::Space),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.mode = match self.mode {
+                Mode::Bundle => Mode::Direct,
+                Mode::Direct => Mode::Bundle,
+            };
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        let show = |ms: Option<f32>| ms.map_or("-".to_string(), |ms| format!("{:.3} ms", ms));
+        Some(format!(
+            "{:?}, {} draws, encoding: bundle {}, direct {}",
+            self.mode,
+            GRID * GRID,
+            show(self.encode_ms[0]),
+            show(self.encode_ms[1]),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let angle = self.start.elapsed().as_secs_f32() * 0.1;
+        let view_matrix = match &self.camera {
+            Some(pose) => pose.view_matrix(),
+            None => Mat4::look_at_rh(
+                Vec3::new(angle.cos() * 110.0, 60.0, angle.sin() * 110.0),
+                Vec3::ZERO,
+                Vec3::Y,
+            ),
+        };
+        let proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), 1.0, 500.0);
+        // The bundle references this buffer, not its contents, so updating
+        // it doesn't invalidate anything
+        gpu.queue.write_buffer(
+            &self.scene.camera_buffer,
+            0,
+            bytemuck::cast_slice(&(proj * view_matrix).to_cols_array()),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        let encode_start = Instant::now();
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.1,
+                                    g: 0.1,
+                                    b: 0.12,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            match self.mode {
+                Mode::Bundle => render_pass.execute_bundles(std::iter::once(&self.bundle)),
+                Mode::Direct => self.scene.record(&mut render_pass),
+            }
+        }
+        let encode_ms = encode_start.elapsed().as_secs_f32() * 1000.0;
+
+        let average = &mut self.encode_ms[self.mode as usize];
+        *average = Some(average.map_or(encode_ms, |average| average * 0.95 + encode_ms * 0.05));
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
@@ -0,0 +1,41 @@
+struct Camera {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(2) offset: vec4<f32>,
+    @location(3) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    // offset.w is the box's height
+    let world = vertex.position * vec3<f32>(1.0, instance.offset.w, 1.0) + instance.offset.xyz;
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(world, 1.0);
+    out.normal = vertex.normal;
+    out.color = instance.color.rgb;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light = normalize(vec3<f32>(0.3, 0.9, 0.4));
+    let diffuse = max(dot(in.normal, light), 0.0);
+    return vec4<f32>(in.color * (0.3 + 0.7 * diffuse), 1.0);
+}
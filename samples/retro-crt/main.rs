@@ -0,0 +1,8 @@
+mod renderer;
+mod stages;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("retro-crt");
+}
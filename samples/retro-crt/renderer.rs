@@ -0,0 +1,216 @@
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::Instant;
+
+use framework::post::{PostChain, HDR_FORMAT};
+use framework::framegraph::FrameGraph;
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::stages::{Curvature, NtscBleed, PhosphorMask, PixelArt, Pixelate, Scanlines, PALETTES};
+
+const STAGE_KEYS: [VirtualKeyCode; 5] = [
+    VirtualKeyCode::Key1,
+    VirtualKeyCode::Key2,
+    VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4,
+    VirtualKeyCode::Key5,
+];
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    chain: PostChain,
+    pixel_art: Rc<Cell<PixelArt>>,
+    held_keys: HashSet<VirtualKeyCode>,
+    start: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::cast_slice(&[0.0f32; 4]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                // The scene goes into the chain, not the surface
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // Signal first, then the tube it's shown on. The scene is already
+        // in displayable range, so there's no tonemap.
+        let pixel_art = Rc::new(Cell::new(PixelArt {
+            pixel_size: 4.0,
+            palette: 1,
+            dither: true,
+        }));
+        let mut chain = PostChain::new(device, &gpu.config);
+        chain.push(device, Pixelate::new(device, pixel_art.clone()));
+        chain.push(device, NtscBleed::new(device));
+        chain.push(device, Scanlines::new(device));
+        chain.push(device, PhosphorMask::new(device));
+        chain.push(device, Curvature::new(device));
+
+        Self {
+            scene_pipeline,
+            uniform_buffer,
+            bind_group,
+            chain,
+            pixel_art,
+            held_keys: HashSet::new(),
+            start: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.chain.resize(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            if let Some(index) = STAGE_KEYS.iter().position(|stage_key| stage_key == key) {
+                self.chain.toggle(index);
+                return;
+            }
+            let mut pixel_art = self.pixel_art.get();
+            match key {
+                VirtualKeyCode::P => pixel_art.palette = (pixel_art.palette + 1) % PALETTES.len(),
+                VirtualKeyCode::D => pixel_art.dither = !pixel_art.dither,
+                VirtualKeyCode::Up => pixel_art.pixel_size = (pixel_art.pixel_size + 1.0).min(16.0),
+                VirtualKeyCode::Down => pixel_art.pixel_size = (pixel_art.pixel_size - 1.0).max(1.0),
+                _ => {}
+            }
+            self.pixel_art.set(pixel_art);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let stages: Vec<String> = self
+            .chain
+            .stages()
+            .enumerate()
+            .map(|(index, (name, enabled))| format!("{} {} {}", index + 1, name, if enabled { "on" } else { "off" }))
+            .collect();
+        let pixel_art = self.pixel_art.get();
+        Some(format!(
+            "{}, {} px, {}{}",
+            stages.join(", "),
+            pixel_art.pixel_size,
+            PALETTES[pixel_art.palette].name,
+            if pixel_art.dither { " dithered" } else { "" },
+        ))
+    }
+
+    fn frame_graph(&self) -> Option<&FrameGraph> {
+        Some(self.chain.graph())
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let uniforms = [self.start.elapsed().as_secs_f32(), gpu.aspect_ratio(), 0.0, 0.0];
+        gpu.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&uniforms));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view: self.chain.input(),
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        self.chain.run(&gpu.device, &gpu.queue, &mut encoder, view);
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
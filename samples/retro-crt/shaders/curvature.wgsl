@@ -0,0 +1,28 @@
+struct Params {
+    // How much the tube bulges, 0 is flat
+    amount: f32,
+    vignette: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    // Barrel distortion: further from the center reads further out
+    var centered = in.uv * 2.0 - 1.0;
+    centered *= 1.0 + dot(centered, centered) * params.amount * 0.25;
+    let uv = centered * 0.5 + 0.5;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let color = textureSampleLevel(source, post_sampler, uv, 0.0).rgb;
+    // Darker toward the edges, with softened corners
+    let edge = uv * (1.0 - uv);
+    let shade = pow(clamp(edge.x * edge.y * 16.0, 0.0, 1.0), params.vignette);
+    let corner = smoothstep(0.0, 0.01, min(edge.x, edge.y));
+    return vec4<f32>(color * shade * corner, 1.0);
+}
@@ -0,0 +1,52 @@
+struct Params {
+    resolution: vec2<f32>,
+    // How far color smears sideways, in screen pixels
+    bleed: f32,
+    // Color carried a little behind brightness, in screen pixels
+    shift: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+fn to_yiq(color: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        dot(color, vec3<f32>(0.299, 0.587, 0.114)),
+        dot(color, vec3<f32>(0.596, -0.274, -0.322)),
+        dot(color, vec3<f32>(0.211, -0.523, 0.312))
+    );
+}
+
+fn from_yiq(yiq: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        dot(yiq, vec3<f32>(1.0, 0.956, 0.621)),
+        dot(yiq, vec3<f32>(1.0, -0.272, -0.647)),
+        dot(yiq, vec3<f32>(1.0, -1.106, 1.703))
+    );
+}
+
+fn yiq_at(uv: vec2<f32>) -> vec3<f32> {
+    let linear = textureSampleLevel(source, post_sampler, uv, 0.0).rgb;
+    return to_yiq(pow(max(linear, vec3<f32>(0.0)), vec3<f32>(1.0 / 2.2)));
+}
+
+// Composite video carries color in much less bandwidth than brightness,
+// so brightness stays fairly sharp while color smears along the line
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / params.resolution.x;
+    let luma = yiq_at(in.uv - vec2<f32>(texel, 0.0)).x * 0.25 + yiq_at(in.uv).x * 0.5 + yiq_at(in.uv + vec2<f32>(texel, 0.0)).x * 0.25;
+
+    var chroma = vec2<f32>(0.0);
+    var total = 0.0;
+    for (var i = -6; i <= 6; i++) {
+        let offset = (f32(i) / 6.0 * params.bleed - params.shift) * texel;
+        let weight = 1.0 - abs(f32(i)) / 7.0;
+        chroma += yiq_at(in.uv + vec2<f32>(offset, 0.0)).yz * weight;
+        total += weight;
+    }
+    let color = from_yiq(vec3<f32>(luma, chroma / total));
+    return vec4<f32>(pow(max(color, vec3<f32>(0.0)), vec3<f32>(2.2)), 1.0);
+}
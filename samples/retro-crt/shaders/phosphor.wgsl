@@ -0,0 +1,28 @@
+struct Params {
+    // Width of a red, green and blue triad in screen pixels
+    triad: f32,
+    // How dark the other two phosphors of each stripe are
+    strength: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+// An aperture grille: vertical stripes of red, green and blue phosphor
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb;
+    let stripe = u32(in.position.x / (params.triad / 3.0)) % 3u;
+    var mask = vec3<f32>(1.0 - params.strength);
+    if stripe == 0u {
+        mask.r = 1.0;
+    } else if stripe == 1u {
+        mask.g = 1.0;
+    } else {
+        mask.b = 1.0;
+    }
+    // Divided by the mask's average so the screen keeps its brightness
+    return vec4<f32>(color * mask / (1.0 - params.strength * 2.0 / 3.0), 1.0);
+}
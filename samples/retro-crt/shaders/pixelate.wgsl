@@ -0,0 +1,47 @@
+struct Params {
+    resolution: vec2<f32>,
+    // In screen pixels
+    pixel_size: f32,
+    // How much of a palette step the dither can push a color by
+    dither: f32,
+    // 0 keeps the full color
+    colors: u32,
+    // sRGB, like palettes are written down
+    palette: array<vec4<f32>, 16>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+// 4x4 ordered dither thresholds
+fn bayer(cell: vec2<u32>) -> f32 {
+    var thresholds = array<f32, 16>(0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0);
+    return (thresholds[(cell.x & 3u) + (cell.y & 3u) * 4u] + 0.5) / 16.0 - 0.5;
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    // Every pixel of a block reads the block's center
+    let cell = floor(in.uv * params.resolution / params.pixel_size);
+    let uv = (cell + 0.5) * params.pixel_size / params.resolution;
+    let linear = textureSampleLevel(source, post_sampler, uv, 0.0).rgb;
+    if params.colors == 0u {
+        return vec4<f32>(linear, 1.0);
+    }
+
+    // Quantized where the palette lives, in sRGB, then back to linear
+    let color = pow(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.2)) + bayer(vec2<u32>(cell)) * params.dither;
+    var nearest = params.palette[0].rgb;
+    var nearest_distance = 1000.0;
+    for (var i = 0u; i < min(params.colors, 16u); i++) {
+        let candidate = params.palette[i].rgb;
+        let distance = dot(color - candidate, color - candidate);
+        if distance < nearest_distance {
+            nearest = candidate;
+            nearest_distance = distance;
+        }
+    }
+    return vec4<f32>(pow(nearest, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,22 @@
+struct Params {
+    // Down the whole screen
+    lines: f32,
+    // 0 is no gaps between lines, 1 black ones
+    strength: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(2)
+var source: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(source, post_sampler, in.uv, 0.0).rgb;
+    let beam = sin(in.uv.y * params.lines * 3.14159265);
+    // Bright lines bloom wider on a real tube and fill the gaps more
+    let brightness = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let gap = mix(1.0, beam * beam, params.strength * (1.0 - 0.5 * clamp(brightness, 0.0, 1.0)));
+    // Brightened to make up for the light the gaps take away
+    return vec4<f32>(color * gap * (1.0 + params.strength * 0.5), 1.0);
+}
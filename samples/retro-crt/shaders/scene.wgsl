@@ -0,0 +1,84 @@
+struct Uniforms {
+    time: f32,
+    aspect: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -aspect to aspect across, -1 to 1 up
+    @location(0) position: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.position = vec2<f32>(ndc.x * uniforms.aspect, ndc.y);
+    return out;
+}
+
+fn box(p: vec2<f32>, center: vec2<f32>, half_size: vec2<f32>) -> bool {
+    let d = abs(p - center);
+    return d.x < half_size.x && d.y < half_size.y;
+}
+
+// A scrolling platformer level in flat colors, the kind of picture the
+// retro stages are made for. Colors are sRGB values, made linear on the
+// way out since the chain works in linear.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position;
+    let t = uniforms.time;
+    let scroll = t * 0.3;
+
+    // Sky with a sun
+    var color = mix(vec3<f32>(0.55, 0.8, 1.0), vec3<f32>(0.2, 0.45, 0.9), p.y * 0.5 + 0.5);
+    if length(p - vec2<f32>(0.9, 0.6)) < 0.15 {
+        color = vec3<f32>(1.0, 0.9, 0.4);
+    }
+    // Two layers of hills, the far one scrolling slower
+    if p.y < -0.1 + sin((p.x + scroll * 0.3) * 2.0) * 0.15 {
+        color = vec3<f32>(0.35, 0.55, 0.75);
+    }
+    if p.y < -0.35 + sin((p.x + scroll * 0.6) * 3.5) * 0.1 {
+        color = vec3<f32>(0.2, 0.6, 0.35);
+    }
+    // Checkered ground
+    let world_x = p.x + scroll;
+    if p.y < -0.6 {
+        let tile = floor(vec2<f32>(world_x, p.y) * 8.0);
+        color = select(vec3<f32>(0.6, 0.35, 0.2), vec3<f32>(0.5, 0.28, 0.15), (i32(tile.x + tile.y) & 1) == 0);
+        if p.y > -0.64 {
+            color = vec3<f32>(0.3, 0.8, 0.3);
+        }
+    }
+    // Floating bricks with coins over them
+    let brick_x = (fract(world_x * 0.4) - 0.5) / 0.4;
+    if box(vec2<f32>(brick_x, p.y), vec2<f32>(0.0, 0.05), vec2<f32>(0.3, 0.06)) {
+        color = vec3<f32>(0.75, 0.4, 0.25);
+        if fract(brick_x * 5.0) < 0.06 || abs(p.y - 0.05) < 0.006 {
+            color = vec3<f32>(0.35, 0.15, 0.1);
+        }
+    }
+    let spin = abs(cos(t * 3.0));
+    if box(vec2<f32>(brick_x, p.y), vec2<f32>(0.0, 0.25 + sin(t * 2.0) * 0.02), vec2<f32>(0.06 * spin + 0.01, 0.08)) {
+        color = vec3<f32>(1.0, 0.85, 0.1);
+    }
+    // The hero, hopping in place while the level scrolls past
+    let hop = abs(sin(t * 2.5)) * 0.35;
+    let hero = vec2<f32>(-0.6, -0.48 + hop);
+    if box(p, hero, vec2<f32>(0.1, 0.14)) {
+        color = vec3<f32>(0.9, 0.15, 0.15);
+        if box(p, hero + vec2<f32>(0.04, 0.05), vec2<f32>(0.02, 0.03)) {
+            color = vec3<f32>(1.0);
+        }
+        if p.y < hero.y - 0.06 {
+            color = vec3<f32>(0.15, 0.2, 0.7);
+        }
+    }
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
@@ -0,0 +1,209 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use bytemuck::{Pod, Zeroable};
+use framework::post::{FullscreenPass, PostFrame, PostStage, HDR_FORMAT};
+use wgpu::Device;
+
+pub struct Palette {
+    pub name: &'static str,
+    // 0xRRGGBB, sRGB; empty keeps the full color
+    pub colors: &'static [u32],
+}
+
+pub const PALETTES: [Palette; 4] = [
+    Palette {
+        name: "full color",
+        colors: &[],
+    },
+    Palette {
+        name: "PICO-8",
+        colors: &[
+            0x000000, 0x1d2b53, 0x7e2553, 0x008751, 0xab5236, 0x5f574f, 0xc2c3c7, 0xfff1e8,
+            0xff004d, 0xffa300, 0xffec27, 0x00e436, 0x29adff, 0x83769c, 0xff77a8, 0xffccaa,
+        ],
+    },
+    Palette {
+        name: "Game Boy",
+        colors: &[0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f],
+    },
+    Palette {
+        name: "CGA",
+        colors: &[0x000000, 0x55ffff, 0xff55ff, 0xffffff],
+    },
+];
+
+// What the renderer changes from the keyboard while the stage itself
+// belongs to the chain
+#[derive(Clone, Copy, Debug)]
+pub struct PixelArt {
+    // In screen pixels
+    pub pixel_size: f32,
+    // Into PALETTES
+    pub palette: usize,
+    pub dither: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct PixelateParams {
+    resolution: [f32; 2],
+    pixel_size: f32,
+    dither: f32,
+    colors: u32,
+    _padding: [u32; 3],
+    palette: [[f32; 4]; 16],
+}
+
+// Big square pixels, optionally snapped to a fixed palette with ordered
+// dithering in between its colors
+pub struct Pixelate {
+    settings: Rc<Cell<PixelArt>>,
+    pass: FullscreenPass,
+}
+
+impl Pixelate {
+    pub fn new(device: &Device, settings: Rc<Cell<PixelArt>>) -> Self {
+        Self {
+            settings,
+            pass: FullscreenPass::new(device, "Pixelate", include_str!("shaders/pixelate.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for Pixelate {
+    fn name(&self) -> &str {
+        "pixelate"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let settings = self.settings.get();
+        let colors = PALETTES[settings.palette].colors;
+        let mut palette = [[0.0; 4]; 16];
+        for (entry, &hex) in palette.iter_mut().zip(colors) {
+            *entry = [(hex >> 16) as f32 / 255.0, ((hex >> 8) & 0xff) as f32 / 255.0, (hex & 0xff) as f32 / 255.0, 1.0];
+        }
+        let params = PixelateParams {
+            resolution: [frame.width as f32, frame.height as f32],
+            pixel_size: settings.pixel_size,
+            // Fewer colors are further apart, so the dither has to reach further
+            dither: if settings.dither { 1.0 / (colors.len() as f32).sqrt().max(1.0) } else { 0.0 },
+            colors: colors.len() as u32,
+            _padding: [0; 3],
+            palette,
+        };
+        self.pass.draw(frame.device, frame.encoder, bytemuck::bytes_of(&params), &[frame.input], frame.output);
+    }
+}
+
+// Composite video's color smear
+pub struct NtscBleed {
+    pub bleed: f32,
+    pub shift: f32,
+    pass: FullscreenPass,
+}
+
+impl NtscBleed {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            bleed: 6.0,
+            shift: 1.5,
+            pass: FullscreenPass::new(device, "NTSC Bleed", include_str!("shaders/ntsc.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for NtscBleed {
+    fn name(&self) -> &str {
+        "ntsc bleed"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let params = [frame.width as f32, frame.height as f32, self.bleed, self.shift];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input], frame.output);
+    }
+}
+
+pub struct Scanlines {
+    // Down the whole screen, whatever the window's size
+    pub lines: f32,
+    pub strength: f32,
+    pass: FullscreenPass,
+}
+
+impl Scanlines {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            lines: 240.0,
+            strength: 0.6,
+            pass: FullscreenPass::new(device, "Scanlines", include_str!("shaders/scanlines.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for Scanlines {
+    fn name(&self) -> &str {
+        "scanlines"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let params = [self.lines, self.strength, 0.0, 0.0];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input], frame.output);
+    }
+}
+
+pub struct PhosphorMask {
+    // In screen pixels
+    pub triad: f32,
+    pub strength: f32,
+    pass: FullscreenPass,
+}
+
+impl PhosphorMask {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            triad: 3.0,
+            strength: 0.4,
+            pass: FullscreenPass::new(device, "Phosphor Mask", include_str!("shaders/phosphor.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for PhosphorMask {
+    fn name(&self) -> &str {
+        "phosphor mask"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let params = [self.triad, self.strength, 0.0, 0.0];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input], frame.output);
+    }
+}
+
+// The bulge of the glass, last so it bends the scanlines and mask too
+pub struct Curvature {
+    pub amount: f32,
+    pub vignette: f32,
+    pass: FullscreenPass,
+}
+
+impl Curvature {
+    pub fn new(device: &Device) -> Self {
+        Self {
+            amount: 0.3,
+            vignette: 0.25,
+            pass: FullscreenPass::new(device, "Curvature", include_str!("shaders/curvature.wgsl"), "fs_main", 1, HDR_FORMAT, None),
+        }
+    }
+}
+
+impl PostStage for Curvature {
+    fn name(&self) -> &str {
+        "curvature"
+    }
+
+    fn record(&mut self, frame: &mut PostFrame) {
+        let params = [self.amount, self.vignette, 0.0, 0.0];
+        self.pass.draw(frame.device, frame.encoder, bytemuck::cast_slice(&params), &[frame.input], frame.output);
+    }
+}
@@ -0,0 +1,255 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+// Seams taken out per frame, so narrowing a big image animates instead of
+// stalling
+pub const SEAMS_PER_FRAME: u32 = 8;
+const GROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    width: u32,
+    height: u32,
+    stride: u32,
+    seam_number: u32,
+}
+
+fn storage_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: binding == 1 },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+// Seam carving entirely on the GPU. Each seam is four dispatches: energy,
+// the dynamic programming pass accumulating it down the rows, the
+// backtrack finding the cheapest seam, and the removal shifting the rest of
+// each row left into the other of two buffers.
+pub struct Carver {
+    pub original_width: u32,
+    pub height: u32,
+    // Of the image as carved so far
+    pub width: u32,
+    energy_pipeline: ComputePipeline,
+    accumulate_pipeline: ComputePipeline,
+    backtrack_pipeline: ComputePipeline,
+    remove_pipeline: ComputePipeline,
+    // One slot per seam of a frame plus the final energy pass, picked with
+    // a dynamic offset; every slot is written before the frame's submit
+    params_buffer: Buffer,
+    params_stride: u32,
+    pub original: Buffer,
+    pub cells: [Buffer; 2],
+    pub energy: Buffer,
+    pub removed: Buffer,
+    // The first reads cells[0] and writes cells[1], the second the reverse
+    bind_groups: [BindGroup; 2],
+    // Which of cells holds the image
+    pub current: usize,
+}
+
+impl Carver {
+    // `pixels` are RGBA8 rows
+    pub fn new(device: &Device, pixels: &[u8], width: u32, height: u32) -> Self {
+        // Each pixel carries where it came from, so pixels can be marked in
+        // the original once a seam takes them
+        let cells: Vec<[u32; 2]> = pixels
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(index, rgba)| [u32::from_le_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]), index as u32])
+            .collect();
+        let original = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Original Pixels"),
+            contents: bytemuck::cast_slice(&cells),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        });
+        let cell_buffers = [0, 1].map(|index| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("Carved Pixels {}", index)),
+                contents: bytemuck::cast_slice(&cells),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+        let per_pixel = |label| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (width * height * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let energy = per_pixel("Energy");
+        let cost = per_pixel("Cost");
+        let removed = per_pixel("Removed");
+        let seam = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Seam"),
+            size: (height * 4) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let params_stride = device.limits().min_uniform_buffer_offset_alignment.max(std::mem::size_of::<Params>() as u32);
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Carve Params"),
+            size: (params_stride * (SEAMS_PER_FRAME + 1)) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Carve Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
+                storage_entry(5),
+                storage_entry(6),
+            ],
+        });
+        let bind_groups = [(0, 1), (1, 0)].map(|(source, destination): (usize, usize)| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Carve Bind Group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &params_buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<Params>() as u64),
+                        }),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: cell_buffers[source].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: cell_buffers[destination].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: energy.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: cost.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: seam.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: removed.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/carve.wgsl"));
+        Self {
+            original_width: width,
+            height,
+            width,
+            energy_pipeline: compute_pipeline(device, "Energy", &layout, &shader, "cs_energy"),
+            accumulate_pipeline: compute_pipeline(device, "Accumulate", &layout, &shader, "cs_accumulate"),
+            backtrack_pipeline: compute_pipeline(device, "Backtrack", &layout, &shader, "cs_backtrack"),
+            remove_pipeline: compute_pipeline(device, "Remove Seam", &layout, &shader, "cs_remove"),
+            params_buffer,
+            params_stride,
+            original,
+            cells: cell_buffers,
+            energy,
+            removed,
+            bind_groups,
+            current: 0,
+        }
+    }
+
+    // Seams taken out so far
+    pub fn seams(&self) -> u32 {
+        self.original_width - self.width
+    }
+
+    // Back to the full image, for when the target grows again. Carving is
+    // deterministic, so re-carving down gives the same seams as before.
+    pub fn reset(&mut self, encoder: &mut CommandEncoder) {
+        let size = self.original.size();
+        encoder.copy_buffer_to_buffer(&self.original, 0, &self.cells[0], 0, size);
+        encoder.clear_buffer(&self.removed, 0, None);
+        self.current = 0;
+        self.width = self.original_width;
+    }
+
+    // Takes out up to SEAMS_PER_FRAME seams on the way to `target`, then
+    // refreshes the energy for display
+    pub fn carve(&mut self, queue: &Queue, encoder: &mut CommandEncoder, target: u32) {
+        let count = self.width.saturating_sub(target.max(2)).min(SEAMS_PER_FRAME);
+        let rows = (self.height + GROUP_SIZE - 1) / GROUP_SIZE;
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Carve Pass"),
+        });
+        for slot in 0..=count {
+            let params = Params {
+                width: self.width,
+                height: self.height,
+                stride: self.original_width,
+                seam_number: self.seams(),
+            };
+            let offset = slot * self.params_stride;
+            queue.write_buffer(&self.params_buffer, offset as wgpu::BufferAddress, bytemuck::bytes_of(&params));
+            let columns = (self.width + GROUP_SIZE - 1) / GROUP_SIZE;
+
+            compute_pass.set_bind_group(0, &self.bind_groups[self.current], &[offset]);
+            compute_pass.set_pipeline(&self.energy_pipeline);
+            compute_pass.dispatch_workgroups(columns, rows, 1);
+            // The last slot is only there for the energy of the result
+            if slot == count {
+                break;
+            }
+            compute_pass.set_pipeline(&self.accumulate_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+            compute_pass.set_pipeline(&self.backtrack_pipeline);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+            compute_pass.set_pipeline(&self.remove_pipeline);
+            compute_pass.dispatch_workgroups(columns, rows, 1);
+
+            self.current = 1 - self.current;
+            self.width -= 1;
+        }
+    }
+}
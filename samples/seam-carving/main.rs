@@ -0,0 +1,9 @@
+mod carver;
+mod picture;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("seam-carving");
+}
@@ -0,0 +1,73 @@
+use framework::assets;
+
+const WIDTH: u32 = 960;
+const HEIGHT: u32 = 540;
+
+// RGBA8 rows: the file given with --image path.png or asset:name, or a
+// made up harbour with lots of empty sky and sea for the carving to take
+// out
+pub fn load() -> (Vec<u8>, u32, u32) {
+    let mut args = std::env::args().skip_while(|arg| arg != "--image");
+    if let Some(path) = args.nth(1) {
+        let image = assets::resolve(&path)
+            .map_err(|error| error.to_string())
+            .and_then(|file| image::open(file).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                eprintln!("{}: {}", path, error);
+                std::process::exit(1);
+            })
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+        return (image.into_raw(), width, height);
+    }
+    (harbour(), WIDTH, HEIGHT)
+}
+
+fn harbour() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((WIDTH * HEIGHT * 4) as usize);
+    let horizon = HEIGHT as f32 * 0.6;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (fx, fy) = (x as f32, y as f32);
+            let mut color = if fy < horizon {
+                // Sky, with a sun low on the right
+                let t = fy / horizon;
+                let mut sky = [0.35 + t * 0.5, 0.55 + t * 0.3, 0.85 + t * 0.05];
+                if (fx - 700.0).hypot(fy - 260.0) < 28.0 {
+                    sky = [1.0, 0.9, 0.6];
+                }
+                sky
+            } else {
+                // Sea with faint ripples
+                let ripple = ((fy * 0.9).sin() * (fx * 0.05 + fy * 0.3).sin()) * 0.02;
+                [0.1 + ripple, 0.3 + ripple, 0.5 + ripple]
+            };
+
+            // A rocky headland with a lighthouse on the left
+            let rock = horizon - 70.0 + ((fx * 0.05).sin() * 12.0) + fx * 0.25;
+            if fx < 240.0 && fy > rock {
+                color = [0.35, 0.3, 0.28];
+            }
+            if (120.0..150.0).contains(&fx) && fy > rock - 130.0 && fy <= rock + 10.0 {
+                let band = ((fy - (rock - 130.0)) / 26.0) as u32 % 2;
+                color = if band == 0 { [0.9, 0.15, 0.12] } else { [0.95, 0.95, 0.95] };
+            }
+            // Two sailing boats, small and sharp-edged
+            for &(bx, size) in &[(430.0f32, 1.0f32), (820.0, 0.7)] {
+                let (dx, dy) = ((fx - bx) / size, (fy - horizon - 40.0 * size) / size);
+                if dy > 0.0 && dy < 14.0 && dx.abs() < 40.0 - dy {
+                    color = [0.45, 0.25, 0.15];
+                }
+                if dy <= 0.0 && dy > -80.0 && dx > 2.0 && dx < 2.0 + (80.0 + dy) * 0.5 {
+                    color = [0.98, 0.98, 0.95];
+                }
+                if dy <= 0.0 && dy > -90.0 && dx.abs() < 2.0 {
+                    color = [0.2, 0.15, 0.1];
+                }
+            }
+            pixels.extend(color.map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8));
+            pixels.push(255);
+        }
+    }
+    pixels
+}
@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::carver::Carver;
+use crate::picture;
+
+// Of the original width, per second of holding a key
+const RESIZE_SPEED: f32 = 0.4;
+const MODES: [&str; 3] = ["carved", "original with seams", "energy"];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DisplayParams {
+    width: u32,
+    height: u32,
+    stride: u32,
+    mode: u32,
+    scale: f32,
+    _padding: f32,
+    offset: [f32; 2],
+}
+
+pub struct Renderer {
+    carver: Carver,
+    pipeline: RenderPipeline,
+    params_buffer: Buffer,
+    // One per carver buffer holding the image
+    bind_groups: [BindGroup; 2],
+    // Fractional, so slow resizing still gets somewhere
+    target: f32,
+    mode: usize,
+    needs_reset: bool,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+        let (pixels, width, height) = picture::load();
+        let carver = Carver::new(device, &pixels, width, height);
+
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display Params"),
+            contents: bytemuck::bytes_of(&DisplayParams::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
+            ],
+        });
+        let bind_groups = [0, 1].map(|current: usize| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Display Bind Group"),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: carver.cells[current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: carver.removed.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: carver.energy.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: carver.original.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Display Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Display Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            target: width as f32 * 0.7,
+            carver,
+            pipeline,
+            params_buffer,
+            bind_groups,
+            mode: 0,
+            // The energy buffer starts empty
+            needs_reset: true,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            if *key == VirtualKeyCode::Tab {
+                self.mode = (self.mode + 1) % MODES.len();
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} of {} wide, {} seams out, target {}, {}",
+            self.carver.width,
+            self.carver.original_width,
+            self.carver.seams(),
+            self.target as u32,
+            MODES[self.mode],
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut resize = 0.0;
+        if held(VirtualKeyCode::Right) { resize += 1.0; }
+        if held(VirtualKeyCode::Left) { resize -= 1.0; }
+        let original_width = self.carver.original_width as f32;
+        self.target = (self.target + resize * RESIZE_SPEED * original_width * dt).clamp(2.0, original_width);
+        // Seams can only come out; wider means starting over from the
+        // original and carving back down
+        if self.target as u32 > self.carver.width {
+            self.needs_reset = true;
+        }
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        if std::mem::take(&mut self.needs_reset) {
+            self.carver.reset(&mut encoder);
+            // Even with nothing to carve, for the energy
+            self.carver.carve(&gpu.queue, &mut encoder, self.target as u32);
+        } else if self.carver.width > self.target as u32 {
+            self.carver.carve(&gpu.queue, &mut encoder, self.target as u32);
+        }
+
+        // Fit the original size in the window, so the carved image visibly
+        // narrows inside the same frame
+        let (width, height) = (gpu.config.width as f32, gpu.config.height as f32);
+        let scale = (width / original_width).min(height / self.carver.height as f32);
+        let offset = [
+            (width - original_width * scale) * 0.5,
+            (height - self.carver.height as f32 * scale) * 0.5,
+        ];
+        gpu.queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&DisplayParams {
+                width: self.carver.width,
+                height: self.carver.height,
+                stride: self.carver.original_width,
+                mode: self.mode as u32,
+                scale,
+                _padding: 0.0,
+                offset,
+            }),
+        );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Display Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_groups[self.carver.current], &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
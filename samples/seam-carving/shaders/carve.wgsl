@@ -0,0 +1,137 @@
+struct Params {
+    // Of the image as carved so far
+    width: u32,
+    height: u32,
+    // Of every buffer, the original width; rows never move
+    stride: u32,
+    // How many seams came out before this one
+    seam_number: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+// x the packed RGBA8 color, y where the pixel was in the original image
+@group(0) @binding(1)
+var<storage, read> source: array<vec2<u32>>;
+@group(0) @binding(2)
+var<storage, read_write> destination: array<vec2<u32>>;
+@group(0) @binding(3)
+var<storage, read_write> energy: array<f32>;
+// The cheapest total energy of any seam from the top row down to a pixel
+@group(0) @binding(4)
+var<storage, read_write> cost: array<f32>;
+// Per row, the column the seam goes through
+@group(0) @binding(5)
+var<storage, read_write> seam: array<u32>;
+// Per original pixel, 0 while it's still there, otherwise the seam that
+// took it, counted from 1
+@group(0) @binding(6)
+var<storage, read_write> removed: array<u32>;
+
+const GROUP_SIZE: u32 = 256u;
+
+fn color(x: u32, y: u32) -> vec3<f32> {
+    let clamped = min(x, params.width - 1u);
+    return unpack4x8unorm(source[min(y, params.height - 1u) * params.stride + clamped].x).rgb;
+}
+
+// The dual gradient: how different each pixel is from its neighbours
+// left and right and above and below. Seams run through the flattest parts.
+@compute @workgroup_size(8, 8)
+fn cs_energy(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let dx = color(id.x + 1u, id.y) - color(max(id.x, 1u) - 1u, id.y);
+    let dy = color(id.x, id.y + 1u) - color(id.x, max(id.y, 1u) - 1u);
+    energy[id.y * params.stride + id.x] = sqrt(dot(dx, dx) + dot(dy, dy));
+}
+
+// Row by row down the image, each pixel adding the cheapest of the three
+// above it. Every row needs the whole row before it finished, so it's one
+// workgroup striding across the columns with a barrier between rows.
+@compute @workgroup_size(256)
+fn cs_accumulate(@builtin(local_invocation_index) thread: u32) {
+    for (var x = thread; x < params.width; x += GROUP_SIZE) {
+        cost[x] = energy[x];
+    }
+    storageBarrier();
+    for (var y = 1u; y < params.height; y++) {
+        let row = y * params.stride;
+        let above = row - params.stride;
+        for (var x = thread; x < params.width; x += GROUP_SIZE) {
+            var best = cost[above + x];
+            if x > 0u {
+                best = min(best, cost[above + x - 1u]);
+            }
+            if x + 1u < params.width {
+                best = min(best, cost[above + x + 1u]);
+            }
+            cost[row + x] = energy[row + x] + best;
+        }
+        storageBarrier();
+    }
+}
+
+var<workgroup> best_costs: array<f32, 256>;
+var<workgroup> best_columns: array<u32, 256>;
+
+// The cheapest pixel of the bottom row, found by every thread taking some
+// columns and then halving the candidates, then followed back up
+@compute @workgroup_size(256)
+fn cs_backtrack(@builtin(local_invocation_index) thread: u32) {
+    let bottom = (params.height - 1u) * params.stride;
+    var best_cost = 1e30;
+    var best_column = 0u;
+    for (var x = thread; x < params.width; x += GROUP_SIZE) {
+        if cost[bottom + x] < best_cost {
+            best_cost = cost[bottom + x];
+            best_column = x;
+        }
+    }
+    best_costs[thread] = best_cost;
+    best_columns[thread] = best_column;
+    workgroupBarrier();
+    for (var half = GROUP_SIZE / 2u; half > 0u; half /= 2u) {
+        if thread < half && best_costs[thread + half] < best_costs[thread] {
+            best_costs[thread] = best_costs[thread + half];
+            best_columns[thread] = best_columns[thread + half];
+        }
+        workgroupBarrier();
+    }
+
+    // Walking up is inherently one step at a time
+    if thread == 0u {
+        var x = best_columns[0];
+        seam[params.height - 1u] = x;
+        for (var y = params.height - 1u; y > 0u; y--) {
+            let above = (y - 1u) * params.stride;
+            var next = x;
+            if x > 0u && cost[above + x - 1u] < cost[above + next] {
+                next = x - 1u;
+            }
+            if x + 1u < params.width && cost[above + x + 1u] < cost[above + next] {
+                next = x + 1u;
+            }
+            x = next;
+            seam[y - 1u] = x;
+        }
+    }
+}
+
+// Every pixel right of the seam moves one to the left, into the other
+// buffer so nothing reads a pixel that's already moved
+@compute @workgroup_size(8, 8)
+fn cs_remove(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let row = id.y * params.stride;
+    let column = seam[id.y];
+    if id.x == column {
+        removed[source[row + id.x].y] = params.seam_number + 1u;
+    }
+    if id.x + 1u < params.width {
+        destination[row + id.x] = source[row + id.x + select(0u, 1u, id.x >= column)];
+    }
+}
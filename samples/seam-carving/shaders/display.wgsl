@@ -0,0 +1,61 @@
+struct Params {
+    width: u32,
+    height: u32,
+    stride: u32,
+    // 0 carved, 1 the original with removed pixels marked, 2 the energy
+    mode: u32,
+    // Window pixels per image pixel, and where the image starts
+    scale: f32,
+    _padding: f32,
+    offset: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+@group(0) @binding(1)
+var<storage, read> cells: array<vec2<u32>>;
+@group(0) @binding(2)
+var<storage, read> removed: array<u32>;
+@group(0) @binding(3)
+var<storage, read> energy: array<f32>;
+@group(0) @binding(4)
+var<storage, read> original: array<vec2<u32>>;
+
+// The pixels are sRGB bytes and the surface expects linear
+fn unpack(bits: u32) -> vec3<f32> {
+    return pow(unpack4x8unorm(bits).rgb, vec3<f32>(2.2));
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+    return vec4<f32>(ndc, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = (position.xy - params.offset) / params.scale;
+    // A checkerboard wherever there's no image, including where the
+    // carved image used to reach
+    let checker = select(0.12, 0.18, ((u32(position.x) / 16u + u32(position.y) / 16u) & 1u) == 0u);
+    var width = params.width;
+    if params.mode == 1u {
+        width = params.stride;
+    }
+    if pixel.x < 0.0 || pixel.y < 0.0 || u32(pixel.x) >= width || u32(pixel.y) >= params.height {
+        return vec4<f32>(vec3<f32>(checker), 1.0);
+    }
+
+    let index = u32(pixel.y) * params.stride + u32(pixel.x);
+    if params.mode == 2u {
+        return vec4<f32>(vec3<f32>(min(energy[index] * 2.0, 1.0)), 1.0);
+    }
+    if params.mode == 1u {
+        let color = unpack(original[index].x);
+        if removed[index] > 0u {
+            return vec4<f32>(mix(color, vec3<f32>(1.0, 0.0, 0.0), 0.7), 1.0);
+        }
+        return vec4<f32>(color, 1.0);
+    }
+    return vec4<f32>(unpack(cells[index].x), 1.0);
+}
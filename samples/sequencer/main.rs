@@ -0,0 +1,16 @@
+// Scenes from other samples played as a timed sequence by a Rhai script,
+// the way launcher hosts them for the number keys
+#[path = "../compute-particles/renderer.rs"]
+mod compute_particles;
+#[path = "../hello-triangle/renderer.rs"]
+mod hello_triangle;
+mod script;
+mod sequencer;
+#[path = "../skybox/renderer.rs"]
+mod skybox;
+
+use crate::sequencer::Sequencer;
+
+fn main() {
+    framework::run_sample::<Sequencer>("sequencer");
+}
@@ -0,0 +1,158 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use framework::CameraPose;
+use glam::Vec3;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+
+const DEMO: &str = include_str!("demo.rhai");
+
+// What a script asked for during a frame, applied by the sequencer after
+pub enum Command {
+    Scene(String),
+    Camera(CameraPose),
+    Parameter(String, f32),
+}
+
+enum Source {
+    Demo,
+    // Reloaded whenever its modification time changes
+    File { path: PathBuf, modified: Option<SystemTime> },
+}
+
+// The sequence: the built in demo, or the file given with --script, which
+// can be edited while it plays
+pub struct Script {
+    engine: Engine,
+    source: Source,
+    // None until something compiled
+    ast: Option<AST>,
+    // Filled by the functions the script calls
+    commands: Rc<RefCell<Vec<Command>>>,
+    // So a frame() that keeps failing the same way says so once, not
+    // every frame
+    last_error: Option<String>,
+}
+
+impl Script {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let commands = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+
+        let queue = commands.clone();
+        engine.register_fn("scene", move |name: &str| {
+            queue.borrow_mut().push(Command::Scene(name.to_string()));
+        });
+        let queue = commands.clone();
+        engine.register_fn("camera", move |x: f64, y: f64, z: f64, yaw: f64, pitch: f64| {
+            queue.borrow_mut().push(Command::Camera(CameraPose {
+                position: Vec3::new(x as f32, y as f32, z as f32),
+                yaw: yaw as f32,
+                pitch: pitch as f32,
+            }));
+        });
+        let queue = commands.clone();
+        engine.register_fn("param", move |name: &str, value: f64| {
+            queue.borrow_mut().push(Command::Parameter(name.to_string(), value as f32));
+        });
+        engine.register_fn("lerp", |a: f64, b: f64, t: f64| a + (b - a) * t);
+        engine.register_fn("smoothstep", |edge0: f64, edge1: f64, x: f64| {
+            let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        });
+
+        let source = match path {
+            Some(path) => Source::File { path, modified: None },
+            None => Source::Demo,
+        };
+        let mut script = Self {
+            engine,
+            source,
+            ast: None,
+            commands,
+            last_error: None,
+        };
+        if matches!(script.source, Source::Demo) {
+            script.compile(DEMO, "demo");
+        } else {
+            script.reload_if_changed();
+        }
+        script
+    }
+
+    pub fn name(&self) -> String {
+        match &self.source {
+            Source::Demo => "the demo script".to_string(),
+            Source::File { path, .. } => path.display().to_string(),
+        }
+    }
+
+    // Checks the file's modification time, cheap enough for every frame.
+    // A script that doesn't compile leaves the previous one playing.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let (path, modified) = match &mut self.source {
+            Source::Demo => return false,
+            Source::File { path, modified } => (path.clone(), modified),
+        };
+        let current = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+        if current.is_none() || current == *modified {
+            return false;
+        }
+        *modified = current;
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let loaded = self.ast.is_some();
+                self.compile(&text, &path.display().to_string());
+                if loaded {
+                    println!("reloaded {}", path.display());
+                }
+                true
+            }
+            Err(error) => {
+                eprintln!("{}: {}", path.display(), error);
+                false
+            }
+        }
+    }
+
+    // Runs frame(time) and hands back what it asked for
+    pub fn frame(&mut self, time: f32) -> Vec<Command> {
+        if let Some(ast) = &self.ast {
+            // The top level ran once when it was compiled, only the
+            // function this time
+            let options = CallFnOptions::new().eval_ast(false);
+            let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, "frame", (time as f64,));
+            match result {
+                Ok(_) => self.last_error = None,
+                Err(error) => {
+                    let error = error.to_string();
+                    if self.last_error.as_ref() != Some(&error) {
+                        eprintln!("{}: {}", self.name(), error);
+                        self.last_error = Some(error);
+                    }
+                }
+            }
+        }
+        std::mem::take(&mut *self.commands.borrow_mut())
+    }
+
+    // Runs the top level once, so a script can pick its first scene or
+    // print something there
+    fn compile(&mut self, text: &str, name: &str) {
+        let ast = match self.engine.compile(text) {
+            Ok(ast) => ast,
+            Err(error) => {
+                eprintln!("{}: {}", name, error);
+                return;
+            }
+        };
+        if let Err(error) = self.engine.run_ast(&ast) {
+            eprintln!("{}: {}", name, error);
+        }
+        self.ast = Some(ast);
+        self.last_error = None;
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use framework::{scene, Gpu, Sample, SceneFactory, SceneStack};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::script::{Command, Script};
+use crate::{compute_particles, hello_triangle, skybox};
+
+// What scene() in a script can name
+const SCENES: &[(&str, SceneFactory)] = &[
+    ("hello-triangle", scene::<hello_triangle::Renderer>),
+    ("compute-particles", scene::<compute_particles::Renderer>),
+    ("skybox", scene::<skybox::Renderer>),
+];
+
+// Plays --script path.rhai, or the built in demo, over the scenes above.
+// Space pauses the sequence and Backspace restarts it; everything else
+// goes to the scene.
+pub struct Sequencer {
+    stack: SceneStack,
+    script: Script,
+    // Seconds into the sequence, what frame() gets
+    time: f32,
+    paused: bool,
+    restart: bool,
+    last_frame: Instant,
+    // Scene and parameter names the script got wrong, reported once each
+    reported: HashSet<String>,
+}
+
+impl Sequencer {
+    fn report_once(&mut self, problem: String) {
+        if self.reported.insert(problem.clone()) {
+            eprintln!("{}: {}", self.script.name(), problem);
+        }
+    }
+
+    fn apply(&mut self, gpu: &Gpu, command: Command) {
+        match command {
+            Command::Scene(name) => {
+                if self.stack.top_name() == Some(name.as_str()) {
+                    return;
+                }
+                match SCENES.iter().find(|(scene, _)| *scene == name) {
+                    Some(&(scene, factory)) => self.stack.switch(gpu, scene, factory),
+                    None => self.report_once(format!("no scene called {}", name)),
+                }
+            }
+            Command::Camera(pose) => {
+                if let Some(scene) = self.stack.top_mut() {
+                    scene.set_camera(&pose);
+                }
+            }
+            Command::Parameter(name, value) => {
+                let known = match self.stack.top_mut() {
+                    Some(scene) => scene.set_parameter(&name, value),
+                    None => return,
+                };
+                if !known {
+                    let scene = self.stack.top_name().unwrap_or_default();
+                    self.report_once(format!("{} has no parameter called {}", scene, name));
+                }
+            }
+        }
+    }
+}
+
+impl Sample for Sequencer {
+    // The union of what the scenes ask for; compute-particles wants compute
+    fn required_limits() -> wgpu::Limits {
+        wgpu::Limits::downlevel_defaults()
+    }
+
+    fn init(_gpu: &Gpu) -> Self {
+        let mut args = std::env::args().skip_while(|arg| arg != "--script");
+        let script = Script::new(args.nth(1).map(PathBuf::from));
+
+        // Scenes are only pushed in render(), like in launcher, so the leak
+        // check compares like with like
+        Self {
+            stack: SceneStack::default(),
+            script,
+            time: 0.0,
+            paused: false,
+            restart: false,
+            last_frame: Instant::now(),
+            reported: HashSet::new(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.stack.reinit_surface_resources(gpu);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match key {
+                VirtualKeyCode::Space => {
+                    self.paused = !self.paused;
+                    return;
+                }
+                VirtualKeyCode::Back => {
+                    self.restart = true;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(scene) = self.stack.top_mut() {
+            scene.update(event);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let name = self.stack.top_name()?;
+        let scene = match self.stack.top().and_then(|scene| scene.status()) {
+            Some(status) => format!("{} - {}", name, status),
+            None => name.to_string(),
+        };
+        Some(format!(
+            "{} at {:.1}s{} of {}",
+            scene,
+            self.time,
+            if self.paused { ", paused" } else { "" },
+            self.script.name(),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        if !self.paused {
+            self.time += (now - self.last_frame).as_secs_f32();
+        }
+        self.last_frame = now;
+        if std::mem::take(&mut self.restart) {
+            self.time = 0.0;
+        }
+
+        // Live coding: picked up on the next frame after saving, and the
+        // sequence carries on where it was
+        if self.script.reload_if_changed() {
+            self.reported.clear();
+        }
+        for command in self.script.frame(self.time) {
+            self.apply(gpu, command);
+        }
+        // A script that doesn't pick one still gets something on screen
+        if self.stack.is_empty() {
+            let (name, factory) = SCENES[0];
+            self.stack.switch(gpu, name, factory);
+        }
+
+        if let Some(scene) = self.stack.top_mut() {
+            scene.render(gpu, view);
+        }
+    }
+}
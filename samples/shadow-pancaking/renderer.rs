@@ -0,0 +1,573 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, BindGroup, Buffer, Device, RenderPipeline, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const SHADOW_MAP_SIZE: u32 = 2048;
+// Half the width of the ground, which is all the shadow map is fitted to
+const GROUND_EXTENT: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DepthMode {
+    // unclipped_depth: casters in front of the near plane are flattened
+    // onto it and still cast shadows
+    Clamp,
+    // The default: whatever is in front of the near plane is cut away
+    Clip,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Instance {
+    offset: [f32; 3],
+    scale: [f32; 3],
+    color: [f32; 3],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![2 => Float32x3, 3 => Float32x3, 4 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    camera_view_proj: [[f32; 4]; 4],
+    light_view_proj: [[f32; 4]; 4],
+    light_dir: [f32; 4],
+}
+
+// A unit cube with flat normals, four vertices per face
+fn cube() -> (Vec<Vertex>, Vec<u16>) {
+    let faces = [
+        (Vec3::X, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::Z),
+        (Vec3::Z, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (normal, up) in faces {
+        let right = up.cross(normal);
+        let base = vertices.len() as u16;
+        for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            vertices.push(Vertex {
+                position: ((normal + right * u + up * v) * 0.5).to_array(),
+                normal: normal.to_array(),
+            });
+        }
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    (vertices, indices)
+}
+
+// The ground, pillars of growing height and a few boxes floating high
+// above everything, all of which cast shadows onto the ground
+fn scene() -> Vec<Instance> {
+    let mut instances = vec![Instance {
+        offset: [0.0, -0.1, 0.0],
+        scale: [GROUND_EXTENT * 2.0, 0.2, GROUND_EXTENT * 2.0],
+        color: [0.6, 0.6, 0.55],
+    }];
+
+    for i in 0..8 {
+        let angle = i as f32 / 8.0 * std::f32::consts::TAU;
+        let height = 4.0 + i as f32 * 3.0;
+        instances.push(Instance {
+            offset: [angle.cos() * 9.0, height / 2.0, angle.sin() * 9.0],
+            scale: [1.5, height, 1.5],
+            color: [0.8, 0.45, 0.3],
+        });
+    }
+
+    for (x, y, z) in [(-6.0, 28.0, 4.0), (5.0, 34.0, -5.0), (0.0, 40.0, 0.0)] {
+        instances.push(Instance {
+            offset: [x, y, z],
+            scale: [4.0, 1.0, 4.0],
+            color: [0.3, 0.5, 0.8],
+        });
+    }
+    instances
+}
+
+fn create_depth_view(device: &Device, width: u32, height: u32, label: &str, usage: wgpu::TextureUsages) -> TextureView {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage,
+        view_formats: &[],
+    }).create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+pub struct Renderer {
+    // Identical apart from unclipped_depth. No clamped one on adapters
+    // without DEPTH_CLIP_CONTROL (e.g. WebGL2), which only get to clip.
+    clamp_shadow_pipeline: Option<RenderPipeline>,
+    clip_shadow_pipeline: RenderPipeline,
+    render_pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    instance_buffer: Buffer,
+    instance_count: u32,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+    shadow_view: TextureView,
+    shadow_bind_group: BindGroup,
+    depth_view: TextureView,
+    mode: DepthMode,
+    // How far the light's near plane is pulled back from the ground
+    near_padding: f32,
+    start: Instant,
+    // Set by --flythrough or a script, an orbit around the scene until then
+    camera: Option<CameraPose>,
+}
+
+impl Sample for Renderer {
+    fn optional_features() -> wgpu::Features {
+        wgpu::Features::DEPTH_CLIP_CONTROL
+    }
+
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let (vertices, indices) = cube();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let instances = scene();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Uniform Bind Group"),
+            layout: &uniform_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shadow_view = create_depth_view(
+            device,
+            SHADOW_MAP_SIZE,
+            SHADOW_MAP_SIZE,
+            "Shadow Map",
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let shadow_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        });
+
+        let shadow_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout: &shadow_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&shadow_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&shadow_sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+
+        let shadow_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Shadow Pipeline Layout"),
+                    bind_group_layouts: &[&uniform_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_shadow_pipeline = |label, unclipped_depth| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&shadow_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_shadow",
+                    buffers: &[Vertex::desc(), Instance::desc()],
+                },
+                // Depth only
+                fragment: None,
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: Some(wgpu::Face::Back),
+                    unclipped_depth,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState {
+                        constant: 2,
+                        slope_scale: 2.0,
+                        clamp: 0.0,
+                    },
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        let clamp_shadow_pipeline = if device.features().contains(wgpu::Features::DEPTH_CLIP_CONTROL) {
+            Some(create_shadow_pipeline("Clamped Shadow Pipeline", true))
+        } else {
+            None
+        };
+        let clip_shadow_pipeline = create_shadow_pipeline("Clipped Shadow Pipeline", false);
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&uniform_layout, &shadow_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), Instance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let mode = if clamp_shadow_pipeline.is_some() { DepthMode::Clamp } else { DepthMode::Clip };
+        Self {
+            clamp_shadow_pipeline,
+            clip_shadow_pipeline,
+            render_pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer,
+            instance_count: instances.len() as u32,
+            uniform_buffer,
+            uniform_bind_group,
+            shadow_view,
+            shadow_bind_group,
+            depth_view: create_depth_view(
+                device,
+                gpu.config.width,
+                gpu.config.height,
+                "Depth",
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ),
+            mode,
+            near_padding: 1.0,
+            start: Instant::now(),
+            camera: None,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth_view = create_depth_view(
+            &gpu.device,
+            gpu.config.width,
+            gpu.config.height,
+            "Depth",
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match key {
+                VirtualKeyCode::Space if self.clamp_shadow_pipeline.is_some() => {
+                    self.mode = match self.mode {
+                        DepthMode::Clamp => DepthMode::Clip,
+                        DepthMode::Clip => DepthMode::Clamp,
+                    };
+                }
+                VirtualKeyCode::Up => self.near_padding = (self.near_padding + 2.0).min(60.0),
+                VirtualKeyCode::Down => self.near_padding = (self.near_padding - 2.0).max(0.0),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        let mode = match self.clamp_shadow_pipeline {
+            Some(_) => format!("{:?}", self.mode),
+            None => "Clip only, the adapter lacks DEPTH_CLIP_CONTROL".to_string(),
+        };
+        Some(format!("{}, light near plane {:.0} above the ground", mode, self.near_padding))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let time = self.start.elapsed().as_secs_f32();
+
+        let angle = time * 0.2;
+        let camera_view = match &self.camera {
+            Some(pose) => pose.view_matrix(),
+            None => Mat4::look_at_rh(
+                Vec3::new(angle.cos() * 45.0, 30.0, angle.sin() * 45.0),
+                Vec3::new(0.0, 6.0, 0.0),
+                Vec3::Y,
+            ),
+        };
+        let camera_proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), 0.5, 200.0);
+
+        let light_dir = Vec3::new(-0.4, -1.0, -0.3).normalize();
+
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                camera_view_proj: (camera_proj * camera_view).to_cols_array_2d(),
+                light_view_proj: self.light_view_proj(light_dir).to_cols_array_2d(),
+                light_dir: light_dir.extend(0.0).to_array(),
+            }),
+        );
+
+        let shadow_pipeline = match (self.mode, &self.clamp_shadow_pipeline) {
+            (DepthMode::Clamp, Some(pipeline)) => pipeline,
+            _ => &self.clip_shadow_pipeline,
+        };
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut shadow_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Shadow Pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.shadow_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: true,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            shadow_pass.set_pipeline(shadow_pipeline);
+            shadow_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            shadow_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            shadow_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            shadow_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            shadow_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+        }
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.55,
+                                    g: 0.65,
+                                    b: 0.8,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &self.depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: false,
+                        }),
+                        stencil_ops: None,
+                    }),
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.shadow_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    // An orthographic light fitted tightly around the ground only. The depth
+    // range is as small as it gets, which is good for precision, but the
+    // pillars and the floating boxes stick out in front of the near plane.
+    fn light_view_proj(&self, light_dir: Vec3) -> Mat4 {
+        let light_view = Mat4::look_to_rh(-light_dir * 50.0, light_dir, Vec3::Y);
+
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+        for x in [-GROUND_EXTENT, GROUND_EXTENT] {
+            for y in [-0.2, 0.0] {
+                for z in [-GROUND_EXTENT, GROUND_EXTENT] {
+                    let corner = light_view.transform_point3(Vec3::new(x, y, z));
+                    min = min.min(corner);
+                    max = max.max(corner);
+                }
+            }
+        }
+
+        // View space looks down -z, so the nearest corner has the largest z
+        let near = -max.z - self.near_padding;
+        let far = -min.z + 0.5;
+        Mat4::orthographic_rh(min.x, max.x, min.y, max.y, near, far) * light_view
+    }
+}
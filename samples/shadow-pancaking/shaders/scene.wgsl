@@ -0,0 +1,65 @@
+struct Uniforms {
+    camera_view_proj: mat4x4<f32>,
+    light_view_proj: mat4x4<f32>,
+    // Direction the light travels in
+    light_dir: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0)
+var shadow_map: texture_depth_2d;
+@group(1) @binding(1)
+var shadow_sampler: sampler_comparison;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(2) offset: vec3<f32>,
+    @location(3) scale: vec3<f32>,
+    @location(4) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+}
+
+@vertex
+fn vs_shadow(vertex: VertexInput, instance: InstanceInput) -> @builtin(position) vec4<f32> {
+    let world = vertex.position * instance.scale + instance.offset;
+    return uniforms.light_view_proj * vec4<f32>(world, 1.0);
+}
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let world = vertex.position * instance.scale + instance.offset;
+
+    var out: VertexOutput;
+    out.clip_position = uniforms.camera_view_proj * vec4<f32>(world, 1.0);
+    out.world_position = world;
+    // Boxes are axis aligned, so the inverse transpose is just 1 / scale
+    out.normal = vertex.normal / instance.scale;
+    out.color = instance.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_clip = uniforms.light_view_proj * vec4<f32>(in.world_position, 1.0);
+    let uv = light_clip.xy * vec2<f32>(0.5, -0.5) + 0.5;
+
+    // The Level variant is fine outside uniform control flow
+    let visibility = textureSampleCompareLevel(shadow_map, shadow_sampler, uv, light_clip.z);
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    let shadow = select(1.0, visibility, inside);
+
+    let diffuse = max(dot(normalize(in.normal), -uniforms.light_dir.xyz), 0.0) * shadow;
+    return vec4<f32>(in.color * (0.25 + 0.75 * diffuse), 1.0);
+}
@@ -0,0 +1,164 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+// How long a client waits before trying to connect again
+const RETRY: Duration = Duration::from_secs(1);
+
+// One object's placement, all the instances tell each other
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub position: [f32; 2],
+    pub angle: f32,
+}
+
+impl Transform {
+    const SIZE: usize = 12;
+
+    // Little endian, so instances on different machines agree
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        for (chunk, value) in bytes.chunks_mut(4).zip([self.position[0], self.position[1], self.angle]) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let value = |index: usize| f32::from_le_bytes(bytes[index * 4..index * 4 + 4].try_into().unwrap());
+        Self {
+            position: [value(0), value(1)],
+            angle: value(2),
+        }
+    }
+}
+
+pub enum Event {
+    Connected(SocketAddr),
+    Received(Transform),
+    // A client's attempt failed, it tries again shortly
+    Retrying(String),
+    Disconnected(String),
+}
+
+enum Peer {
+    Listener(TcpListener),
+    Address(String),
+}
+
+// The connection to the other instance. Sockets block, so they live on
+// threads of their own and the render loop only ever polls channels: a
+// frame never waits for the network, however slow or gone the peer is.
+pub struct Link {
+    outgoing: SyncSender<Transform>,
+    events: Receiver<Event>,
+}
+
+impl Link {
+    // Waits for another instance to connect, and for the next one whenever
+    // it leaves. Fails when the address can't be listened on, e.g. another
+    // instance is already hosting there.
+    pub fn host(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        Ok(Self::spawn(Peer::Listener(listener)))
+    }
+
+    // Connects to a hosting instance, retrying until it's there
+    pub fn connect(address: &str) -> Self {
+        Self::spawn(Peer::Address(address.to_string()))
+    }
+
+    fn spawn(peer: Peer) -> Self {
+        // Room for a couple of updates only. When the socket can't keep up
+        // newer ones are dropped rather than queued, they'd be stale by the
+        // time they went out anyway.
+        let (outgoing, updates) = sync_channel(2);
+        let (events_sender, events) = channel();
+        thread::spawn(move || run(peer, updates, events_sender));
+        Self { outgoing, events }
+    }
+
+    // Never blocks; dropped when not connected or the socket is backed up
+    pub fn send(&self, transform: Transform) {
+        let _ = self.outgoing.try_send(transform);
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.events.try_iter()
+    }
+}
+
+fn run(peer: Peer, updates: Receiver<Transform>, events: Sender<Event>) {
+    loop {
+        let stream = match &peer {
+            Peer::Listener(listener) => listener.accept().map(|(stream, _)| stream),
+            Peer::Address(address) => TcpStream::connect(address),
+        };
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                if events.send(Event::Retrying(error.to_string())).is_err() {
+                    return;
+                }
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        // Updates are tiny and latency is all that matters
+        let _ = stream.set_nodelay(true);
+        let address = match stream.peer_addr() {
+            Ok(address) => address,
+            Err(_) => continue,
+        };
+        if events.send(Event::Connected(address)).is_err() {
+            return;
+        }
+        // Whatever piled up while nobody was connected is out of date
+        while updates.try_recv().is_ok() {}
+
+        let reader = match stream.try_clone() {
+            Ok(reader) => reader,
+            Err(_) => continue,
+        };
+        let received = events.clone();
+        thread::spawn(move || read_updates(reader, received));
+
+        match write_updates(&stream, &updates) {
+            Some(error) => {
+                let _ = stream.shutdown(Shutdown::Both);
+                if events.send(Event::Disconnected(error.to_string())).is_err() {
+                    return;
+                }
+            }
+            // The renderer is gone
+            None => return,
+        }
+    }
+}
+
+// Returns the error that ended the connection, or None once there's
+// nothing left to send from
+fn write_updates(mut stream: &TcpStream, updates: &Receiver<Transform>) -> Option<io::Error> {
+    for transform in updates {
+        if let Err(error) = stream.write_all(&transform.to_bytes()) {
+            return Some(error);
+        }
+    }
+    None
+}
+
+// Ends quietly when the peer leaves; shutting the socket down makes the
+// writer's next update fail, and it reports the disconnect
+fn read_updates(mut stream: TcpStream, events: Sender<Event>) {
+    let mut bytes = [0; Transform::SIZE];
+    while stream.read_exact(&mut bytes).is_ok() {
+        if events.send(Event::Received(Transform::from_bytes(&bytes))).is_err() {
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
@@ -0,0 +1,8 @@
+mod link;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("shared-scene");
+}
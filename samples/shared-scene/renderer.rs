@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::link::{Event, Link, Transform, DEFAULT_ADDRESS};
+
+// Per second, in a shared square 2 units across
+const SPEED: f32 = 0.8;
+const TURN_SPEED: f32 = 3.0;
+// Updates go out at this rate, whatever the frame rate
+const SEND_INTERVAL: Duration = Duration::from_millis(33);
+// How quickly the peer's ship catches up with its last update; higher
+// follows closer, lower hides more jitter
+const SMOOTHING: f32 = 15.0;
+// The host flies the first, whoever connects the second
+const COLORS: [[f32; 4]; 2] = [[0.3, 0.6, 1.0, 1.0], [1.0, 0.6, 0.2, 1.0]];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Instance {
+    position: [f32; 2],
+    angle: f32,
+    color: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32, 2 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            // Every ship is one instance of a three vertex triangle
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct View {
+    aspect: f32,
+    _padding: [f32; 3],
+}
+
+fn start(player: usize) -> Transform {
+    Transform {
+        position: [if player == 0 { -0.5 } else { 0.5 }, 0.0],
+        angle: 0.0,
+    }
+}
+
+pub struct Renderer {
+    link: Link,
+    // 0 when hosting, 1 when connected to a host
+    player: usize,
+    connection: String,
+    local: Transform,
+    // The last update from the peer, and where its ship is drawn on the
+    // way there. None while nobody's connected.
+    remote: Option<Transform>,
+    shown_remote: Transform,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+    last_sent: Instant,
+    pipeline: RenderPipeline,
+    view_buffer: Buffer,
+    instance_buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        // --connect address joins a running instance, otherwise this one
+        // hosts, on --host address or the default
+        let mut args = std::env::args().skip_while(|arg| arg != "--connect");
+        let (link, player, connection) = match args.nth(1) {
+            Some(address) => (Link::connect(&address), 1, format!("connecting to {}", address)),
+            None => {
+                let mut args = std::env::args().skip_while(|arg| arg != "--host");
+                let address = args.nth(1).unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+                let link = Link::host(&address).unwrap_or_else(|error| {
+                    eprintln!("{}: {}", address, error);
+                    std::process::exit(1);
+                });
+                println!("hosting on {}, run another with --connect {} to join", address, address);
+                (link, 0, format!("waiting on {}", address))
+            }
+        };
+
+        let view_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("View"),
+            size: std::mem::size_of::<View>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ships"),
+            size: (std::mem::size_of::<Instance>() * COLORS.len()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("View Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("View Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/ships.wgsl"));
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Ships Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ships Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[Instance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            link,
+            player,
+            connection,
+            local: start(player),
+            remote: None,
+            shown_remote: start(1 - player),
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+            last_sent: Instant::now(),
+            pipeline,
+            view_buffer,
+            instance_buffer,
+            bind_group,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match state {
+                ElementState::Pressed => self.held_keys.insert(*key),
+                ElementState::Released => self.held_keys.remove(key),
+            };
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{}, arrows or WASD fly the {} ship", self.connection, if self.player == 0 { "blue" } else { "orange" }))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        // Clamped so a long hitch (e.g. dragging the window) doesn't
+        // teleport anyone
+        let delta_time = (now - self.last_frame).as_secs_f32().min(1.0 / 30.0);
+        self.last_frame = now;
+
+        // Whatever arrived since the last frame, without waiting for more
+        for event in self.link.events() {
+            match event {
+                Event::Connected(address) => {
+                    self.connection = format!("connected to {}", address);
+                }
+                Event::Received(transform) => {
+                    if self.remote.is_none() {
+                        self.shown_remote = transform;
+                    }
+                    self.remote = Some(transform);
+                }
+                Event::Retrying(error) => {
+                    self.connection = format!("can't connect ({}), retrying", error);
+                }
+                Event::Disconnected(error) => {
+                    self.connection = format!("lost the peer ({}), waiting", error);
+                    self.remote = None;
+                }
+            }
+        }
+
+        let held = |keys: [VirtualKeyCode; 2]| keys.iter().any(|key| self.held_keys.contains(key));
+        let mut turn = 0.0;
+        if held([VirtualKeyCode::Left, VirtualKeyCode::A]) {
+            turn += 1.0;
+        }
+        if held([VirtualKeyCode::Right, VirtualKeyCode::D]) {
+            turn -= 1.0;
+        }
+        let mut thrust = 0.0;
+        if held([VirtualKeyCode::Up, VirtualKeyCode::W]) {
+            thrust += 1.0;
+        }
+        if held([VirtualKeyCode::Down, VirtualKeyCode::S]) {
+            thrust -= 1.0;
+        }
+        self.local.angle += turn * TURN_SPEED * delta_time;
+        // Both instances keep to the same square, whatever their windows'
+        // shapes, so they agree on where the edges are
+        for (axis, direction) in [self.local.angle.cos(), self.local.angle.sin()].into_iter().enumerate() {
+            self.local.position[axis] = (self.local.position[axis] + direction * thrust * SPEED * delta_time).clamp(-1.0, 1.0);
+        }
+
+        if now - self.last_sent >= SEND_INTERVAL {
+            self.link.send(self.local);
+            self.last_sent = now;
+        }
+
+        // Updates come in at SEND_INTERVAL at best, and unevenly; easing
+        // towards the last one hides the steps
+        if let Some(remote) = self.remote {
+            let t = 1.0 - (-SMOOTHING * delta_time).exp();
+            for axis in 0..2 {
+                self.shown_remote.position[axis] += (remote.position[axis] - self.shown_remote.position[axis]) * t;
+            }
+            // The short way round
+            let turn = (remote.angle - self.shown_remote.angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+                - std::f32::consts::PI;
+            self.shown_remote.angle += turn * t;
+        }
+
+        let mut ships = vec![(self.player, self.local)];
+        if self.remote.is_some() {
+            ships.push((1 - self.player, self.shown_remote));
+        }
+        let instances: Vec<Instance> = ships
+            .into_iter()
+            .map(|(player, transform)| Instance {
+                position: transform.position,
+                angle: transform.angle,
+                color: COLORS[player],
+            })
+            .collect();
+        gpu.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        gpu.queue.write_buffer(
+            &self.view_buffer,
+            0,
+            bytemuck::bytes_of(&View {
+                aspect: gpu.aspect_ratio(),
+                _padding: [0.0; 3],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Ships Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.02,
+                                    g: 0.02,
+                                    b: 0.04,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+            render_pass.draw(0..3, 0..instances.len() as u32);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
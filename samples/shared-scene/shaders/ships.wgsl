@@ -0,0 +1,42 @@
+struct View {
+    aspect: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+// A dart pointing along +x, rotated by the instance's angle
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32,
+    @location(0) position: vec2<f32>,
+    @location(1) angle: f32,
+    @location(2) color: vec4<f32>
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 3>(
+        vec2<f32>(0.08, 0.0),
+        vec2<f32>(-0.05, 0.045),
+        vec2<f32>(-0.05, -0.045)
+    );
+    let corner = corners[index];
+    let c = cos(angle);
+    let s = sin(angle);
+    let world = position + vec2<f32>(corner.x * c - corner.y * s, corner.x * s + corner.y * c);
+
+    // The -1..1 square both instances share, as big as fits the window
+    let fit = min(view.aspect, 1.0) * 0.95;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(world.x * fit / view.aspect, world.y * fit, 0.0, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,419 @@
+use std::f32::consts::PI;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::envmap::{cube_view, EnvironmentTools};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Quat, Vec3};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, Sampler, Texture};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+const EQUIRECT_WIDTH: u32 = 1024;
+const FACE_SIZE: u32 = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum View {
+    Cubemap,
+    // The cubemap converted back to an equirect, to check the round trip
+    Equirect,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    inverse_view_proj: [[f32; 4]; 4],
+    lod: f32,
+    mode: u32,
+    _padding: [u32; 2],
+}
+
+// A sky with a sun, a 15 degree grid and a red line at longitude 0, so
+// seams, rotation and blurry mips are easy to spot
+fn procedural_equirect() -> Vec<u8> {
+    let height = EQUIRECT_WIDTH / 2;
+    let sun_lon = 40f32.to_radians();
+    let sun_lat = 30f32.to_radians();
+
+    let mut pixels = Vec::with_capacity((EQUIRECT_WIDTH * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..EQUIRECT_WIDTH {
+            let lon = ((x as f32 + 0.5) / EQUIRECT_WIDTH as f32 - 0.5) * 2.0 * PI;
+            let lat = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+
+            let mut color = if lat > 0.0 {
+                Vec3::new(0.8, 0.85, 0.9).lerp(Vec3::new(0.2, 0.4, 0.8), lat / (PI / 2.0))
+            } else {
+                Vec3::new(0.35, 0.3, 0.25)
+            };
+
+            let grid = 15f32.to_radians();
+            let near_line = |angle: f32| {
+                let offset = angle / grid;
+                (offset - offset.round()).abs() < 0.03
+            };
+            if near_line(lon) || near_line(lat) {
+                color = color * 0.6 + Vec3::splat(0.4);
+            }
+            if lon.abs() < 0.01 {
+                color = Vec3::new(0.9, 0.1, 0.1);
+            }
+
+            let cos_distance = lat.sin() * sun_lat.sin() + lat.cos() * sun_lat.cos() * (lon - sun_lon).cos();
+            if cos_distance > 4f32.to_radians().cos() {
+                color = Vec3::new(1.0, 0.95, 0.8);
+            }
+
+            let color = (color * 255.0).to_array();
+            pixels.extend([color[0] as u8, color[1] as u8, color[2] as u8, 255]);
+        }
+    }
+    pixels
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    bind_group_layout: BindGroupLayout,
+    sampler: Sampler,
+    uniform_buffer: Buffer,
+    tools: EnvironmentTools,
+    // Straight from the equirect, rotations always start from this one so
+    // they don't blur a little more each time
+    base_cubemap: Texture,
+    bind_group: BindGroup,
+    view: View,
+    lod: f32,
+    // Turns of the environment around Y, in steps of 30 degrees
+    rotation_steps: u32,
+    rotation_changed: bool,
+    start: Instant,
+    // Only the direction matters, the sky is infinitely far away. Set by
+    // --flythrough or a script, a slow automatic pan until then.
+    camera: Option<CameraPose>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let equirect = device.create_texture_with_data(
+            &gpu.queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Sky Equirect"),
+                size: wgpu::Extent3d {
+                    width: EQUIRECT_WIDTH,
+                    height: EQUIRECT_WIDTH / 2,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            &procedural_equirect(),
+        );
+
+        let tools = EnvironmentTools::new(device);
+        let mut encoder =
+            device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Environment Encoder"),
+                },
+            );
+        let base_cubemap = tools.equirect_to_cubemap(
+            device,
+            &mut encoder,
+            &equirect.create_view(&wgpu::TextureViewDescriptor::default()),
+            FACE_SIZE,
+            Quat::IDENTITY,
+        );
+        tools.generate_mips(device, &mut encoder, &base_cubemap);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Sky Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/skybox.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let (cubemap, equirect) = build_environment(device, &gpu.queue, &tools, &base_cubemap, 0);
+        let bind_group = create_bind_group(device, &bind_group_layout, &uniform_buffer, &sampler, &cubemap, &equirect);
+
+        Self {
+            render_pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            tools,
+            base_cubemap,
+            bind_group,
+            view: View::Cubemap,
+            lod: 0.0,
+            rotation_steps: 0,
+            rotation_changed: false,
+            start: Instant::now(),
+            camera: None,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            let max_lod = FACE_SIZE.ilog2() as f32;
+            match key {
+                VirtualKeyCode::Up => self.lod = (self.lod + 0.5).min(max_lod),
+                VirtualKeyCode::Down => self.lod = (self.lod - 0.5).max(0.0),
+                VirtualKeyCode::Tab => {
+                    self.view = match self.view {
+                        View::Cubemap => View::Equirect,
+                        View::Equirect => View::Cubemap,
+                    };
+                }
+                VirtualKeyCode::R => {
+                    self.rotation_steps = (self.rotation_steps + 1) % 12;
+                    self.rotation_changed = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "lod" => self.lod = value.clamp(0.0, FACE_SIZE.ilog2() as f32),
+            _ => return false,
+        }
+        true
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{:?}, mip {:.1}, rotated {} degrees",
+            self.view,
+            self.lod,
+            self.rotation_steps * 30,
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        if std::mem::take(&mut self.rotation_changed) {
+            let (cubemap, equirect) =
+                build_environment(&gpu.device, &gpu.queue, &self.tools, &self.base_cubemap, self.rotation_steps);
+            self.bind_group = create_bind_group(
+                &gpu.device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.sampler,
+                &cubemap,
+                &equirect,
+            );
+        }
+
+        let time = self.start.elapsed().as_secs_f32();
+        let (yaw, pitch) = match &self.camera {
+            Some(pose) => (pose.yaw, pose.pitch),
+            None => (time * 0.1, (time * 0.2).sin() * 0.6),
+        };
+        let direction = Vec3::new(yaw.sin() * pitch.cos(), pitch.sin(), -yaw.cos() * pitch.cos());
+        let view_matrix = Mat4::look_to_rh(Vec3::ZERO, direction, Vec3::Y);
+        let proj = Mat4::perspective_rh(70f32.to_radians(), gpu.aspect_ratio(), 0.1, 10.0);
+
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                inverse_view_proj: (proj * view_matrix).inverse().to_cols_array_2d(),
+                lod: self.lod,
+                mode: self.view as u32,
+                _padding: [0; 2],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+// Rotates the base cubemap, refilters its mips and unwraps it into an
+// equirect again
+fn build_environment(
+    device: &Device,
+    queue: &wgpu::Queue,
+    tools: &EnvironmentTools,
+    base_cubemap: &Texture,
+    rotation_steps: u32,
+) -> (Texture, Texture) {
+    let mut encoder =
+        device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor {
+                label: Some("Environment Encoder"),
+            },
+        );
+    let rotation = Quat::from_rotation_y(((rotation_steps * 30) as f32).to_radians());
+    let cubemap = tools.rotate_cubemap(device, &mut encoder, base_cubemap, rotation);
+    tools.generate_mips(device, &mut encoder, &cubemap);
+    let equirect = tools.cubemap_to_equirect(device, &mut encoder, &cubemap, EQUIRECT_WIDTH);
+    queue.submit(std::iter::once(encoder.finish()));
+    (cubemap, equirect)
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &BindGroupLayout,
+    uniform_buffer: &Buffer,
+    sampler: &Sampler,
+    cubemap: &Texture,
+    equirect: &Texture,
+) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Sky Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&cube_view(cubemap)),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(
+                    &equirect.create_view(&wgpu::TextureViewDescriptor::default()),
+                ),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
@@ -0,0 +1,44 @@
+struct Uniforms {
+    // Rotation only, the sky is infinitely far away
+    inverse_view_proj: mat4x4<f32>,
+    lod: f32,
+    // 0 shows the cubemap, 1 the equirect converted back from it
+    mode: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var environment: texture_cube<f32>;
+@group(0) @binding(2)
+var equirect: texture_2d<f32>;
+@group(0) @binding(3)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+// One triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(in_vertex_index & 1u) * 4.0 - 1.0, f32(in_vertex_index >> 1u) * 4.0 - 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = uniforms.inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let dir = normalize(world.xyz / world.w);
+    let sky = textureSampleLevel(environment, environment_sampler, dir, uniforms.lod);
+
+    let uv = in.ndc * vec2<f32>(0.5, -0.5) + 0.5;
+    let unwrapped = textureSampleLevel(equirect, environment_sampler, uv, 0.0);
+
+    return select(sky, unwrapped, uniforms.mode == 1u);
+}
@@ -0,0 +1,536 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::depth_fade::{self, SceneDepth};
+use framework::{CameraPose, Gpu, Sample};
+use glam::{Mat4, Vec3};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+const MAX_PUFFS: usize = 4096;
+const PUFF_LIFETIME: f32 = 7.0;
+// Per emitter
+const PUFFS_PER_SECOND: f32 = 180.0;
+const NEAR: f32 = 0.1;
+const FAR: f32 = 500.0;
+const ORBIT_SPEED: f32 = 0.8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x3];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct PuffInstance {
+    // w is the size
+    position: [f32; 4],
+    // Straight alpha
+    color: [f32; 4],
+    // x is the rotation
+    spin: [f32; 4],
+}
+
+impl PuffInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<PuffInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    view_proj: [[f32; 4]; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    depth: [f32; 4],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + self.next_f32() * (max - min)
+    }
+}
+
+struct Puff {
+    position: Vec3,
+    velocity: Vec3,
+    age: f32,
+    spin: f32,
+    spin_rate: f32,
+}
+
+// Smoke pours out of these, right up against the walls and the ramp so
+// there's plenty of puffs cutting into geometry
+const EMITTERS: [[f32; 3]; 3] = [[-6.0, 0.0, 1.5], [4.0, 0.0, -2.5], [2.0, 2.0, 6.0]];
+
+// The ground, a wall, a pillar and a ramp
+fn scene() -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    let mut quad = |corners: [Vec3; 4], color: [f32; 3]| {
+        let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize();
+        for index in [0, 1, 2, 0, 2, 3] {
+            vertices.push(Vertex {
+                position: corners[index].to_array(),
+                normal: normal.to_array(),
+                color,
+            });
+        }
+    };
+    let mut cuboid = |min: Vec3, max: Vec3, color: [f32; 3]| {
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(if x { max.x } else { min.x }, if y { max.y } else { min.y }, if z { max.z } else { min.z })
+        };
+        quad([corner(false, false, true), corner(true, false, true), corner(true, true, true), corner(false, true, true)], color);
+        quad([corner(true, false, true), corner(true, false, false), corner(true, true, false), corner(true, true, true)], color);
+        quad([corner(true, false, false), corner(false, false, false), corner(false, true, false), corner(true, true, false)], color);
+        quad([corner(false, false, false), corner(false, false, true), corner(false, true, true), corner(false, true, false)], color);
+        quad([corner(false, true, false), corner(false, true, true), corner(true, true, true), corner(true, true, false)], color);
+    };
+
+    cuboid(Vec3::new(-40.0, -1.0, -40.0), Vec3::new(40.0, 0.0, 40.0), [0.35, 0.33, 0.3]);
+    cuboid(Vec3::new(-10.0, 0.0, -0.5), Vec3::new(-2.0, 5.0, 0.5), [0.55, 0.4, 0.3]);
+    cuboid(Vec3::new(4.5, 0.0, -3.0), Vec3::new(5.5, 8.0, -2.0), [0.5, 0.5, 0.55]);
+    cuboid(Vec3::new(1.0, 0.0, 5.0), Vec3::new(3.0, 2.0, 7.0), [0.45, 0.5, 0.4]);
+    // A slope rising across the middle
+    quad(
+        [
+            Vec3::new(-4.0, 0.0, 9.0),
+            Vec3::new(6.0, 0.0, 9.0),
+            Vec3::new(6.0, 4.0, 3.0),
+            Vec3::new(-4.0, 4.0, 3.0),
+        ],
+        [0.4, 0.42, 0.5],
+    );
+    vertices
+}
+
+pub struct Renderer {
+    scene_pipeline: RenderPipeline,
+    smoke_pipeline: RenderPipeline,
+    scene_bind_group: BindGroup,
+    smoke_layout: BindGroupLayout,
+    smoke_bind_group: BindGroup,
+    uniform_buffer: Buffer,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    instance_buffer: Buffer,
+    depth: SceneDepth,
+    puffs: Vec<Puff>,
+    // Fractional puffs carried over to the next frame
+    spawn_debt: f32,
+    rng: Rng,
+    softness: f32,
+    soft: bool,
+    show_fade: bool,
+    orbit: f32,
+    // Set by --flythrough or a script, orbiting with the arrow keys until then
+    camera: Option<CameraPose>,
+    held_keys: HashSet<VirtualKeyCode>,
+    last_frame: Instant,
+}
+
+fn create_smoke_bind_group(device: &Device, layout: &BindGroupLayout, uniform_buffer: &Buffer, depth: &SceneDepth) -> BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Smoke Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: depth.binding(),
+            },
+        ],
+    })
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let vertices = scene();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Scene Vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Puffs"),
+            size: (MAX_PUFFS * std::mem::size_of::<PuffInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let uniform_entry = wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scene Layout"),
+            entries: &[uniform_entry],
+        });
+        let scene_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Scene Bind Group"),
+            layout: &scene_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let smoke_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Smoke Layout"),
+            entries: &[uniform_entry, SceneDepth::layout_entry(1)],
+        });
+        let depth = SceneDepth::new(device, &gpu.config);
+        let smoke_bind_group = create_smoke_bind_group(device, &smoke_layout, &uniform_buffer, &depth);
+
+        let scene_shader = device.create_shader_module(include_wgsl!("shaders/scene.wgsl"));
+        let scene_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Scene Pipeline Layout"),
+            bind_group_layouts: &[&scene_layout],
+            push_constant_ranges: &[],
+        });
+        let scene_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Scene Pipeline"),
+            layout: Some(&scene_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &scene_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &scene_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_fade::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let smoke_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Smoke Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{}", depth_fade::WGSL, include_str!("shaders/smoke.wgsl")).into(),
+            ),
+        });
+        let smoke_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Smoke Pipeline Layout"),
+            bind_group_layouts: &[&smoke_layout],
+            push_constant_ranges: &[],
+        });
+        let smoke_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Smoke Pipeline"),
+            layout: Some(&smoke_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &smoke_shader,
+                entry_point: "vs_main",
+                buffers: &[PuffInstance::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &smoke_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(depth_fade::particle_depth_state()),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            scene_pipeline,
+            smoke_pipeline,
+            scene_bind_group,
+            smoke_layout,
+            smoke_bind_group,
+            uniform_buffer,
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            instance_buffer,
+            depth,
+            puffs: Vec::new(),
+            spawn_debt: 0.0,
+            rng: Rng(0x51ed_270b),
+            softness: 1.5,
+            soft: true,
+            show_fade: false,
+            orbit: 0.4,
+            camera: None,
+            held_keys: HashSet::new(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.depth = SceneDepth::new(&gpu.device, &gpu.config);
+        // Holds the old depth view, so it has to follow it
+        self.smoke_bind_group = create_smoke_bind_group(&gpu.device, &self.smoke_layout, &self.uniform_buffer, &self.depth);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            if *state == ElementState::Released {
+                self.held_keys.remove(key);
+                return;
+            }
+            // Key repeat keeps sending presses, only the first one toggles
+            if !self.held_keys.insert(*key) {
+                return;
+            }
+            match key {
+                VirtualKeyCode::F => self.soft = !self.soft,
+                VirtualKeyCode::H => self.show_fade = !self.show_fade,
+                VirtualKeyCode::Up => self.softness = (self.softness * 1.5).min(20.0),
+                VirtualKeyCode::Down => self.softness = (self.softness / 1.5).max(0.05),
+                _ => {}
+            }
+        }
+    }
+
+    fn set_camera(&mut self, pose: &CameraPose) {
+        self.camera = Some(*pose);
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} puffs, {}{}",
+            self.puffs.len(),
+            if self.soft { format!("soft over {:.2} units", self.softness) } else { "hard".to_string() },
+            if self.show_fade { ", showing the fade" } else { "" },
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let dt = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+
+        let held = |key| self.held_keys.contains(&key);
+        let mut turn = 0.0;
+        if held(VirtualKeyCode::Right) { turn += 1.0; }
+        if held(VirtualKeyCode::Left) { turn -= 1.0; }
+        self.orbit += turn * ORBIT_SPEED * dt;
+
+        let (eye, target) = match &self.camera {
+            Some(pose) => (pose.position, pose.position + pose.direction()),
+            None => (Vec3::new(self.orbit.sin() * 26.0, 9.0, self.orbit.cos() * 26.0), Vec3::new(0.0, 3.0, 0.0)),
+        };
+        self.simulate(dt);
+        let instances = self.sorted_instances(eye);
+
+        let view_matrix = Mat4::look_at_rh(eye, target, Vec3::Y);
+        let proj = Mat4::perspective_rh(50f32.to_radians(), gpu.aspect_ratio(), NEAR, FAR);
+        let forward = (target - eye).normalize();
+        let right = forward.cross(Vec3::Y).normalize();
+        let up = right.cross(forward);
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                view_proj: (proj * view_matrix).to_cols_array_2d(),
+                right: right.extend(0.0).to_array(),
+                up: up.extend(0.0).to_array(),
+                // Hard is the same fade squeezed into almost nothing
+                depth: [NEAR, FAR, if self.soft { self.softness } else { 0.001 }, if self.show_fade { 1.0 } else { 0.0 }],
+            }),
+        );
+        gpu.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Scene Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color {
+                                    r: 0.5,
+                                    g: 0.6,
+                                    b: 0.75,
+                                    a: 1.0,
+                                }),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(self.depth.attachment()),
+                },
+            );
+            render_pass.set_pipeline(&self.scene_pipeline);
+            render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+
+        // A separate pass: the depth texture can only be bound for reading
+        // once the pass writing it has ended
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Smoke Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: Some(self.depth.read_only_attachment()),
+                },
+            );
+            if !instances.is_empty() {
+                render_pass.set_pipeline(&self.smoke_pipeline);
+                render_pass.set_bind_group(0, &self.smoke_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+                render_pass.draw(0..6, 0..instances.len() as u32);
+            }
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn simulate(&mut self, dt: f32) {
+        for puff in &mut self.puffs {
+            puff.age += dt;
+            // Rises, slows down and drifts off with the breeze
+            puff.velocity += (Vec3::new(0.6, 0.3, 0.2) - puff.velocity) * (dt * 0.5);
+            puff.position += puff.velocity * dt;
+            puff.spin += puff.spin_rate * dt;
+        }
+        self.puffs.retain(|puff| puff.age < PUFF_LIFETIME);
+
+        self.spawn_debt += PUFFS_PER_SECOND * dt;
+        while self.spawn_debt >= 1.0 {
+            self.spawn_debt -= 1.0;
+            for emitter in EMITTERS {
+                if self.puffs.len() >= MAX_PUFFS {
+                    break;
+                }
+                let rng = &mut self.rng;
+                self.puffs.push(Puff {
+                    position: Vec3::from(emitter) + Vec3::new(rng.range(-0.5, 0.5), 0.2, rng.range(-0.5, 0.5)),
+                    velocity: Vec3::new(rng.range(-1.0, 1.0), rng.range(1.5, 3.0), rng.range(-1.0, 1.0)),
+                    age: 0.0,
+                    spin: rng.range(0.0, std::f32::consts::TAU),
+                    spin_rate: rng.range(-0.5, 0.5),
+                });
+            }
+        }
+    }
+
+    // Back to front, for blending over each other in the right order
+    fn sorted_instances(&self, eye: Vec3) -> Vec<PuffInstance> {
+        let mut order: Vec<(f32, &Puff)> = self
+            .puffs
+            .iter()
+            .map(|puff| (puff.position.distance_squared(eye), puff))
+            .collect();
+        order.sort_by(|a, b| b.0.total_cmp(&a.0));
+        order
+            .into_iter()
+            .map(|(_, puff)| {
+                let life = puff.age / PUFF_LIFETIME;
+                // Grows as it spreads, fades in quickly and out slowly
+                let size = 1.5 + life * 6.0;
+                let alpha = (life * 10.0).min(1.0) * (1.0 - life) * 0.35;
+                let grey = 0.75 - life * 0.2;
+                PuffInstance {
+                    position: puff.position.extend(size).to_array(),
+                    color: [grey, grey, grey * 1.02, alpha],
+                    spin: [puff.spin, 0.0, 0.0, 0.0],
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    // Camera right and up for billboarding, w unused
+    right: vec4<f32>,
+    up: vec4<f32>,
+    // x near, y far, z softness, w 1 to show the fade instead of smoke
+    depth: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(vertex.position, 1.0);
+    out.normal = vertex.normal;
+    out.color = vertex.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sun = normalize(vec3<f32>(-0.4, 0.8, 0.45));
+    let light = max(dot(normalize(in.normal), sun), 0.0) * 0.7 + 0.25;
+    return vec4<f32>(in.color * light, 1.0);
+}
@@ -0,0 +1,76 @@
+// framework::depth_fade::WGSL is pasted in front of this
+
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    // Camera right and up for billboarding, w unused
+    right: vec4<f32>,
+    up: vec4<f32>,
+    // x near, y far, z softness, w 1 to show the fade instead of smoke
+    depth: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+@group(0) @binding(1)
+var scene_depth: texture_2d<f32>;
+
+struct Puff {
+    // w is the size
+    @location(0) position: vec4<f32>,
+    // Straight alpha
+    @location(1) color: vec4<f32>,
+    // x turns the puff so they don't all look alike
+    @location(2) spin: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // -1 to 1 across the quad, already turned
+    @location(0) offset: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, puff: Puff) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0)
+    );
+    let corner = corners[vertex_index];
+    let c = cos(puff.spin.x);
+    let s = sin(puff.spin.x);
+
+    let world = puff.position.xyz
+        + (uniforms.right.xyz * corner.x + uniforms.up.xyz * corner.y) * puff.position.w * 0.5;
+    var out: VertexOutput;
+    out.clip_position = uniforms.view_proj * vec4<f32>(world, 1.0);
+    out.offset = vec2<f32>(corner.x * c - corner.y * s, corner.x * s + corner.y * c);
+    out.color = puff.color;
+    return out;
+}
+
+// Cheap lumpy falloff instead of a texture
+fn density(offset: vec2<f32>) -> f32 {
+    let angle = atan2(offset.y, offset.x);
+    let radius = length(offset) * (1.0 + 0.12 * sin(angle * 5.0) + 0.06 * sin(angle * 11.0));
+    let falloff = clamp(1.0 - radius, 0.0, 1.0);
+    return falloff * falloff;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let fade = depth_fade(scene_depth, in.clip_position, uniforms.depth.x, uniforms.depth.y, uniforms.depth.z);
+    let alpha = in.color.a * density(in.offset);
+
+    // Red where a puff is being faded out by the scene, green where it isn't
+    if uniforms.depth.w > 0.5 {
+        let shown = alpha * 2.0;
+        return vec4<f32>(vec3<f32>(1.0 - fade, fade, 0.0) * shown, shown);
+    }
+    let faded = alpha * fade;
+    return vec4<f32>(in.color.rgb * faded, faded);
+}
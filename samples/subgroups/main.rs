@@ -0,0 +1,8 @@
+mod renderer;
+mod scan;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("subgroups");
+}
@@ -0,0 +1,319 @@
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use framework::timing::time_submission;
+use framework::Gpu;
+use wgpu::util::DeviceExt;
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, CommandEncoder, ComputePipeline, Device, Queue};
+
+pub const COUNT: usize = 1 << 20;
+// Values per workgroup, two per invocation
+const BLOCK: usize = 512;
+// Runs per timed submission, and timed submissions per variant; the
+// fastest submission counts
+const RUNS: u32 = 8;
+const ROUNDS: u32 = 5;
+
+// Each reduction and scan the sample compares, by name and entry point.
+// There are no subgroup variants: wgpu 0.16 has no subgroup feature to
+// request and naga 0.12 can't parse the subgroup builtins.
+pub const REDUCTIONS: [(&str, &str); 2] = [
+    ("shared memory tree", "cs_reduce_tree"),
+    ("workgroup atomic", "cs_reduce_atomic"),
+];
+pub const SCANS: [(&str, &str); 2] = [
+    ("Blelloch", "cs_scan_blelloch"),
+    ("Hillis-Steele", "cs_scan_hillis_steele"),
+];
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Params {
+    count: u32,
+    _padding: [u32; 3],
+}
+
+struct Rng(u32);
+
+impl Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+// Small enough that the total of a million stays well inside a u32
+pub fn random_values(seed: u32) -> Vec<u32> {
+    let mut rng = Rng(seed.wrapping_mul(0x9e3779b9) | 1);
+    (0..COUNT).map(|_| rng.next_u32() >> 28).collect()
+}
+
+pub struct Timing {
+    pub name: &'static str,
+    pub milliseconds: f32,
+    pub correct: bool,
+}
+
+fn storage_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: false },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn compute_pipeline(device: &Device, label: &str, layout: &BindGroupLayout, module: &wgpu::ShaderModule, entry_point: &str) -> ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(label),
+        layout: Some(&pipeline_layout),
+        module,
+        entry_point,
+    })
+}
+
+fn groups(count: u32) -> u32 {
+    (count + BLOCK as u32 - 1) / BLOCK as u32
+}
+
+// A million values reduced and prefix summed a block per workgroup. Every
+// level of the hierarchy holds a sum per block of the level below, down to
+// a single value, so either operation is a dispatch per level; the scan
+// then walks back down adding each block's offset.
+pub struct Scan {
+    input: Buffer,
+    // Level 0 starts as a copy of the input and ends up scanned in place
+    levels: Vec<Buffer>,
+    // Per level but the last, binding it and the level above
+    bind_groups: Vec<BindGroup>,
+    counts: Vec<u32>,
+    reductions: Vec<ComputePipeline>,
+    scans: Vec<ComputePipeline>,
+    add_offsets: ComputePipeline,
+    readback: Buffer,
+    // What the GPU should come up with, from the CPU
+    expected_total: u32,
+    expected_scan: Vec<u32>,
+}
+
+impl Scan {
+    pub fn new(device: &Device) -> Self {
+        let input = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Input"),
+            size: (COUNT * 4) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut counts = vec![COUNT as u32];
+        while *counts.last().unwrap() > 1 {
+            counts.push(groups(*counts.last().unwrap()));
+        }
+        let levels: Vec<Buffer> = counts
+            .iter()
+            .enumerate()
+            .map(|(level, count)| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&format!("Level {}", level)),
+                    size: *count as wgpu::BufferAddress * 4,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scan Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1),
+                storage_entry(2),
+            ],
+        });
+        let bind_groups = (0..levels.len() - 1)
+            .map(|level| {
+                let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Scan Params"),
+                    contents: bytemuck::bytes_of(&Params {
+                        count: counts[level],
+                        _padding: [0; 3],
+                    }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Scan Bind Group"),
+                    layout: &layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: params_buffer.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: levels[level].as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: levels[level + 1].as_entire_binding(),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/scan.wgsl"));
+        let pipelines = |variants: &[(&str, &str)]| -> Vec<ComputePipeline> {
+            variants
+                .iter()
+                .map(|(name, entry_point)| compute_pipeline(device, name, &layout, &shader, entry_point))
+                .collect()
+        };
+        Self {
+            input,
+            bind_groups,
+            reductions: pipelines(&REDUCTIONS),
+            scans: pipelines(&SCANS),
+            add_offsets: compute_pipeline(device, "Add Offsets", &layout, &shader, "cs_add_offsets"),
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Readback"),
+                size: (COUNT * 4) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+            levels,
+            counts,
+            expected_total: 0,
+            expected_scan: Vec::new(),
+        }
+    }
+
+    pub fn set_input(&mut self, queue: &Queue, values: &[u32]) {
+        queue.write_buffer(&self.input, 0, bytemuck::cast_slice(values));
+        self.expected_scan = values
+            .iter()
+            .scan(0, |sum, value| {
+                let before = *sum;
+                *sum += value;
+                Some(before)
+            })
+            .collect();
+        self.expected_total = values.iter().sum();
+    }
+
+    fn record_reduce(&self, encoder: &mut CommandEncoder, pipeline: &ComputePipeline) {
+        encoder.copy_buffer_to_buffer(&self.input, 0, &self.levels[0], 0, self.input.size());
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Reduce Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        for (bind_group, count) in self.bind_groups.iter().zip(&self.counts) {
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(groups(*count), 1, 1);
+        }
+    }
+
+    fn record_scan(&self, encoder: &mut CommandEncoder, pipeline: &ComputePipeline) {
+        encoder.copy_buffer_to_buffer(&self.input, 0, &self.levels[0], 0, self.input.size());
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Scan Pass"),
+        });
+        compute_pass.set_pipeline(pipeline);
+        for (bind_group, count) in self.bind_groups.iter().zip(&self.counts) {
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(groups(*count), 1, 1);
+        }
+        // The top level is a single block, so its offsets are all zero and
+        // it's the one left out on the way down
+        compute_pass.set_pipeline(&self.add_offsets);
+        let below_top = self.bind_groups.len() - 1;
+        for (bind_group, count) in self.bind_groups[..below_top].iter().zip(&self.counts).rev() {
+            compute_pass.set_bind_group(0, bind_group, &[]);
+            compute_pass.dispatch_workgroups(groups(*count), 1, 1);
+        }
+    }
+
+    fn time(&self, gpu: &Gpu, runs: u32, record: &dyn Fn(&mut CommandEncoder)) -> Duration {
+        time_submission(gpu, |encoder| {
+            for _ in 0..runs {
+                record(encoder);
+            }
+        })
+    }
+
+    // Blocks until the level's values are back on the CPU
+    fn read(&self, gpu: &Gpu, level: usize) -> Vec<u32> {
+        let size = self.levels[level].size();
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Readback Encoder"),
+                },
+            );
+        encoder.copy_buffer_to_buffer(&self.levels[level], 0, &self.readback, 0, size);
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        let slice = self.readback.slice(..size);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        gpu.device.poll(wgpu::Maintain::Wait);
+        let values = bytemuck::cast_slice::<u8, u32>(&slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        values
+    }
+
+    fn measure(&self, gpu: &Gpu, record: &dyn Fn(&mut CommandEncoder)) -> f32 {
+        // The first submission pays for pipeline creation in the driver
+        self.time(gpu, 1, record);
+        let fastest = (0..ROUNDS).map(|_| self.time(gpu, RUNS, record)).min().unwrap();
+        fastest.as_secs_f32() * 1000.0 / RUNS as f32
+    }
+
+    // Times and checks every variant, reductions first, printing a table
+    // along the way
+    pub fn benchmark(&self, gpu: &Gpu) -> Vec<Timing> {
+        let mut timings = Vec::new();
+        for ((name, _), pipeline) in REDUCTIONS.iter().zip(&self.reductions) {
+            timings.push(Timing {
+                name,
+                milliseconds: self.measure(gpu, &|encoder| self.record_reduce(encoder, pipeline)),
+                correct: self.read(gpu, self.levels.len() - 1)[0] == self.expected_total,
+            });
+        }
+        for ((name, _), pipeline) in SCANS.iter().zip(&self.scans) {
+            timings.push(Timing {
+                name,
+                milliseconds: self.measure(gpu, &|encoder| self.record_scan(encoder, pipeline)),
+                correct: self.read(gpu, 0) == self.expected_scan,
+            });
+        }
+
+        for (index, timing) in timings.iter().enumerate() {
+            let operation = if index < REDUCTIONS.len() { "reduce" } else { "scan" };
+            println!(
+                "{} {:<20} {:>8.3}ms {}",
+                operation,
+                timing.name,
+                timing.milliseconds,
+                if timing.correct { "ok" } else { "WRONG" },
+            );
+        }
+        timings
+    }
+}
@@ -0,0 +1,57 @@
+struct Display {
+    // Per bar: length 0-1, 1 if its result was right, 0 for reductions and
+    // 1 for scans, and a spare
+    bars: array<vec4<f32>, 4>,
+    size: vec2<f32>,
+    _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> display: Display;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+const MARGIN: f32 = 0.08;
+// Four bars and a gap between the reductions and the scans
+const ROWS: f32 = 5.0;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = in.position.xy / display.size;
+    let background = vec3<f32>(0.06);
+    let area = (uv - MARGIN) / (1.0 - MARGIN * 2.0);
+    if any(area < vec2<f32>(0.0)) || any(area >= vec2<f32>(1.0)) {
+        return vec4<f32>(pow(background, vec3<f32>(2.2)), 1.0);
+    }
+
+    var row = i32(area.y * ROWS);
+    if row == 2 {
+        return vec4<f32>(pow(background, vec3<f32>(2.2)), 1.0);
+    }
+    if row > 2 {
+        row -= 1;
+    }
+    // Bars a bit thinner than their rows
+    if fract(area.y * ROWS) > 0.8 {
+        return vec4<f32>(pow(background, vec3<f32>(2.2)), 1.0);
+    }
+
+    var bars = display.bars;
+    let bar = bars[row];
+    var color = background;
+    if area.x < bar.x {
+        color = select(vec3<f32>(0.2, 0.7, 0.75), vec3<f32>(0.95, 0.6, 0.2), bar.z > 0.5);
+        if bar.y < 0.5 {
+            color = vec3<f32>(0.9, 0.15, 0.1);
+        }
+    }
+    return vec4<f32>(pow(color, vec3<f32>(2.2)), 1.0);
+}
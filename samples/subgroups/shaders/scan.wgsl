@@ -0,0 +1,148 @@
+struct Params {
+    count: u32,
+    _padding_a: u32,
+    _padding_b: u32,
+    _padding_c: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// One level of the hierarchy and the level above it, which holds a sum per
+// block of this one
+@group(0) @binding(1) var<storage, read_write> values: array<u32>;
+@group(0) @binding(2) var<storage, read_write> sums: array<u32>;
+
+// Each workgroup takes a block of two values per invocation
+const GROUP_SIZE: u32 = 256u;
+const BLOCK: u32 = 512u;
+
+var<workgroup> block: array<u32, 1024>;
+var<workgroup> total: atomic<u32>;
+
+// Past the end reads as zero so partial blocks need no special case
+fn load(index: u32) -> u32 {
+    if index < params.count {
+        return values[index];
+    }
+    return 0u;
+}
+
+fn store(index: u32, value: u32) {
+    if index < params.count {
+        values[index] = value;
+    }
+}
+
+// Halves the live values every step, each invocation adding a pair
+@compute @workgroup_size(256)
+fn cs_reduce_tree(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let base = group.x * BLOCK;
+    block[local] = load(base + local) + load(base + local + GROUP_SIZE);
+    for (var stride = GROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        workgroupBarrier();
+        if local < stride {
+            block[local] += block[local + stride];
+        }
+    }
+    if local == 0u {
+        sums[group.x] = block[0];
+    }
+}
+
+// Every invocation adds straight into one workgroup atomic; fewer barriers,
+// but the adds serialize on the one address
+@compute @workgroup_size(256)
+fn cs_reduce_atomic(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let base = group.x * BLOCK;
+    if local == 0u {
+        atomicStore(&total, 0u);
+    }
+    workgroupBarrier();
+    atomicAdd(&total, load(base + local) + load(base + local + GROUP_SIZE));
+    workgroupBarrier();
+    if local == 0u {
+        sums[group.x] = atomicLoad(&total);
+    }
+}
+
+// Work-efficient exclusive scan: sums up a tree in place, then sweeps back
+// down it handing each left child's sum to its right sibling
+@compute @workgroup_size(256)
+fn cs_scan_blelloch(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let base = group.x * BLOCK;
+    block[local * 2u] = load(base + local * 2u);
+    block[local * 2u + 1u] = load(base + local * 2u + 1u);
+
+    var offset = 1u;
+    for (var threads = BLOCK / 2u; threads > 0u; threads = threads / 2u) {
+        workgroupBarrier();
+        if local < threads {
+            let left = offset * (local * 2u + 1u) - 1u;
+            let right = offset * (local * 2u + 2u) - 1u;
+            block[right] += block[left];
+        }
+        offset *= 2u;
+    }
+    workgroupBarrier();
+    if local == 0u {
+        sums[group.x] = block[BLOCK - 1u];
+        block[BLOCK - 1u] = 0u;
+    }
+    for (var threads = 1u; threads < BLOCK; threads *= 2u) {
+        offset /= 2u;
+        workgroupBarrier();
+        if local < threads {
+            let left = offset * (local * 2u + 1u) - 1u;
+            let right = offset * (local * 2u + 2u) - 1u;
+            let carried = block[left];
+            block[left] = block[right];
+            block[right] += carried;
+        }
+    }
+    workgroupBarrier();
+
+    store(base + local * 2u, block[local * 2u]);
+    store(base + local * 2u + 1u, block[local * 2u + 1u]);
+}
+
+// Every value adds the one a stride back each step, ping-ponging between
+// the two halves of the block array. More adds than Blelloch, half the
+// steps.
+@compute @workgroup_size(256)
+fn cs_scan_hillis_steele(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let base = group.x * BLOCK;
+    let own = vec2<u32>(load(base + local * 2u), load(base + local * 2u + 1u));
+    block[local * 2u] = own.x;
+    block[local * 2u + 1u] = own.y;
+
+    var source = 0u;
+    for (var stride = 1u; stride < BLOCK; stride *= 2u) {
+        workgroupBarrier();
+        for (var i = 0u; i < 2u; i++) {
+            let index = local * 2u + i;
+            var value = block[source + index];
+            if index >= stride {
+                value += block[source + index - stride];
+            }
+            block[BLOCK - source + index] = value;
+        }
+        source = BLOCK - source;
+    }
+    workgroupBarrier();
+
+    // Inclusive so far; taking each value back off makes it exclusive
+    let inclusive = vec2<u32>(block[source + local * 2u], block[source + local * 2u + 1u]);
+    store(base + local * 2u, inclusive.x - own.x);
+    store(base + local * 2u + 1u, inclusive.y - own.y);
+    if local == GROUP_SIZE - 1u {
+        sums[group.x] = inclusive.y;
+    }
+}
+
+// Adds each block's scanned sum from the level above to its values
+@compute @workgroup_size(256)
+fn cs_add_offsets(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) group: vec3<u32>) {
+    let base = group.x * BLOCK;
+    let offset = sums[group.x];
+    store(base + local * 2u, load(base + local * 2u) + offset);
+    store(base + local * 2u + 1u, load(base + local * 2u + 1u) + offset);
+}
@@ -0,0 +1,447 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use framework::font::{glyph_index, packed_glyphs};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, Texture};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+// Gamma space, like most text renderers blend in
+const CANVAS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+// Text, scale in screen pixels per font pixel, color. The odd scales put
+// glyph edges at every possible subpixel offset.
+const LINES: &[(&str, f32, [f32; 4])] = &[
+    ("SUBPIXEL TEXT 0123456789", 1.0, [0.0, 0.0, 0.0, 1.0]),
+    ("SUBPIXEL TEXT 0123456789", 1.35, [1.0, 1.0, 1.0, 1.0]),
+    ("THE QUICK BROWN FOX", 1.7, [0.9, 0.1, 0.1, 1.0]),
+    ("JUMPS OVER THE LAZY DOG", 2.15, [0.1, 0.7, 0.2, 1.0]),
+    ("PER CHANNEL COVERAGE", 2.6, [0.2, 0.3, 0.9, 1.0]),
+    ("NEEDS PER CHANNEL ALPHA", 3.3, [0.6, 0.6, 0.6, 1.0]),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Coverage {
+    Subpixel,
+    Grayscale,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Globals {
+    viewport: [f32; 2],
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Glyph {
+    origin: [f32; 2],
+    scale: f32,
+    glyph: u32,
+    color: [f32; 4],
+}
+
+impl Glyph {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32, 2 => Uint32, 3 => Float32x4];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Glyph>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+fn layout_line(glyphs: &mut Vec<Glyph>, text: &str, origin: [f32; 2], scale: f32, color: [f32; 4]) {
+    for (column, c) in text.chars().enumerate() {
+        glyphs.push(Glyph {
+            origin: [origin[0] + column as f32 * 6.0 * scale, origin[1]],
+            scale,
+            glyph: glyph_index(c) as u32,
+            color,
+        });
+    }
+}
+
+// Without dual-source blending the text shader blends by itself, reading
+// what's underneath from a copy of the canvas. wgpu 0.16 doesn't expose
+// dual-source blending at all (later versions add
+// Features::DUAL_SOURCE_BLENDING), so this is the only path for now.
+struct Canvas {
+    texture: Texture,
+    backdrop: Texture,
+    backdrop_bind_group: BindGroup,
+    blit_bind_group: BindGroup,
+}
+
+impl Canvas {
+    fn new(device: &Device, config: &wgpu::SurfaceConfiguration, layout: &BindGroupLayout) -> Self {
+        let create_texture = |label, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: config.width,
+                    height: config.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: CANVAS_FORMAT,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let texture = create_texture(
+            "Canvas",
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+        );
+        let backdrop = create_texture(
+            "Backdrop",
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        );
+
+        let create_bind_group = |label, texture: &Texture| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                }],
+            })
+        };
+        let backdrop_bind_group = create_bind_group("Backdrop Bind Group", &backdrop);
+        let blit_bind_group = create_bind_group("Blit Bind Group", &texture);
+
+        Self {
+            texture,
+            backdrop,
+            backdrop_bind_group,
+            blit_bind_group,
+        }
+    }
+}
+
+pub struct Renderer {
+    background_pipeline: RenderPipeline,
+    subpixel_pipeline: RenderPipeline,
+    grayscale_pipeline: RenderPipeline,
+    blit_pipeline: RenderPipeline,
+    globals_buffer: Buffer,
+    globals_bind_group: BindGroup,
+    glyph_buffer: Buffer,
+    // Each layer is one draw and sees everything drawn before it
+    layers: Vec<Range<u32>>,
+    texture_layout: BindGroupLayout,
+    canvas: Canvas,
+    coverage: Coverage,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let mut glyphs = Vec::new();
+        let mut y = 20.0;
+        for (text, scale, color) in LINES {
+            // Fractional origins on purpose, the point is subpixel placement
+            layout_line(&mut glyphs, text, [20.3, y], *scale, *color);
+            layout_line(&mut glyphs, text, [500.6, y + 0.5], *scale, *color);
+            y += 10.0 * scale + 8.0;
+        }
+        let first_layer = 0..glyphs.len() as u32;
+        // Translucent and across the lines above, so it has to blend with text
+        layout_line(&mut glyphs, "OVERLAP", [60.0, 40.0], 9.0, [0.8, 0.2, 0.8, 0.5]);
+        let second_layer = first_layer.end..glyphs.len() as u32;
+
+        let glyph_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Glyphs"),
+            contents: bytemuck::cast_slice(&glyphs),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let globals_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Globals"),
+            contents: bytemuck::bytes_of(&Globals::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let font_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Font"),
+            contents: bytemuck::cast_slice(&packed_glyphs()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let globals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Globals Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let globals_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Globals Bind Group"),
+            layout: &globals_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: globals_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: font_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        // Read with textureLoad, so no sampler and no filtering
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let fullscreen_shader = device.create_shader_module(include_wgsl!("shaders/fullscreen.wgsl"));
+        let text_shader = device.create_shader_module(include_wgsl!("shaders/text.wgsl"));
+
+        let background_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Background Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        let text_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Text Pipeline Layout"),
+            bind_group_layouts: &[&globals_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let blit_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label,
+                               layout: &wgpu::PipelineLayout,
+                               shader: &wgpu::ShaderModule,
+                               vertex_buffers: &[wgpu::VertexBufferLayout],
+                               fragment_entry_point,
+                               format| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: vertex_buffers,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: fragment_entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        // Blending happens in the shader
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        let background_pipeline = create_pipeline(
+            "Background Pipeline", &background_layout, &fullscreen_shader, &[], "fs_background", CANVAS_FORMAT,
+        );
+        let subpixel_pipeline = create_pipeline(
+            "Subpixel Pipeline", &text_layout, &text_shader, &[Glyph::desc()], "fs_subpixel", CANVAS_FORMAT,
+        );
+        let grayscale_pipeline = create_pipeline(
+            "Grayscale Pipeline", &text_layout, &text_shader, &[Glyph::desc()], "fs_grayscale", CANVAS_FORMAT,
+        );
+        let blit_pipeline = create_pipeline(
+            "Blit Pipeline",
+            &blit_layout,
+            &fullscreen_shader,
+            &[],
+            if gpu.config.format.is_srgb() { "fs_blit_decode" } else { "fs_blit" },
+            gpu.config.format,
+        );
+
+        let canvas = Canvas::new(device, &gpu.config, &texture_layout);
+
+        Self {
+            background_pipeline,
+            subpixel_pipeline,
+            grayscale_pipeline,
+            blit_pipeline,
+            globals_buffer,
+            globals_bind_group,
+            glyph_buffer,
+            layers: vec![first_layer, second_layer],
+            texture_layout,
+            canvas,
+            coverage: Coverage::Subpixel,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        self.canvas = Canvas::new(&gpu.device, &gpu.config, &self.texture_layout);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::Space),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.coverage = match self.coverage {
+                Coverage::Subpixel => Coverage::Grayscale,
+                Coverage::Grayscale => Coverage::Subpixel,
+            };
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!("{:?} coverage, emulated framebuffer fetch", self.coverage))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        gpu.queue.write_buffer(
+            &self.globals_buffer,
+            0,
+            bytemuck::bytes_of(&Globals {
+                viewport: [gpu.config.width as f32, gpu.config.height as f32],
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let canvas_view = self.canvas.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let text_pipeline = match self.coverage {
+            Coverage::Subpixel => &self.subpixel_pipeline,
+            Coverage::Grayscale => &self.grayscale_pipeline,
+        };
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut background_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Background Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &canvas_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            background_pass.set_pipeline(&self.background_pipeline);
+            background_pass.draw(0..3, 0..1);
+        }
+
+        for layer in &self.layers {
+            // A texture can't be sampled and rendered to in the same pass,
+            // so snapshot it first. This copy is the cost dual-source
+            // blending would save.
+            encoder.copy_texture_to_texture(
+                self.canvas.texture.as_image_copy(),
+                self.canvas.backdrop.as_image_copy(),
+                self.canvas.texture.size(),
+            );
+
+            let mut text_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Text Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &canvas_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            text_pass.set_pipeline(text_pipeline);
+            text_pass.set_bind_group(0, &self.globals_bind_group, &[]);
+            text_pass.set_bind_group(1, &self.canvas.backdrop_bind_group, &[]);
+            text_pass.set_vertex_buffer(0, self.glyph_buffer.slice(..));
+            // Glyph quads within a layer never overlap, so one snapshot
+            // per draw is enough
+            text_pass.draw(0..6, layer.clone());
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.blit_pipeline);
+            render_pass.set_bind_group(0, &self.canvas.blit_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
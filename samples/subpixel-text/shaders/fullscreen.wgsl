@@ -0,0 +1,36 @@
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // One triangle covering the whole screen
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Four vertical bands, so every line of text crosses light, dark and
+// saturated backgrounds
+@fragment
+fn fs_background(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    var bands = array<vec3<f32>, 4>(
+        vec3<f32>(0.95, 0.95, 0.92),
+        vec3<f32>(0.05, 0.05, 0.07),
+        vec3<f32>(0.1, 0.2, 0.6),
+        vec3<f32>(0.9, 0.55, 0.1)
+    );
+    let band = u32(position.x / 240.0) % 4u;
+    return vec4<f32>(bands[band], 1.0);
+}
+
+@group(0) @binding(0)
+var canvas: texture_2d<f32>;
+
+@fragment
+fn fs_blit(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(canvas, vec2<i32>(position.xy), 0);
+}
+
+// The canvas holds gamma encoded values, an sRGB surface would encode
+// them a second time on write
+@fragment
+fn fs_blit_decode(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureLoad(canvas, vec2<i32>(position.xy), 0);
+    return vec4<f32>(pow(color.rgb, vec3<f32>(2.2)), color.a);
+}
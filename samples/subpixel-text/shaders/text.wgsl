@@ -0,0 +1,115 @@
+struct Globals {
+    viewport: vec2<f32>,
+    padding: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+// 5x7 glyphs, see core/font.rs for the packing
+@group(0) @binding(1)
+var<storage, read> font: array<vec2<u32>>;
+
+// A copy of the canvas taken right before this draw, standing in for
+// framebuffer fetch
+@group(1) @binding(0)
+var backdrop: texture_2d<f32>;
+
+struct GlyphInput {
+    @location(0) origin: vec2<f32>,
+    @location(1) scale: f32,
+    @location(2) glyph: u32,
+    @location(3) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // In font pixels, y pointing down
+    @location(0) font_position: vec2<f32>,
+    @location(1) @interpolate(flat) glyph: u32,
+    @location(2) @interpolate(flat) scale: f32,
+    @location(3) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: GlyphInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0)
+    );
+    // The 6x8 cell shifted to leave half a font pixel around the 5x7 glyph
+    // on every side. At scales of 1 and up that covers every pixel with a
+    // stripe touching the glyph, and neighbouring cells still don't overlap.
+    let font_position = corners[vertex_index] * vec2<f32>(6.0, 8.0) - 0.5;
+    let pixel = instance.origin + font_position * instance.scale;
+    let ndc = pixel / globals.viewport * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.font_position = font_position;
+    out.glyph = instance.glyph;
+    out.scale = instance.scale;
+    out.color = instance.color;
+    return out;
+}
+
+fn covered(glyph: u32, position: vec2<f32>) -> f32 {
+    if position.x < 0.0 || position.y < 0.0 {
+        return 0.0;
+    }
+    let cell = vec2<u32>(position);
+    if cell.x >= 5u || cell.y >= 7u {
+        return 0.0;
+    }
+    let rows = font[glyph];
+    let word = select(rows.x, rows.y, cell.y >= 4u);
+    let bits = word >> (8u * (cell.y % 4u));
+    return f32((bits >> (4u - cell.x)) & 1u);
+}
+
+// How much of a `size` footprint around `center` the glyph covers, both in
+// font pixels
+fn coverage(glyph: u32, center: vec2<f32>, size: vec2<f32>) -> f32 {
+    var sum = 0.0;
+    for (var y = 0; y < 4; y++) {
+        for (var x = 0; x < 4; x++) {
+            let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / 4.0 - 0.5;
+            sum += covered(glyph, center + offset * size);
+        }
+    }
+    return sum / 16.0;
+}
+
+// What the blend unit would do with dual-source blending:
+// src * src1 + dst * (1 - src1), with a separate alpha per channel
+fn blend(position: vec4<f32>, color: vec3<f32>, alpha: vec3<f32>) -> vec4<f32> {
+    let dst = textureLoad(backdrop, vec2<i32>(position.xy), 0).rgb;
+    return vec4<f32>(color * alpha + dst * (1.0 - alpha), 1.0);
+}
+
+// LCD panels split every pixel into R, G and B stripes a third of a pixel
+// wide, each one gets the coverage of its own stripe
+@fragment
+fn fs_subpixel(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = 1.0 / in.scale;
+    let stripe = vec2<f32>(pixel / 3.0, pixel);
+    let alpha = vec3<f32>(
+        coverage(in.glyph, in.font_position - vec2<f32>(stripe.x, 0.0), stripe),
+        coverage(in.glyph, in.font_position, stripe),
+        coverage(in.glyph, in.font_position + vec2<f32>(stripe.x, 0.0), stripe)
+    );
+    return blend(in.clip_position, in.color.rgb, alpha * in.color.a);
+}
+
+@fragment
+fn fs_grayscale(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = 1.0 / in.scale;
+    let alpha = coverage(in.glyph, in.font_position, vec2<f32>(pixel));
+    return blend(in.clip_position, in.color.rgb, vec3<f32>(alpha * in.color.a));
+}
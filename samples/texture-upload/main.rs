@@ -0,0 +1,8 @@
+mod renderer;
+mod strategy;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("texture-upload");
+}
@@ -0,0 +1,375 @@
+use std::time::Instant;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, BindGroup, Buffer, RenderPipeline, Texture};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::strategy::{Strategy, Uploader, HEIGHT, TEXTURE_BYTES, WIDTH};
+
+// Frames each strategy gets during a sweep, the first few of which are
+// thrown away while buffers get created and caches warm up
+const SWEEP_FRAMES: u32 = 60;
+const SWEEP_WARMUP: u32 = 10;
+// Rows the content moves per frame
+const SCROLL_SPEED: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Uniforms {
+    scale: [f32; 2],
+    _padding: [f32; 2],
+}
+
+struct Sweep {
+    index: usize,
+    frame: u32,
+    total_ms: f32,
+    results: Vec<(Strategy, f32)>,
+}
+
+// Soft diagonal stripes over a colour gradient, with a grid so tearing or
+// stale rows would stand out
+fn source_image() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(TEXTURE_BYTES as usize);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let stripes = (((x + y) as f32 / 40.0).sin() * 0.5 + 0.5) * 80.0;
+            let grid = if x % 240 < 4 || y % 240 < 4 { 255 } else { 0 };
+            pixels.extend([
+                (x * 255 / WIDTH) as u8 / 2 + stripes as u8,
+                (y * 255 / HEIGHT) as u8 / 2 + stripes as u8,
+                grid,
+                255,
+            ]);
+        }
+    }
+    pixels
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    texture: Texture,
+    source: Vec<u8>,
+    uploader: Uploader,
+    strategy: Strategy,
+    // Set when switching to Strategy::Once
+    uploaded_once: bool,
+    // Wait for the GPU after each upload, so the time includes the copy
+    // itself and not just handing it to the driver
+    sync: bool,
+    scroll: u32,
+    upload_ms: [Option<f32>; Strategy::ALL.len()],
+    frame_ms: Option<f32>,
+    last_frame: Instant,
+    sweep: Option<Sweep>,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Streamed"),
+            size: wgpu::Extent3d {
+                width: WIDTH,
+                height: HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniforms"),
+            contents: bytemuck::bytes_of(&Uniforms::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Streamed Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Display Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Display Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/display.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: gpu.config.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let uploader = Uploader::new(gpu, &texture);
+
+        Self {
+            render_pipeline,
+            uniform_buffer,
+            bind_group,
+            texture,
+            source: source_image(),
+            uploader,
+            strategy: Strategy::WriteTexture,
+            uploaded_once: false,
+            sync: false,
+            scroll: 0,
+            upload_ms: [None; Strategy::ALL.len()],
+            frame_ms: None,
+            last_frame: Instant::now(),
+            sweep: None,
+        }
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            match key {
+                VirtualKeyCode::Tab if self.sweep.is_none() => self.select(self.strategy.next()),
+                VirtualKeyCode::S => {
+                    self.sync = !self.sync;
+                    self.upload_ms = [None; Strategy::ALL.len()];
+                }
+                VirtualKeyCode::B if self.sweep.is_none() => {
+                    self.sweep = Some(Sweep {
+                        index: 0,
+                        frame: 0,
+                        total_ms: 0.0,
+                        results: Vec::new(),
+                    });
+                    self.select(Strategy::ALL[0]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let upload_ms = self.upload_ms[self.strategy as usize].unwrap_or(0.0);
+        let frame_ms = self.frame_ms.unwrap_or(0.0);
+        // A whole texture per frame, except for Once
+        let gb_per_second = if self.strategy == Strategy::Once { 0.0 } else { TEXTURE_BYTES as f32 / frame_ms / 1.0e6 };
+        Some(format!(
+            "{:?}{}{}, upload {:.2} ms, frame {:.2} ms, {:.2} GB/s, {} ring buffers",
+            self.strategy,
+            if self.sync { ", synced" } else { "" },
+            if self.sweep.is_some() { ", sweeping" } else { "" },
+            upload_ms,
+            frame_ms,
+            gb_per_second,
+            self.uploader.staging_buffers(),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        let now = Instant::now();
+        let frame_ms = (now - self.last_frame).as_secs_f32() * 1000.0;
+        self.frame_ms = Some(self.frame_ms.map_or(frame_ms, |average| average * 0.95 + frame_ms * 0.05));
+        self.last_frame = now;
+
+        // Its own submission, so the time covers exactly the upload
+        let upload_start = Instant::now();
+        if self.strategy != Strategy::Once || !self.uploaded_once {
+            self.scroll = (self.scroll + SCROLL_SPEED) % HEIGHT;
+            let mut encoder =
+                gpu.device.create_command_encoder(
+                    &wgpu::CommandEncoderDescriptor {
+                        label: Some("Upload Encoder"),
+                    },
+                );
+            self.uploader.upload(self.strategy, gpu, &mut encoder, &self.texture, &self.source, self.scroll);
+            gpu.queue.submit(std::iter::once(encoder.finish()));
+            self.uploader.submitted();
+            self.uploaded_once = true;
+            if self.sync || self.sweep.is_some() {
+                gpu.device.poll(wgpu::Maintain::Wait);
+            }
+        }
+        let upload_ms = upload_start.elapsed().as_secs_f32() * 1000.0;
+        let average = &mut self.upload_ms[self.strategy as usize];
+        *average = Some(average.map_or(upload_ms, |average| average * 0.95 + upload_ms * 0.05));
+        self.advance_sweep(upload_ms);
+
+        // Fit the texture into the window without stretching it
+        let ratio = gpu.aspect_ratio() / (WIDTH as f32 / HEIGHT as f32);
+        let scale = if ratio > 1.0 { [ratio, 1.0] } else { [1.0, 1.0 / ratio] };
+        gpu.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&Uniforms {
+                scale,
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl Renderer {
+    fn select(&mut self, strategy: Strategy) {
+        self.strategy = strategy;
+        self.uploaded_once = false;
+    }
+
+    // Times every strategy that streams with the GPU waited on, prints a
+    // table and leaves the fastest selected
+    fn advance_sweep(&mut self, upload_ms: f32) {
+        let Some(sweep) = &mut self.sweep else {
+            return;
+        };
+
+        sweep.frame += 1;
+        if sweep.frame > SWEEP_WARMUP {
+            sweep.total_ms += upload_ms;
+        }
+        if sweep.frame < SWEEP_FRAMES {
+            return;
+        }
+
+        sweep.results.push((self.strategy, sweep.total_ms / (SWEEP_FRAMES - SWEEP_WARMUP) as f32));
+        sweep.index += 1;
+        sweep.frame = 0;
+        sweep.total_ms = 0.0;
+        // Once doesn't stream, there's nothing to compare
+        if Strategy::ALL[sweep.index] != Strategy::Once {
+            let next = Strategy::ALL[sweep.index];
+            self.select(next);
+            return;
+        }
+
+        let results = std::mem::take(&mut sweep.results);
+        self.sweep = None;
+        println!("{:<18} {:>10} {:>10}", "strategy", "ms", "GB/s");
+        for (strategy, ms) in &results {
+            println!("{:<18} {:>10.2} {:>10.2}", format!("{:?}", strategy), ms, TEXTURE_BYTES as f32 / ms / 1.0e6);
+        }
+        let fastest = results.iter().min_by(|(_, a), (_, b)| a.total_cmp(b)).unwrap().0;
+        println!("fastest: {:?}", fastest);
+        self.select(fastest);
+    }
+}
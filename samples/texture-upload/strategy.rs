@@ -0,0 +1,161 @@
+use framework::upload::TextureStreamer;
+use framework::Gpu;
+use wgpu::{Buffer, CommandEncoder, Texture};
+
+// 4K, streamed in full every frame
+pub const WIDTH: u32 = 3840;
+pub const HEIGHT: u32 = 2160;
+// 15360, a multiple of 256 already, so buffer copies need no row padding
+pub const BYTES_PER_ROW: u32 = WIDTH * 4;
+pub const TEXTURE_BYTES: u64 = BYTES_PER_ROW as u64 * HEIGHT as u64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    // queue.write_texture, wgpu copies into its staging memory
+    WriteTexture,
+    // queue.write_buffer into one persistent buffer, then a buffer to
+    // texture copy. Same copies as write_texture plus an extra GPU one.
+    StagingCopy,
+    // A fresh buffer mapped at creation every frame, written directly
+    MappedAtCreation,
+    // framework::upload::TextureStreamer, reusing mapped buffers
+    MappedRing,
+    // Uploaded once when selected, the baseline with no streaming at all
+    Once,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 5] = [
+        Strategy::WriteTexture,
+        Strategy::StagingCopy,
+        Strategy::MappedAtCreation,
+        Strategy::MappedRing,
+        Strategy::Once,
+    ];
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+// The frame's content is the source image scrolled up by `scroll` rows, so
+// every strategy has to move all of it every frame and it's visible when
+// one doesn't
+pub struct Uploader {
+    staging: Buffer,
+    streamer: TextureStreamer,
+}
+
+impl Uploader {
+    pub fn new(gpu: &Gpu, texture: &Texture) -> Self {
+        Self {
+            staging: gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Staging Copy"),
+                size: TEXTURE_BYTES,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            streamer: TextureStreamer::new(texture),
+        }
+    }
+
+    pub fn staging_buffers(&self) -> usize {
+        self.streamer.staging_buffers()
+    }
+
+    pub fn upload(
+        &mut self,
+        strategy: Strategy,
+        gpu: &Gpu,
+        encoder: &mut CommandEncoder,
+        texture: &Texture,
+        source: &[u8],
+        scroll: u32,
+    ) {
+        // Rows scroll..HEIGHT go to the top of the texture, 0..scroll after them
+        let split = (scroll * BYTES_PER_ROW) as usize;
+        let (wrapped, top) = source.split_at(split);
+
+        match strategy {
+            Strategy::WriteTexture | Strategy::Once => {
+                for (data, y, rows) in [(top, 0, HEIGHT - scroll), (wrapped, HEIGHT - scroll, scroll)] {
+                    if rows == 0 {
+                        continue;
+                    }
+                    gpu.queue.write_texture(
+                        wgpu::ImageCopyTexture {
+                            texture,
+                            mip_level: 0,
+                            origin: wgpu::Origin3d { x: 0, y, z: 0 },
+                            aspect: wgpu::TextureAspect::All,
+                        },
+                        data,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(BYTES_PER_ROW),
+                            rows_per_image: Some(rows),
+                        },
+                        wgpu::Extent3d {
+                            width: WIDTH,
+                            height: rows,
+                            depth_or_array_layers: 1,
+                        },
+                    );
+                }
+            }
+            Strategy::StagingCopy => {
+                gpu.queue.write_buffer(&self.staging, 0, top);
+                if !wrapped.is_empty() {
+                    gpu.queue.write_buffer(&self.staging, top.len() as u64, wrapped);
+                }
+                copy_to_texture(encoder, &self.staging, texture);
+            }
+            Strategy::MappedAtCreation => {
+                let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Mapped At Creation"),
+                    size: TEXTURE_BYTES,
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                });
+                {
+                    let mut mapped = buffer.slice(..).get_mapped_range_mut();
+                    mapped[..top.len()].copy_from_slice(top);
+                    mapped[top.len()..].copy_from_slice(wrapped);
+                }
+                buffer.unmap();
+                // Dropping it right away is fine, wgpu keeps it alive until
+                // the copy is done
+                copy_to_texture(encoder, &buffer, texture);
+            }
+            Strategy::MappedRing => {
+                let padded = self.streamer.padded_bytes_per_row() as usize;
+                self.streamer.upload(&gpu.device, encoder, texture, |mapped| {
+                    let rows = top.chunks(BYTES_PER_ROW as usize).chain(wrapped.chunks(BYTES_PER_ROW as usize));
+                    for (row, data) in mapped.chunks_mut(padded).zip(rows) {
+                        row[..data.len()].copy_from_slice(data);
+                    }
+                });
+            }
+        }
+    }
+
+    // Call after submitting the encoder passed to upload
+    pub fn submitted(&mut self) {
+        self.streamer.map_submitted();
+    }
+}
+
+fn copy_to_texture(encoder: &mut CommandEncoder, buffer: &Buffer, texture: &Texture) {
+    encoder.copy_buffer_to_texture(
+        wgpu::ImageCopyBuffer {
+            buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(BYTES_PER_ROW),
+                rows_per_image: Some(HEIGHT),
+            },
+        },
+        texture.as_image_copy(),
+        texture.size(),
+    );
+}
@@ -0,0 +1,8 @@
+mod renderer;
+mod texture;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("textured-quad");
+}
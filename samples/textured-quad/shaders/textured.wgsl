@@ -0,0 +1,24 @@
+struct VertexInput {
+  @location(0) position: vec3<f32>,
+  @location(1) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  return VertexOutput(vec4(in.position, 1.0), in.tex_coords);
+}
+
+@group(0) @binding(0)
+var quad_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var quad_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  return textureSample(quad_texture, quad_sampler, in.tex_coords);
+}
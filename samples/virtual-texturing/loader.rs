@@ -0,0 +1,74 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use crate::page_cache::{PageId, PAGES_AT_MIP_0, PAGE_BORDER, PAGE_SIZE, SLOT_SIZE};
+
+const MAX_ITERATIONS: u32 = 256;
+
+// Stands in for reading and decoding tiles from disk: it's slow, so it runs
+// on its own thread and the render loop only ever picks up finished pages
+pub struct PageLoader {
+    requests: Sender<PageId>,
+    loaded: Receiver<(PageId, Vec<u8>)>,
+}
+
+impl PageLoader {
+    pub fn spawn() -> Self {
+        let (requests, incoming) = channel::<PageId>();
+        let (outgoing, loaded) = channel();
+
+        thread::spawn(move || {
+            for page in incoming {
+                if outgoing.send((page, generate_page(page))).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { requests, loaded }
+    }
+
+    pub fn request(&self, page: PageId) {
+        self.requests.send(page).unwrap();
+    }
+
+    pub fn finished(&self) -> impl Iterator<Item = (PageId, Vec<u8>)> + '_ {
+        self.loaded.try_iter()
+    }
+}
+
+// The virtual texture is the Mandelbrot set: zoomed all the way in it is
+// 65536x65536 texels, which would take 16GiB as a plain RGBA8 texture
+pub fn generate_page(page: PageId) -> Vec<u8> {
+    let pages = (PAGES_AT_MIP_0 >> page.mip) as f64;
+    let texel_size = 1.0 / (pages * PAGE_SIZE as f64);
+
+    let mut pixels = Vec::with_capacity((SLOT_SIZE * SLOT_SIZE * 4) as usize);
+    for y in 0..SLOT_SIZE {
+        for x in 0..SLOT_SIZE {
+            // Border texels repeat the neighbouring pages' content so
+            // bilinear filtering doesn't bleed in unrelated atlas slots
+            let u = (page.x as f64 / pages) + (x as f64 - PAGE_BORDER as f64 + 0.5) * texel_size;
+            let v = (page.y as f64 / pages) + (y as f64 - PAGE_BORDER as f64 + 0.5) * texel_size;
+            pixels.extend_from_slice(&mandelbrot(-2.2 + u * 3.0, -1.5 + v * 3.0));
+        }
+    }
+    pixels
+}
+
+fn mandelbrot(c_re: f64, c_im: f64) -> [u8; 4] {
+    let (mut re, mut im) = (0.0, 0.0);
+    for i in 0..MAX_ITERATIONS {
+        if re * re + im * im > 4.0 {
+            let t = i as f64 / MAX_ITERATIONS as f64;
+            let r = (9.0 * (1.0 - t) * t * t * t * 255.0) as u8;
+            let g = (15.0 * (1.0 - t) * (1.0 - t) * t * t * 255.0) as u8;
+            let b = (8.5 * (1.0 - t) * (1.0 - t) * (1.0 - t) * t * 255.0) as u8;
+            return [r, g, b, 255];
+        }
+        let next_re = re * re - im * im + c_re;
+        im = 2.0 * re * im + c_im;
+        re = next_re;
+    }
+    [0, 0, 0, 255]
+}
@@ -0,0 +1,9 @@
+mod loader;
+mod page_cache;
+mod renderer;
+
+use crate::renderer::Renderer;
+
+fn main() {
+    framework::run_sample::<Renderer>("virtual-texturing");
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+
+use wgpu::{Queue, Texture};
+
+// Must match the constants in shaders/virtual_texture.wgsl
+pub const PAGE_SIZE: u32 = 128;
+pub const PAGE_BORDER: u32 = 1;
+pub const SLOT_SIZE: u32 = PAGE_SIZE + 2 * PAGE_BORDER;
+pub const ATLAS_SLOTS: u32 = 16;
+pub const ATLAS_SIZE: u32 = ATLAS_SLOTS * SLOT_SIZE;
+pub const PAGES_AT_MIP_0: u32 = 512;
+pub const MAX_MIP: u32 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PageId {
+    pub mip: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl PageId {
+    // Inverse of the packing in fs_feedback
+    pub fn unpack(packed: u32) -> Option<Self> {
+        if packed == u32::MAX {
+            return None;
+        }
+        Some(Self {
+            mip: packed >> 20,
+            x: packed & 0x3ff,
+            y: (packed >> 10) & 0x3ff,
+        })
+    }
+
+    pub fn parent(&self) -> Option<Self> {
+        (self.mip < MAX_MIP).then(|| Self {
+            mip: self.mip + 1,
+            x: self.x / 2,
+            y: self.y / 2,
+        })
+    }
+}
+
+// The single page covering the whole texture, never evicted so the shader
+// always has something to fall back to
+pub const ROOT_PAGE: PageId = PageId { mip: MAX_MIP, x: 0, y: 0 };
+const ROOT_SLOT: usize = 0;
+
+struct Slot {
+    page: PageId,
+    last_used: u64,
+}
+
+// Tracks which virtual page lives in which atlas slot and keeps the
+// indirection texture in sync with it
+pub struct PageCache {
+    slots: Vec<Option<Slot>>,
+    resident: HashMap<PageId, usize>,
+    frame: u64,
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self {
+            slots: (0..ATLAS_SLOTS * ATLAS_SLOTS).map(|_| None).collect(),
+            resident: HashMap::new(),
+            frame: 0,
+        }
+    }
+}
+
+impl PageCache {
+    pub fn resident_count(&self) -> usize {
+        self.resident.len()
+    }
+
+    // Marks the pages seen in the latest feedback as used and returns the
+    // ones that still have to be loaded, coarsest first
+    pub fn process_feedback(&mut self, requested: &HashSet<PageId>) -> Vec<PageId> {
+        self.frame += 1;
+
+        // A page's ancestors are what the shader falls back to while it loads
+        let mut needed = HashSet::new();
+        for page in requested {
+            let mut current = Some(*page);
+            while let Some(page) = current {
+                if !needed.insert(page) {
+                    break;
+                }
+                current = page.parent();
+            }
+        }
+
+        let mut missing = Vec::new();
+        for page in needed {
+            match self.resident.get(&page) {
+                Some(&slot) => self.slots[slot].as_mut().unwrap().last_used = self.frame,
+                None => missing.push(page),
+            }
+        }
+        missing.sort_by_key(|page| std::cmp::Reverse(page.mip));
+        missing
+    }
+
+    pub fn insert(
+        &mut self,
+        queue: &Queue,
+        atlas: &Texture,
+        indirection: &Texture,
+        page: PageId,
+        pixels: &[u8],
+    ) {
+        if self.resident.contains_key(&page) {
+            return;
+        }
+        let Some(slot) = self.find_slot(page) else {
+            // Every slot is in use this frame; the feedback will ask again
+            return;
+        };
+
+        if let Some(old) = self.slots[slot].take() {
+            self.resident.remove(&old.page);
+            write_indirection(queue, indirection, old.page, [0, 0, 0, 0]);
+        }
+
+        let slot_x = slot as u32 % ATLAS_SLOTS;
+        let slot_y = slot as u32 / ATLAS_SLOTS;
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: slot_x * SLOT_SIZE,
+                    y: slot_y * SLOT_SIZE,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * SLOT_SIZE),
+                rows_per_image: Some(SLOT_SIZE),
+            },
+            wgpu::Extent3d {
+                width: SLOT_SIZE,
+                height: SLOT_SIZE,
+                depth_or_array_layers: 1,
+            },
+        );
+        write_indirection(queue, indirection, page, [slot_x as u8, slot_y as u8, 1, 0]);
+
+        self.slots[slot] = Some(Slot {
+            page,
+            last_used: self.frame,
+        });
+        self.resident.insert(page, slot);
+    }
+
+    fn find_slot(&self, page: PageId) -> Option<usize> {
+        if page == ROOT_PAGE {
+            return Some(ROOT_SLOT);
+        }
+
+        let free = (ROOT_SLOT + 1..self.slots.len()).find(|&index| self.slots[index].is_none());
+        if free.is_some() {
+            return free;
+        }
+
+        // Least recently used, but never something the current frame needs
+        self.slots
+            .iter()
+            .enumerate()
+            .skip(ROOT_SLOT + 1)
+            .filter_map(|(index, slot)| slot.as_ref().map(|slot| (index, slot.last_used)))
+            .filter(|&(_, last_used)| last_used < self.frame)
+            .min_by_key(|&(_, last_used)| last_used)
+            .map(|(index, _)| index)
+    }
+}
+
+fn write_indirection(queue: &Queue, indirection: &Texture, page: PageId, entry: [u8; 4]) {
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: indirection,
+            mip_level: page.mip,
+            origin: wgpu::Origin3d {
+                x: page.x,
+                y: page.y,
+                z: 0,
+            },
+            aspect: wgpu::TextureAspect::All,
+        },
+        &entry,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4),
+            rows_per_image: Some(1),
+        },
+        wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+}
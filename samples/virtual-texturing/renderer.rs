@@ -0,0 +1,455 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use framework::{Gpu, Sample};
+use wgpu::{include_wgsl, Device, RenderPipeline, SurfaceConfiguration, Buffer, BindGroup, Texture, TextureView};
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+
+use crate::loader::{generate_page, PageLoader};
+use crate::page_cache::{PageCache, PageId, ATLAS_SIZE, MAX_MIP, PAGES_AT_MIP_0, ROOT_PAGE};
+
+// Must match FEEDBACK_SCALE in shaders/virtual_texture.wgsl
+const FEEDBACK_SCALE: u32 = 8;
+// Pages handed to the loader thread but not back yet
+const MAX_IN_FLIGHT: usize = 16;
+// Roughly one screen pixel per virtual texel on a 1080p window
+const MIN_EXTENT: f32 = 0.008;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ViewUniform {
+    center: [f32; 2],
+    extent: [f32; 2],
+}
+
+// The feedback target and the buffer it is read back through
+struct Feedback {
+    view: TextureView,
+    texture: Texture,
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    // Set by the map_async callback once the buffer can be read
+    ready: Arc<AtomicBool>,
+    pending: bool,
+}
+
+impl Feedback {
+    fn new(device: &Device, config: &SurfaceConfiguration) -> Self {
+        let width = (config.width / FEEDBACK_SCALE).max(1);
+        let height = (config.height / FEEDBACK_SCALE).max(1);
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Feedback"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Uint,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        // Texture to buffer copies need rows aligned to 256 bytes
+        let padded_bytes_per_row = wgpu::util::align_to(width * 4, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Feedback Readback"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture,
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            ready: Arc::new(AtomicBool::new(false)),
+            pending: false,
+        }
+    }
+
+    fn read(&mut self) -> HashSet<PageId> {
+        let mut pages = HashSet::new();
+        {
+            let data = self.buffer.slice(..).get_mapped_range();
+            for row in data.chunks(self.padded_bytes_per_row as usize) {
+                let texels: &[u32] = bytemuck::cast_slice(&row[..(self.width * 4) as usize]);
+                pages.extend(texels.iter().filter_map(|&texel| PageId::unpack(texel)));
+            }
+        }
+        self.buffer.unmap();
+        self.ready.store(false, Ordering::Release);
+        self.pending = false;
+        pages
+    }
+}
+
+pub struct Renderer {
+    render_pipeline: RenderPipeline,
+    feedback_pipeline: RenderPipeline,
+    view_buffer: Buffer,
+    bind_group: BindGroup,
+    atlas: Texture,
+    indirection: Texture,
+    feedback: Feedback,
+    cache: PageCache,
+    loader: PageLoader,
+    in_flight: HashSet<PageId>,
+    center: [f32; 2],
+    // Half the visible height in virtual texture coordinates
+    zoom: f32,
+}
+
+impl Sample for Renderer {
+    fn init(gpu: &Gpu) -> Self {
+        let device = &gpu.device;
+
+        // The only memory the virtual texture really occupies: 256 pages
+        let atlas = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Page Atlas"),
+            size: wgpu::Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        // Zero-initialized, so every page starts out as not resident
+        let indirection = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Indirection"),
+            size: wgpu::Extent3d {
+                width: PAGES_AT_MIP_0,
+                height: PAGES_AT_MIP_0,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: MAX_MIP + 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Uint,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("View"),
+            contents: bytemuck::bytes_of(&ViewUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Virtual Texture Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Virtual Texture Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: view_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &indirection.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(
+                        &atlas.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(include_wgsl!("shaders/virtual_texture.wgsl"));
+
+        let render_pipeline_layout = device
+            .create_pipeline_layout(
+                &wgpu::PipelineLayoutDescriptor {
+                    label: Some("Render Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                },
+            );
+
+        let create_pipeline = |label, entry_point, format| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        };
+
+        let render_pipeline = create_pipeline("Render Pipeline", "fs_main", gpu.config.format);
+        let feedback_pipeline = create_pipeline("Feedback Pipeline", "fs_feedback", wgpu::TextureFormat::R32Uint);
+
+        let feedback = Feedback::new(device, &gpu.config);
+
+        let mut cache = PageCache::default();
+        // Loaded up front so there is never a frame without any texture data
+        cache.insert(&gpu.queue, &atlas, &indirection, ROOT_PAGE, &generate_page(ROOT_PAGE));
+
+        Self {
+            render_pipeline,
+            feedback_pipeline,
+            view_buffer,
+            bind_group,
+            atlas,
+            indirection,
+            feedback,
+            cache,
+            loader: PageLoader::spawn(),
+            in_flight: HashSet::new(),
+            center: [0.5, 0.5],
+            zoom: 0.5,
+        }
+    }
+
+    fn reinit_surface_resources(&mut self, gpu: &Gpu) {
+        // Drops any readback still in flight along with the old buffer
+        self.feedback = Feedback::new(&gpu.device, &gpu.config);
+    }
+
+    fn update(&mut self, event: &WindowEvent) {
+        if let WindowEvent::KeyboardInput {
+            input: KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            },
+            ..
+        } = event
+        {
+            self.key_pressed(*key);
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        Some(format!(
+            "{} pages resident, {} loading",
+            self.cache.resident_count(),
+            self.in_flight.len(),
+        ))
+    }
+
+    fn render(&mut self, gpu: &Gpu, view: &wgpu::TextureView) {
+        self.stream_pages(gpu);
+
+        let aspect = gpu.aspect_ratio();
+        gpu.queue.write_buffer(
+            &self.view_buffer,
+            0,
+            bytemuck::bytes_of(&ViewUniform {
+                center: self.center,
+                extent: [self.zoom * aspect, self.zoom],
+            }),
+        );
+
+        let mut encoder =
+            gpu.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                },
+            );
+
+        // Only one readback at a time; the feedback is a few frames late,
+        // which is fine since the fallback mips cover the gap
+        let record_feedback = !self.feedback.pending;
+        if record_feedback {
+            {
+                let mut feedback_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Feedback Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self.feedback.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                feedback_pass.set_pipeline(&self.feedback_pipeline);
+                feedback_pass.set_bind_group(0, &self.bind_group, &[]);
+                feedback_pass.draw(0..3, 0..1);
+            }
+
+            encoder.copy_texture_to_buffer(
+                self.feedback.texture.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &self.feedback.buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(self.feedback.padded_bytes_per_row),
+                        rows_per_image: Some(self.feedback.height),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: self.feedback.width,
+                    height: self.feedback.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        {
+            let mut render_pass = encoder.begin_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(
+                        wgpu::RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(
+                                    wgpu::Color::BLACK,
+                                ),
+                                store: true,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                },
+            );
+
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        // submit will accept anything that implements IntoIter
+        gpu.queue
+            .submit(std::iter::once(encoder.finish()));
+
+        if record_feedback {
+            let ready = self.feedback.ready.clone();
+            self.feedback.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                if result.is_ok() {
+                    ready.store(true, Ordering::Release);
+                }
+            });
+            self.feedback.pending = true;
+        }
+    }
+}
+
+impl Renderer {
+    fn key_pressed(&mut self, key: VirtualKeyCode) {
+        let step = self.zoom * 0.25;
+        match key {
+            VirtualKeyCode::W | VirtualKeyCode::Up => self.center[1] -= step,
+            VirtualKeyCode::S | VirtualKeyCode::Down => self.center[1] += step,
+            VirtualKeyCode::A | VirtualKeyCode::Left => self.center[0] -= step,
+            VirtualKeyCode::D | VirtualKeyCode::Right => self.center[0] += step,
+            VirtualKeyCode::Q => self.zoom = (self.zoom * 1.25).min(1.0),
+            VirtualKeyCode::E => self.zoom = (self.zoom / 1.25).max(MIN_EXTENT),
+            _ => {}
+        }
+    }
+
+    fn stream_pages(&mut self, gpu: &Gpu) {
+        gpu.device.poll(wgpu::Maintain::Poll);
+
+        if self.feedback.pending && self.feedback.ready.load(Ordering::Acquire) {
+            let requested = self.feedback.read();
+            for page in self.cache.process_feedback(&requested) {
+                if self.in_flight.len() >= MAX_IN_FLIGHT {
+                    break;
+                }
+                if self.in_flight.insert(page) {
+                    self.loader.request(page);
+                }
+            }
+        }
+
+        for (page, pixels) in self.loader.finished() {
+            self.in_flight.remove(&page);
+            self.cache.insert(&gpu.queue, &self.atlas, &self.indirection, page, &pixels);
+        }
+    }
+}
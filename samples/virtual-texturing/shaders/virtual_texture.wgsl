@@ -0,0 +1,94 @@
+// Must match the constants in page_cache.rs and renderer.rs
+const VIRTUAL_SIZE: f32 = 65536.0;
+const PAGE_SIZE: f32 = 128.0;
+const PAGE_BORDER: f32 = 1.0;
+const SLOT_SIZE: f32 = 130.0;
+const ATLAS_SIZE: f32 = 2080.0;
+const MAX_MIP: u32 = 9u;
+const PAGES_AT_MIP_0: u32 = 512u;
+// The feedback target is this many times smaller than the screen
+const FEEDBACK_SCALE: f32 = 8.0;
+
+struct View {
+  // Both in virtual texture coordinates, 0..1 covers the whole texture
+  center: vec2<f32>,
+  extent: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> view: View;
+// One texel per virtual page and one mip per virtual mip. rg is the atlas
+// slot the page lives in, b is 1 while the page is resident.
+@group(0) @binding(1)
+var indirection: texture_2d<u32>;
+@group(0) @binding(2)
+var atlas: texture_2d<f32>;
+@group(0) @binding(3)
+var atlas_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var pos = array<vec2<f32>, 3>(
+    vec2(-1.0, -1.0),
+    vec2(3.0, -1.0),
+    vec2(-1.0, 3.0)
+  );
+  let p = pos[index];
+  return VertexOutput(vec4(p, 0.0, 1.0), view.center + vec2(p.x, -p.y) * view.extent);
+}
+
+// The mip the hardware would pick for a texture of VIRTUAL_SIZE, from the
+// derivatives of uv * VIRTUAL_SIZE. The entry points take those themselves:
+// naga's GLSL output puts helpers in the vertex shader too, where dpdx
+// doesn't compile.
+fn mip_level(dx: vec2<f32>, dy: vec2<f32>) -> f32 {
+  let footprint = max(length(dx), length(dy));
+  return clamp(log2(footprint), 0.0, f32(MAX_MIP));
+}
+
+fn outside(uv: vec2<f32>) -> bool {
+  return any(uv < vec2(0.0)) || any(uv >= vec2(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // Derivatives have to be taken before any divergent branch
+  let texels = in.uv * VIRTUAL_SIZE;
+  let wanted_mip = u32(mip_level(dpdx(texels), dpdy(texels)));
+  if outside(in.uv) {
+    return vec4(0.05, 0.05, 0.05, 1.0);
+  }
+
+  // Fall back to coarser mips until we hit a resident page. The coarsest
+  // page is always resident, so this always finds something.
+  for (var mip = wanted_mip; mip <= MAX_MIP; mip++) {
+    let pages = f32(PAGES_AT_MIP_0 >> mip);
+    let page_uv = in.uv * pages;
+    let entry = textureLoad(indirection, vec2<u32>(page_uv), i32(mip));
+    if entry.b != 0u {
+      let texel = vec2<f32>(entry.rg) * SLOT_SIZE + PAGE_BORDER + fract(page_uv) * PAGE_SIZE;
+      return textureSampleLevel(atlas, atlas_sampler, texel / ATLAS_SIZE, 0.0);
+    }
+  }
+
+  return vec4(1.0, 0.0, 1.0, 1.0);
+}
+
+// Records which page every (feedback) pixel would like to sample
+@fragment
+fn fs_feedback(in: VertexOutput) -> @location(0) u32 {
+  // The smaller target makes the derivatives FEEDBACK_SCALE times bigger
+  let texels = in.uv * VIRTUAL_SIZE;
+  let wanted_mip = u32(max(mip_level(dpdx(texels), dpdy(texels)) - log2(FEEDBACK_SCALE), 0.0));
+  if outside(in.uv) {
+    return 0xffffffffu;
+  }
+
+  let page = vec2<u32>(in.uv * f32(PAGES_AT_MIP_0 >> wanted_mip));
+  return (wanted_mip << 20u) | (page.y << 10u) | page.x;
+}